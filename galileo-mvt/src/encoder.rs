@@ -0,0 +1,424 @@
+use crate::{MvtFeature, MvtGeometry, MvtLayer, MvtTile, MvtValue, Point};
+use galileo_types::contour::Contour as _;
+use galileo_types::impls::{ClosedContour, Polygon};
+use geozero::mvt::tile::{Feature, GeomType, Layer, Value};
+use geozero::mvt::Message as GeozeroMessage;
+use geozero::mvt::Tile;
+use std::collections::HashMap;
+
+const MOVE_TO: u32 = 1;
+const LINE_TO: u32 = 2;
+const CLOSE_PATH: u32 = 7;
+
+impl MvtTile {
+    /// Encodes the tile into the Mapbox Vector Tile protobuf format.
+    ///
+    /// Coordinates of the features are expected in the same form [`MvtTile::decode`] returns
+    /// them: as portions of the tile size in the `[0, 1]` range. They are quantized to the grid
+    /// of the layer extent ([`MvtLayer::size`]). Properties with [`MvtValue::Unknown`] values
+    /// cannot be represented and are skipped.
+    pub fn encode(&self) -> Vec<u8> {
+        let tile = Tile {
+            layers: self.layers.iter().map(MvtLayer::encode).collect(),
+        };
+
+        tile.encode_to_vec()
+    }
+}
+
+impl MvtLayer {
+    fn encode(&self) -> Layer {
+        let mut tags = TagsEncoder::default();
+        for key in &self.properties {
+            tags.key_index(key);
+        }
+
+        let features = self
+            .features
+            .iter()
+            .map(|feature| feature.encode(self.size, &mut tags))
+            .collect();
+
+        Layer {
+            version: 2,
+            name: self.name.clone(),
+            features,
+            keys: tags.keys,
+            values: tags.values,
+            extent: Some(self.size),
+        }
+    }
+}
+
+impl MvtFeature {
+    fn encode(&self, extent: u32, tags: &mut TagsEncoder) -> Feature {
+        let mut properties: Vec<_> = self.properties.iter().collect();
+        properties.sort_by_key(|(key, _)| *key);
+
+        let mut feature_tags = Vec::with_capacity(properties.len() * 2);
+        for (key, value) in properties {
+            let Some(value_index) = tags.value_index(value) else {
+                continue;
+            };
+
+            feature_tags.push(tags.key_index(key));
+            feature_tags.push(value_index);
+        }
+
+        let mut geometry = GeometryEncoder::new(extent);
+        let geom_type = match &self.geometry {
+            MvtGeometry::Point(points) => {
+                geometry.encode_points(points);
+                GeomType::Point
+            }
+            MvtGeometry::LineString(contours) => {
+                for contour in contours {
+                    geometry.encode_line(contour.iter_points());
+                }
+                GeomType::Linestring
+            }
+            MvtGeometry::Polygon(polygons) => {
+                for polygon in polygons {
+                    geometry.encode_polygon(polygon);
+                }
+                GeomType::Polygon
+            }
+        };
+
+        Feature {
+            id: self.id,
+            tags: feature_tags,
+            r#type: Some(geom_type as i32),
+            geometry: geometry.commands,
+        }
+    }
+}
+
+/// Keys and values tables of a layer. Every key and every value is stored only once.
+#[derive(Default)]
+struct TagsEncoder {
+    keys: Vec<String>,
+    key_indices: HashMap<String, u32>,
+    values: Vec<Value>,
+    value_indices: HashMap<ValueKey, u32>,
+}
+
+/// Hashable representation of [`MvtValue`]. Floats are compared by their bits.
+#[derive(PartialEq, Eq, Hash)]
+enum ValueKey {
+    String(String),
+    Float(u32),
+    Double(u64),
+    Int64(i64),
+    Uint64(u64),
+    Bool(bool),
+}
+
+impl TagsEncoder {
+    fn key_index(&mut self, key: &str) -> u32 {
+        if let Some(index) = self.key_indices.get(key) {
+            return *index;
+        }
+
+        let index = self.keys.len() as u32;
+        self.keys.push(key.to_string());
+        self.key_indices.insert(key.to_string(), index);
+        index
+    }
+
+    fn value_index(&mut self, value: &MvtValue) -> Option<u32> {
+        let key = match value {
+            MvtValue::String(v) => ValueKey::String(v.clone()),
+            MvtValue::Float(v) => ValueKey::Float(v.to_bits()),
+            MvtValue::Double(v) => ValueKey::Double(v.to_bits()),
+            MvtValue::Int64(v) => ValueKey::Int64(*v),
+            MvtValue::Uint64(v) => ValueKey::Uint64(*v),
+            MvtValue::Bool(v) => ValueKey::Bool(*v),
+            MvtValue::Unknown => return None,
+        };
+
+        if let Some(index) = self.value_indices.get(&key) {
+            return Some(*index);
+        }
+
+        let mut pb_value = Value::default();
+        match value {
+            MvtValue::String(v) => pb_value.string_value = Some(v.clone()),
+            MvtValue::Float(v) => pb_value.float_value = Some(*v),
+            MvtValue::Double(v) => pb_value.double_value = Some(*v),
+            // Negative numbers are shorter in zig-zag encoding.
+            MvtValue::Int64(v) if *v < 0 => pb_value.sint_value = Some(*v),
+            MvtValue::Int64(v) => pb_value.int_value = Some(*v),
+            MvtValue::Uint64(v) => pb_value.uint_value = Some(*v),
+            MvtValue::Bool(v) => pb_value.bool_value = Some(*v),
+            MvtValue::Unknown => return None,
+        }
+
+        let index = self.values.len() as u32;
+        self.values.push(pb_value);
+        self.value_indices.insert(key, index);
+        Some(index)
+    }
+}
+
+struct GeometryEncoder {
+    extent: f32,
+    cursor: [i32; 2],
+    commands: Vec<u32>,
+}
+
+impl GeometryEncoder {
+    fn new(extent: u32) -> Self {
+        Self {
+            extent: extent as f32,
+            cursor: [0, 0],
+            commands: vec![],
+        }
+    }
+
+    fn quantize(&self, point: &Point) -> [i32; 2] {
+        [
+            (point.x * self.extent).round() as i32,
+            (point.y * self.extent).round() as i32,
+        ]
+    }
+
+    /// Converts points to the tile grid, dropping the points that fall into the same cell as the
+    /// previous one.
+    fn quantize_all<'a>(&self, points: impl Iterator<Item = &'a Point>) -> Vec<[i32; 2]> {
+        let mut quantized: Vec<[i32; 2]> = vec![];
+        for point in points {
+            let point = self.quantize(point);
+            if quantized.last() != Some(&point) {
+                quantized.push(point);
+            }
+        }
+
+        quantized
+    }
+
+    fn encode_points(&mut self, points: &[Point]) {
+        if points.is_empty() {
+            return;
+        }
+
+        self.commands
+            .push(command_integer(MOVE_TO, points.len() as u32));
+        for point in points {
+            let point = self.quantize(point);
+            self.push_point(point);
+        }
+    }
+
+    fn encode_line<'a>(&mut self, points: impl Iterator<Item = &'a Point>) {
+        let points = self.quantize_all(points);
+        if points.len() < 2 {
+            return;
+        }
+
+        self.push_path(&points);
+    }
+
+    fn encode_polygon(&mut self, polygon: &Polygon<Point>) {
+        let Some(outer) = self.quantize_ring(&polygon.outer_contour) else {
+            return;
+        };
+
+        // The decoder expects outer rings with positive area and holes with negative area in the
+        // tile coordinates.
+        self.push_ring(outer, true);
+        for inner in &polygon.inner_contours {
+            if let Some(inner) = self.quantize_ring(inner) {
+                self.push_ring(inner, false);
+            }
+        }
+    }
+
+    fn quantize_ring(&self, contour: &ClosedContour<Point>) -> Option<Vec<[i32; 2]>> {
+        let mut points = self.quantize_all(contour.points.iter());
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+
+        (points.len() >= 3 && signed_area(&points) != 0).then_some(points)
+    }
+
+    fn push_ring(&mut self, mut points: Vec<[i32; 2]>, is_outer: bool) {
+        if (signed_area(&points) > 0) != is_outer {
+            points.reverse();
+        }
+
+        self.push_path(&points);
+        self.commands.push(command_integer(CLOSE_PATH, 1));
+    }
+
+    fn push_path(&mut self, points: &[[i32; 2]]) {
+        self.commands.push(command_integer(MOVE_TO, 1));
+        self.push_point(points[0]);
+
+        self.commands
+            .push(command_integer(LINE_TO, points.len() as u32 - 1));
+        for point in &points[1..] {
+            self.push_point(*point);
+        }
+    }
+
+    fn push_point(&mut self, point: [i32; 2]) {
+        self.commands.push(int_to_sint(point[0] - self.cursor[0]));
+        self.commands.push(int_to_sint(point[1] - self.cursor[1]));
+        self.cursor = point;
+    }
+}
+
+fn command_integer(id: u32, count: u32) -> u32 {
+    (id & 0x7) | (count << 3)
+}
+
+fn int_to_sint(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+/// Doubled signed area of a ring.
+fn signed_area(points: &[[i32; 2]]) -> i64 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a[0] as i64 * b[1] as i64 - b[0] as i64 * a[1] as i64)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sint_to_int;
+    use galileo_types::impls::Contour;
+    use std::io::Cursor;
+
+    fn layer(features: Vec<MvtFeature>, size: u32) -> MvtLayer {
+        MvtLayer {
+            name: "test".into(),
+            features,
+            properties: vec![],
+            size,
+        }
+    }
+
+    fn encode_layer(layer: MvtLayer) -> Layer {
+        let tile = MvtTile {
+            layers: vec![layer],
+        };
+        let mut pb = Tile::decode(Cursor::new(tile.encode())).unwrap();
+        pb.layers.remove(0)
+    }
+
+    fn tile_point(x: f32, y: f32) -> Point {
+        Point::new(x, y)
+    }
+
+    #[test]
+    fn int_to_sint_test() {
+        for value in [0, 1, -1, 2, -2, 1000, -1000, i32::MAX, i32::MIN] {
+            assert_eq!(sint_to_int(int_to_sint(value)), value);
+        }
+
+        assert_eq!(int_to_sint(-1), 1);
+        assert_eq!(int_to_sint(1), 2);
+    }
+
+    #[test]
+    fn encodes_commands() {
+        // Examples from the vector tile specification.
+        let point = MvtFeature {
+            id: Some(1),
+            properties: HashMap::new(),
+            geometry: MvtGeometry::Point(vec![tile_point(25.0 / 4096.0, 17.0 / 4096.0)]),
+        };
+        let line = MvtFeature {
+            id: None,
+            properties: HashMap::new(),
+            geometry: MvtGeometry::LineString(vec![Contour::open(
+                [[2.0, 2.0], [2.0, 10.0], [10.0, 10.0]]
+                    .into_iter()
+                    .map(|[x, y]| tile_point(x / 4096.0, y / 4096.0))
+                    .collect(),
+            )]),
+        };
+        let polygon = MvtFeature {
+            id: None,
+            properties: HashMap::new(),
+            geometry: MvtGeometry::Polygon(vec![Polygon {
+                // Negative area in the tile coordinates, must be reversed.
+                outer_contour: ClosedContour::new(
+                    [[20.0, 34.0], [8.0, 12.0], [3.0, 6.0]]
+                        .into_iter()
+                        .map(|[x, y]| tile_point(x / 4096.0, y / 4096.0))
+                        .collect(),
+                ),
+                inner_contours: vec![],
+            }]),
+        };
+
+        let pb = encode_layer(layer(vec![point, line, polygon], 4096));
+        assert_eq!(pb.version, 2);
+        assert_eq!(pb.extent, Some(4096));
+        assert_eq!(pb.features[0].id, Some(1));
+        assert_eq!(pb.features[0].r#type, Some(GeomType::Point as i32));
+        assert_eq!(pb.features[0].geometry, [9, 50, 34]);
+        assert_eq!(pb.features[1].geometry, [9, 4, 4, 18, 0, 16, 16, 0]);
+        assert_eq!(pb.features[2].geometry, [9, 6, 12, 18, 10, 12, 24, 44, 15]);
+    }
+
+    #[test]
+    fn deduplicates_keys_and_values() {
+        let feature = |class: &str, rank: i64| MvtFeature {
+            id: None,
+            properties: HashMap::from([
+                ("class".to_string(), MvtValue::String(class.into())),
+                ("rank".to_string(), MvtValue::Int64(rank)),
+                ("unknown".to_string(), MvtValue::Unknown),
+            ]),
+            geometry: MvtGeometry::Point(vec![tile_point(0.5, 0.5)]),
+        };
+
+        let pb = encode_layer(layer(
+            vec![feature("park", 1), feature("park", -1), feature("lake", 1)],
+            512,
+        ));
+
+        assert_eq!(pb.extent, Some(512));
+        assert_eq!(pb.keys, ["class", "rank"]);
+        assert_eq!(pb.values.len(), 4);
+        assert_eq!(pb.features[0].tags, [0, 0, 1, 1]);
+        assert_eq!(pb.features[1].tags, [0, 0, 1, 2]);
+        assert_eq!(pb.features[2].tags, [0, 3, 1, 1]);
+        assert_eq!(pb.values[2].sint_value, Some(-1));
+        assert_eq!(pb.features[0].geometry, [9, 512, 512]);
+    }
+
+    #[test]
+    fn round_trips_with_decoder() {
+        let vt = include_bytes!("../test-data/vt.mvt");
+        let tile = MvtTile::decode(&mut Cursor::new(&vt), false).unwrap();
+        let decoded = MvtTile::decode(Cursor::new(tile.encode()), false).unwrap();
+
+        assert_eq!(tile.layers.len(), decoded.layers.len());
+        for (expected, actual) in tile.layers.iter().zip(&decoded.layers) {
+            assert_eq!(expected.name, actual.name);
+            assert_eq!(expected.size, actual.size);
+            assert_eq!(expected.features.len(), actual.features.len());
+
+            for (expected, actual) in expected.features.iter().zip(&actual.features) {
+                assert_eq!(expected.id, actual.id);
+                assert_eq!(
+                    format!("{:?}", expected.geometry),
+                    format!("{:?}", actual.geometry)
+                );
+
+                assert_eq!(expected.properties.len(), actual.properties.len());
+                for (key, value) in &expected.properties {
+                    assert_eq!(value.to_string(), actual.properties[key].to_string());
+                }
+            }
+        }
+    }
+}
@@ -11,6 +11,7 @@ use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use strfmt::DisplayStr;
 
+mod encoder;
 pub mod error;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,6 +60,39 @@ impl Display for MvtValue {
     }
 }
 
+impl MvtValue {
+    /// Returns the numeric value of a `Float`, `Double`, `Int64` or `Uint64` value, and `None` for
+    /// other variants.
+    ///
+    /// Integers are converted with `as f64`, so values with magnitude above 2^53 lose precision.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            MvtValue::Float(v) => Some(*v as f64),
+            MvtValue::Double(v) => Some(*v),
+            MvtValue::Int64(v) => Some(*v as f64),
+            MvtValue::Uint64(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the string of a `String` value, and `None` for other variants. Other values are
+    /// not converted to strings, use `to_string()` for that.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MvtValue::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a `Bool` value, and `None` for other variants.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            MvtValue::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
 impl DisplayStr for MvtValue {
     fn display_str(&self, f: &mut strfmt::Formatter) -> strfmt::Result<()> {
         f.str(&self.to_string())?;
@@ -1,9 +1,11 @@
 use bytes::Bytes;
-use galileo::layer::vector_tile_layer::style::{VectorTileStyle, VectorTileSymbol};
+use galileo::layer::vector_tile_layer::style::{
+    VectorTileLabelSymbol, VectorTileStyle, VectorTileSymbol,
+};
 #[cfg(target_arch = "wasm32")]
 use galileo::layer::vector_tile_layer::tile_provider::WebWorkerVectorTileProvider;
 use galileo::layer::vector_tile_layer::VectorTileLayer;
-use galileo::render::point_paint::PointPaint;
+use galileo::render::line_label_paint::LineLabelPaint;
 use galileo::render::text::font_service::FontService;
 use galileo::render::text::TextStyle;
 use galileo::tile_scheme::{TileIndex, TileSchema, VerticalDirection};
@@ -69,21 +71,27 @@ pub async fn run(builder: MapBuilder, style: VectorTileStyle, api_key: String) {
 
     let style = VectorTileStyle {
         rules: vec![],
+        rule_matching: Default::default(),
         default_symbol: VectorTileSymbol {
-            point: Some(PointPaint::label_owed(
-                "{name_en}".into(),
-                TextStyle {
+            label: Some(VectorTileLabelSymbol {
+                pattern: "{name_en}".into(),
+                text_style: TextStyle {
                     font_name: "Noto Sans".to_string(),
                     font_size: 12.0,
                     font_color: Color::BLACK,
                     horizontal_alignment: Default::default(),
                     vertical_alignment: Default::default(),
                 },
-            )),
-            line: None,
-            polygon: None,
+                collision: Default::default(),
+                spacing: LineLabelPaint::DEFAULT_SPACING,
+                max_angle: LineLabelPaint::DEFAULT_MAX_ANGLE,
+                offset: [0.0, 0.0],
+                anchor: None,
+            }),
+            ..Default::default()
         },
         background: Default::default(),
+        ..Default::default()
     };
     let label_layer = VectorTileLayer::from_url(tile_provider, style, tile_schema()).await;
 
@@ -21,3 +21,38 @@ where
         future.await;
     });
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn sleep(duration: web_time::Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+#[cfg(target_arch = "wasm32")]
+pub async fn sleep(duration: web_time::Duration) {
+    let timeout = duration.as_millis().min(i32::MAX as u128) as i32;
+    let mut callback = |resolve: js_sys::Function, _reject: js_sys::Function| {
+        use wasm_bindgen::JsCast;
+
+        if let Some(window) = web_sys::window() {
+            if window
+                .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, timeout)
+                .is_ok()
+            {
+                return;
+            }
+        } else if let Ok(global) = js_sys::global().dyn_into::<web_sys::WorkerGlobalScope>() {
+            if global
+                .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, timeout)
+                .is_ok()
+            {
+                return;
+            }
+        }
+
+        // No timer is available, so resolve immediately instead of never.
+        let _ = resolve.call0(&wasm_bindgen::JsValue::NULL);
+    };
+
+    let promise = js_sys::Promise::new(&mut callback);
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
@@ -109,6 +109,13 @@ impl Color {
         Self { a, ..*self }
     }
 
+    /// Returns a new color instance with the alpha channel multiplied by `opacity`. The opacity is
+    /// clamped to the `[0.0, 1.0]` range.
+    pub fn with_opacity(&self, opacity: f64) -> Self {
+        let alpha = self.a as f64 * opacity.clamp(0.0, 1.0);
+        self.with_alpha(alpha.round() as u8)
+    }
+
     /// Returns true if the color is fully transparent (`a == 0`).
     pub fn is_transparent(&self) -> bool {
         self.a == 0
@@ -144,4 +151,12 @@ mod tests {
 
         assert_eq!(Color::from_hex(&hex), color);
     }
+
+    #[test]
+    fn color_opacity() {
+        let color = Color::rgba(10, 20, 30, 200);
+        assert_eq!(color.with_opacity(0.5), Color::rgba(10, 20, 30, 100));
+        assert_eq!(color.with_opacity(2.0), color);
+        assert_eq!(color.with_opacity(-1.0), Color::rgba(10, 20, 30, 0));
+    }
 }
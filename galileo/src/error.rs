@@ -1,17 +1,28 @@
 //! Error types used by the crate.
 
 use galileo_mvt::error::GalileoMvtError;
-use std::io::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 use thiserror::Error;
 
 pub type GalileoResult<T> = Result<T, GalileoError>;
 
+/// Source error stored inside [`GalileoError`]. It is reference counted, so that the errors
+/// can be cloned.
+pub type ErrorSource = Arc<dyn std::error::Error + Send + Sync + 'static>;
+
 /// Galileo error type.
 #[derive(Debug, Error, Clone)]
 pub enum GalileoError {
     /// I/O error (network or file)
+    #[deprecated(
+        note = "network errors are reported as `GalileoError::Network` and file errors as `GalileoError::FsIo`"
+    )]
     #[error("failed to load data")]
     IO,
+    /// Failed to load data over network.
+    #[error(transparent)]
+    Network(#[from] NetworkError),
     /// Error decoding data.
     #[error("failed to decode data")]
     Decoding(#[from] GalileoMvtError),
@@ -30,25 +41,211 @@ pub enum GalileoError {
     Generic(String),
     /// Error reading/writing data to the FS.
     #[error("failed to read file: {0}")]
-    FsIo(#[from] std::io::Error),
+    FsIo(#[source] Arc<std::io::Error>),
     /// Converts errors from the `winit` crate.
+    #[cfg(feature = "winit")]
     #[error("Event loop error: {0}")]
-    WinitEventLoop(#[from] winit::error::EventLoopError),
+    WinitEventLoop(#[source] Arc<winit::error::EventLoopError>),
     /// Converts errors from the `winit` crate.
+    #[cfg(feature = "winit")]
     #[error("OS error: {0}")]
-    WinitOs(#[from] winit::error::OsError),
+    WinitOs(#[source] Arc<winit::error::OsError>),
+}
+
+impl From<std::io::Error> for GalileoError {
+    fn from(value: std::io::Error) -> Self {
+        Self::FsIo(Arc::new(value))
+    }
+}
+
+#[cfg(feature = "winit")]
+impl From<winit::error::EventLoopError> for GalileoError {
+    fn from(value: winit::error::EventLoopError) -> Self {
+        Self::WinitEventLoop(Arc::new(value))
+    }
+}
+
+#[cfg(feature = "winit")]
+impl From<winit::error::OsError> for GalileoError {
+    fn from(value: winit::error::OsError) -> Self {
+        Self::WinitOs(Arc::new(value))
+    }
+}
+
+impl GalileoError {
+    /// Returns true if the operation that failed with this error may succeed if it is repeated.
+    ///
+    /// Only network errors can be retryable, see [`NetworkError::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Network(err) => err.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the requested item does not exist: either [`GalileoError::NotFound`] or an
+    /// HTTP `404 Not Found` or `410 Gone` response.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Self::NotFound => true,
+            Self::Network(err) => matches!(err.status, Some(404) | Some(410)),
+            _ => false,
+        }
+    }
+
+    /// Url of the request that failed, if the error was caused by a network request.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Self::Network(err) => err.url.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// HTTP status of the response, if the server responded with an error status.
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            Self::Network(err) => err.status,
+            _ => None,
+        }
+    }
+}
+
+/// Kind of a [`NetworkError`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum NetworkErrorKind {
+    /// The server responded with an error HTTP status.
+    Status,
+    /// The request timed out.
+    Timeout,
+    /// Failed to connect to the server, e.g. DNS, TLS handshake or refused connection.
+    Connect,
+    /// The connection was interrupted while receiving the response.
+    Body,
+    /// The request could not be created or sent, e.g. invalid url or redirect loop.
+    Request,
+    /// Other error.
+    Other,
+}
+
+/// Error of a network request.
+#[derive(Debug, Clone)]
+pub struct NetworkError {
+    /// Url of the request.
+    pub url: Option<String>,
+    /// HTTP status of the response, if the server responded with an error status.
+    pub status: Option<u16>,
+    /// Kind of the error.
+    pub kind: NetworkErrorKind,
+    /// The underlying error.
+    pub source: Option<ErrorSource>,
+}
+
+impl NetworkError {
+    /// Creates a new error of the given kind.
+    pub fn new(url: impl Into<String>, kind: NetworkErrorKind) -> Self {
+        Self {
+            url: Some(url.into()),
+            status: None,
+            kind,
+            source: None,
+        }
+    }
+
+    /// Creates an error for a response with an error HTTP status.
+    pub fn status(url: impl Into<String>, status: u16) -> Self {
+        Self {
+            status: Some(status),
+            ..Self::new(url, NetworkErrorKind::Status)
+        }
+    }
+
+    /// Sets the underlying error.
+    pub fn with_source(
+        mut self,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
+    ) -> Self {
+        self.source = Some(source.into().into());
+        self
+    }
+
+    /// Returns true if the request may succeed if it is repeated.
+    ///
+    /// Timeouts, connection errors, interrupted responses and the `408`, `425`, `429` and `5xx`
+    /// (except `501` and `505`) HTTP statuses are considered retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self.kind {
+            NetworkErrorKind::Status => matches!(
+                self.status,
+                Some(408 | 425 | 429 | 500 | 502..=504 | 506..=599)
+            ),
+            NetworkErrorKind::Timeout | NetworkErrorKind::Connect | NetworkErrorKind::Body => true,
+            NetworkErrorKind::Request | NetworkErrorKind::Other => false,
+        }
+    }
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let url = self.url.as_deref().unwrap_or("<unknown url>");
+        match (self.kind, self.status) {
+            (NetworkErrorKind::Status, Some(status)) => {
+                write!(f, "failed to load {url}: HTTP status {status}")?
+            }
+            (NetworkErrorKind::Timeout, _) => write!(f, "failed to load {url}: request timed out")?,
+            (NetworkErrorKind::Connect, _) => write!(f, "failed to load {url}: connection error")?,
+            (NetworkErrorKind::Body, _) => {
+                write!(f, "failed to load {url}: response was interrupted")?
+            }
+            _ => write!(f, "failed to load {url}")?,
+        }
+
+        if let Some(source) = &self.source {
+            write!(f, " ({source})")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for NetworkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-impl From<reqwest::Error> for GalileoError {
-    fn from(_value: reqwest::Error) -> Self {
-        Self::IO
+impl From<reqwest::Error> for NetworkError {
+    fn from(value: reqwest::Error) -> Self {
+        let kind = if value.is_timeout() {
+            NetworkErrorKind::Timeout
+        } else if value.is_connect() {
+            NetworkErrorKind::Connect
+        } else if value.is_status() {
+            NetworkErrorKind::Status
+        } else if value.is_body() || value.is_decode() {
+            NetworkErrorKind::Body
+        } else if value.is_builder() || value.is_redirect() || value.is_request() {
+            NetworkErrorKind::Request
+        } else {
+            NetworkErrorKind::Other
+        };
+
+        Self {
+            url: value.url().map(|url| url.to_string()),
+            status: value.status().map(|status| status.as_u16()),
+            kind,
+            source: Some(Arc::new(value)),
+        }
     }
 }
 
-impl From<std::io::Error> for GalileoError {
-    fn from(_value: Error) -> Self {
-        Self::FsIo
+#[cfg(not(target_arch = "wasm32"))]
+impl From<reqwest::Error> for GalileoError {
+    fn from(value: reqwest::Error) -> Self {
+        Self::Network(value.into())
     }
 }
 
@@ -71,3 +268,55 @@ impl From<js_sys::Object> for GalileoError {
         GalileoError::Wasm(Some(format!("Failed to cast {value:?} into target type")))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retryable_statuses() {
+        let retryable = |status| NetworkError::status("https://example.com", status).is_retryable();
+        assert!(retryable(429));
+        assert!(retryable(500));
+        assert!(retryable(503));
+        assert!(!retryable(501));
+        assert!(!retryable(404));
+        assert!(!retryable(403));
+    }
+
+    #[test]
+    fn retryable_kinds() {
+        let retryable = |kind| NetworkError::new("https://example.com", kind).is_retryable();
+        assert!(retryable(NetworkErrorKind::Timeout));
+        assert!(retryable(NetworkErrorKind::Connect));
+        assert!(retryable(NetworkErrorKind::Body));
+        assert!(!retryable(NetworkErrorKind::Request));
+        assert!(!retryable(NetworkErrorKind::Other));
+        assert!(!GalileoError::NotFound.is_retryable());
+    }
+
+    #[test]
+    fn error_details() {
+        let err: GalileoError = NetworkError::status("https://example.com/1/2/3.png", 404).into();
+        assert!(err.is_not_found());
+        assert_eq!(err.url(), Some("https://example.com/1/2/3.png"));
+        assert_eq!(err.http_status(), Some(404));
+        assert_eq!(
+            err.to_string(),
+            "failed to load https://example.com/1/2/3.png: HTTP status 404"
+        );
+
+        let source = std::io::Error::new(std::io::ErrorKind::TimedOut, "deadline exceeded");
+        let err: GalileoError = NetworkError::new("https://example.com", NetworkErrorKind::Timeout)
+            .with_source(source)
+            .into();
+        assert!(!err.is_not_found());
+        assert!(err.is_retryable());
+        assert!(std::error::Error::source(&err).is_some());
+        assert_eq!(err.clone().to_string(), err.to_string());
+        assert_eq!(
+            err.to_string(),
+            "failed to load https://example.com: request timed out (deadline exceeded)"
+        );
+    }
+}
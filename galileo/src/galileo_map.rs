@@ -3,6 +3,7 @@ use crate::layer::data_provider::UrlSource;
 use crate::layer::vector_tile_layer::style::VectorTileStyle;
 use crate::layer::Layer;
 use crate::map::Map;
+use crate::platform::{PlatformService, PlatformServiceImpl};
 use crate::render::WgpuRenderer;
 use crate::tile_scheme::{TileIndex, TileSchema};
 use crate::view::MapView;
@@ -143,6 +144,7 @@ pub struct MapBuilder {
     pub(crate) view: Option<MapView>,
     pub(crate) layers: Vec<Box<dyn Layer>>,
     pub(crate) event_handlers: Vec<Box<EventHandler>>,
+    pub(crate) platform_service: PlatformServiceImpl,
 }
 
 impl MapBuilder {
@@ -215,6 +217,13 @@ impl MapBuilder {
         self
     }
 
+    /// Set the platform service used by the tile layers added by the builder, e.g. one created
+    /// with custom headers by [`PlatformService::with_config`].
+    pub fn with_platform_service(mut self, platform_service: PlatformServiceImpl) -> Self {
+        self.platform_service = platform_service;
+        self
+    }
+
     /// Add a vector tile layer with the given parameters.
     pub async fn with_vector_tiles(
         mut self,
@@ -222,9 +231,14 @@ impl MapBuilder {
         tile_scheme: TileSchema,
         style: VectorTileStyle,
     ) -> Self {
-        self.layers.push(Box::new(
-            Self::create_vector_tile_layer(tile_source, tile_scheme, style).await,
-        ));
+        let layer = Self::create_vector_tile_layer_with_platform_service(
+            tile_source,
+            tile_scheme,
+            style,
+            self.platform_service.clone(),
+        )
+        .await;
+        self.layers.push(Box::new(layer));
         self
     }
 }
@@ -237,6 +251,7 @@ impl Default for MapBuilder {
             view: None,
             layers: vec![],
             event_handlers: vec![],
+            platform_service: PlatformServiceImpl::new(),
         }
     }
 }
@@ -49,7 +49,9 @@ impl PersistentCacheController<str, Bytes> for FileCacheController {
                 debug!(
                     "Failed to add {key} entry to the cache failed {file_path:?} - no parent folder"
                 );
-                Err(GalileoError::IO)
+                Err(GalileoError::Generic(format!(
+                    "invalid cache file path {file_path:?}"
+                )))
             }
         }
     }
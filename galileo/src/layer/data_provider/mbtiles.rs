@@ -0,0 +1,279 @@
+//! Access to tiles stored in [MBTiles](https://github.com/mapbox/mbtiles-spec) files.
+
+use crate::decoded_image::DecodedImage;
+use crate::error::GalileoError;
+use crate::layer::data_provider::DataProvider;
+use crate::layer::vector_tile_layer::tile_provider::loader::{TileLoadError, VectorTileLoader};
+use crate::tile_scheme::TileIndex;
+use bytes::Bytes;
+use galileo_mvt::MvtTile;
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// Maximum number of idle connections kept open to read tiles.
+const MAX_IDLE_CONNECTIONS: usize = 4;
+const TILE_QUERY: &str = "SELECT tile_data FROM tiles \
+                          WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3";
+
+/// Contents of the `metadata` table of an MBTiles file.
+///
+/// Well-known keys are parsed into the corresponding fields, all the values (including unknown
+/// keys) are also available as strings with [`MbTilesMetadata::get`].
+#[derive(Debug, Clone, Default)]
+pub struct MbTilesMetadata {
+    /// Name of the tileset.
+    pub name: Option<String>,
+    /// Format of the tile data: `pbf` for vector tiles, `png`, `jpg` or `webp` for images.
+    pub format: Option<String>,
+    /// Extent of the tileset as `[west, south, east, north]` in degrees.
+    pub bounds: Option<[f64; 4]>,
+    /// Default position of the map as `[longitude, latitude, zoom]`.
+    pub center: Option<[f64; 3]>,
+    /// Lowest z-level of the tiles.
+    pub min_zoom: Option<u32>,
+    /// Highest z-level of the tiles.
+    pub max_zoom: Option<u32>,
+    /// Attribution of the data. May contain HTML.
+    pub attribution: Option<String>,
+    /// Description of the tileset.
+    pub description: Option<String>,
+    values: HashMap<String, String>,
+}
+
+impl MbTilesMetadata {
+    fn new(values: HashMap<String, String>) -> Self {
+        let numbers = |key: &str| -> Option<Vec<f64>> {
+            values
+                .get(key)?
+                .split(',')
+                .map(|v| v.trim().parse().ok())
+                .collect()
+        };
+        let zoom = |key: &str| values.get(key).and_then(|v| v.trim().parse().ok());
+
+        Self {
+            name: values.get("name").cloned(),
+            format: values.get("format").cloned(),
+            bounds: numbers("bounds").and_then(|v| v.try_into().ok()),
+            center: numbers("center").and_then(|v| v.try_into().ok()),
+            min_zoom: zoom("minzoom"),
+            max_zoom: zoom("maxzoom"),
+            attribution: values.get("attribution").cloned(),
+            description: values.get("description").cloned(),
+            values,
+        }
+    }
+
+    /// Returns the raw value of the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|v| v.as_str())
+    }
+}
+
+/// Reads tiles from an MBTiles file.
+///
+/// The provider can be used both as a [`DataProvider`] for raster tile layers and as a
+/// [`VectorTileLoader`] for vector tile layers.
+///
+/// MBTiles stores tiles with the TMS row numbering, so the tile rows are flipped to match the
+/// [`TileSchema::web`](crate::TileSchema::web) indices. Vector tiles compressed with gzip are
+/// decompressed when loaded.
+///
+/// Tiles are read on the blocking thread pool of the runtime. Each read uses its own read only
+/// connection to the file, so several tiles can be read at the same time. Up to 4 connections are
+/// kept open between the reads.
+pub struct MbTilesProvider {
+    path: PathBuf,
+    idle_connections: Arc<Mutex<Vec<Connection>>>,
+    metadata: MbTilesMetadata,
+}
+
+impl MbTilesProvider {
+    /// Opens the file at the given path in read only mode, and reads its metadata.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GalileoError> {
+        let path = path.as_ref().to_path_buf();
+        let connection = open_connection(&path)?;
+
+        let values = {
+            let mut statement = connection
+                .prepare("SELECT name, value FROM metadata")
+                .map_err(sqlite_error)?;
+            let rows = statement
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(sqlite_error)?;
+            rows.collect::<Result<HashMap<String, String>, _>>()
+                .map_err(sqlite_error)?
+        };
+
+        Ok(Self {
+            path,
+            idle_connections: Arc::new(Mutex::new(vec![connection])),
+            metadata: MbTilesMetadata::new(values),
+        })
+    }
+
+    /// Metadata of the tileset.
+    pub fn metadata(&self) -> &MbTilesMetadata {
+        &self.metadata
+    }
+
+    /// Returns the data of the tile with the given index as it is stored in the file.
+    ///
+    /// Returns [`GalileoError::NotFound`] if there is no such tile in the file.
+    pub async fn get_tile(&self, index: TileIndex) -> Result<Bytes, GalileoError> {
+        if index.z > 30 || index.x < 0 || index.y < 0 || index.y >= 1 << index.z {
+            return Err(GalileoError::NotFound);
+        }
+
+        let tms_row = (1i64 << index.z) - 1 - index.y as i64;
+        let path = self.path.clone();
+        let idle_connections = self.idle_connections.clone();
+        let data = tokio::task::spawn_blocking(move || {
+            let idle = idle_connections
+                .lock()
+                .expect("mbtiles connection mutex is poisoned")
+                .pop();
+            let connection = match idle {
+                Some(connection) => connection,
+                None => open_connection(&path)?,
+            };
+
+            let data = read_tile(&connection, index.z, index.x, tms_row);
+
+            let mut idle_connections = idle_connections
+                .lock()
+                .expect("mbtiles connection mutex is poisoned");
+            if idle_connections.len() < MAX_IDLE_CONNECTIONS {
+                idle_connections.push(connection);
+            }
+
+            data
+        })
+        .await
+        .map_err(|err| GalileoError::Generic(format!("mbtiles error: {err}")))??;
+
+        data.map(Bytes::from).ok_or(GalileoError::NotFound)
+    }
+}
+
+fn open_connection(path: &Path) -> Result<Connection, GalileoError> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(sqlite_error)
+}
+
+fn read_tile(
+    connection: &Connection,
+    z: u32,
+    x: i32,
+    tms_row: i64,
+) -> Result<Option<Vec<u8>>, GalileoError> {
+    connection
+        .prepare_cached(TILE_QUERY)
+        .and_then(|mut statement| {
+            statement
+                .query_row((z, x, tms_row), |row| row.get(0))
+                .optional()
+        })
+        .map_err(sqlite_error)
+}
+
+impl DataProvider<TileIndex, DecodedImage, ()> for MbTilesProvider {
+    async fn load_raw(&self, key: &TileIndex) -> Result<Bytes, GalileoError> {
+        self.get_tile(*key).await
+    }
+
+    fn decode(&self, bytes: Bytes, _context: ()) -> Result<DecodedImage, GalileoError> {
+        DecodedImage::new(&bytes)
+    }
+}
+
+#[async_trait::async_trait]
+impl VectorTileLoader for MbTilesProvider {
+    async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
+        let bytes = self.get_tile(index).await?;
+        let bytes = decompress_gzip(bytes).map_err(TileLoadError::Decoding)?;
+        MvtTile::decode(bytes, false).map_err(|err| TileLoadError::Decoding(err.into()))
+    }
+}
+
+/// Decompresses the data if it starts with the gzip header, otherwise returns it as is.
+fn decompress_gzip(bytes: Bytes) -> Result<Bytes, GalileoError> {
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return Ok(bytes);
+    }
+
+    let mut decompressed = vec![];
+    flate2::read::GzDecoder::new(&bytes[..]).read_to_end(&mut decompressed)?;
+
+    Ok(decompressed.into())
+}
+
+fn sqlite_error(err: rusqlite::Error) -> GalileoError {
+    GalileoError::Generic(format!("mbtiles error: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn create_file(path: &Path) {
+        let connection = Connection::open(path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE metadata (name TEXT, value TEXT);
+                 CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+                 INSERT INTO metadata VALUES ('name', 'test'), ('format', 'pbf'),
+                     ('bounds', '-180,-85.05,180,85.05'), ('minzoom', '0'), ('maxzoom', '14'),
+                     ('attribution', '(c) test'), ('json', '{}');",
+            )
+            .unwrap();
+
+        let mvt = std::fs::read("../galileo-mvt/test-data/vt.mvt").unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&mvt).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        // Tile x=1, y=0 at z=1 in XYZ numbering.
+        connection
+            .execute("INSERT INTO tiles VALUES (1, 1, 1, ?1)", [gzipped])
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reads_metadata_and_tiles() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        create_file(file.path());
+
+        let provider = MbTilesProvider::open(file.path()).unwrap();
+        let metadata = provider.metadata();
+        assert_eq!(metadata.name.as_deref(), Some("test"));
+        assert_eq!(metadata.format.as_deref(), Some("pbf"));
+        assert_eq!(metadata.bounds, Some([-180.0, -85.05, 180.0, 85.05]));
+        assert_eq!(metadata.min_zoom, Some(0));
+        assert_eq!(metadata.max_zoom, Some(14));
+        assert_eq!(metadata.attribution.as_deref(), Some("(c) test"));
+        assert_eq!(metadata.get("json"), Some("{}"));
+        assert_eq!(metadata.center, None);
+
+        assert!(provider.get_tile(TileIndex::new(1, 0, 1)).await.is_ok());
+        assert!(matches!(
+            provider.get_tile(TileIndex::new(1, 1, 1)).await,
+            Err(GalileoError::NotFound)
+        ));
+
+        let (first, second) = tokio::join!(
+            VectorTileLoader::load(&provider, TileIndex::new(1, 0, 1)),
+            VectorTileLoader::load(&provider, TileIndex::new(1, 0, 1))
+        );
+        assert!(!first.unwrap().layers.is_empty());
+        assert!(!second.unwrap().layers.is_empty());
+    }
+}
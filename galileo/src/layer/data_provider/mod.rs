@@ -12,6 +12,24 @@ mod file_cache;
 #[cfg(not(target_arch = "wasm32"))]
 pub use file_cache::FileCacheController;
 
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+mod mbtiles;
+
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+pub use mbtiles::{MbTilesMetadata, MbTilesProvider};
+
+#[cfg(feature = "pmtiles")]
+mod pmtiles;
+
+#[cfg(feature = "pmtiles")]
+pub use pmtiles::{
+    PmTilesCompression, PmTilesHeader, PmTilesHttpSource, PmTilesProvider, PmTilesSource,
+    PmTilesTileType, PmTilesWriter,
+};
+
+#[cfg(all(feature = "pmtiles", not(target_arch = "wasm32")))]
+pub use pmtiles::PmTilesFileSource;
+
 use crate::error::GalileoError;
 use bytes::Bytes;
 use maybe_sync::{MaybeSend, MaybeSync};
@@ -79,6 +97,20 @@ pub trait PersistentCacheController<Key: ?Sized, Data> {
 pub trait UrlSource<Key: ?Sized>: (Fn(&Key) -> String) + MaybeSend + MaybeSync {}
 impl<Key: ?Sized, T: Fn(&Key) -> String> UrlSource<Key> for T where T: MaybeSend + MaybeSync {}
 
+/// Encodes a value for use in a URL query, keeping only unreserved characters as is.
+pub(crate) fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    encoded
+}
+
 pub(crate) mod dummy {
     use crate::error::GalileoError;
     use crate::layer::data_provider::PersistentCacheController;
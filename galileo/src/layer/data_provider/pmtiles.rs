@@ -0,0 +1,1042 @@
+//! Access to tiles stored in [PMTiles](https://github.com/protomaps/PMTiles/blob/main/spec/v3/spec.md)
+//! v3 archives.
+
+use crate::decoded_image::DecodedImage;
+use crate::error::GalileoError;
+use crate::layer::data_provider::DataProvider;
+use crate::layer::vector_tile_layer::tile_provider::loader::{TileLoadError, VectorTileLoader};
+use crate::layer::vector_tile_layer::tiler::TileSink;
+use crate::platform::{PlatformService, PlatformServiceImpl};
+use crate::tile_scheme::{TileIndex, TileSchema};
+use bytes::{Buf, Bytes};
+use galileo_mvt::MvtTile;
+use maybe_sync::{MaybeSend, MaybeSync};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::sync::Arc;
+
+const HEADER_SIZE: usize = 127;
+/// The header and the root directory are guaranteed to fit into the first 16 KiB of an archive.
+const INITIAL_FETCH_SIZE: u64 = 16384;
+const MAX_DIRECTORY_DEPTH: usize = 4;
+const LEAF_CACHE_SIZE: usize = 64;
+/// Number of entries in the leaf directories of written archives, if the root directory does not
+/// fit into the initial fetch size.
+const MIN_LEAF_DIRECTORY_SIZE: usize = 4096;
+
+/// Source of the bytes of a PMTiles archive.
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+pub trait PmTilesSource: MaybeSend + MaybeSync {
+    /// Reads the given byte range of the archive. If the range goes past the end of the archive,
+    /// only the available bytes are returned.
+    async fn read(&self, range: Range<u64>) -> Result<Bytes, GalileoError>;
+}
+
+/// Archive loaded into memory as a whole.
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl PmTilesSource for Bytes {
+    async fn read(&self, range: Range<u64>) -> Result<Bytes, GalileoError> {
+        let end = (range.end as usize).min(self.len());
+        let start = (range.start as usize).min(end);
+        Ok(self.slice(start..end))
+    }
+}
+
+/// Reads an archive from a local file.
+///
+/// The file is read on the blocking thread pool of the runtime. Reads do not move the file cursor,
+/// so several tiles can be read at the same time.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct PmTilesFileSource {
+    file: Arc<std::fs::File>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PmTilesFileSource {
+    /// Opens the file at the given path.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, GalileoError> {
+        Ok(Self {
+            file: Arc::new(std::fs::File::open(path)?),
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait::async_trait]
+impl PmTilesSource for PmTilesFileSource {
+    async fn read(&self, range: Range<u64>) -> Result<Bytes, GalileoError> {
+        let file = self.file.clone();
+        tokio::task::spawn_blocking(move || read_file_range(&file, range))
+            .await
+            .map_err(|err| GalileoError::Generic(format!("failed to read pmtiles file: {err}")))?
+    }
+}
+
+/// Reads the given byte range of the file without using the file cursor.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_file_range(file: &std::fs::File, range: Range<u64>) -> Result<Bytes, GalileoError> {
+    #[cfg(unix)]
+    use std::os::unix::fs::FileExt;
+    #[cfg(windows)]
+    use std::os::windows::fs::FileExt;
+
+    let mut data = vec![0; range.end.saturating_sub(range.start) as usize];
+    let mut filled = 0;
+    while filled < data.len() {
+        let offset = range.start + filled as u64;
+
+        #[cfg(unix)]
+        let result = file.read_at(&mut data[filled..], offset);
+        #[cfg(windows)]
+        let result = file.seek_read(&mut data[filled..], offset);
+
+        match result {
+            // End of the file.
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    data.truncate(filled);
+    Ok(data.into())
+}
+
+/// Reads an archive from a remote server using HTTP range requests.
+pub struct PmTilesHttpSource {
+    url: String,
+    platform_service: PlatformServiceImpl,
+}
+
+impl PmTilesHttpSource {
+    /// Creates a new source for the archive at the given url, that makes the requests with the
+    /// given platform service.
+    pub fn new(url: impl Into<String>, platform_service: PlatformServiceImpl) -> Self {
+        Self {
+            url: url.into(),
+            platform_service,
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl PmTilesSource for PmTilesHttpSource {
+    async fn read(&self, range: Range<u64>) -> Result<Bytes, GalileoError> {
+        self.platform_service
+            .load_bytes_range(&self.url, range)
+            .await
+    }
+}
+
+/// Compression used for the tiles or for the internal structures of an archive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PmTilesCompression {
+    /// Compression is not specified.
+    Unknown,
+    /// Data is not compressed.
+    None,
+    /// Gzip compression.
+    Gzip,
+    /// Brotli compression. Not supported.
+    Brotli,
+    /// Zstandard compression. Not supported.
+    Zstd,
+}
+
+impl PmTilesCompression {
+    fn from_byte(value: u8) -> Self {
+        match value {
+            1 => Self::None,
+            2 => Self::Gzip,
+            3 => Self::Brotli,
+            4 => Self::Zstd,
+            _ => Self::Unknown,
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Unknown => 0,
+            Self::None => 1,
+            Self::Gzip => 2,
+            Self::Brotli => 3,
+            Self::Zstd => 4,
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> Result<Vec<u8>, GalileoError> {
+        match self {
+            Self::Unknown | Self::None => Ok(data.to_vec()),
+            Self::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Self::Brotli | Self::Zstd => Err(GalileoError::Generic(format!(
+                "{self:?} compression of PMTiles is not supported"
+            ))),
+        }
+    }
+
+    fn decompress(self, data: Bytes) -> Result<Bytes, GalileoError> {
+        match self {
+            Self::Unknown | Self::None => Ok(data),
+            Self::Gzip => {
+                let mut decompressed = vec![];
+                flate2::read::GzDecoder::new(&data[..]).read_to_end(&mut decompressed)?;
+                Ok(decompressed.into())
+            }
+            Self::Brotli | Self::Zstd => Err(GalileoError::Generic(format!(
+                "{self:?} compression of PMTiles is not supported"
+            ))),
+        }
+    }
+}
+
+/// Type of the tiles in an archive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PmTilesTileType {
+    /// Type is not specified.
+    Unknown,
+    /// Mapbox vector tiles.
+    Mvt,
+    /// PNG images.
+    Png,
+    /// JPEG images.
+    Jpeg,
+    /// WebP images.
+    Webp,
+    /// AVIF images.
+    Avif,
+}
+
+impl PmTilesTileType {
+    fn from_byte(value: u8) -> Self {
+        match value {
+            1 => Self::Mvt,
+            2 => Self::Png,
+            3 => Self::Jpeg,
+            4 => Self::Webp,
+            5 => Self::Avif,
+            _ => Self::Unknown,
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Unknown => 0,
+            Self::Mvt => 1,
+            Self::Png => 2,
+            Self::Jpeg => 3,
+            Self::Webp => 4,
+            Self::Avif => 5,
+        }
+    }
+}
+
+/// Header of a PMTiles archive.
+#[derive(Debug, Clone)]
+pub struct PmTilesHeader {
+    /// Compression of the directories and the metadata.
+    pub internal_compression: PmTilesCompression,
+    /// Compression of the tiles.
+    pub tile_compression: PmTilesCompression,
+    /// Type of the tiles.
+    pub tile_type: PmTilesTileType,
+    /// Lowest z-level of the tiles.
+    pub min_zoom: u32,
+    /// Highest z-level of the tiles.
+    pub max_zoom: u32,
+    /// Extent of the tiles as `[west, south, east, north]` in degrees.
+    pub bounds: [f64; 4],
+    /// Default position of the map as `[longitude, latitude, zoom]`.
+    pub center: [f64; 3],
+    /// Number of tiles that can be loaded from the archive.
+    pub addressed_tiles_count: u64,
+    root_directory: Range<u64>,
+    metadata: Range<u64>,
+    leaf_directories_offset: u64,
+    tile_data_offset: u64,
+}
+
+impl PmTilesHeader {
+    fn decode(data: &[u8]) -> Result<Self, GalileoError> {
+        if data.len() < HEADER_SIZE || &data[0..7] != b"PMTiles" {
+            return Err(GalileoError::Generic("not a PMTiles archive".into()));
+        }
+
+        if data[7] != 3 {
+            return Err(GalileoError::Generic(format!(
+                "unsupported PMTiles version: {}",
+                data[7]
+            )));
+        }
+
+        let mut buf = &data[8..HEADER_SIZE];
+        let range = |buf: &mut &[u8]| {
+            let offset = buf.get_u64_le();
+            offset..offset + buf.get_u64_le()
+        };
+        let root_directory = range(&mut buf);
+        let metadata = range(&mut buf);
+        let leaf_directories_offset = range(&mut buf).start;
+        let tile_data_offset = range(&mut buf).start;
+        let addressed_tiles_count = buf.get_u64_le();
+        let _tile_entries_count = buf.get_u64_le();
+        let _tile_contents_count = buf.get_u64_le();
+        let _clustered = buf.get_u8();
+        let internal_compression = PmTilesCompression::from_byte(buf.get_u8());
+        let tile_compression = PmTilesCompression::from_byte(buf.get_u8());
+        let tile_type = PmTilesTileType::from_byte(buf.get_u8());
+        let min_zoom = buf.get_u8() as u32;
+        let max_zoom = buf.get_u8() as u32;
+        let coordinate = |buf: &mut &[u8]| buf.get_i32_le() as f64 / 10_000_000.0;
+        let bounds = [
+            coordinate(&mut buf),
+            coordinate(&mut buf),
+            coordinate(&mut buf),
+            coordinate(&mut buf),
+        ];
+        let center_zoom = buf.get_u8() as f64;
+        let center = [coordinate(&mut buf), coordinate(&mut buf), center_zoom];
+
+        Ok(Self {
+            internal_compression,
+            tile_compression,
+            tile_type,
+            min_zoom,
+            max_zoom,
+            bounds,
+            center,
+            addressed_tiles_count,
+            root_directory,
+            metadata,
+            leaf_directories_offset,
+            tile_data_offset,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DirectoryEntry {
+    tile_id: u64,
+    offset: u64,
+    length: u64,
+    run_length: u64,
+}
+
+type Directory = Arc<Vec<DirectoryEntry>>;
+
+/// Reads tiles from a PMTiles v3 archive.
+///
+/// The provider can be used both as a [`DataProvider`] for raster tile layers and as a
+/// [`VectorTileLoader`] for vector tile layers. A tile schema matching the archive can be created
+/// with [`PmTilesProvider::tile_schema`].
+///
+/// Only archives with gzip compression or without compression are supported.
+pub struct PmTilesProvider<Source: PmTilesSource> {
+    source: Source,
+    header: PmTilesHeader,
+    root_directory: Directory,
+    leaf_directories: quick_cache::sync::Cache<u64, Directory>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PmTilesProvider<PmTilesFileSource> {
+    /// Opens the archive at the given path.
+    pub async fn open(path: impl AsRef<std::path::Path>) -> Result<Self, GalileoError> {
+        Self::new(PmTilesFileSource::open(path)?).await
+    }
+}
+
+impl PmTilesProvider<PmTilesHttpSource> {
+    /// Opens a remote archive. The server must support HTTP range requests.
+    pub async fn from_url(
+        url: impl Into<String>,
+        platform_service: PlatformServiceImpl,
+    ) -> Result<Self, GalileoError> {
+        Self::new(PmTilesHttpSource::new(url, platform_service)).await
+    }
+}
+
+impl<Source: PmTilesSource> PmTilesProvider<Source> {
+    /// Creates a new provider, reading the header and the root directory of the archive.
+    pub async fn new(source: Source) -> Result<Self, GalileoError> {
+        let initial = source.read(0..INITIAL_FETCH_SIZE).await?;
+        let header = PmTilesHeader::decode(&initial)?;
+
+        let root_range = &header.root_directory;
+        let root_data = if root_range.end <= initial.len() as u64 {
+            initial.slice(root_range.start as usize..root_range.end as usize)
+        } else {
+            source.read(root_range.clone()).await?
+        };
+        let root_directory = decode_directory(header.internal_compression.decompress(root_data)?)?;
+
+        Ok(Self {
+            source,
+            header,
+            root_directory: Arc::new(root_directory),
+            leaf_directories: quick_cache::sync::Cache::new(LEAF_CACHE_SIZE),
+        })
+    }
+
+    /// Header of the archive.
+    pub fn header(&self) -> &PmTilesHeader {
+        &self.header
+    }
+
+    /// Reads the JSON metadata of the archive.
+    pub async fn metadata(&self) -> Result<serde_json::Value, GalileoError> {
+        if self.header.metadata.is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+
+        let data = self.source.read(self.header.metadata.clone()).await?;
+        let data = self.header.internal_compression.decompress(data)?;
+
+        serde_json::from_slice(&data)
+            .map_err(|err| GalileoError::Generic(format!("invalid PMTiles metadata: {err}")))
+    }
+
+    /// Creates a Web Mercator tile schema with the z-levels and bounds of the archive.
+    pub fn tile_schema(&self) -> TileSchema {
+        TileSchema::web_for_area(
+            self.header.min_zoom,
+            self.header.max_zoom,
+            self.header.bounds,
+        )
+    }
+
+    /// Returns the decompressed data of the tile with the given index.
+    ///
+    /// Returns [`GalileoError::NotFound`] if there is no such tile in the archive.
+    pub async fn get_tile(&self, index: TileIndex) -> Result<Bytes, GalileoError> {
+        let tile_id = zxy_to_tile_id(index.z, index.x, index.y).ok_or(GalileoError::NotFound)?;
+
+        let mut directory = self.root_directory.clone();
+        for _ in 0..MAX_DIRECTORY_DEPTH {
+            let entry = find_entry(&directory, tile_id).ok_or(GalileoError::NotFound)?;
+            if entry.run_length > 0 {
+                let start = self.header.tile_data_offset + entry.offset;
+                let data = self.source.read(start..start + entry.length).await?;
+                return self.header.tile_compression.decompress(data);
+            }
+
+            directory = self.leaf_directory(entry).await?;
+        }
+
+        Err(GalileoError::Generic(
+            "PMTiles directories are nested too deep".into(),
+        ))
+    }
+
+    async fn leaf_directory(&self, entry: DirectoryEntry) -> Result<Directory, GalileoError> {
+        let start = self.header.leaf_directories_offset + entry.offset;
+        self.leaf_directories
+            .get_or_insert_async(&start, async {
+                let data = self.source.read(start..start + entry.length).await?;
+                let data = self.header.internal_compression.decompress(data)?;
+                Ok(Arc::new(decode_directory(data)?))
+            })
+            .await
+    }
+}
+
+impl<Source: PmTilesSource> DataProvider<TileIndex, DecodedImage, ()> for PmTilesProvider<Source> {
+    async fn load_raw(&self, key: &TileIndex) -> Result<Bytes, GalileoError> {
+        self.get_tile(*key).await
+    }
+
+    fn decode(&self, bytes: Bytes, _context: ()) -> Result<DecodedImage, GalileoError> {
+        DecodedImage::new(&bytes)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl<Source: PmTilesSource> VectorTileLoader for PmTilesProvider<Source> {
+    async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
+        let bytes = self.get_tile(index).await?;
+
+        MvtTile::decode(bytes, false).map_err(|err| TileLoadError::Decoding(err.into()))
+    }
+}
+
+/// Writes tiles into a PMTiles v3 archive.
+///
+/// Tiles can be added in any order. The tile data is written as it is added, and the directories
+/// and the header are written by [`PmTilesWriter::finish`]. Vector tiles and the directories are
+/// compressed with gzip.
+///
+/// The writer implements [`TileSink`], so it can be used to store the tiles generated by a
+/// [`VectorTiler`](crate::layer::vector_tile_layer::tiler::VectorTiler):
+///
+/// ```no_run
+/// # use galileo::layer::data_provider::PmTilesWriter;
+/// # use galileo::layer::vector_tile_layer::tiler::VectorTiler;
+/// # use galileo::TileSchema;
+/// # fn generate() -> Result<(), galileo::error::GalileoError> {
+/// let tiler = VectorTiler::new(TileSchema::web(14));
+/// let mut writer = PmTilesWriter::create("tiles.pmtiles")?;
+/// tiler.generate(0..=14, &mut writer)?;
+/// writer.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct PmTilesWriter<W: Write + Seek> {
+    writer: W,
+    tile_type: PmTilesTileType,
+    bounds: [f64; 4],
+    metadata: serde_json::Value,
+    entries: Vec<DirectoryEntry>,
+    tile_data_length: u64,
+    zoom_range: Option<(u32, u32)>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PmTilesWriter<std::io::BufWriter<std::fs::File>> {
+    /// Creates a file at the given path to write the archive to. An existing file is truncated.
+    pub fn create(path: impl AsRef<std::path::Path>) -> Result<Self, GalileoError> {
+        Self::new(std::io::BufWriter::new(std::fs::File::create(path)?))
+    }
+}
+
+impl<W: Write + Seek> PmTilesWriter<W> {
+    /// Creates a new writer of vector tiles covering the whole world.
+    ///
+    /// The archive is written from the current position of the `writer`.
+    pub fn new(mut writer: W) -> Result<Self, GalileoError> {
+        // Space for the header and the root directory.
+        writer.write_all(&[0; INITIAL_FETCH_SIZE as usize])?;
+
+        Ok(Self {
+            writer,
+            tile_type: PmTilesTileType::Mvt,
+            bounds: [-180.0, -85.051129, 180.0, 85.051129],
+            metadata: serde_json::Value::Object(Default::default()),
+            entries: vec![],
+            tile_data_length: 0,
+            zoom_range: None,
+        })
+    }
+
+    /// Sets the type of the tiles. Only vector tiles are compressed by the writer, images are
+    /// stored as they are.
+    pub fn with_tile_type(mut self, tile_type: PmTilesTileType) -> Self {
+        self.tile_type = tile_type;
+        self
+    }
+
+    /// Sets the extent of the tiles as `[west, south, east, north]` in degrees.
+    pub fn with_bounds(mut self, bounds: [f64; 4]) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Sets the JSON metadata of the archive, e.g. the `vector_layers` description.
+    pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    fn tile_compression(&self) -> PmTilesCompression {
+        match self.tile_type {
+            PmTilesTileType::Mvt => PmTilesCompression::Gzip,
+            _ => PmTilesCompression::None,
+        }
+    }
+
+    /// Writes the data of the tile with the given index.
+    pub fn add_tile(&mut self, index: TileIndex, data: &[u8]) -> Result<(), GalileoError> {
+        let tile_id = zxy_to_tile_id(index.z, index.x, index.y).ok_or_else(|| {
+            GalileoError::Generic(format!("invalid PMTiles tile index: {index:?}"))
+        })?;
+        let data = self.tile_compression().compress(data)?;
+        self.writer.write_all(&data)?;
+
+        self.entries.push(DirectoryEntry {
+            tile_id,
+            offset: self.tile_data_length,
+            length: data.len() as u64,
+            run_length: 1,
+        });
+        self.tile_data_length += data.len() as u64;
+        self.zoom_range = Some(match self.zoom_range {
+            Some((min, max)) => (min.min(index.z), max.max(index.z)),
+            None => (index.z, index.z),
+        });
+
+        Ok(())
+    }
+
+    /// Writes the directories, the metadata and the header of the archive and returns the
+    /// underlying writer.
+    pub fn finish(mut self) -> Result<W, GalileoError> {
+        self.entries.sort_by_key(|entry| entry.tile_id);
+        if let Some(pair) = self
+            .entries
+            .windows(2)
+            .find(|pair| pair[0].tile_id == pair[1].tile_id)
+        {
+            return Err(GalileoError::Generic(format!(
+                "tile {} is added to the PMTiles archive twice",
+                pair[0].tile_id
+            )));
+        }
+
+        let internal_compression = PmTilesCompression::Gzip;
+        let (root, leaves) = build_directories(&self.entries, internal_compression)?;
+        let metadata =
+            internal_compression.compress(&serde_json::to_vec(&self.metadata).map_err(
+                |err| GalileoError::Generic(format!("failed to write PMTiles metadata: {err}")),
+            )?)?;
+
+        let tile_data_offset = INITIAL_FETCH_SIZE;
+        let metadata_offset = tile_data_offset + self.tile_data_length;
+        let leaves_offset = metadata_offset + metadata.len() as u64;
+        self.writer.write_all(&metadata)?;
+        self.writer.write_all(&leaves)?;
+
+        let (min_zoom, max_zoom) = self.zoom_range.unwrap_or((0, 0));
+        let tiles_count = self.entries.len() as u64;
+        let mut header = b"PMTiles".to_vec();
+        header.push(3);
+        for value in [
+            HEADER_SIZE as u64,
+            root.len() as u64,
+            metadata_offset,
+            metadata.len() as u64,
+            leaves_offset,
+            leaves.len() as u64,
+            tile_data_offset,
+            self.tile_data_length,
+            tiles_count,
+            tiles_count,
+            tiles_count,
+        ] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+
+        // Tiles are written in the order they are added, so the archive is not clustered.
+        header.extend_from_slice(&[
+            0,
+            internal_compression.to_byte(),
+            self.tile_compression().to_byte(),
+            self.tile_type.to_byte(),
+            min_zoom as u8,
+            max_zoom as u8,
+        ]);
+        let coordinate = |value: f64| ((value * 10_000_000.0).round() as i32).to_le_bytes();
+        for value in self.bounds {
+            header.extend_from_slice(&coordinate(value));
+        }
+        header.push(min_zoom as u8);
+        header.extend_from_slice(&coordinate((self.bounds[0] + self.bounds[2]) / 2.0));
+        header.extend_from_slice(&coordinate((self.bounds[1] + self.bounds[3]) / 2.0));
+
+        let end = self.writer.stream_position()?;
+        let start = end - leaves_offset - leaves.len() as u64;
+        self.writer.seek(SeekFrom::Start(start))?;
+        self.writer.write_all(&header)?;
+        self.writer.write_all(&root)?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write + Seek> TileSink for PmTilesWriter<W> {
+    fn write_tile(&mut self, index: TileIndex, tile: MvtTile) -> Result<(), GalileoError> {
+        self.add_tile(index, &tile.encode())
+    }
+}
+
+/// Encodes the entries into the root directory and leaf directories, so that the root directory
+/// fits into the initial fetch size together with the header.
+fn build_directories(
+    entries: &[DirectoryEntry],
+    compression: PmTilesCompression,
+) -> Result<(Vec<u8>, Vec<u8>), GalileoError> {
+    let max_root_size = INITIAL_FETCH_SIZE as usize - HEADER_SIZE;
+    let root = compression.compress(&encode_directory(entries))?;
+    if root.len() <= max_root_size {
+        return Ok((root, vec![]));
+    }
+
+    let mut leaf_size = MIN_LEAF_DIRECTORY_SIZE;
+    loop {
+        let mut leaves = vec![];
+        let mut root_entries = vec![];
+        for chunk in entries.chunks(leaf_size) {
+            let leaf = compression.compress(&encode_directory(chunk))?;
+            root_entries.push(DirectoryEntry {
+                tile_id: chunk[0].tile_id,
+                offset: leaves.len() as u64,
+                length: leaf.len() as u64,
+                run_length: 0,
+            });
+            leaves.extend_from_slice(&leaf);
+        }
+
+        let root = compression.compress(&encode_directory(&root_entries))?;
+        if root.len() <= max_root_size {
+            return Ok((root, leaves));
+        }
+
+        leaf_size *= 2;
+    }
+}
+
+/// Converts the tile index into the position of the tile on the Hilbert curve going through all
+/// the tiles of all z-levels.
+fn zxy_to_tile_id(z: u32, x: i32, y: i32) -> Option<u64> {
+    if z > 31 || x < 0 || y < 0 || x as u64 >= 1 << z || y as u64 >= 1 << z {
+        return None;
+    }
+
+    let n = 1u64 << z;
+    let (mut x, mut y) = (x as u64, y as u64);
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = u64::from(x & s > 0);
+        let ry = u64::from(y & s > 0);
+        d += s * s * ((3 * rx) ^ ry);
+
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        s /= 2;
+    }
+
+    // Number of tiles on all the lower z-levels.
+    let base = ((1u64 << (2 * z)) - 1) / 3;
+    Some(base + d)
+}
+
+fn find_entry(entries: &[DirectoryEntry], tile_id: u64) -> Option<DirectoryEntry> {
+    let index = match entries.binary_search_by_key(&tile_id, |entry| entry.tile_id) {
+        Ok(index) => return Some(entries[index]),
+        Err(0) => return None,
+        Err(index) => index - 1,
+    };
+
+    let entry = entries[index];
+    // Entries with zero run length point to leaf directories, that start with the given tile id.
+    (entry.run_length == 0 || tile_id - entry.tile_id < entry.run_length).then_some(entry)
+}
+
+fn decode_directory(data: Bytes) -> Result<Vec<DirectoryEntry>, GalileoError> {
+    let mut buf = &data[..];
+    let count = read_varint(&mut buf)? as usize;
+    // Each entry takes at least 4 bytes.
+    if count > data.len() {
+        return Err(GalileoError::Generic("invalid PMTiles directory".into()));
+    }
+
+    let mut entries = vec![
+        DirectoryEntry {
+            tile_id: 0,
+            offset: 0,
+            length: 0,
+            run_length: 0,
+        };
+        count
+    ];
+
+    let mut last_id = 0;
+    for entry in &mut entries {
+        last_id += read_varint(&mut buf)?;
+        entry.tile_id = last_id;
+    }
+
+    for entry in &mut entries {
+        entry.run_length = read_varint(&mut buf)?;
+    }
+
+    for entry in &mut entries {
+        entry.length = read_varint(&mut buf)?;
+    }
+
+    for index in 0..count {
+        let value = read_varint(&mut buf)?;
+        entries[index].offset = if value == 0 && index > 0 {
+            entries[index - 1].offset + entries[index - 1].length
+        } else {
+            value.saturating_sub(1)
+        };
+    }
+
+    Ok(entries)
+}
+
+fn read_varint(buf: &mut &[u8]) -> Result<u64, GalileoError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        if !buf.has_remaining() {
+            break;
+        }
+
+        let byte = buf.get_u8();
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(GalileoError::Generic(
+        "invalid varint in PMTiles directory".into(),
+    ))
+}
+
+fn encode_directory(entries: &[DirectoryEntry]) -> Vec<u8> {
+    let mut data = vec![];
+    write_varint(&mut data, entries.len() as u64);
+    let mut last_id = 0;
+    for entry in entries {
+        write_varint(&mut data, entry.tile_id - last_id);
+        last_id = entry.tile_id;
+    }
+
+    for entry in entries {
+        write_varint(&mut data, entry.run_length);
+    }
+
+    for entry in entries {
+        write_varint(&mut data, entry.length);
+    }
+
+    for entry in entries {
+        write_varint(&mut data, entry.offset + 1);
+    }
+
+    data
+}
+
+fn write_varint(data: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        data.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+
+    data.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        PmTilesCompression::Gzip.compress(data).unwrap()
+    }
+
+    fn entry(tile_id: u64, offset: u64, length: u64, run_length: u64) -> DirectoryEntry {
+        DirectoryEntry {
+            tile_id,
+            offset,
+            length,
+            run_length,
+        }
+    }
+
+    /// Archive with tile 0 = "a", tiles 1 and 2 = "b" and tile 5 = "c" stored in a leaf directory.
+    fn archive() -> Bytes {
+        let tiles = [gzip(b"a"), gzip(b"b"), gzip(b"c")];
+        let mut tile_data = vec![];
+        let mut offsets = vec![];
+        for tile in &tiles {
+            offsets.push((tile_data.len() as u64, tile.len() as u64));
+            tile_data.extend_from_slice(tile);
+        }
+
+        let leaf = encode_directory(&[entry(5, offsets[2].0, offsets[2].1, 1)]);
+        let root = encode_directory(&[
+            entry(0, offsets[0].0, offsets[0].1, 1),
+            entry(1, offsets[1].0, offsets[1].1, 2),
+            entry(5, 0, leaf.len() as u64, 0),
+        ]);
+        let metadata = br#"{"name":"test"}"#;
+
+        let root_offset = HEADER_SIZE as u64;
+        let metadata_offset = root_offset + root.len() as u64;
+        let leaf_offset = metadata_offset + metadata.len() as u64;
+        let data_offset = leaf_offset + leaf.len() as u64;
+
+        let mut data = b"PMTiles".to_vec();
+        data.push(3);
+        for value in [
+            root_offset,
+            root.len() as u64,
+            metadata_offset,
+            metadata.len() as u64,
+            leaf_offset,
+            leaf.len() as u64,
+            data_offset,
+            tile_data.len() as u64,
+            4,
+            3,
+            3,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        // Clustered, internal compression, tile compression, tile type, min and max zoom.
+        data.extend_from_slice(&[1, 1, 2, 1, 0, 2]);
+        for value in [-1_800_000_000i32, -850_000_000, 1_800_000_000, 850_000_000] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.push(1);
+        data.extend_from_slice(&0i32.to_le_bytes());
+        data.extend_from_slice(&0i32.to_le_bytes());
+        assert_eq!(data.len(), HEADER_SIZE);
+
+        data.extend_from_slice(&root);
+        data.extend_from_slice(metadata);
+        data.extend_from_slice(&leaf);
+        data.extend_from_slice(&tile_data);
+
+        data.into()
+    }
+
+    #[test]
+    fn tile_ids() {
+        assert_eq!(zxy_to_tile_id(0, 0, 0), Some(0));
+        assert_eq!(zxy_to_tile_id(1, 0, 0), Some(1));
+        assert_eq!(zxy_to_tile_id(1, 0, 1), Some(2));
+        assert_eq!(zxy_to_tile_id(1, 1, 1), Some(3));
+        assert_eq!(zxy_to_tile_id(1, 1, 0), Some(4));
+        assert_eq!(zxy_to_tile_id(2, 0, 0), Some(5));
+        assert_eq!(zxy_to_tile_id(2, 1, 3), Some(11));
+        assert_eq!(zxy_to_tile_id(3, 3, 0), Some(26));
+        assert_eq!(zxy_to_tile_id(1, 2, 0), None);
+        assert_eq!(zxy_to_tile_id(1, -1, 0), None);
+    }
+
+    #[test]
+    fn directory_round_trip() {
+        let entries = vec![entry(1, 0, 10, 1), entry(2, 10, 5, 3), entry(10, 100, 1, 0)];
+        let decoded = decode_directory(encode_directory(&entries).into()).unwrap();
+        assert_eq!(decoded, entries);
+
+        assert_eq!(find_entry(&entries, 0), None);
+        assert_eq!(find_entry(&entries, 1), Some(entries[0]));
+        assert_eq!(find_entry(&entries, 4), Some(entries[1]));
+        assert_eq!(find_entry(&entries, 5), None);
+        assert_eq!(find_entry(&entries, 12), Some(entries[2]));
+    }
+
+    #[test]
+    fn reads_archive() {
+        let provider = tokio_test::block_on(PmTilesProvider::new(archive())).unwrap();
+        let header = provider.header();
+        assert_eq!(header.tile_type, PmTilesTileType::Mvt);
+        assert_eq!(header.tile_compression, PmTilesCompression::Gzip);
+        assert_eq!((header.min_zoom, header.max_zoom), (0, 2));
+        assert_eq!(header.bounds, [-180.0, -85.0, 180.0, 85.0]);
+
+        let tile = |z, x, y| tokio_test::block_on(provider.get_tile(TileIndex::new(x, y, z)));
+        assert_eq!(&tile(0, 0, 0).unwrap()[..], b"a");
+        assert_eq!(&tile(1, 0, 0).unwrap()[..], b"b");
+        assert_eq!(&tile(1, 0, 1).unwrap()[..], b"b");
+        assert!(matches!(tile(1, 1, 1), Err(GalileoError::NotFound)));
+        assert_eq!(&tile(2, 0, 0).unwrap()[..], b"c");
+        assert!(matches!(tile(2, 0, 1), Err(GalileoError::NotFound)));
+
+        let metadata = tokio_test::block_on(provider.metadata()).unwrap();
+        assert_eq!(metadata["name"], "test");
+
+        let schema = provider.tile_schema();
+        assert_eq!(schema.lods.len(), 3);
+        assert!(schema.bounds.y_max() < 20037508.0);
+    }
+
+    #[tokio::test]
+    async fn reads_file_ranges() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"0123456789").unwrap();
+        let source = PmTilesFileSource::open(file.path()).unwrap();
+
+        let (head, tail, past_end) =
+            tokio::join!(source.read(0..4), source.read(6..10), source.read(8..100));
+        assert_eq!(&head.unwrap()[..], b"0123");
+        assert_eq!(&tail.unwrap()[..], b"6789");
+        assert_eq!(&past_end.unwrap()[..], b"89");
+        assert!(source.read(20..30).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn written_archive_can_be_read() {
+        let mut writer = PmTilesWriter::new(std::io::Cursor::new(vec![]))
+            .unwrap()
+            .with_metadata(serde_json::json!({"name": "written"}));
+        writer.add_tile(TileIndex::new(1, 1, 1), b"b").unwrap();
+        writer.add_tile(TileIndex::new(0, 0, 0), b"a").unwrap();
+        writer.add_tile(TileIndex::new(3, 2, 2), b"c").unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        let provider = tokio_test::block_on(PmTilesProvider::new(Bytes::from(data))).unwrap();
+        let header = provider.header();
+        assert_eq!(header.tile_type, PmTilesTileType::Mvt);
+        assert_eq!((header.min_zoom, header.max_zoom), (0, 2));
+        assert_eq!(header.addressed_tiles_count, 3);
+
+        let tile = |z, x, y| tokio_test::block_on(provider.get_tile(TileIndex::new(x, y, z)));
+        assert_eq!(&tile(0, 0, 0).unwrap()[..], b"a");
+        assert_eq!(&tile(1, 1, 1).unwrap()[..], b"b");
+        assert_eq!(&tile(2, 3, 2).unwrap()[..], b"c");
+        assert!(matches!(tile(1, 0, 0), Err(GalileoError::NotFound)));
+
+        let metadata = tokio_test::block_on(provider.metadata()).unwrap();
+        assert_eq!(metadata["name"], "written");
+    }
+
+    #[test]
+    fn large_archive_uses_leaf_directories() {
+        let mut writer = PmTilesWriter::new(std::io::Cursor::new(vec![]))
+            .unwrap()
+            .with_tile_type(PmTilesTileType::Png);
+        let z = 8;
+        for x in 0..1 << z {
+            for y in 0..1 << z {
+                let data = format!("{x}/{y}");
+                writer
+                    .add_tile(TileIndex::new(x, y, z), data.as_bytes())
+                    .unwrap();
+            }
+        }
+        let data = writer.finish().unwrap().into_inner();
+
+        let provider = tokio_test::block_on(PmTilesProvider::new(Bytes::from(data))).unwrap();
+        assert!(provider.header.leaf_directories_offset > 0);
+        assert!(!provider.root_directory.is_empty());
+        assert!(provider
+            .root_directory
+            .iter()
+            .all(|entry| entry.run_length == 0));
+
+        for (x, y) in [(0, 0), (17, 200), (255, 255)] {
+            let tile = tokio_test::block_on(provider.get_tile(TileIndex::new(x, y, z))).unwrap();
+            assert_eq!(&tile[..], format!("{x}/{y}").as_bytes());
+        }
+    }
+
+    #[test]
+    fn duplicate_tiles_are_rejected() {
+        let mut writer = PmTilesWriter::new(std::io::Cursor::new(vec![])).unwrap();
+        writer.add_tile(TileIndex::new(0, 0, 1), b"a").unwrap();
+        writer.add_tile(TileIndex::new(0, 0, 1), b"b").unwrap();
+        assert!(writer.finish().is_err());
+    }
+}
@@ -30,12 +30,22 @@ where
 {
     /// Creates a new instance without persistent cache.
     pub fn new(url_source: impl UrlSource<Key> + 'static, decoder: Decoder) -> Self {
+        Self::new_with_platform_service(url_source, decoder, PlatformServiceImpl::new())
+    }
+
+    /// Creates a new instance without persistent cache, that makes the requests with the given
+    /// platform service, e.g. one created with custom headers by [`PlatformService::with_config`].
+    pub fn new_with_platform_service(
+        url_source: impl UrlSource<Key> + 'static,
+        decoder: Decoder,
+        platform_service: PlatformServiceImpl,
+    ) -> Self {
         Self {
             url_source: Box::new(url_source),
             decoder,
             cache: None,
             offline_mode: false,
-            platform_service: PlatformServiceImpl::new(),
+            platform_service,
             _phantom_key: Default::default(),
         }
     }
@@ -53,13 +63,29 @@ where
         url_source: impl UrlSource<Key> + 'static,
         decoder: Decoder,
         cache: Cache,
+    ) -> Self {
+        Self::new_cached_with_platform_service(
+            url_source,
+            decoder,
+            cache,
+            PlatformServiceImpl::new(),
+        )
+    }
+
+    /// Creates a new instance with persistent cache, that makes the requests with the given
+    /// platform service.
+    pub fn new_cached_with_platform_service(
+        url_source: impl UrlSource<Key> + 'static,
+        decoder: Decoder,
+        cache: Cache,
+        platform_service: PlatformServiceImpl,
     ) -> Self {
         Self {
             url_source: Box::new(url_source),
             decoder,
             cache: Some(cache),
             offline_mode: false,
-            platform_service: PlatformServiceImpl::new(),
+            platform_service,
             _phantom_key: Default::default(),
         }
     }
@@ -23,10 +23,19 @@ pub struct UrlImageProvider<Key, Cache = DummyCacheController> {
 impl<Key> UrlImageProvider<Key, DummyCacheController> {
     /// Creates a new instance without persistent cache.
     pub fn new(url_source: impl UrlSource<Key> + 'static) -> Self {
+        Self::new_with_platform_service(url_source, PlatformServiceImpl::new())
+    }
+
+    /// Creates a new instance without persistent cache, that makes the requests with the given
+    /// platform service, e.g. one created with custom headers by [`PlatformService::with_config`].
+    pub fn new_with_platform_service(
+        url_source: impl UrlSource<Key> + 'static,
+        platform_service: PlatformServiceImpl,
+    ) -> Self {
         Self {
             url_source: Box::new(url_source),
             cache: None,
-            platform_service: PlatformServiceImpl::new(),
+            platform_service,
             offline_mode: false,
             _phantom_key: Default::default(),
         }
@@ -36,10 +45,20 @@ impl<Key> UrlImageProvider<Key, DummyCacheController> {
 impl<Key, Cache> UrlImageProvider<Key, Cache> {
     /// Creates a new instance with persistent cache.
     pub fn new_cached(url_source: impl UrlSource<Key> + 'static, cache: Cache) -> Self {
+        Self::new_cached_with_platform_service(url_source, cache, PlatformServiceImpl::new())
+    }
+
+    /// Creates a new instance with persistent cache, that makes the requests with the given
+    /// platform service.
+    pub fn new_cached_with_platform_service(
+        url_source: impl UrlSource<Key> + 'static,
+        cache: Cache,
+        platform_service: PlatformServiceImpl,
+    ) -> Self {
         Self {
             url_source: Box::new(url_source),
             cache: Some(cache),
-            platform_service: PlatformServiceImpl::new(),
+            platform_service,
             offline_mode: false,
             _phantom_key: Default::default(),
         }
@@ -1,6 +1,6 @@
 use crate::layer::feature_layer::symbol::Symbol;
 use crate::render::render_bundle::RenderPrimitive;
-use crate::render::{LineCap, LinePaint};
+use crate::render::{DashArray, LineCap, LineJoin, LinePaint};
 use crate::Color;
 use galileo_types::cartesian::CartesianPoint3d;
 use galileo_types::geometry::Geom;
@@ -15,12 +15,51 @@ pub struct SimpleContourSymbol {
     pub color: Color,
     /// Width of the line in pixels.
     pub width: f64,
+    /// If set, the line is drawn dashed with the given pattern.
+    pub dash_array: Option<DashArray>,
+    /// Type of the caps of the line.
+    pub line_cap: LineCap,
+    /// Type of the joins between the segments of the line.
+    pub line_join: LineJoin,
+    /// Miter limit for [`LineJoin::Miter`] joins. See [`LinePaint::miter_limit`].
+    pub miter_limit: f64,
 }
 
 impl SimpleContourSymbol {
     /// Creates a new instance.
     pub fn new(color: Color, width: f64) -> Self {
-        Self { color, width }
+        Self {
+            color,
+            width,
+            dash_array: None,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::default(),
+            miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+        }
+    }
+
+    /// Sets the dash pattern of the line.
+    pub fn with_dash_array(mut self, dash_array: DashArray) -> Self {
+        self.dash_array = Some(dash_array);
+        self
+    }
+
+    /// Sets the type of the caps of the line.
+    pub fn with_line_cap(mut self, line_cap: LineCap) -> Self {
+        self.line_cap = line_cap;
+        self
+    }
+
+    /// Sets the type of the joins between the segments of the line.
+    pub fn with_line_join(mut self, line_join: LineJoin) -> Self {
+        self.line_join = line_join;
+        self
+    }
+
+    /// Sets the miter limit for [`LineJoin::Miter`] joins.
+    pub fn with_miter_limit(mut self, miter_limit: f64) -> Self {
+        self.miter_limit = miter_limit;
+        self
     }
 }
 
@@ -39,7 +78,10 @@ impl<F> Symbol<F> for SimpleContourSymbol {
             color: self.color,
             width: self.width,
             offset: 0.0,
-            line_cap: LineCap::Butt,
+            line_cap: self.line_cap,
+            line_join: self.line_join,
+            miter_limit: self.miter_limit,
+            dash_array: self.dash_array,
         };
 
         match geometry {
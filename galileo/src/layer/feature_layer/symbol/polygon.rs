@@ -1,6 +1,6 @@
 use crate::layer::feature_layer::symbol::Symbol;
 use crate::render::render_bundle::RenderPrimitive;
-use crate::render::{LineCap, LinePaint, PolygonPaint};
+use crate::render::{LineCap, LineJoin, LinePaint, PatternHandle, PolygonPaint};
 use crate::Color;
 use galileo_types::cartesian::CartesianPoint3d;
 use galileo_types::geometry::Geom;
@@ -20,6 +20,9 @@ pub struct SimplePolygonSymbol {
     /// Offset of the outline in pixels. Positive offset will move outline outside of the polygon, negative offset
     /// will move the outline inside the polygon.
     pub stroke_offset: f64,
+    /// If set, the inner area of the polygon is filled with the pattern. Colors of the pattern
+    /// are multiplied by the `fill_color`.
+    pub fill_pattern: Option<PatternHandle>,
 }
 
 impl SimplePolygonSymbol {
@@ -30,6 +33,7 @@ impl SimplePolygonSymbol {
             stroke_color: Default::default(),
             stroke_width: 0.0,
             stroke_offset: 0.0,
+            fill_pattern: None,
         }
     }
 
@@ -57,6 +61,14 @@ impl SimplePolygonSymbol {
         }
     }
 
+    /// Creates a new instance from a copy of the current, but with the given fill pattern.
+    pub fn with_fill_pattern(&self, fill_pattern: PatternHandle) -> Self {
+        Self {
+            fill_pattern: Some(fill_pattern),
+            ..*self
+        }
+    }
+
     fn render_poly<'a, N, P>(
         &self,
         polygon: &'a galileo_types::impls::Polygon<P>,
@@ -70,6 +82,7 @@ impl SimplePolygonSymbol {
             polygon,
             PolygonPaint {
                 color: self.fill_color,
+                pattern: self.fill_pattern,
             },
         ));
 
@@ -78,6 +91,9 @@ impl SimplePolygonSymbol {
             width: self.stroke_width,
             offset: self.stroke_offset,
             line_cap: LineCap::Butt,
+            line_join: LineJoin::default(),
+            miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+            dash_array: None,
         };
 
         for contour in polygon.iter_contours() {
@@ -10,16 +10,21 @@ use std::sync::{Arc, RwLock};
 pub mod data_provider;
 pub mod feature_layer;
 mod raster_tile_layer;
+mod retry_policy;
 pub mod vector_tile_layer;
+mod wms_layer;
 
 pub use feature_layer::FeatureLayer;
 pub use raster_tile_layer::RasterTileLayer;
+pub use retry_policy::RetryPolicy;
 pub use vector_tile_layer::VectorTileLayer;
+pub use wms_layer::{WmsLayer, WmsParameters};
 
 /// Layers specify a data source and the way the data should be rendered to the map.
 ///
-/// There are currently 3 types of layers:
+/// There are currently 4 types of layers:
 /// * [`RasterTileLayer`] - downloads prerendered tiles from an Internet source and draws them as is.
+/// * [`WmsLayer`] - requests an image for the whole view from a WMS server and draws it as is.
 /// * [`VectorTileLayer`] - downloads vector tiles (in MVT format) from an Internet source and draws them using the
 ///   provided stylesheet.
 /// * [`FeatureLayer`] - draws custom set of geographic objects with the given [`feature_layer::Symbol`];
@@ -1,8 +1,12 @@
 use crate::decoded_image::DecodedImage;
-use crate::layer::data_provider::DataProvider;
+use crate::error::GalileoError;
+use crate::layer::data_provider::{DataProvider, UrlImageProvider};
+use crate::layer::RetryPolicy;
 use crate::messenger::Messenger;
+use crate::platform::PlatformServiceImpl;
 use crate::render::render_bundle::RenderBundle;
 use crate::render::{Canvas, ImagePaint, PackedBundle, PrimitiveId, RenderOptions};
+use crate::tile_json::TileJson;
 use crate::tile_scheme::{TileIndex, TileSchema};
 use crate::view::MapView;
 use maybe_sync::{MaybeSend, MaybeSync, Mutex};
@@ -25,13 +29,17 @@ where
     tiles: Arc<Cache<TileIndex, Arc<TileState>>>,
     prev_drawn_tiles: Mutex<Vec<TileIndex>>,
     messenger: Option<Arc<dyn Messenger>>,
+    retry_policy: RetryPolicy,
 }
 
 enum TileState {
     Loading,
     Loaded(Mutex<DecodedImage>),
     Rendered(Box<Mutex<RenderedTile>>),
-    Error,
+    /// Loading failed at the given time.
+    Error(SystemTime),
+    /// The tile does not exist in the source, so it is never requested again.
+    NotFound,
 }
 
 struct RenderedTile {
@@ -59,6 +67,7 @@ where
             fade_in_duration: Duration::from_millis(300),
             tiles: Arc::new(Cache::new(5000)),
             messenger,
+            retry_policy: RetryPolicy::default(),
         }
     }
 
@@ -67,6 +76,11 @@ where
         self.fade_in_duration = duration;
     }
 
+    /// Sets the policy of repeating failed tile requests.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
     fn get_tiles_to_draw(&self, view: &MapView) -> Vec<(TileIndex, Arc<TileState>)> {
         let mut tiles = vec![];
         let Some(tile_iter) = self.tile_scheme.iter_tiles(view) else {
@@ -259,12 +273,24 @@ where
         tile_provider: Arc<Provider>,
         tiles: &Cache<TileIndex, Arc<TileState>>,
         messenger: Option<Arc<dyn Messenger>>,
+        retry_policy: RetryPolicy,
     ) {
+        // Failed tiles are loaded again once the error cooldown is over.
+        tiles.remove_if(&index, |state| match **state {
+            TileState::Error(failed_at) => retry_policy.is_cooldown_over(failed_at),
+            _ => false,
+        });
+
         match tiles.get_value_or_guard_async(&index).await {
             Ok(_) => {}
             Err(guard) => {
                 let _ = guard.insert(Arc::new(TileState::Loading));
-                let load_result = tile_provider.load(&index, ()).await;
+                let load_result = retry_policy
+                    .run(
+                        || tile_provider.load(&index, ()),
+                        GalileoError::is_retryable,
+                    )
+                    .await;
 
                 match load_result {
                     Ok(decoded_image) => {
@@ -283,7 +309,14 @@ where
                             messenger.request_redraw();
                         }
                     }
-                    Err(_) => tiles.insert(index, Arc::new(TileState::Error)),
+                    Err(err) if err.is_not_found() => {
+                        log::debug!("Tile {index:?} does not exist");
+                        tiles.insert(index, Arc::new(TileState::NotFound));
+                    }
+                    Err(err) => {
+                        log::info!("Failed to load tile {index:?}: {err}");
+                        tiles.insert(index, Arc::new(TileState::Error(SystemTime::now())));
+                    }
                 }
             }
         }
@@ -296,12 +329,28 @@ where
                 let tile_provider = self.tile_provider.clone();
                 let tiles = self.tiles.clone();
                 let messenger = self.messenger.clone();
-                Self::load_tile(index, tile_provider, &tiles, messenger).await;
+                Self::load_tile(index, tile_provider, &tiles, messenger, self.retry_policy).await;
             }
         }
     }
 }
 
+impl RasterTileLayer<UrlImageProvider<TileIndex>> {
+    /// Creates a new layer loading tiles from the source described by the TileJSON document with
+    /// the given platform service.
+    pub fn from_tile_json(
+        tile_json: &TileJson,
+        platform_service: PlatformServiceImpl,
+        messenger: Option<Arc<dyn Messenger>>,
+    ) -> Self {
+        Self::new(
+            tile_json.tile_schema(),
+            UrlImageProvider::new_with_platform_service(tile_json.url_source(), platform_service),
+            messenger,
+        )
+    }
+}
+
 impl<Provider> Layer for RasterTileLayer<Provider>
 where
     Provider: DataProvider<TileIndex, DecodedImage, ()> + MaybeSync + MaybeSend + 'static,
@@ -337,8 +386,9 @@ where
                 let tile_provider = self.tile_provider.clone();
                 let tiles = self.tiles.clone();
                 let messenger = self.messenger.clone();
+                let retry_policy = self.retry_policy;
                 crate::async_runtime::spawn(async move {
-                    Self::load_tile(index, tile_provider, &tiles, messenger).await;
+                    Self::load_tile(index, tile_provider, &tiles, messenger, retry_policy).await;
                 });
             }
         }
@@ -356,3 +406,42 @@ where
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Provider of a tile set without any tiles.
+    #[derive(Default)]
+    struct EmptyProvider {
+        requests: AtomicUsize,
+    }
+
+    impl DataProvider<TileIndex, DecodedImage, ()> for EmptyProvider {
+        async fn load_raw(&self, _key: &TileIndex) -> Result<Bytes, GalileoError> {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            Err(GalileoError::NotFound)
+        }
+
+        fn decode(&self, _bytes: Bytes, _context: ()) -> Result<DecodedImage, GalileoError> {
+            unreachable!("no tiles are loaded")
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_tiles_are_not_requested_again() {
+        let provider = Arc::new(EmptyProvider::default());
+        let tiles = Cache::new(10);
+        let index = TileIndex::new(0, 0, 0);
+        let retry_policy = RetryPolicy::default().with_error_cooldown(Duration::ZERO);
+
+        for _ in 0..3 {
+            RasterTileLayer::load_tile(index, provider.clone(), &tiles, None, retry_policy).await;
+        }
+
+        assert_eq!(provider.requests.load(Ordering::Relaxed), 1);
+        assert!(matches!(*tiles.get(&index).unwrap(), TileState::NotFound));
+    }
+}
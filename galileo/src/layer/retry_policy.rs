@@ -0,0 +1,253 @@
+use std::future::Future;
+use web_time::{Duration, SystemTime};
+
+/// Policy of repeating failed tile requests.
+///
+/// A failed request is repeated only if the error is retryable (e.g. a timeout or `503` HTTP
+/// status, see [`GalileoError::is_retryable`](crate::error::GalileoError::is_retryable)). The
+/// delay before each next attempt grows exponentially up to the [maximum
+/// backoff](Self::with_backoff) and is randomized by the [jitter](Self::with_jitter), so that the
+/// tiles failed together are not requested again at the same moment.
+///
+/// If all attempts fail, the tile is marked as failed and is not requested again until the
+/// [error cooldown](Self::with_error_cooldown) passes. Tiles that do not exist are never requested
+/// again.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    backoff_multiplier: f64,
+    jitter: f64,
+    error_cooldown: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            backoff_multiplier: 2.0,
+            jitter: 0.5,
+            error_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never repeats failed requests. Failed tiles are still requested again after
+    /// the default error cooldown.
+    pub fn no_retry() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the maximum number of attempts to load a tile, including the first one. At least one
+    /// attempt is always made.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the second attempt and the maximum delay between attempts.
+    pub fn with_backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff.max(initial_backoff);
+        self
+    }
+
+    /// Sets the factor the delay is multiplied by after each attempt. Values less than `1.0` and
+    /// `NaN` are replaced by `1.0`.
+    pub fn with_backoff_multiplier(mut self, backoff_multiplier: f64) -> Self {
+        self.backoff_multiplier = backoff_multiplier.max(1.0);
+        self
+    }
+
+    /// Sets the part of the delay that is randomized, from `0.0` (fixed delays) to `1.0` (delays
+    /// are anywhere between zero and the computed value). Values outside of this range are
+    /// clamped, `NaN` is replaced by `0.0`.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = if jitter.is_nan() {
+            0.0
+        } else {
+            jitter.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    /// Sets the time after which a failed tile can be requested again.
+    pub fn with_error_cooldown(mut self, error_cooldown: Duration) -> Self {
+        self.error_cooldown = error_cooldown;
+        self
+    }
+
+    /// Returns the delay after the given failed `attempt` (starting from 1) without jitter.
+    pub fn base_backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.backoff_multiplier.powi(exponent);
+        Duration::from_secs_f64(backoff.min(self.max_backoff.as_secs_f64()))
+    }
+
+    /// Returns the delay after the given failed `attempt` (starting from 1) with random jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff_with_random(attempt, random_unit())
+    }
+
+    fn backoff_with_random(&self, attempt: u32, random: f64) -> Duration {
+        self.base_backoff(attempt)
+            .mul_f64(1.0 - self.jitter + self.jitter * random.clamp(0.0, 1.0))
+    }
+
+    /// Returns true if the item that failed to load at `failed_at` can be requested again.
+    pub fn is_cooldown_over(&self, failed_at: SystemTime) -> bool {
+        SystemTime::now()
+            .duration_since(failed_at)
+            .is_ok_and(|elapsed| elapsed >= self.error_cooldown)
+    }
+
+    /// Runs `operation` until it succeeds, fails with an error for which `is_retryable` returns
+    /// false, or the maximum number of attempts is reached.
+    pub(crate) async fn run<T, E, Fut>(
+        &self,
+        mut operation: impl FnMut() -> Fut,
+        is_retryable: impl Fn(&E) -> bool,
+    ) -> Result<T, E>
+    where
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Err(err) if attempt < self.max_attempts && is_retryable(&err) => {
+                    crate::async_runtime::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Returns a random value in `[0.0, 1.0)`. Good enough to spread retries in time.
+fn random_unit() -> f64 {
+    let mut bytes = [0; 8];
+    if let Err(err) = getrandom::getrandom(&mut bytes) {
+        log::debug!("Failed to get random value for retry jitter: {err}");
+        return 0.5;
+    }
+
+    (u64::from_ne_bytes(bytes) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn backoff_grows_exponentially() {
+        let policy = RetryPolicy::default()
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(1000));
+
+        assert_eq!(policy.base_backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.base_backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.base_backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.base_backoff(5), Duration::from_millis(1000));
+        assert_eq!(policy.base_backoff(100), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn jitter_is_within_bounds() {
+        let policy = RetryPolicy::default()
+            .with_backoff(Duration::from_millis(100), Duration::from_secs(10))
+            .with_jitter(0.5);
+
+        assert_eq!(
+            policy.backoff_with_random(1, 0.0),
+            Duration::from_millis(50)
+        );
+        assert_eq!(
+            policy.backoff_with_random(1, 1.0),
+            Duration::from_millis(100)
+        );
+        for _ in 0..100 {
+            let backoff = policy.backoff(1);
+            assert!(backoff >= Duration::from_millis(50) && backoff <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn invalid_parameters_are_corrected() {
+        let policy = RetryPolicy::default()
+            .with_backoff(Duration::from_millis(100), Duration::from_secs(10))
+            .with_backoff_multiplier(f64::NAN);
+        for jitter in [f64::NAN, -1.0, f64::NEG_INFINITY] {
+            let policy = policy.with_jitter(jitter);
+            assert_eq!(policy.backoff(2), Duration::from_millis(100));
+        }
+
+        let policy = policy.with_jitter(f64::INFINITY);
+        assert_eq!(policy.backoff_with_random(2, 0.0), Duration::ZERO);
+        assert_eq!(policy.with_max_attempts(0).max_attempts, 1);
+    }
+
+    #[test]
+    fn checks_cooldown() {
+        let policy = RetryPolicy::default().with_error_cooldown(Duration::from_secs(10));
+
+        assert!(!policy.is_cooldown_over(SystemTime::now()));
+        assert!(policy.is_cooldown_over(SystemTime::now() - Duration::from_secs(11)));
+    }
+
+    #[tokio::test]
+    async fn retries_only_retryable_errors() {
+        let policy = RetryPolicy::default()
+            .with_max_attempts(3)
+            .with_backoff(Duration::from_millis(1), Duration::from_secs(10));
+
+        let attempts = Cell::new(0);
+        let result: Result<(), bool> = policy
+            .run(
+                || {
+                    attempts.set(attempts.get() + 1);
+                    std::future::ready(Err(true))
+                },
+                |retryable| *retryable,
+            )
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), 3);
+
+        attempts.set(0);
+        let result: Result<(), bool> = policy
+            .run(
+                || {
+                    attempts.set(attempts.get() + 1);
+                    std::future::ready(Err(false))
+                },
+                |retryable| *retryable,
+            )
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), 1);
+
+        attempts.set(0);
+        let result: Result<u32, bool> = policy
+            .run(
+                || {
+                    attempts.set(attempts.get() + 1);
+                    std::future::ready(if attempts.get() < 2 {
+                        Err(true)
+                    } else {
+                        Ok(attempts.get())
+                    })
+                },
+                |retryable| *retryable,
+            )
+            .await;
+        assert_eq!(result, Ok(2));
+    }
+}
@@ -1,6 +1,7 @@
 //! [Vector tile layers](VectorTileLayer) load prepared vector tiles using a [data provider](VectorTileProviderT)
 //! and draw them to the map with the given [`VectorTileStyle`].
 
+use bytes::Bytes;
 use maybe_sync::{MaybeSend, MaybeSync};
 use std::any::Any;
 use std::collections::HashSet;
@@ -13,18 +14,22 @@ use galileo_types::cartesian::CartesianPoint2d;
 use galileo_types::geometry::CartesianGeometry2d;
 pub use vector_tile::VectorTile;
 
+use crate::layer::data_provider::PersistentCacheController;
 use crate::layer::vector_tile_layer::style::VectorTileStyle;
-use crate::layer::vector_tile_layer::tile_provider::loader::VectorTileLoader;
+use crate::layer::vector_tile_layer::tile_provider::loader::{VectorTileLoader, WebVtLoader};
 use crate::layer::vector_tile_layer::tile_provider::processor::VectorTileProcessor;
 use crate::layer::vector_tile_layer::tile_provider::{VectorTileProvider, VtStyleId};
-use crate::layer::Layer;
+use crate::layer::{Layer, RetryPolicy};
 use crate::messenger::Messenger;
+use crate::platform::PlatformServiceImpl;
 use crate::render::{Canvas, PackedBundle, RenderOptions};
+use crate::tile_json::TileJson;
 use crate::tile_scheme::TileSchema;
 use crate::view::MapView;
 
 pub mod style;
 pub mod tile_provider;
+pub mod tiler;
 mod vector_tile;
 
 /// Vector tile layers use [`Providers`](VectorTileProviderT) to load prepared vector tiles, and then render them using
@@ -84,6 +89,11 @@ where
             .unwrap_or_default()
     }
 
+    /// Sets the policy of repeating failed tile requests.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.tile_provider.set_retry_policy(retry_policy);
+    }
+
     /// Creates a new layer with the given url source.
     pub async fn from_url(
         mut tile_provider: VectorTileProvider<Loader, Processor>,
@@ -202,3 +212,25 @@ where
         features
     }
 }
+
+impl<Cache, Processor> VectorTileLayer<WebVtLoader<Cache>, Processor>
+where
+    Cache: PersistentCacheController<str, Bytes> + MaybeSend + MaybeSync + 'static,
+    Processor: VectorTileProcessor + MaybeSend + MaybeSync + 'static,
+{
+    /// Creates a new layer loading tiles from the source described by the TileJSON document with
+    /// the given platform service.
+    ///
+    /// The processor should be created for the [tile schema](TileJson::tile_schema) of the document.
+    pub async fn from_tile_json(
+        tile_json: &TileJson,
+        platform_service: PlatformServiceImpl,
+        cache: Cache,
+        processor: Processor,
+        style: VectorTileStyle,
+    ) -> Self {
+        let loader = WebVtLoader::new(platform_service, cache, tile_json.url_source());
+        let tile_provider = VectorTileProvider::new(Arc::new(loader), Arc::new(processor));
+        Self::from_url(tile_provider, style, tile_json.tile_schema()).await
+    }
+}
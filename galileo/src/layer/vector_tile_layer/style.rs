@@ -1,10 +1,25 @@
 //! See [`VectorTileStyle`].
 
+use crate::error::GalileoError;
+use crate::layer::vector_tile_layer::style::filter::StyleFilter;
+use crate::layer::vector_tile_layer::style::value::StyleValue;
+use crate::platform::{PlatformService, PlatformServiceImpl};
+use crate::render::collision::CollisionOptions;
+use crate::render::line_label_paint::LineLabelPaint;
 use crate::render::point_paint::PointPaint;
+use crate::render::sprite::SpriteAtlas;
+use crate::render::text::TextStyle;
+use crate::render::{DashArray, LineCap, LineJoin, LinePaint, PatternFill};
 use crate::Color;
-use galileo_mvt::MvtFeature;
+use galileo_mvt::{MvtFeature, MvtValue};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+use strfmt::{strfmt_map, DisplayStr, FmtError, Formatter};
+
+pub mod filter;
+pub mod maplibre;
+pub mod value;
 
 /// Style of a vector tile layer. This specifies how each feature in a tile should be rendered.
 ///
@@ -13,33 +28,164 @@ use std::collections::HashMap;
 pub struct VectorTileStyle {
     /// Rules for feature to be drawn. Rules are traversed in sequence until a rule that corresponds to a current feature
     /// is found, and that rule is used for drawing. If no rule corresponds to the feature, default symbol is used.
+    ///
+    /// If the found rule is not visible at the current resolution, the feature is not drawn at all.
+    ///
+    /// If `rule_matching` is set to [`RuleMatching::All`], every rule that corresponds to the feature is used.
     pub rules: Vec<StyleRule>,
 
+    /// Specifies how many of the rules corresponding to a feature are used to draw it.
+    #[serde(default)]
+    pub rule_matching: RuleMatching,
+
     /// Default symbol that is used for features, for which other rules don't apply.
     pub default_symbol: VectorTileSymbol,
 
     /// Background color of tiles.
     pub background: Color,
+
+    /// Identifier of the sprite atlas the icons of [`VectorTileIconSymbol`]s are taken from.
+    ///
+    /// For a MapLibre sprite this is the URL of the sprite without the extension, and the atlas
+    /// can be loaded from it with [`VectorTileStyle::load_sprite`]. Otherwise, the atlas is set with
+    /// [`VectorTileStyle::set_sprite`].
+    #[serde(default)]
+    pub sprite: Option<String>,
+
+    /// The atlas itself is not serialized with the style, so that it is not copied every time the
+    /// style is sent to a tile processor. Processors that need it receive it separately.
+    #[serde(skip)]
+    pub(crate) sprite_atlas: Option<Arc<SpriteAtlas>>,
 }
 
 impl VectorTileStyle {
-    /// Get a rule for the given feature.
-    pub fn get_style_rule(&self, layer_name: &str, feature: &MvtFeature) -> Option<&StyleRule> {
-        self.rules.iter().find(|&rule| {
-            let layer_name_check_passed = match &rule.layer_name {
-                Some(name) => name == layer_name,
-                None => true,
-            };
-            layer_name_check_passed
-                && (rule.properties.is_empty()
-                    || rule.properties.iter().all(|(key, value)| {
-                        feature.properties.get(key).map(|v| v.to_string())
-                            == Some(value.to_string())
-                    }))
-        })
+    /// Sets the sprite atlas of the style. The `id` identifies the atlas in the tile processors,
+    /// so different atlases must have different ids.
+    pub fn set_sprite(&mut self, id: impl Into<String>, atlas: SpriteAtlas) {
+        self.sprite = Some(id.into());
+        self.sprite_atlas = Some(Arc::new(atlas));
+    }
+
+    /// Returns the sprite atlas of the style if it was set or loaded.
+    pub fn sprite_atlas(&self) -> Option<&SpriteAtlas> {
+        self.sprite_atlas.as_deref()
+    }
+
+    /// Loads the MapLibre sprite referenced by [`VectorTileStyle::sprite`]: the `{sprite}.json`
+    /// index and the `{sprite}.png` image. Does nothing if the style has no sprite reference.
+    pub async fn load_sprite(
+        &mut self,
+        platform_service: &PlatformServiceImpl,
+    ) -> Result<(), GalileoError> {
+        let Some(url) = &self.sprite else {
+            return Ok(());
+        };
+
+        let json = platform_service
+            .load_bytes_from_url(&format!("{url}.json"))
+            .await?;
+        let json = std::str::from_utf8(&json)
+            .map_err(|err| GalileoError::Generic(format!("invalid sprite index: {err}")))?;
+        let image = platform_service
+            .load_image_url(&format!("{url}.png"))
+            .await?;
+
+        self.sprite_atlas = Some(Arc::new(SpriteAtlas::from_json(json, image)?));
+        Ok(())
+    }
+
+    /// Converts a [MapLibre style](https://maplibre.org/maplibre-style-spec/) JSON document into
+    /// a vector tile style.
+    ///
+    /// Parts of the style that cannot be represented are returned as
+    /// [warnings](maplibre::ConversionWarning) along with the converted style. See
+    /// [`maplibre::convert`] for details.
+    pub fn from_maplibre_json(
+        json: &str,
+    ) -> Result<maplibre::MaplibreStyleConversion, GalileoError> {
+        maplibre::convert(json)
+    }
+
+    /// Get a rule for the given feature at the given map resolution.
+    ///
+    /// Only the first rule matching the feature is considered. If that rule is not visible at the
+    /// given resolution, `None` is returned, even if some of the following rules are visible.
+    pub fn get_style_rule(
+        &self,
+        layer_name: &str,
+        feature: &MvtFeature,
+        resolution: f64,
+    ) -> Option<&StyleRule> {
+        self.first_matching_rule(layer_name, feature)
+            .filter(|rule| rule.is_visible_at(resolution))
+    }
+
+    /// Returns the rules to draw the feature with at the given resolution, according to the
+    /// `rule_matching` of the style.
+    ///
+    /// Returns `None` if no rule matches the feature, so the default symbol must be used. If some
+    /// rules match the feature but none of them are visible at the resolution, the returned list is
+    /// empty and the feature must not be drawn.
+    pub(crate) fn rules_for_feature<'a>(
+        &'a self,
+        layer_name: &'a str,
+        feature: &'a MvtFeature,
+        resolution: f64,
+    ) -> Option<Vec<&'a StyleRule>> {
+        match self.rule_matching {
+            RuleMatching::First => {
+                let rule = self.first_matching_rule(layer_name, feature)?;
+                Some(
+                    Some(rule)
+                        .filter(|rule| rule.is_visible_at(resolution))
+                        .into_iter()
+                        .collect(),
+                )
+            }
+            RuleMatching::All => {
+                let mut has_match = false;
+                let rules = self
+                    .rules
+                    .iter()
+                    .filter(|rule| rule.matches(layer_name, feature))
+                    .inspect(|_| has_match = true)
+                    .filter(|rule| rule.is_visible_at(resolution))
+                    .collect();
+
+                has_match.then_some(rules)
+            }
+        }
+    }
+
+    fn first_matching_rule(&self, layer_name: &str, feature: &MvtFeature) -> Option<&StyleRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(layer_name, feature))
+    }
+
+    /// Returns false if none of the features of the given layer can be drawn at the given
+    /// resolution, so the layer can be skipped altogether.
+    pub(crate) fn is_layer_visible(&self, layer_name: &str, resolution: f64) -> bool {
+        !self.default_symbol.is_empty()
+            || self.rules.iter().any(|rule| {
+                rule.applies_to_layer(layer_name)
+                    && rule.is_visible_at(resolution)
+                    && !rule.symbol.is_empty()
+            })
     }
 }
 
+/// Specifies how many of the [`StyleRule`]s corresponding to a feature are used to draw it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleMatching {
+    /// Only the first corresponding rule is used.
+    #[default]
+    First,
+    /// Every corresponding rule adds its own primitives. This allows, for example, drawing a road
+    /// as a wide dark casing under a narrow bright line.
+    All,
+}
+
 /// A rule that specifies what kind of features can be drawing with the given symbol.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct StyleRule {
@@ -48,28 +194,98 @@ pub struct StyleRule {
     /// Specifies a set of attibutes of a feature that must have the given values for this rule to be applied.
     #[serde(default)]
     pub properties: HashMap<String, String>,
+    /// If set, a feature must pass this filter for this rule to be applied. This is checked in
+    /// addition to `properties`.
+    #[serde(default)]
+    pub filter: Option<StyleFilter>,
+    /// If set, the rule is applied only when the map resolution is larger than this value.
+    #[serde(default)]
+    pub min_resolution: Option<f64>,
+    /// If set, the rule is applied only when the map resolution is less than or equal to this
+    /// value.
+    #[serde(default)]
+    pub max_resolution: Option<f64>,
+    /// Draw order of the primitives created by this rule. Primitives with smaller keys are drawn
+    /// first (below the others) regardless of the tile layer the feature belongs to. Primitives
+    /// with equal keys are drawn in the order of features in the tile. Features drawn with the
+    /// default symbol have the key `0`.
+    #[serde(default)]
+    pub sort_key: i32,
     /// Symbol to draw a feature with.
     pub symbol: VectorTileSymbol,
 }
 
+impl StyleRule {
+    /// Returns true if the layer, properties and filter of the rule match the feature, regardless
+    /// of the map resolution.
+    fn matches(&self, layer_name: &str, feature: &MvtFeature) -> bool {
+        self.applies_to_layer(layer_name)
+            && (self.properties.is_empty()
+                || self.properties.iter().all(|(key, value)| {
+                    feature.properties.get(key).map(|v| v.to_string()) == Some(value.to_string())
+                }))
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(&feature.properties))
+    }
+
+    fn applies_to_layer(&self, layer_name: &str) -> bool {
+        match &self.layer_name {
+            Some(name) => name == layer_name,
+            None => true,
+        }
+    }
+
+    /// Returns true if the rule is applied at the given map resolution.
+    pub fn is_visible_at(&self, resolution: f64) -> bool {
+        self.min_resolution.is_none_or(|min| resolution > min)
+            && self.max_resolution.is_none_or(|max| resolution <= max)
+    }
+}
+
 /// Symbol to draw a vector tile feature.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct VectorTileSymbol {
     /// If set, points will be drawn with this symbol.
     pub point: Option<PointPaint<'static>>,
+    /// Scale of the point symbol. `1.0` draws the point with the size set in `point` symbol.
+    #[serde(default)]
+    pub point_scale: Option<StyleValue<f64>>,
+    /// Opacity of the point symbol in the range `[0.0, 1.0]`.
+    #[serde(default)]
+    pub point_opacity: Option<StyleValue<f64>>,
     /// If set, lines will be drawn with this symbol.
     pub line: Option<VectorTileLineSymbol>,
     /// If set, polygons will be drawn with this symbol.
     pub polygon: Option<VectorTilePolygonSymbol>,
+    /// If set, points and polygons will be labeled with this symbol.
+    #[serde(default)]
+    pub label: Option<VectorTileLabelSymbol>,
+    /// If set, points and polygons will be marked with an icon from the sprite atlas of the style.
+    #[serde(default)]
+    pub icon: Option<VectorTileIconSymbol>,
 }
 
 impl VectorTileSymbol {
+    /// Returns true if the symbol does not draw any geometries.
+    pub fn is_empty(&self) -> bool {
+        self.point.is_none()
+            && self.line.is_none()
+            && self.polygon.is_none()
+            && self.label.is_none()
+            && self.icon.is_none()
+    }
+
     /// Creates a new symbol for polygon geometries.
     pub fn polygon(color: Color) -> Self {
         Self {
-            point: None,
-            line: None,
-            polygon: Some(VectorTilePolygonSymbol { fill_color: color }),
+            polygon: Some(VectorTilePolygonSymbol {
+                fill_color: color.into(),
+                opacity: default_opacity(),
+                pattern: None,
+            }),
+            ..Default::default()
         }
     }
 }
@@ -87,14 +303,239 @@ pub struct VectorTilePointSymbol {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VectorTileLineSymbol {
     /// Width of the line in pixels.
-    pub width: f64,
+    pub width: StyleValue<f64>,
     /// Color of the line in pixels.
-    pub stroke_color: Color,
+    pub stroke_color: StyleValue<Color>,
+    /// Opacity of the line in the range `[0.0, 1.0]`.
+    #[serde(default = "default_opacity")]
+    pub opacity: StyleValue<f64>,
+    /// If set, the line is drawn dashed with the given pattern, e.g. `[4, 2]`.
+    #[serde(default)]
+    pub dash_array: Option<DashArray>,
+    /// Type of the caps of the line.
+    #[serde(default = "default_line_cap")]
+    pub line_cap: LineCap,
+    /// Type of the joins between the segments of the line.
+    #[serde(default)]
+    pub line_join: LineJoin,
+    /// Miter limit for [`LineJoin::Miter`] joins. See [`LinePaint::miter_limit`].
+    #[serde(default = "default_miter_limit")]
+    pub miter_limit: f64,
 }
 
 /// Symbol for polygon geometries.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VectorTilePolygonSymbol {
     /// Color of the fill of polygon.
-    pub fill_color: Color,
+    pub fill_color: StyleValue<Color>,
+    /// Opacity of the fill in the range `[0.0, 1.0]`.
+    #[serde(default = "default_opacity")]
+    pub opacity: StyleValue<f64>,
+    /// If set, the polygon is filled with the repeated image. Colors of the image are multiplied by
+    /// the `fill_color`, so use white color to draw the image as is.
+    #[serde(default)]
+    pub pattern: Option<PatternFill>,
+}
+
+/// Symbol for text labels of point, line and polygon geometries.
+///
+/// Points are labeled at their position. A polygon feature is labeled once, at a point inside its
+/// largest polygon. Lines are labeled with text following the line, repeated every `spacing`
+/// pixels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorTileLabelSymbol {
+    /// Format template of the label text. Names of the feature properties in braces are replaced
+    /// by the property values, e.g. `"{name} ({ele} m)"`. If the feature does not have any of the
+    /// properties used in the template, the label is not drawn.
+    ///
+    /// `:` in braces starts the format specification of the value, so a property with `:` in its
+    /// name is written with `.` instead, e.g. `"{name.latin}"` for the `name:latin` property.
+    pub pattern: String,
+    /// Style of the label text.
+    pub text_style: TextStyle,
+    /// Parameters of collision detection of the label with other labels and icons on the map.
+    #[serde(default)]
+    pub collision: CollisionOptions,
+    /// Distance in pixels between repeated labels of a line.
+    #[serde(default = "default_label_spacing")]
+    pub spacing: f32,
+    /// Maximum angle in degrees between two adjacent glyphs of a line label. Labels are not placed
+    /// on the parts of a line that turn sharper than this.
+    #[serde(default = "default_label_max_angle")]
+    pub max_angle: f32,
+    /// Offset of point and polygon labels from the feature position in pixels. Positive `y`
+    /// values move the label towards the top of the screen.
+    #[serde(default)]
+    pub offset: [f32; 2],
+    /// Position of the anchor point of point and polygon labels as a portion of the text size,
+    /// the same way as [`VectorTileIconSymbol::anchor`]. If not set, the text starts at the
+    /// feature position on its baseline.
+    #[serde(default)]
+    pub anchor: Option<[f32; 2]>,
+}
+
+/// Symbol for icons of point and polygon geometries.
+///
+/// Icons are taken from the [sprite atlas](VectorTileStyle::sprite_atlas) of the style. Points are
+/// marked at their position, polygons at the same point as their label.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorTileIconSymbol {
+    /// Format template of the icon name, e.g. `"{class}_11"`. Names of the feature properties in
+    /// braces are replaced by the property values the same way as in
+    /// [`VectorTileLabelSymbol::pattern`]. If the feature does not have any of the properties used
+    /// in the template, or the atlas does not contain the icon, the icon is not drawn.
+    pub name: String,
+    /// Scale of the icon. `1.0` draws the icon with its size in the atlas.
+    #[serde(default = "default_icon_scale")]
+    pub scale: f32,
+    /// Position of the anchor point of the icon as a portion of the icon size, e.g. `[0.5, 1.0]`
+    /// places the center-bottom point of the icon at the feature position.
+    #[serde(default = "default_icon_anchor")]
+    pub anchor: [f32; 2],
+    /// Parameters of collision detection of the icon with other labels and icons on the map.
+    #[serde(default)]
+    pub collision: CollisionOptions,
+}
+
+fn default_icon_scale() -> f32 {
+    1.0
+}
+
+fn default_icon_anchor() -> [f32; 2] {
+    [0.5, 0.5]
+}
+
+/// Character that replaces `:` in the property names used in templates.
+pub(crate) const TEMPLATE_COLON_REPLACEMENT: char = '.';
+
+/// Formats a label or icon name template with the properties of a feature. Returns `None` if the
+/// feature does not have any of the properties used in the template.
+pub(crate) fn format_template(
+    template: &str,
+    properties: &HashMap<String, MvtValue>,
+) -> Option<String> {
+    strfmt_map(template, |mut formatter: Formatter| {
+        let key = formatter.key;
+        let value = properties
+            .get(key)
+            .or_else(|| properties.get(&key.replace(TEMPLATE_COLON_REPLACEMENT, ":")));
+        match value {
+            Some(value) => value.display_str(&mut formatter),
+            None => Err(FmtError::KeyError(key.to_string())),
+        }
+    })
+    .ok()
+}
+
+fn default_label_spacing() -> f32 {
+    LineLabelPaint::DEFAULT_SPACING
+}
+
+fn default_label_max_angle() -> f32 {
+    LineLabelPaint::DEFAULT_MAX_ANGLE
+}
+
+fn default_opacity() -> StyleValue<f64> {
+    StyleValue::Constant(1.0)
+}
+
+fn default_line_cap() -> LineCap {
+    LineCap::Butt
+}
+
+fn default_miter_limit() -> f64 {
+    LinePaint::DEFAULT_MITER_LIMIT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_mvt::MvtGeometry;
+
+    fn building_rule(min_resolution: Option<f64>, max_resolution: Option<f64>) -> StyleRule {
+        StyleRule {
+            layer_name: Some("building".into()),
+            min_resolution,
+            max_resolution,
+            symbol: VectorTileSymbol::polygon(Color::RED),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_outside_of_resolution_range_hides_feature() {
+        let style = VectorTileStyle {
+            rules: vec![
+                building_rule(Some(1.0), Some(10.0)),
+                building_rule(None, None),
+            ],
+            ..Default::default()
+        };
+        let feature = MvtFeature {
+            id: None,
+            properties: HashMap::new(),
+            geometry: MvtGeometry::Point(vec![]),
+        };
+
+        let rule_index = |resolution| {
+            let rule = style.get_style_rule("building", &feature, resolution)?;
+            style.rules.iter().position(|r| std::ptr::eq(r, rule))
+        };
+
+        assert_eq!(rule_index(10.0), Some(0));
+        assert_eq!(rule_index(2.0), Some(0));
+        assert_eq!(rule_index(1.0), None);
+        assert_eq!(rule_index(20.0), None);
+
+        let rule_count = |layer_name, resolution| {
+            style
+                .rules_for_feature(layer_name, &feature, resolution)
+                .map(|rules| rules.len())
+        };
+        assert_eq!(rule_count("building", 2.0), Some(1));
+        assert_eq!(rule_count("building", 20.0), Some(0));
+        assert_eq!(rule_count("water", 2.0), None);
+    }
+
+    #[test]
+    fn all_corresponding_rules_are_returned() {
+        let style = VectorTileStyle {
+            rules: vec![
+                building_rule(None, None),
+                StyleRule {
+                    layer_name: Some("water".into()),
+                    ..Default::default()
+                },
+                building_rule(None, Some(10.0)),
+            ],
+            rule_matching: RuleMatching::All,
+            ..Default::default()
+        };
+        let feature = MvtFeature {
+            id: None,
+            properties: HashMap::new(),
+            geometry: MvtGeometry::Point(vec![]),
+        };
+
+        let rule_count = |resolution| {
+            style
+                .rules_for_feature("building", &feature, resolution)
+                .map(|rules| rules.len())
+        };
+        assert_eq!(rule_count(5.0), Some(2));
+        assert_eq!(rule_count(20.0), Some(1));
+        assert!(style.rules_for_feature("road", &feature, 5.0).is_none());
+    }
+
+    #[test]
+    fn layer_visibility() {
+        let style = VectorTileStyle {
+            rules: vec![building_rule(None, Some(10.0))],
+            ..Default::default()
+        };
+
+        assert!(style.is_layer_visible("building", 5.0));
+        assert!(!style.is_layer_visible("building", 20.0));
+        assert!(!style.is_layer_visible("water", 5.0));
+    }
 }
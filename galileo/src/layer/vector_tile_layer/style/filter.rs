@@ -0,0 +1,307 @@
+//! Filter expressions used to select features for a [`StyleRule`](super::StyleRule).
+
+use galileo_mvt::MvtValue;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Expression that checks properties of a vector tile feature.
+///
+/// In JSON, the filter is an object with the `op` field specifying the kind of the expression:
+///
+/// ```json
+/// {
+///     "op": "all",
+///     "filters": [
+///         { "op": "in", "key": "class", "values": ["primary", "secondary"] },
+///         { "op": "gt", "key": "population", "value": 100000 },
+///         { "op": "not_has", "key": "name" }
+///     ]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum StyleFilter {
+    /// Property `key` is equal to the `value`.
+    Eq {
+        /// Property name.
+        key: String,
+        /// Value to compare with.
+        value: FilterValue,
+    },
+    /// Property `key` is not equal to the `value` or is not set.
+    Ne {
+        /// Property name.
+        key: String,
+        /// Value to compare with.
+        value: FilterValue,
+    },
+    /// Property `key` is less than the `value`.
+    Lt {
+        /// Property name.
+        key: String,
+        /// Value to compare with.
+        value: FilterValue,
+    },
+    /// Property `key` is less than or equal to the `value`.
+    Le {
+        /// Property name.
+        key: String,
+        /// Value to compare with.
+        value: FilterValue,
+    },
+    /// Property `key` is greater than the `value`.
+    Gt {
+        /// Property name.
+        key: String,
+        /// Value to compare with.
+        value: FilterValue,
+    },
+    /// Property `key` is greater than or equal to the `value`.
+    Ge {
+        /// Property name.
+        key: String,
+        /// Value to compare with.
+        value: FilterValue,
+    },
+    /// Property `key` is equal to one of the `values`.
+    In {
+        /// Property name.
+        key: String,
+        /// Values to compare with.
+        values: Vec<FilterValue>,
+    },
+    /// Property `key` is not equal to any of the `values` or is not set.
+    NotIn {
+        /// Property name.
+        key: String,
+        /// Values to compare with.
+        values: Vec<FilterValue>,
+    },
+    /// Feature has property `key`.
+    Has {
+        /// Property name.
+        key: String,
+    },
+    /// Feature does not have property `key`.
+    NotHas {
+        /// Property name.
+        key: String,
+    },
+    /// All of the `filters` match. Empty list always matches.
+    All {
+        /// Nested filters.
+        filters: Vec<StyleFilter>,
+    },
+    /// At least one of the `filters` matches. Empty list never matches.
+    Any {
+        /// Nested filters.
+        filters: Vec<StyleFilter>,
+    },
+    /// None of the `filters` match. Empty list always matches.
+    None {
+        /// Nested filters.
+        filters: Vec<StyleFilter>,
+    },
+}
+
+/// Literal value used in a [`StyleFilter`].
+///
+/// Numbers are compared with any numeric [`MvtValue`] regardless of its type, strings and
+/// booleans only with values of the same type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FilterValue {
+    /// Boolean value.
+    Bool(bool),
+    /// Numeric value.
+    Number(f64),
+    /// String value.
+    String(String),
+}
+
+impl From<bool> for FilterValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f64> for FilterValue {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<&str> for FilterValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for FilterValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl FilterValue {
+    fn compare(&self, value: &MvtValue) -> Option<Ordering> {
+        match self {
+            FilterValue::Number(expected) => value.as_f64()?.partial_cmp(expected),
+            FilterValue::String(expected) => Some(value.as_str()?.cmp(expected)),
+            FilterValue::Bool(expected) => Some(value.as_bool()?.cmp(expected)),
+        }
+    }
+}
+
+impl StyleFilter {
+    /// Returns true if a feature with the given properties passes the filter.
+    pub fn matches(&self, properties: &HashMap<String, MvtValue>) -> bool {
+        let compare =
+            |key: &str, value: &FilterValue| properties.get(key).and_then(|v| value.compare(v));
+        let is_equal =
+            |key: &str, value: &FilterValue| compare(key, value) == Some(Ordering::Equal);
+
+        match self {
+            StyleFilter::Eq { key, value } => is_equal(key, value),
+            StyleFilter::Ne { key, value } => !is_equal(key, value),
+            StyleFilter::Lt { key, value } => compare(key, value) == Some(Ordering::Less),
+            StyleFilter::Le { key, value } => {
+                matches!(compare(key, value), Some(Ordering::Less | Ordering::Equal))
+            }
+            StyleFilter::Gt { key, value } => compare(key, value) == Some(Ordering::Greater),
+            StyleFilter::Ge { key, value } => matches!(
+                compare(key, value),
+                Some(Ordering::Greater | Ordering::Equal)
+            ),
+            StyleFilter::In { key, values } => values.iter().any(|value| is_equal(key, value)),
+            StyleFilter::NotIn { key, values } => !values.iter().any(|value| is_equal(key, value)),
+            StyleFilter::Has { key } => properties.contains_key(key),
+            StyleFilter::NotHas { key } => !properties.contains_key(key),
+            StyleFilter::All { filters } => filters.iter().all(|f| f.matches(properties)),
+            StyleFilter::Any { filters } => filters.iter().any(|f| f.matches(properties)),
+            StyleFilter::None { filters } => !filters.iter().any(|f| f.matches(properties)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn properties() -> HashMap<String, MvtValue> {
+        HashMap::from([
+            ("class".to_string(), MvtValue::String("primary".into())),
+            ("population".to_string(), MvtValue::Int64(250_000)),
+            ("area".to_string(), MvtValue::Float(12.5)),
+            ("oneway".to_string(), MvtValue::Bool(true)),
+        ])
+    }
+
+    #[test]
+    fn numeric_comparisons() {
+        let properties = properties();
+        let gt = |key: &str, value: f64| StyleFilter::Gt {
+            key: key.into(),
+            value: value.into(),
+        };
+
+        assert!(gt("population", 100_000.0).matches(&properties));
+        assert!(!gt("population", 250_000.0).matches(&properties));
+        assert!(!gt("class", 0.0).matches(&properties));
+        assert!(!gt("missing", 0.0).matches(&properties));
+        assert!(StyleFilter::Le {
+            key: "population".into(),
+            value: 250_000.0.into()
+        }
+        .matches(&properties));
+        assert!(StyleFilter::Eq {
+            key: "area".into(),
+            value: 12.5.into()
+        }
+        .matches(&properties));
+    }
+
+    #[test]
+    fn set_and_presence_checks() {
+        let properties = properties();
+
+        let class_in = |values: &[&str]| StyleFilter::In {
+            key: "class".into(),
+            values: values.iter().map(|&v| v.into()).collect(),
+        };
+        assert!(class_in(&["primary", "secondary"]).matches(&properties));
+        assert!(!class_in(&["tertiary"]).matches(&properties));
+
+        assert!(StyleFilter::Has {
+            key: "oneway".into()
+        }
+        .matches(&properties));
+        assert!(StyleFilter::NotHas { key: "name".into() }.matches(&properties));
+        assert!(StyleFilter::Ne {
+            key: "name".into(),
+            value: "Main street".into()
+        }
+        .matches(&properties));
+        assert!(StyleFilter::Eq {
+            key: "oneway".into(),
+            value: true.into()
+        }
+        .matches(&properties));
+    }
+
+    #[test]
+    fn combinators() {
+        let properties = properties();
+        let matching = StyleFilter::Has {
+            key: "class".into(),
+        };
+        let not_matching = StyleFilter::Has { key: "name".into() };
+
+        let all = StyleFilter::All {
+            filters: vec![matching.clone(), not_matching.clone()],
+        };
+        let any = StyleFilter::Any {
+            filters: vec![matching.clone(), not_matching.clone()],
+        };
+        let none = StyleFilter::None {
+            filters: vec![not_matching],
+        };
+
+        assert!(!all.matches(&properties));
+        assert!(any.matches(&properties));
+        assert!(none.matches(&properties));
+        assert!(StyleFilter::All { filters: vec![] }.matches(&properties));
+        assert!(!StyleFilter::Any { filters: vec![] }.matches(&properties));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let json = r#"{
+            "op": "all",
+            "filters": [
+                {"op": "in", "key": "class", "values": ["primary", "secondary"]},
+                {"op": "gt", "key": "population", "value": 100000},
+                {"op": "not_has", "key": "name"}
+            ]
+        }"#;
+
+        let filter: StyleFilter = serde_json::from_str(json).unwrap();
+        assert!(filter.matches(&properties()));
+
+        let StyleFilter::All { filters } = &filter else {
+            panic!("expected all");
+        };
+        assert_eq!(
+            filters[1],
+            StyleFilter::Gt {
+                key: "population".into(),
+                value: FilterValue::Number(100_000.0)
+            }
+        );
+
+        let serialized = serde_json::to_string(&filter).unwrap();
+        let deserialized: StyleFilter = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(filter, deserialized);
+    }
+}
@@ -0,0 +1,1342 @@
+//! Conversion of [MapLibre style specification](https://maplibre.org/maplibre-style-spec/) documents
+//! (also used by Mapbox GL and Maputnik) into [`VectorTileStyle`].
+//!
+//! Only a subset of the specification can be represented by [`VectorTileStyle`]. Everything the
+//! converter cannot express is reported back as a [`ConversionWarning`] instead of being silently
+//! dropped.
+
+use crate::error::GalileoError;
+use crate::layer::vector_tile_layer::style::filter::{FilterValue, StyleFilter};
+use crate::layer::vector_tile_layer::style::value::{Interpolation, Stop, Stops, StyleValue};
+use crate::layer::vector_tile_layer::style::{
+    RuleMatching, StyleRule, VectorTileIconSymbol, VectorTileLabelSymbol, VectorTileLineSymbol,
+    VectorTilePolygonSymbol, VectorTileStyle, VectorTileSymbol, TEMPLATE_COLON_REPLACEMENT,
+};
+use crate::render::collision::CollisionOptions;
+use crate::render::point_paint::PointPaint;
+use crate::render::text::TextStyle;
+use crate::render::{DashArray, LineCap, LineJoin};
+use crate::Color;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+const DEFAULT_FONT: &str = "Open Sans Regular";
+const DEFAULT_TEXT_SIZE: f64 = 16.0;
+const DEFAULT_TEXT_PADDING: f64 = 2.0;
+const DEFAULT_ICON_PADDING: f64 = 2.0;
+const DEFAULT_SYMBOL_SPACING: f64 = 250.0;
+const DEFAULT_TEXT_MAX_ANGLE: f64 = 45.0;
+const DEFAULT_CIRCLE_RADIUS: f64 = 5.0;
+const DEFAULT_MITER_LIMIT: f64 = 2.0;
+
+/// Values of `icon-anchor` and `text-anchor` as the position of the anchor point in the portions
+/// of the symbol size.
+const ANCHORS: &[(&str, [f32; 2])] = &[
+    ("center", [0.5, 0.5]),
+    ("top", [0.5, 0.0]),
+    ("bottom", [0.5, 1.0]),
+    ("left", [0.0, 0.5]),
+    ("right", [1.0, 0.5]),
+    ("top-left", [0.0, 0.0]),
+    ("top-right", [1.0, 0.0]),
+    ("bottom-left", [0.0, 1.0]),
+    ("bottom-right", [1.0, 1.0]),
+];
+
+/// Resolution (in meters per pixel) at zoom level 0. MapLibre zoom levels are defined for
+/// 512 pixel wide Web Mercator tiles.
+const ZOOM_0_RESOLUTION: f64 = 78271.51696402048;
+
+/// Result of converting a MapLibre style into a [`VectorTileStyle`].
+#[derive(Debug, Clone)]
+pub struct MaplibreStyleConversion {
+    /// Converted style.
+    pub style: VectorTileStyle,
+    /// Parts of the source style that could not be represented in the converted style.
+    pub warnings: Vec<ConversionWarning>,
+}
+
+/// Part of a MapLibre style that could not be converted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionWarning {
+    /// Id of the MapLibre layer the warning relates to. Empty for the properties of the style
+    /// itself, e.g. `sprite`.
+    pub layer_id: String,
+    /// What exactly could not be converted.
+    pub kind: ConversionWarningKind,
+}
+
+/// Kind of [`ConversionWarning`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionWarningKind {
+    /// The layer type (e.g. `raster` or `fill-extrusion`) is not supported. The layer is skipped.
+    UnsupportedLayerType(String),
+    /// The layer does not reference a `source-layer` of a vector source. The layer is skipped.
+    MissingSourceLayer,
+    /// Paint or layout property is not supported. The property is ignored.
+    UnsupportedProperty(String),
+    /// Value of the property cannot be converted (e.g. it is an expression). The default value
+    /// of the property is used instead.
+    UnsupportedValue {
+        /// Name of the property.
+        property: String,
+        /// JSON representation of the value.
+        value: String,
+    },
+    /// The filter of the layer cannot be expressed. The layer is skipped.
+    UnsupportedFilter(String),
+}
+
+/// Converts a MapLibre style JSON document into a [`VectorTileStyle`].
+///
+/// MapLibre draws every layer a feature belongs to, bottom to top. So the resulting style uses
+/// [`RuleMatching::All`], and the `sort_key` of each rule is the index of its source layer.
+pub fn convert(json: &str) -> Result<MaplibreStyleConversion, GalileoError> {
+    let document: StyleDocument = serde_json::from_str(json)
+        .map_err(|err| GalileoError::Generic(format!("invalid MapLibre style: {err}")))?;
+
+    let mut style = VectorTileStyle {
+        rule_matching: RuleMatching::All,
+        ..Default::default()
+    };
+    let mut warnings = vec![];
+
+    // Only a single sprite given by its URL is supported.
+    match &document.sprite {
+        None => {}
+        Some(Value::String(url)) => style.sprite = Some(url.clone()),
+        Some(other) => warnings.push(ConversionWarning {
+            layer_id: String::new(),
+            kind: ConversionWarningKind::UnsupportedValue {
+                property: "sprite".into(),
+                value: other.to_string(),
+            },
+        }),
+    }
+
+    for (index, layer) in document.layers.iter().enumerate() {
+        let mut converter = LayerConverter::new(layer, &mut warnings);
+        match converter.convert() {
+            Some(ConvertedLayer::Background(color)) => style.background = color,
+            Some(ConvertedLayer::Rule(rule)) => style.rules.push(StyleRule {
+                sort_key: index as i32,
+                ..*rule
+            }),
+            None => {}
+        }
+        converter.report_unused_properties();
+    }
+
+    Ok(MaplibreStyleConversion { style, warnings })
+}
+
+#[derive(Debug, Deserialize)]
+struct StyleDocument {
+    #[serde(default)]
+    layers: Vec<LayerDocument>,
+    sprite: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct LayerDocument {
+    id: String,
+    #[serde(rename = "type")]
+    layer_type: String,
+    source_layer: Option<String>,
+    minzoom: Option<f64>,
+    maxzoom: Option<f64>,
+    filter: Option<Value>,
+    #[serde(default)]
+    paint: Map<String, Value>,
+    #[serde(default)]
+    layout: Map<String, Value>,
+}
+
+enum ConvertedLayer {
+    Background(Color),
+    Rule(Box<StyleRule>),
+}
+
+struct LayerConverter<'a> {
+    layer: &'a LayerDocument,
+    warnings: &'a mut Vec<ConversionWarning>,
+    used_properties: HashSet<&'static str>,
+    skipped: bool,
+}
+
+impl<'a> LayerConverter<'a> {
+    fn new(layer: &'a LayerDocument, warnings: &'a mut Vec<ConversionWarning>) -> Self {
+        Self {
+            layer,
+            warnings,
+            used_properties: HashSet::new(),
+            skipped: false,
+        }
+    }
+
+    fn convert(&mut self) -> Option<ConvertedLayer> {
+        if self.layout_value("visibility") == Some(&Value::String("none".into())) {
+            self.skipped = true;
+            return None;
+        }
+
+        if self.layer.layer_type == "background" {
+            let color = self.paint_color("background-color", Color::BLACK);
+            let opacity = self.paint_number("background-opacity", 1.0);
+            return Some(ConvertedLayer::Background(color.with_opacity(opacity)));
+        }
+
+        let symbol = match self.layer.layer_type.as_str() {
+            "fill" => self.fill_symbol(),
+            "line" => self.line_symbol(),
+            "circle" => self.circle_symbol(),
+            "symbol" => self.symbol_symbol()?,
+            other => {
+                self.skip(ConversionWarningKind::UnsupportedLayerType(
+                    other.to_string(),
+                ));
+                return None;
+            }
+        };
+
+        let Some(source_layer) = &self.layer.source_layer else {
+            self.skip(ConversionWarningKind::MissingSourceLayer);
+            return None;
+        };
+
+        let geometry_types = drawn_geometry_types(&self.layer.layer_type);
+        let filter = match &self.layer.filter {
+            None => None,
+            Some(filter) => match convert_filter(filter, geometry_types) {
+                Some(converted) if is_always_true(&converted) => None,
+                Some(converted) => Some(converted),
+                None => {
+                    self.skip(ConversionWarningKind::UnsupportedFilter(filter.to_string()));
+                    return None;
+                }
+            },
+        };
+
+        Some(ConvertedLayer::Rule(Box::new(StyleRule {
+            layer_name: Some(source_layer.clone()),
+            properties: HashMap::new(),
+            filter,
+            min_resolution: self.layer.maxzoom.map(zoom_to_resolution),
+            max_resolution: self.layer.minzoom.map(zoom_to_resolution),
+            sort_key: 0,
+            symbol,
+        })))
+    }
+
+    fn fill_symbol(&mut self) -> VectorTileSymbol {
+        let color = self.paint_color_value("fill-color", Color::BLACK);
+        let opacity = self.paint_number_value("fill-opacity", 1.0);
+
+        VectorTileSymbol {
+            polygon: Some(VectorTilePolygonSymbol {
+                fill_color: color,
+                opacity,
+                pattern: None,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn line_symbol(&mut self) -> VectorTileSymbol {
+        let color = self.paint_color_value("line-color", Color::BLACK);
+        let opacity = self.paint_number_value("line-opacity", 1.0);
+        let width = self.paint_number_value("line-width", 1.0);
+        // Dashes are most noticeable at the closest zoom levels, so their length is calculated for
+        // the width at the last stop.
+        let dash_width = match &width {
+            StyleValue::Constant(width) => *width,
+            StyleValue::Stops(stops) => stops.stops.last().map_or(1.0, |stop| stop.value),
+        };
+        let dash_array = self.paint_dash_array("line-dasharray", dash_width);
+        let line_cap = self.layout_enum(
+            "line-cap",
+            &[
+                ("butt", LineCap::Butt),
+                ("round", LineCap::Round),
+                ("square", LineCap::Square),
+            ],
+            LineCap::Butt,
+        );
+        let line_join = self.layout_enum(
+            "line-join",
+            &[
+                ("miter", LineJoin::Miter),
+                ("bevel", LineJoin::Bevel),
+                ("round", LineJoin::Round),
+            ],
+            LineJoin::Miter,
+        );
+        let miter_limit = self.layout_number("line-miter-limit", DEFAULT_MITER_LIMIT);
+
+        VectorTileSymbol {
+            line: Some(VectorTileLineSymbol {
+                width,
+                stroke_color: color,
+                opacity,
+                dash_array,
+                line_cap,
+                line_join,
+                miter_limit,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn circle_symbol(&mut self) -> VectorTileSymbol {
+        let color = self.paint_color("circle-color", Color::BLACK);
+        let opacity = self.paint_number("circle-opacity", 1.0);
+        let radius = self.paint_number("circle-radius", DEFAULT_CIRCLE_RADIUS);
+        let stroke_width = self.paint_number("circle-stroke-width", 0.0);
+        let stroke_color = self.paint_color("circle-stroke-color", Color::BLACK);
+        let stroke_opacity = self.paint_number("circle-stroke-opacity", 1.0);
+
+        let mut paint = PointPaint::circle(color.with_opacity(opacity), radius as f32 * 2.0);
+        if stroke_width > 0.0 {
+            paint = paint.with_outline(
+                stroke_color.with_opacity(stroke_opacity),
+                stroke_width as f32,
+            );
+        }
+
+        VectorTileSymbol {
+            point: Some(paint),
+            ..Default::default()
+        }
+    }
+
+    fn symbol_symbol(&mut self) -> Option<VectorTileSymbol> {
+        let label = self.label_symbol();
+        let icon = self.icon_symbol();
+        if label.is_none() && icon.is_none() {
+            self.skipped = true;
+            return None;
+        }
+
+        Some(VectorTileSymbol {
+            label,
+            icon,
+            ..Default::default()
+        })
+    }
+
+    fn label_symbol(&mut self) -> Option<VectorTileLabelSymbol> {
+        let text_field = self.layout_value("text-field")?;
+        let Some(template) = text_template(text_field) else {
+            self.warn_value("text-field", text_field);
+            return None;
+        };
+
+        let font_name = match self.layout_value("text-font") {
+            None => DEFAULT_FONT.to_string(),
+            Some(Value::Array(fonts)) => match fonts.first() {
+                Some(Value::String(font)) => font.clone(),
+                _ => DEFAULT_FONT.to_string(),
+            },
+            Some(other) => {
+                let value = other.to_string();
+                self.warn(ConversionWarningKind::UnsupportedValue {
+                    property: "text-font".into(),
+                    value,
+                });
+                DEFAULT_FONT.to_string()
+            }
+        };
+
+        let font_size = self.layout_number("text-size", DEFAULT_TEXT_SIZE);
+        let color = self.paint_color("text-color", Color::BLACK);
+        let opacity = self.paint_number("text-opacity", 1.0);
+        let padding = self.layout_number("text-padding", DEFAULT_TEXT_PADDING);
+        let allow_overlap = self.layout_bool("text-allow-overlap", false);
+        let spacing = self.layout_number("symbol-spacing", DEFAULT_SYMBOL_SPACING);
+        let max_angle = self.layout_number("text-max-angle", DEFAULT_TEXT_MAX_ANGLE);
+        // Offset is given in ems with `y` axis pointing down.
+        let [offset_x, offset_y] = self.layout_number_pair("text-offset", [0.0, 0.0]);
+        let anchor = self.layout_enum("text-anchor", ANCHORS, [0.5, 0.5]);
+
+        Some(VectorTileLabelSymbol {
+            pattern: template,
+            text_style: TextStyle {
+                font_name,
+                font_size: font_size as f32,
+                font_color: color.with_opacity(opacity),
+                horizontal_alignment: Default::default(),
+                vertical_alignment: Default::default(),
+            },
+            collision: CollisionOptions {
+                padding: padding as f32,
+                allow_overlap,
+                ..Default::default()
+            },
+            spacing: spacing as f32,
+            max_angle: max_angle as f32,
+            offset: [
+                (offset_x * font_size) as f32,
+                (-offset_y * font_size) as f32,
+            ],
+            anchor: Some(anchor),
+        })
+    }
+
+    /// Icons are taken from the sprite atlas of the converted style. The converter only keeps the
+    /// URL of the sprite referenced by the MapLibre style, the atlas must be loaded with
+    /// [`VectorTileStyle::load_sprite`].
+    fn icon_symbol(&mut self) -> Option<VectorTileIconSymbol> {
+        let icon_image = self.layout_value("icon-image")?;
+        let Some(name) = text_template(icon_image) else {
+            self.warn_value("icon-image", icon_image);
+            return None;
+        };
+
+        let scale = self.layout_number("icon-size", 1.0);
+        let anchor = self.layout_enum("icon-anchor", ANCHORS, [0.5, 0.5]);
+        let padding = self.layout_number("icon-padding", DEFAULT_ICON_PADDING);
+        let allow_overlap = self.layout_bool("icon-allow-overlap", false);
+
+        Some(VectorTileIconSymbol {
+            name,
+            scale: scale as f32,
+            anchor,
+            collision: CollisionOptions {
+                padding: padding as f32,
+                allow_overlap,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn paint_color(&mut self, name: &'static str, default: Color) -> Color {
+        self.used_properties.insert(name);
+        match self.layer.paint.get(name) {
+            None => default,
+            Some(Value::String(value)) => parse_css_color(value).unwrap_or_else(|| {
+                self.warn_value(name, &Value::String(value.clone()));
+                default
+            }),
+            Some(other) => {
+                self.warn_value(name, other);
+                default
+            }
+        }
+    }
+
+    /// Color that can change with the zoom level.
+    fn paint_color_value(&mut self, name: &'static str, default: Color) -> StyleValue<Color> {
+        self.paint_value(name, default, |value| parse_css_color(value.as_str()?))
+    }
+
+    /// Number that can change with the zoom level.
+    fn paint_number_value(&mut self, name: &'static str, default: f64) -> StyleValue<f64> {
+        self.paint_value(name, default, Value::as_f64)
+    }
+
+    fn paint_value<T>(
+        &mut self,
+        name: &'static str,
+        default: T,
+        parse: impl Fn(&Value) -> Option<T>,
+    ) -> StyleValue<T> {
+        self.used_properties.insert(name);
+        let Some(value) = self.layer.paint.get(name) else {
+            return default.into();
+        };
+
+        style_value(value, &parse).unwrap_or_else(|| {
+            self.warn_value(name, value);
+            default.into()
+        })
+    }
+
+    /// Dash array values in MapLibre styles are given in line widths.
+    fn paint_dash_array(&mut self, name: &'static str, line_width: f64) -> Option<DashArray> {
+        self.used_properties.insert(name);
+        let value = self.layer.paint.get(name)?;
+        let lengths: Option<Vec<f32>> = match value {
+            Value::Array(values) => values
+                .iter()
+                .map(|v| v.as_f64().map(|v| (v * line_width) as f32))
+                .collect(),
+            _ => None,
+        };
+
+        let dash_array = lengths.and_then(|lengths| DashArray::new(&lengths));
+        if dash_array.is_none() {
+            self.warn_value(name, value);
+        }
+
+        dash_array
+    }
+
+    fn paint_number(&mut self, name: &'static str, default: f64) -> f64 {
+        self.used_properties.insert(name);
+        let value = self.layer.paint.get(name);
+        self.number(name, value, default)
+    }
+
+    fn layout_number(&mut self, name: &'static str, default: f64) -> f64 {
+        self.used_properties.insert(name);
+        let value = self.layer.layout.get(name);
+        self.number(name, value, default)
+    }
+
+    fn layout_number_pair(&mut self, name: &'static str, default: [f64; 2]) -> [f64; 2] {
+        let Some(value) = self.layout_value(name) else {
+            return default;
+        };
+
+        match value.as_array().map(|values| &values[..]) {
+            Some([Value::Number(x), Value::Number(y)]) => [
+                x.as_f64().unwrap_or(default[0]),
+                y.as_f64().unwrap_or(default[1]),
+            ],
+            _ => {
+                self.warn_value(name, value);
+                default
+            }
+        }
+    }
+
+    fn layout_bool(&mut self, name: &'static str, default: bool) -> bool {
+        match self.layout_value(name) {
+            None => default,
+            Some(Value::Bool(value)) => *value,
+            Some(other) => {
+                self.warn_value(name, other);
+                default
+            }
+        }
+    }
+
+    fn layout_enum<T: Copy>(&mut self, name: &'static str, values: &[(&str, T)], default: T) -> T {
+        let Some(value) = self.layout_value(name) else {
+            return default;
+        };
+
+        let variant = values
+            .iter()
+            .find(|(key, _)| value.as_str() == Some(key))
+            .map(|(_, variant)| *variant);
+        variant.unwrap_or_else(|| {
+            self.warn_value(name, value);
+            default
+        })
+    }
+
+    fn number(&mut self, name: &str, value: Option<&Value>, default: f64) -> f64 {
+        match value {
+            None => default,
+            Some(Value::Number(v)) => v.as_f64().unwrap_or(default),
+            Some(other) => {
+                self.warn_value(name, other);
+                default
+            }
+        }
+    }
+
+    fn layout_value(&mut self, name: &'static str) -> Option<&'a Value> {
+        self.used_properties.insert(name);
+        self.layer.layout.get(name)
+    }
+
+    fn warn_value(&mut self, name: &str, value: &Value) {
+        self.warn(ConversionWarningKind::UnsupportedValue {
+            property: name.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    fn warn(&mut self, kind: ConversionWarningKind) {
+        self.warnings.push(ConversionWarning {
+            layer_id: self.layer.id.clone(),
+            kind,
+        });
+    }
+
+    fn skip(&mut self, kind: ConversionWarningKind) {
+        self.skipped = true;
+        self.warn(kind);
+    }
+
+    fn report_unused_properties(mut self) {
+        // Properties of the layers that are not drawn at all are not interesting.
+        if self.skipped {
+            return;
+        }
+
+        let layer = self.layer;
+        for name in layer.paint.keys().chain(layer.layout.keys()) {
+            if !self.used_properties.contains(name.as_str()) {
+                self.warn(ConversionWarningKind::UnsupportedProperty(name.clone()));
+            }
+        }
+    }
+}
+
+fn zoom_to_resolution(zoom: f64) -> f64 {
+    ZOOM_0_RESOLUTION / 2f64.powf(zoom)
+}
+
+/// Geometry types of the features the symbol of the layer type is drawn for.
+fn drawn_geometry_types(layer_type: &str) -> &'static [&'static str] {
+    match layer_type {
+        "fill" => &["Polygon"],
+        "line" => &["LineString"],
+        "circle" => &["Point"],
+        _ => &["Point", "LineString", "Polygon"],
+    }
+}
+
+/// Converts a filter, written either in the legacy or in the expression syntax, into a
+/// [`StyleFilter`]. Returns `None` if the filter cannot be represented.
+///
+/// [`StyleFilter`] cannot check the geometry type of a feature. Instead, geometry type checks are
+/// evaluated for every type of the `geometry_types` the layer is drawn for. If the result is the
+/// same for all of them, the check is replaced by an always or never matching filter.
+fn convert_filter(filter: &Value, geometry_types: &[&str]) -> Option<StyleFilter> {
+    let Value::Array(items) = filter else {
+        return (filter == &Value::Bool(true)).then(|| StyleFilter::All { filters: vec![] });
+    };
+
+    let (op, args) = items.split_first()?;
+    let op = op.as_str()?;
+
+    if let Some((key, _)) = args.split_first() {
+        if is_geometry_type_key(key) {
+            return convert_geometry_type_check(op, args, geometry_types);
+        }
+    }
+
+    match op {
+        "all" | "any" | "none" => {
+            let mut filters = args
+                .iter()
+                .map(|arg| convert_filter(arg, geometry_types))
+                .collect::<Option<Vec<_>>>()?;
+
+            if op == "all" {
+                filters.retain(|filter| !is_always_true(filter));
+            }
+
+            Some(match op {
+                "all" if filters.len() == 1 => filters.remove(0),
+                "all" => StyleFilter::All { filters },
+                "any" => StyleFilter::Any { filters },
+                _ => StyleFilter::None { filters },
+            })
+        }
+        "!" => match args {
+            [inner] => Some(StyleFilter::None {
+                filters: vec![convert_filter(inner, geometry_types)?],
+            }),
+            _ => None,
+        },
+        "has" | "!has" => {
+            let [key] = args else {
+                return None;
+            };
+            let key = filter_key(key)?;
+            Some(match op {
+                "has" => StyleFilter::Has { key },
+                _ => StyleFilter::NotHas { key },
+            })
+        }
+        "in" | "!in" => {
+            let (key, values) = args.split_first()?;
+            let key = filter_key(key)?;
+            let values = filter_values(values)?;
+
+            Some(match op {
+                "in" => StyleFilter::In { key, values },
+                _ => StyleFilter::NotIn { key, values },
+            })
+        }
+        "==" | "!=" | "<" | "<=" | ">" | ">=" => {
+            let [key, value] = args else {
+                return None;
+            };
+            let key = filter_key(key)?;
+            let value = filter_value(value)?;
+
+            Some(match op {
+                "==" => StyleFilter::Eq { key, value },
+                "!=" => StyleFilter::Ne { key, value },
+                "<" => StyleFilter::Lt { key, value },
+                "<=" => StyleFilter::Le { key, value },
+                ">" => StyleFilter::Gt { key, value },
+                _ => StyleFilter::Ge { key, value },
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Returns true if the value is either a legacy `"$type"` key or a `["geometry-type"]` expression.
+fn is_geometry_type_key(value: &Value) -> bool {
+    match value {
+        Value::String(key) => key == "$type",
+        Value::Array(getter) => getter.as_slice() == [Value::String("geometry-type".into())],
+        _ => false,
+    }
+}
+
+/// Evaluates a geometry type check for each of the `geometry_types`. Returns `None` if the result
+/// depends on the geometry type.
+fn convert_geometry_type_check(
+    op: &str,
+    args: &[Value],
+    geometry_types: &[&str],
+) -> Option<StyleFilter> {
+    let (_, values) = args.split_first()?;
+    let values = filter_values(values)?;
+    let (expected, negate) = match (op, values.as_slice()) {
+        ("==" | "!=", [_]) | ("in" | "!in", _) => (values.as_slice(), op.starts_with('!')),
+        _ => return None,
+    };
+
+    let matches = |geometry_type: &&str| {
+        // Expressions distinguish multi-geometries, while MapLibre and vector tiles don't.
+        let is_expected = expected.iter().any(|value| match value {
+            FilterValue::String(value) => value.trim_start_matches("Multi") == *geometry_type,
+            _ => false,
+        });
+        is_expected != negate
+    };
+
+    if geometry_types.iter().all(matches) {
+        Some(StyleFilter::All { filters: vec![] })
+    } else if !geometry_types.iter().any(matches) {
+        Some(StyleFilter::Any { filters: vec![] })
+    } else {
+        None
+    }
+}
+
+fn is_always_true(filter: &StyleFilter) -> bool {
+    matches!(filter, StyleFilter::All { filters } if filters.is_empty())
+}
+
+/// Returns the property name for either a legacy key (`"key"`) or a `["get", "key"]` expression.
+/// Geometry type and feature id checks are not supported.
+fn filter_key(value: &Value) -> Option<String> {
+    let key = match value {
+        Value::String(key) => key,
+        Value::Array(getter) => match getter.as_slice() {
+            [Value::String(op), Value::String(key)] if op == "get" => key,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    (key != "$type" && key != "$id").then(|| key.clone())
+}
+
+/// Returns the values of an `in` filter, given either as separate arguments (legacy syntax) or as
+/// a `["literal", [values]]` expression.
+fn filter_values(values: &[Value]) -> Option<Vec<FilterValue>> {
+    match values {
+        [Value::Array(literal)] => match literal.as_slice() {
+            [Value::String(literal_op), Value::Array(values)] if literal_op == "literal" => {
+                values.iter().map(filter_value).collect()
+            }
+            _ => None,
+        },
+        values => values.iter().map(filter_value).collect(),
+    }
+}
+
+fn filter_value(value: &Value) -> Option<FilterValue> {
+    match value {
+        Value::String(v) => Some(FilterValue::String(v.clone())),
+        Value::Bool(v) => Some(FilterValue::Bool(*v)),
+        Value::Number(v) => v.as_f64().map(FilterValue::Number),
+        _ => None,
+    }
+}
+
+/// Converts a `text-field` value into a label template in the format used by
+/// [`VectorTileLabelSymbol::pattern`].
+fn text_template(value: &Value) -> Option<String> {
+    match value {
+        Value::String(template) => Some(template_tokens(template)),
+        Value::Array(items) => match items.as_slice() {
+            [Value::String(op), Value::String(key)] if op == "get" => {
+                Some(template_tokens(&format!("{{{key}}}")))
+            }
+            [Value::String(op), inner] if op == "to-string" => text_template(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Replaces `:` in the property names of `{token}`s, as it starts the format specification in
+/// templates of [`VectorTileStyle`], while MapLibre tokens consist of the property name only.
+fn template_tokens(template: &str) -> String {
+    let mut in_token = false;
+    template
+        .chars()
+        .map(|c| match c {
+            '{' => {
+                in_token = true;
+                c
+            }
+            '}' => {
+                in_token = false;
+                c
+            }
+            ':' if in_token => TEMPLATE_COLON_REPLACEMENT,
+            c => c,
+        })
+        .collect()
+}
+
+/// Converts a constant, a zoom function (`{"stops": [...]}`) or a zoom `interpolate` or `step`
+/// expression into a [`StyleValue`]. Returns `None` if the value depends on feature properties or
+/// cannot be converted otherwise.
+fn style_value<T>(value: &Value, parse: &impl Fn(&Value) -> Option<T>) -> Option<StyleValue<T>> {
+    if let Some(constant) = parse(value) {
+        return Some(StyleValue::Constant(constant));
+    }
+
+    match value {
+        Value::Object(function) => zoom_function(function, parse),
+        Value::Array(expression) => zoom_expression(expression, parse),
+        _ => None,
+    }
+}
+
+fn zoom_function<T>(
+    function: &Map<String, Value>,
+    parse: &impl Fn(&Value) -> Option<T>,
+) -> Option<StyleValue<T>> {
+    // Functions of feature properties cannot be represented.
+    if function.contains_key("property") {
+        return None;
+    }
+
+    let base = match function.get("base") {
+        None => 1.0,
+        Some(base) => base.as_f64()?,
+    };
+    let interpolation = match function.get("type").map(Value::as_str) {
+        None | Some(Some("exponential")) => exponential_interpolation(base),
+        Some(Some("interval")) => Interpolation::Step,
+        _ => return None,
+    };
+
+    let stops = function
+        .get("stops")?
+        .as_array()?
+        .iter()
+        .map(|stop| match stop.as_array()?.as_slice() {
+            [zoom, value] => Some(Stop {
+                resolution: zoom_to_resolution(zoom.as_f64()?),
+                value: parse(value)?,
+            }),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(StyleValue::Stops(Stops {
+        interpolation,
+        stops,
+    }))
+}
+
+fn zoom_expression<T>(
+    expression: &[Value],
+    parse: &impl Fn(&Value) -> Option<T>,
+) -> Option<StyleValue<T>> {
+    let is_zoom = |input: &Value| {
+        input.as_array().map(Vec::as_slice) == Some(&[Value::String("zoom".into())])
+    };
+
+    match expression {
+        [Value::String(op), interpolation, input, stops @ ..]
+            if op == "interpolate" && is_zoom(input) =>
+        {
+            let interpolation = match interpolation.as_array()?.as_slice() {
+                [Value::String(kind)] if kind == "linear" => Interpolation::Linear,
+                [Value::String(kind), base] if kind == "exponential" => {
+                    exponential_interpolation(base.as_f64()?)
+                }
+                _ => return None,
+            };
+
+            Some(StyleValue::Stops(Stops {
+                interpolation,
+                stops: zoom_stops(stops, parse)?,
+            }))
+        }
+        [Value::String(op), input, first, stops @ ..] if op == "step" && is_zoom(input) => {
+            let first = parse(first)?;
+            let mut stops = zoom_stops(stops, parse)?;
+            let Some(next) = stops.first() else {
+                return Some(StyleValue::Constant(first));
+            };
+
+            // The first value is used for all zoom levels below the first stop.
+            stops.insert(
+                0,
+                Stop {
+                    resolution: next.resolution * 2.0,
+                    value: first,
+                },
+            );
+            Some(StyleValue::Stops(Stops {
+                interpolation: Interpolation::Step,
+                stops,
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Converts the `zoom, value` pairs of an expression into stops.
+fn zoom_stops<T>(pairs: &[Value], parse: &impl Fn(&Value) -> Option<T>) -> Option<Vec<Stop<T>>> {
+    let pairs = pairs.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+
+    pairs
+        .map(|pair| {
+            Some(Stop {
+                resolution: zoom_to_resolution(pair[0].as_f64()?),
+                value: parse(&pair[1])?,
+            })
+        })
+        .collect()
+}
+
+fn exponential_interpolation(base: f64) -> Interpolation {
+    if base == 1.0 {
+        Interpolation::Linear
+    } else {
+        Interpolation::Exponential { base }
+    }
+}
+
+/// Parses a CSS color string as used by the MapLibre style specification.
+fn parse_css_color(value: &str) -> Option<Color> {
+    let value = value.trim().to_ascii_lowercase();
+
+    if let Some(hex) = value.strip_prefix('#') {
+        return parse_hex_color(hex);
+    }
+
+    if let Some((function, args)) = value.strip_suffix(')').and_then(|v| v.split_once('(')) {
+        let args: Vec<&str> = args.split(',').map(str::trim).collect();
+        return match (function.trim(), args.as_slice()) {
+            ("rgb", [r, g, b]) => Some(Color::rgba(
+                parse_channel(r)?,
+                parse_channel(g)?,
+                parse_channel(b)?,
+                255,
+            )),
+            ("rgba", [r, g, b, a]) => Some(Color::rgba(
+                parse_channel(r)?,
+                parse_channel(g)?,
+                parse_channel(b)?,
+                parse_alpha(a)?,
+            )),
+            ("hsl", [h, s, l]) => hsl_to_color(h.parse().ok()?, percent(s)?, percent(l)?, 255),
+            ("hsla", [h, s, l, a]) => {
+                hsl_to_color(h.parse().ok()?, percent(s)?, percent(l)?, parse_alpha(a)?)
+            }
+            _ => None,
+        };
+    }
+
+    named_color(&value)
+}
+
+fn parse_hex_color(hex: &str) -> Option<Color> {
+    if !hex.is_ascii() {
+        return None;
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|v| v * 17);
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    match hex.len() {
+        3 => Some(Color::rgba(digit(0)?, digit(1)?, digit(2)?, 255)),
+        4 => Some(Color::rgba(digit(0)?, digit(1)?, digit(2)?, digit(3)?)),
+        6 => Some(Color::rgba(byte(0)?, byte(2)?, byte(4)?, 255)),
+        8 => Some(Color::rgba(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+        _ => None,
+    }
+}
+
+fn parse_channel(value: &str) -> Option<u8> {
+    match value.strip_suffix('%') {
+        Some(percent) => {
+            Some((percent.parse::<f64>().ok()?.clamp(0.0, 100.0) * 2.55).round() as u8)
+        }
+        None => Some(value.parse::<f64>().ok()?.clamp(0.0, 255.0).round() as u8),
+    }
+}
+
+fn parse_alpha(value: &str) -> Option<u8> {
+    Some((value.parse::<f64>().ok()?.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+fn percent(value: &str) -> Option<f64> {
+    Some(
+        value
+            .strip_suffix('%')?
+            .parse::<f64>()
+            .ok()?
+            .clamp(0.0, 100.0)
+            / 100.0,
+    )
+}
+
+fn hsl_to_color(hue: f64, saturation: f64, lightness: f64, alpha: u8) -> Option<Color> {
+    let hue = hue.rem_euclid(360.0) / 360.0;
+    let q = if lightness < 0.5 {
+        lightness * (1.0 + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2.0 * lightness - q;
+
+    let channel = |t: f64| {
+        let t = t.rem_euclid(1.0);
+        let v = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (v * 255.0).round() as u8
+    };
+
+    Some(Color::rgba(
+        channel(hue + 1.0 / 3.0),
+        channel(hue),
+        channel(hue - 1.0 / 3.0),
+        alpha,
+    ))
+}
+
+fn named_color(name: &str) -> Option<Color> {
+    Some(match name {
+        "transparent" => Color::TRANSPARENT,
+        "black" => Color::BLACK,
+        "white" => Color::WHITE,
+        "red" => Color::RED,
+        "lime" => Color::GREEN,
+        "blue" => Color::BLUE,
+        "green" => Color::rgba(0, 128, 0, 255),
+        "yellow" => Color::rgba(255, 255, 0, 255),
+        "orange" => Color::rgba(255, 165, 0, 255),
+        "gray" | "grey" => Color::rgba(128, 128, 128, 255),
+        "silver" => Color::rgba(192, 192, 192, 255),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::point_paint::PointShape;
+
+    const STYLE: &str = r##"{
+        "version": 8,
+        "sprite": "https://example.com/sprite",
+        "sources": {"openmaptiles": {"type": "vector", "url": "https://example.com/tiles.json"}},
+        "layers": [
+            {"id": "background", "type": "background", "paint": {"background-color": "#f8f4f0"}},
+            {"id": "water", "type": "fill", "source": "openmaptiles", "source-layer": "water",
+             "filter": ["all", ["==", "$type", "Polygon"], ["==", "class", "lake"]],
+             "paint": {"fill-color": "rgba(0, 0, 255, 0.5)"}},
+            {"id": "roads", "type": "line", "source": "openmaptiles", "source-layer": "transportation",
+             "minzoom": 5,
+             "filter": ["==", ["get", "class"], "primary"],
+             "layout": {"line-cap": "round", "line-join": "bevel", "line-miter-limit": 3},
+             "paint": {"line-color": "hsl(0, 100%, 50%)", "line-width": 3}},
+            {"id": "poi", "type": "circle", "source": "openmaptiles", "source-layer": "poi",
+             "paint": {"circle-radius": 4, "circle-color": "#fff"}},
+            {"id": "places", "type": "symbol", "source": "openmaptiles", "source-layer": "place",
+             "layout": {"text-field": ["get", "name"], "text-font": ["Noto Sans Regular"], "text-size": 12,
+                        "text-offset": [0, 1.5], "text-anchor": "top"}},
+            {"id": "hillshade", "type": "hillshade", "source": "dem"},
+            {"id": "admin", "type": "line", "source": "openmaptiles", "source-layer": "boundary",
+             "filter": ["all", ["in", "admin_level", 2, 4], ["!", ["has", "maritime"]]]},
+            {"id": "rail", "type": "line", "source": "openmaptiles", "source-layer": "transportation",
+             "filter": ["==", ["zoom"], 5]},
+            {"id": "poi_icons", "type": "symbol", "source": "openmaptiles", "source-layer": "poi",
+             "layout": {"icon-image": "{class}_11", "icon-size": 0.5, "icon-anchor": "bottom"}}
+        ]
+    }"##;
+
+    #[test]
+    fn converts_supported_layers() {
+        let MaplibreStyleConversion { style, .. } = convert(STYLE).unwrap();
+
+        assert_eq!(style.background, Color::rgba(0xf8, 0xf4, 0xf0, 255));
+        assert_eq!(style.rules.len(), 6);
+
+        let layer_names: Vec<_> = style
+            .rules
+            .iter()
+            .map(|rule| rule.layer_name.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            layer_names,
+            ["water", "transportation", "poi", "place", "boundary", "poi"]
+        );
+        assert_eq!(style.rule_matching, RuleMatching::All);
+
+        let sort_keys: Vec<_> = style.rules.iter().map(|rule| rule.sort_key).collect();
+        assert_eq!(sort_keys, [1, 2, 3, 4, 6, 8]);
+
+        let water = &style.rules[0];
+        assert_eq!(
+            water.filter,
+            Some(StyleFilter::Eq {
+                key: "class".into(),
+                value: "lake".into()
+            })
+        );
+        assert_eq!(
+            water.symbol.polygon.as_ref().unwrap().fill_color,
+            Color::rgba(0, 0, 255, 128).into()
+        );
+
+        let roads = &style.rules[1];
+        assert_eq!(
+            roads.filter,
+            Some(StyleFilter::Eq {
+                key: "class".into(),
+                value: "primary".into()
+            })
+        );
+        assert_eq!(roads.max_resolution, Some(ZOOM_0_RESOLUTION / 32.0));
+        assert_eq!(roads.min_resolution, None);
+        let line = roads.symbol.line.as_ref().unwrap();
+        assert_eq!(line.width, 3.0.into());
+        assert_eq!(line.stroke_color, Color::RED.into());
+        assert_eq!(line.line_cap, LineCap::Round);
+        assert_eq!(line.line_join, LineJoin::Bevel);
+        assert_eq!(line.miter_limit, 3.0);
+
+        let poi = style.rules[2].symbol.point.as_ref().unwrap();
+        assert!(matches!(poi.shape, PointShape::Circle { radius, .. } if radius == 4.0));
+
+        let boundary = &style.rules[4];
+        assert_eq!(
+            boundary.filter,
+            Some(StyleFilter::All {
+                filters: vec![
+                    StyleFilter::In {
+                        key: "admin_level".into(),
+                        values: vec![2.0.into(), 4.0.into()]
+                    },
+                    StyleFilter::None {
+                        filters: vec![StyleFilter::Has {
+                            key: "maritime".into()
+                        }]
+                    },
+                ]
+            })
+        );
+
+        let places = style.rules[3].symbol.label.as_ref().unwrap();
+        assert_eq!(places.pattern, "{name}");
+        assert_eq!(places.text_style.font_name, "Noto Sans Regular");
+        assert_eq!(places.text_style.font_size, 12.0);
+        assert_eq!(places.offset, [0.0, -18.0]);
+        assert_eq!(places.anchor, Some([0.5, 0.0]));
+        assert!(style.rules[3].symbol.icon.is_none());
+
+        let poi_icons = &style.rules[5].symbol;
+        assert!(poi_icons.label.is_none());
+        let icon = poi_icons.icon.as_ref().unwrap();
+        assert_eq!(icon.name, "{class}_11");
+        assert_eq!(icon.scale, 0.5);
+        assert_eq!(icon.anchor, [0.5, 1.0]);
+        assert_eq!(icon.collision.padding, DEFAULT_ICON_PADDING as f32);
+        assert_eq!(style.sprite.as_deref(), Some("https://example.com/sprite"));
+        assert!(style.sprite_atlas().is_none());
+    }
+
+    #[test]
+    fn reports_what_cannot_be_converted() {
+        let MaplibreStyleConversion { warnings, .. } = convert(STYLE).unwrap();
+
+        let warning = |layer_id: &str, kind: ConversionWarningKind| ConversionWarning {
+            layer_id: layer_id.into(),
+            kind,
+        };
+
+        assert!(warnings.contains(&warning(
+            "hillshade",
+            ConversionWarningKind::UnsupportedLayerType("hillshade".into())
+        )));
+        assert!(warnings.iter().any(|w| w.layer_id == "rail"
+            && matches!(w.kind, ConversionWarningKind::UnsupportedFilter(_))));
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[test]
+    fn reports_multiple_sprites() {
+        let json =
+            r#"{"layers": [], "sprite": [{"id": "default", "url": "https://example.com/a"}]}"#;
+        let MaplibreStyleConversion { style, warnings } = convert(json).unwrap();
+
+        assert!(style.sprite.is_none());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].layer_id, "");
+        assert!(matches!(
+            &warnings[0].kind,
+            ConversionWarningKind::UnsupportedValue { property, .. } if property == "sprite"
+        ));
+    }
+
+    #[test]
+    fn skips_hidden_layers() {
+        let json = r#"{"layers": [{"id": "hidden", "type": "fill", "source-layer": "water",
+            "layout": {"visibility": "none"}, "paint": {"fill-pattern": "dots"}}]}"#;
+        let MaplibreStyleConversion { style, warnings } = convert(json).unwrap();
+        assert!(style.rules.is_empty());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn converts_zoom_dependent_values() {
+        let json = r##"{"layers": [
+            {"id": "roads", "type": "line", "source-layer": "transportation",
+             "paint": {
+                "line-width": {"base": 1.5, "stops": [[5, 1], [10, 4]]},
+                "line-color": ["interpolate", ["linear"], ["zoom"], 5, "#f00", 10, "#00f"],
+                "line-opacity": ["step", ["zoom"], 0.5, 8, 1]
+             }},
+            {"id": "water", "type": "fill", "source-layer": "water",
+             "paint": {"fill-opacity": ["interpolate", ["linear"], ["get", "depth"], 0, 1, 10, 0]}}
+        ]}"##;
+        let MaplibreStyleConversion { style, warnings } = convert(json).unwrap();
+
+        let line = style.rules[0].symbol.line.as_ref().unwrap();
+        assert_eq!(
+            line.width,
+            StyleValue::Stops(Stops {
+                interpolation: Interpolation::Exponential { base: 1.5 },
+                stops: vec![
+                    Stop {
+                        resolution: zoom_to_resolution(5.0),
+                        value: 1.0
+                    },
+                    Stop {
+                        resolution: zoom_to_resolution(10.0),
+                        value: 4.0
+                    },
+                ],
+            })
+        );
+        assert_eq!(
+            line.stroke_color,
+            StyleValue::Stops(Stops {
+                interpolation: Interpolation::Linear,
+                stops: vec![
+                    Stop {
+                        resolution: zoom_to_resolution(5.0),
+                        value: Color::RED
+                    },
+                    Stop {
+                        resolution: zoom_to_resolution(10.0),
+                        value: Color::BLUE
+                    },
+                ],
+            })
+        );
+        assert_eq!(line.opacity.get(zoom_to_resolution(6.0)), Some(0.5));
+        assert_eq!(line.opacity.get(zoom_to_resolution(7.9)), Some(0.5));
+        assert_eq!(line.opacity.get(zoom_to_resolution(8.0)), Some(1.0));
+        assert_eq!(line.opacity.get(zoom_to_resolution(12.0)), Some(1.0));
+
+        // Values depending on feature properties cannot be converted.
+        let fill = style.rules[1].symbol.polygon.as_ref().unwrap();
+        assert_eq!(fill.opacity, 1.0.into());
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            &warnings[0].kind,
+            ConversionWarningKind::UnsupportedValue { property, .. } if property == "fill-opacity"
+        ));
+    }
+
+    #[test]
+    fn evaluates_geometry_type_filters() {
+        let json = r#"{"layers": [
+            {"id": "lakes", "type": "fill", "source-layer": "water",
+             "filter": ["==", "$type", "Polygon"]},
+            {"id": "rivers", "type": "fill", "source-layer": "water",
+             "filter": ["==", ["geometry-type"], "LineString"]},
+            {"id": "shores", "type": "line", "source-layer": "water",
+             "filter": ["all", ["in", "$type", "LineString", "Polygon"], ["has", "name"]]},
+            {"id": "names", "type": "symbol", "source-layer": "water",
+             "filter": ["==", "$type", "Point"], "layout": {"text-field": "{name}"}}
+        ]}"#;
+        let MaplibreStyleConversion { style, warnings } = convert(json).unwrap();
+
+        assert_eq!(style.rules.len(), 3);
+        assert_eq!(style.rules[0].filter, None);
+        assert_eq!(
+            style.rules[1].filter,
+            Some(StyleFilter::Any { filters: vec![] })
+        );
+        assert_eq!(
+            style.rules[2].filter,
+            Some(StyleFilter::Has { key: "name".into() })
+        );
+
+        // Labels are drawn for all geometry types, so the check cannot be evaluated.
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].layer_id, "names");
+        assert!(matches!(
+            warnings[0].kind,
+            ConversionWarningKind::UnsupportedFilter(_)
+        ));
+    }
+
+    #[test]
+    fn replaces_colons_in_template_tokens() {
+        assert_eq!(
+            text_template(&Value::String("{name:latin} ({ele})".into())),
+            Some("{name.latin} ({ele})".into())
+        );
+        assert_eq!(
+            text_template(&serde_json::json!(["get", "name:en"])),
+            Some("{name.en}".into())
+        );
+    }
+
+    #[test]
+    fn parses_css_colors() {
+        assert_eq!(parse_css_color("#f00"), Some(Color::RED));
+        assert_eq!(
+            parse_css_color("#FF000080"),
+            Some(Color::rgba(255, 0, 0, 128))
+        );
+        assert_eq!(
+            parse_css_color("rgb(10, 20, 30)"),
+            Some(Color::rgba(10, 20, 30, 255))
+        );
+        assert_eq!(
+            parse_css_color("hsla(240, 100%, 50%, 0.5)"),
+            Some(Color::rgba(0, 0, 255, 128))
+        );
+        assert_eq!(parse_css_color("white"), Some(Color::WHITE));
+        assert_eq!(parse_css_color("not a color"), None);
+    }
+}
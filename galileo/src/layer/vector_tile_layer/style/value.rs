@@ -0,0 +1,252 @@
+//! Style values that can change depending on the map resolution. See [`StyleValue`].
+
+use crate::Color;
+use serde::{Deserialize, Serialize};
+
+/// Value of a style property. It can be either a constant, or a set of [`Stops`] that specify how
+/// the value changes with the map resolution.
+///
+/// In JSON, a constant value is written as is, while stops are written as an object:
+///
+/// ```json
+/// {
+///     "interpolation": { "type": "exponential", "base": 1.5 },
+///     "stops": [
+///         { "resolution": 2445.98, "value": 0.5 },
+///         { "resolution": 4.78, "value": 12.0 }
+///     ]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StyleValue<T> {
+    /// Value that does not depend on resolution.
+    Constant(T),
+    /// Value interpolated between the stops.
+    Stops(Stops<T>),
+}
+
+impl<T> From<T> for StyleValue<T> {
+    fn from(value: T) -> Self {
+        Self::Constant(value)
+    }
+}
+
+impl<T: Interpolate + Clone> StyleValue<T> {
+    /// Returns the value for the given resolution.
+    ///
+    /// Returns `None` if the value is set with stops, but the list of stops is empty.
+    pub fn get(&self, resolution: f64) -> Option<T> {
+        match self {
+            StyleValue::Constant(value) => Some(value.clone()),
+            StyleValue::Stops(stops) => stops.get(resolution),
+        }
+    }
+}
+
+/// Set of values specified for some map resolutions. Values for the resolutions in between the
+/// stops are calculated using the [`Interpolation`] method.
+///
+/// Stops must be ordered from the largest resolution to the smallest one (from the farthest
+/// zoom level to the closest). For resolutions outside the range of the stops, the value of the
+/// first or last stop is used.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stops<T> {
+    /// Interpolation method.
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    /// List of stops.
+    pub stops: Vec<Stop<T>>,
+}
+
+/// Value of a [`Stops`] property at the given resolution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stop<T> {
+    /// Map resolution (map units per pixel).
+    pub resolution: f64,
+    /// Value at this resolution.
+    pub value: T,
+}
+
+/// Method of calculating values between [`Stops`].
+///
+/// Interpolation is done over the zoom level `-log2(resolution)`, so that every two-fold change
+/// in resolution is one step, same as zoom levels of the Web Mercator tile schema.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Interpolation {
+    /// Value changes linearly between stops.
+    #[default]
+    Linear,
+    /// Value changes exponentially between stops. Values of `base` larger than `1.0` make the
+    /// value change faster towards the end of the range.
+    Exponential {
+        /// Base of the exponent.
+        base: f64,
+    },
+    /// Value of the previous stop is used until the next stop is reached.
+    Step,
+}
+
+impl<T: Interpolate + Clone> Stops<T> {
+    /// Returns the value for the given resolution.
+    ///
+    /// Returns `None` if the list of stops is empty.
+    pub fn get(&self, resolution: f64) -> Option<T> {
+        let level = zoom_level(resolution);
+        let first = self.stops.first()?;
+        if level <= zoom_level(first.resolution) {
+            return Some(first.value.clone());
+        }
+
+        for pair in self.stops.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            let from_level = zoom_level(from.resolution);
+            let to_level = zoom_level(to.resolution);
+            if level >= to_level {
+                continue;
+            }
+
+            let k = self
+                .interpolation
+                .factor(level - from_level, to_level - from_level);
+            return Some(from.value.interpolate(&to.value, k));
+        }
+
+        self.stops.last().map(|stop| stop.value.clone())
+    }
+}
+
+impl Interpolation {
+    fn factor(&self, offset: f64, range: f64) -> f64 {
+        if range <= 0.0 {
+            return 0.0;
+        }
+
+        match *self {
+            Interpolation::Linear => offset / range,
+            Interpolation::Exponential { base } if (base - 1.0).abs() < f64::EPSILON => {
+                offset / range
+            }
+            Interpolation::Exponential { base } => {
+                (base.powf(offset) - 1.0) / (base.powf(range) - 1.0)
+            }
+            Interpolation::Step => 0.0,
+        }
+    }
+}
+
+fn zoom_level(resolution: f64) -> f64 {
+    -resolution.log2()
+}
+
+/// Values that can be interpolated between [`Stops`].
+pub trait Interpolate {
+    /// Returns the value between `self` and `other`. `k` is in the range `[0.0, 1.0]`, with `0.0`
+    /// corresponding to `self`.
+    fn interpolate(&self, other: &Self, k: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, other: &Self, k: f64) -> Self {
+        self + (other - self) * k
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, k: f64) -> Self {
+        let from = self.to_u8_array();
+        let to = other.to_u8_array();
+        let channel = |i: usize| (from[i] as f64).interpolate(&(to[i] as f64), k).round() as u8;
+
+        Color::rgba(channel(0), channel(1), channel(2), channel(3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stops(interpolation: Interpolation) -> StyleValue<f64> {
+        StyleValue::Stops(Stops {
+            interpolation,
+            stops: vec![
+                Stop {
+                    resolution: 8.0,
+                    value: 1.0,
+                },
+                Stop {
+                    resolution: 2.0,
+                    value: 5.0,
+                },
+            ],
+        })
+    }
+
+    #[test]
+    fn values_outside_of_stops() {
+        let value = stops(Interpolation::Linear);
+        assert_eq!(value.get(100.0), Some(1.0));
+        assert_eq!(value.get(8.0), Some(1.0));
+        assert_eq!(value.get(2.0), Some(5.0));
+        assert_eq!(value.get(0.1), Some(5.0));
+    }
+
+    #[test]
+    fn interpolation_methods() {
+        assert_eq!(stops(Interpolation::Linear).get(4.0), Some(3.0));
+        assert_eq!(stops(Interpolation::Step).get(4.0), Some(1.0));
+        assert_eq!(
+            stops(Interpolation::Exponential { base: 1.0 }).get(4.0),
+            Some(3.0)
+        );
+
+        let exponential = stops(Interpolation::Exponential { base: 2.0 })
+            .get(4.0)
+            .unwrap();
+        assert!((exponential - 7.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn color_interpolation() {
+        let value = StyleValue::Stops(Stops {
+            interpolation: Interpolation::Linear,
+            stops: vec![
+                Stop {
+                    resolution: 2.0,
+                    value: Color::BLACK,
+                },
+                Stop {
+                    resolution: 1.0,
+                    value: Color::rgba(200, 100, 0, 255),
+                },
+            ],
+        });
+
+        assert_eq!(
+            value.get(2f64.powf(0.5)),
+            Some(Color::rgba(100, 50, 0, 255))
+        );
+    }
+
+    #[test]
+    fn deserialize_constant_and_stops() {
+        let constant: StyleValue<Color> = serde_json::from_str(r##""#FF0000FF""##).unwrap();
+        assert_eq!(constant, StyleValue::Constant(Color::RED));
+
+        let stops: StyleValue<f64> = serde_json::from_str(
+            r#"{"interpolation": {"type": "step"}, "stops": [{"resolution": 10, "value": 2}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            stops,
+            StyleValue::Stops(Stops {
+                interpolation: Interpolation::Step,
+                stops: vec![Stop {
+                    resolution: 10.0,
+                    value: 2.0
+                }],
+            })
+        );
+    }
+}
@@ -2,20 +2,51 @@
 
 use crate::error::GalileoError;
 use crate::layer::data_provider::{PersistentCacheController, UrlSource};
+use crate::layer::vector_tile_layer::tiler::{cut_sub_tile, VectorTiler};
 use crate::platform::{PlatformService, PlatformServiceImpl};
-use crate::tile_scheme::TileIndex;
+use crate::tile_scheme::{TileIndex, TileSchema};
 use bytes::Bytes;
 use galileo_mvt::MvtTile;
+use galileo_types::cartesian::Rect;
 use maybe_sync::{MaybeSend, MaybeSync};
+use std::sync::Arc;
+use thiserror::Error;
 
 /// Error that can occur when trying to load a vector tile.
+#[derive(Debug, Error)]
 pub enum TileLoadError {
-    /// Could not connect to the remote server.
-    Network,
+    /// Failed to load the tile data.
+    #[error("failed to load tile: {0}")]
+    Network(GalileoError),
     /// Tile with the given index does not exist.
+    #[error("tile does not exist")]
     DoesNotExist,
     /// Failed to decode vector tile from the binary data.
-    Decoding,
+    #[error("failed to decode tile: {0}")]
+    Decoding(GalileoError),
+}
+
+impl TileLoadError {
+    /// Returns true if loading the tile may succeed if it is repeated.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Network(err) => err.is_retryable(),
+            Self::DoesNotExist | Self::Decoding(_) => false,
+        }
+    }
+}
+
+impl From<GalileoError> for TileLoadError {
+    fn from(value: GalileoError) -> Self {
+        if value.is_not_found() {
+            return Self::DoesNotExist;
+        }
+
+        match value {
+            GalileoError::Decoding(_) => Self::Decoding(value),
+            _ => Self::Network(value),
+        }
+    }
 }
 
 /// Loader for vector tiles.
@@ -41,6 +72,9 @@ where
     Cache: PersistentCacheController<str, Bytes> + MaybeSend + MaybeSync,
 {
     /// Create a new instance.
+    ///
+    /// The `platform_service` can be created with [`PlatformService::with_config`] to send custom
+    /// headers with the tile requests.
     pub fn new(
         platform_service: PlatformServiceImpl,
         cache: Cache,
@@ -59,14 +93,7 @@ where
             return Ok(data);
         }
 
-        let bytes = self
-            .platform_service
-            .load_bytes_from_url(url)
-            .await
-            .map_err(|err| match err {
-                GalileoError::NotFound => TileLoadError::DoesNotExist,
-                _ => TileLoadError::Network,
-            })?;
+        let bytes = self.platform_service.load_bytes_from_url(url).await?;
 
         log::info!("Loaded tile from url: {url}");
 
@@ -92,10 +119,213 @@ where
 
         log::trace!("Tile {index:?} loaded. Byte size: {}", bytes.len());
 
-        let mvt = MvtTile::decode(bytes, false).map_err(|_| TileLoadError::Decoding)?;
+        let mvt =
+            MvtTile::decode(bytes, false).map_err(|err| TileLoadError::Decoding(err.into()))?;
 
         log::trace!("Tile {index:?} successfully decoded");
 
         Ok(mvt)
     }
 }
+
+/// Cuts tiles on demand from features kept in memory.
+///
+/// Tiles are cut only when the layer requests them, so large feature collections can be displayed
+/// without tessellating all features at every zoom level. Loaded tiles are cached and processed
+/// by the tile provider the same way as tiles loaded from the Web.
+///
+/// Tiles without any features are loaded as empty tiles. The tile schema of the tiler must be the
+/// same as the one of the layer.
+///
+/// On native platforms the tiles are cut on the blocking thread pool of the runtime.
+pub struct TilerVtLoader {
+    tiler: Arc<VectorTiler>,
+}
+
+impl TilerVtLoader {
+    /// Create a new instance.
+    pub fn new(tiler: Arc<VectorTiler>) -> Self {
+        Self { tiler }
+    }
+
+    /// Tiler used to cut the tiles.
+    pub fn tiler(&self) -> &VectorTiler {
+        &self.tiler
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl VectorTileLoader for TilerVtLoader {
+    async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
+        log::trace!("Cutting tile {index:?}");
+        let cut_tile =
+            move |tiler: &VectorTiler| tiler.tile(index).unwrap_or(MvtTile { layers: vec![] });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let tiler = self.tiler.clone();
+            tokio::task::spawn_blocking(move || cut_tile(&tiler))
+                .await
+                .map_err(|err| {
+                    TileLoadError::Decoding(GalileoError::Generic(format!(
+                        "failed to cut tile: {err}"
+                    )))
+                })
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            Ok(cut_tile(&self.tiler))
+        }
+    }
+}
+
+/// Size of the buffer around overzoomed tiles, as a part of the tile size.
+const OVERZOOM_BUFFER: f64 = 1.0 / 64.0;
+
+/// Number of source tiles kept in memory to cut overzoomed tiles from.
+const OVERZOOM_CACHE_SIZE: usize = 32;
+
+/// Loads tiles with z-levels higher than the data max zoom by cutting them out of the tiles of the
+/// max zoom level.
+///
+/// Most vector tile sources have data only up to some z-level (usually 14). When the map is zoomed
+/// in further, this loader loads the ancestor tile from the source and cuts the required part of
+/// it, so that the tile is rendered at the display level of detail with correct line widths and
+/// symbol sizes. Tiles up to the data max zoom are loaded from the source as is.
+pub struct OverzoomVtLoader<Loader>
+where
+    Loader: VectorTileLoader + MaybeSend + MaybeSync,
+{
+    inner: Loader,
+    tile_schema: TileSchema,
+    data_max_zoom: u32,
+    source_tiles: quick_cache::sync::Cache<TileIndex, Arc<MvtTile>>,
+}
+
+impl<Loader> OverzoomVtLoader<Loader>
+where
+    Loader: VectorTileLoader + MaybeSend + MaybeSync,
+{
+    /// Create a new instance. The `tile_schema` must be the same as the one of the layer.
+    pub fn new(inner: Loader, tile_schema: TileSchema, data_max_zoom: u32) -> Self {
+        Self {
+            inner,
+            tile_schema,
+            data_max_zoom,
+            source_tiles: quick_cache::sync::Cache::new(OVERZOOM_CACHE_SIZE),
+        }
+    }
+
+    /// Max z-level of the source tiles.
+    pub fn data_max_zoom(&self) -> u32 {
+        self.data_max_zoom
+    }
+
+    /// Returns the part of the ancestor tile the tile with the given index covers, in normalized
+    /// coordinates of the ancestor tile.
+    fn sub_region(&self, ancestor: TileIndex, index: TileIndex) -> Option<Rect> {
+        let ancestor_bbox = self.tile_schema.tile_bbox(ancestor)?;
+        let bbox = self.tile_schema.tile_bbox(index)?;
+
+        Some(Rect::new(
+            (bbox.x_min() - ancestor_bbox.x_min()) / ancestor_bbox.width(),
+            (ancestor_bbox.y_max() - bbox.y_max()) / ancestor_bbox.height(),
+            (bbox.x_max() - ancestor_bbox.x_min()) / ancestor_bbox.width(),
+            (ancestor_bbox.y_max() - bbox.y_min()) / ancestor_bbox.height(),
+        ))
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl<Loader> VectorTileLoader for OverzoomVtLoader<Loader>
+where
+    Loader: VectorTileLoader + MaybeSend + MaybeSync,
+{
+    async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
+        if index.z <= self.data_max_zoom {
+            return self.inner.load(index).await;
+        }
+
+        let ancestor = self
+            .tile_schema
+            .get_ancestor(index, self.data_max_zoom)
+            .ok_or(TileLoadError::DoesNotExist)?;
+        let region = self
+            .sub_region(ancestor, index)
+            .ok_or(TileLoadError::DoesNotExist)?;
+
+        log::trace!("Cutting tile {index:?} from tile {ancestor:?}");
+
+        let source = self
+            .source_tiles
+            .get_or_insert_async(&ancestor, async {
+                self.inner.load(ancestor).await.map(Arc::new)
+            })
+            .await?;
+
+        Ok(cut_sub_tile(&source, region, OVERZOOM_BUFFER))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::vector_tile_layer::tiler::TilerFeature;
+    use galileo_mvt::MvtGeometry;
+    use galileo_types::cartesian::Point2d;
+    use galileo_types::geo::impls::GeoPoint2d;
+    use galileo_types::geo::Crs;
+    use galileo_types::geo::NewGeoPoint;
+    use galileo_types::impls::Contour;
+    use std::collections::HashMap;
+
+    fn loader() -> TilerVtLoader {
+        let point = GeoPoint2d::latlon(10.0, 10.0);
+        let line = Contour::open(vec![
+            Point2d::new(-1_000_000.0, -1_000_000.0),
+            Point2d::new(-2_000_000.0, -2_000_000.0),
+        ]);
+
+        let mut tiler = VectorTiler::new(TileSchema::web(4));
+        tiler.add_layer(
+            "points",
+            TilerFeature::project(&point, &Crs::EPSG3857, HashMap::new()),
+        );
+        tiler.add_layer("lines", [TilerFeature::new(line.into(), HashMap::new())]);
+        TilerVtLoader::new(Arc::new(tiler))
+    }
+
+    #[tokio::test]
+    async fn tiler_loader_cuts_tiles() {
+        let loader = loader();
+
+        let tile = loader.load(TileIndex::new(1, 0, 1)).await.unwrap();
+        assert_eq!(tile.layers.len(), 1);
+        assert_eq!(tile.layers[0].name, "points");
+        assert!(matches!(
+            tile.layers[0].features[0].geometry,
+            MvtGeometry::Point(_)
+        ));
+
+        let tile = loader.load(TileIndex::new(0, 1, 1)).await.unwrap();
+        assert_eq!(tile.layers.len(), 1);
+        assert_eq!(tile.layers[0].name, "lines");
+
+        let tile = loader.load(TileIndex::new(1, 1, 1)).await.unwrap();
+        assert!(tile.layers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn overzoomed_tiles_are_cut_from_tiler_tiles() {
+        let loader = OverzoomVtLoader::new(loader(), TileSchema::web(8), 1);
+
+        let tile = loader.load(TileIndex::new(2, 1, 2)).await.unwrap();
+        assert_eq!(tile.layers[0].name, "points");
+
+        let tile = loader.load(TileIndex::new(3, 0, 2)).await.unwrap();
+        assert!(tile.layers.is_empty());
+    }
+}
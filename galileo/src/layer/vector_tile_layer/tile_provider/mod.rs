@@ -2,17 +2,19 @@
 
 use crate::layer::vector_tile_layer::style::VectorTileStyle;
 use crate::layer::vector_tile_layer::vector_tile::VectorTile;
+use crate::layer::RetryPolicy;
 use crate::messenger::Messenger;
 use crate::render::render_bundle::RenderBundle;
 use crate::render::{Canvas, PackedBundle};
 use crate::tile_scheme::TileIndex;
 use galileo_mvt::MvtTile;
-use loader::VectorTileLoader;
+use loader::{TileLoadError, VectorTileLoader};
 use maybe_sync::{MaybeSend, MaybeSync};
 use processor::VectorTileProcessor;
 use quick_cache::unsync::Cache;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, MutexGuard, RwLock};
+use web_time::SystemTime;
 
 #[cfg(not(target_arch = "wasm32"))]
 mod threaded_provider;
@@ -50,6 +52,7 @@ where
     loader: Arc<Loader>,
     processor: Arc<Processor>,
     messenger: Option<Arc<dyn Messenger>>,
+    retry_policy: RetryPolicy,
 }
 
 impl<Loader, Processor> Clone for VectorTileProvider<Loader, Processor>
@@ -63,6 +66,7 @@ where
             loader: self.loader.clone(),
             processor: self.processor.clone(),
             messenger: self.messenger.clone(),
+            retry_policy: self.retry_policy,
         }
     }
 }
@@ -79,9 +83,15 @@ where
             loader,
             processor,
             messenger: None,
+            retry_policy: RetryPolicy::default(),
         }
     }
 
+    /// Sets the policy of repeating failed tile requests.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
     /// Return the style with the given id.
     pub fn get_style(&self, style_id: VtStyleId) -> Option<Arc<VectorTileStyle>> {
         self.processor.get_style(style_id)
@@ -110,11 +120,12 @@ where
         }
 
         let tile_store = self.tiles.clone();
-        if tile_store
-            .read()
-            .expect("lock is poisoned")
-            .contains(index, style_id)
-        {
+        let retry_policy = self.retry_policy;
+        if !tile_store.read().expect("lock is poisoned").needs_loading(
+            index,
+            style_id,
+            &retry_policy,
+        ) {
             return;
         }
 
@@ -127,15 +138,15 @@ where
         crate::async_runtime::spawn(async move {
             let cell = {
                 let mut store = tile_store.write().expect("lock is poisoned");
-                if store.contains(index, style_id) {
+                if !store.needs_loading(index, style_id, &retry_policy) {
                     return;
                 }
 
-                store.start_loading_tile(index, style_id)
+                store.start_loading_tile(index, style_id, &retry_policy)
             };
 
             let tile_state = cell
-                .get_or_init(|| async { Self::download(index, data_provider).await })
+                .get_or_init(|| async { Self::download(index, data_provider, retry_policy).await })
                 .await;
 
             log::debug!("Tile {index:?} is loaded. Preparing.");
@@ -197,10 +208,25 @@ where
         self.messenger = Some(messenger.into());
     }
 
-    async fn download(tile_index: TileIndex, loader: Arc<Loader>) -> MvtTileState {
-        match loader.load(tile_index).await {
+    async fn download(
+        tile_index: TileIndex,
+        loader: Arc<Loader>,
+        retry_policy: RetryPolicy,
+    ) -> MvtTileState {
+        let load_result = retry_policy
+            .run(|| loader.load(tile_index), TileLoadError::is_retryable)
+            .await;
+
+        match load_result {
             Ok(mvt_tile) => MvtTileState::Loaded(Arc::new(mvt_tile)),
-            Err(_) => MvtTileState::Error(),
+            Err(TileLoadError::DoesNotExist) => {
+                log::debug!("Vector tile {tile_index:?} does not exist");
+                MvtTileState::NotFound
+            }
+            Err(err) => {
+                log::info!("Failed to load vector tile {tile_index:?}: {err}");
+                MvtTileState::Error(SystemTime::now())
+            }
         }
     }
 
@@ -217,10 +243,11 @@ where
                     .await
                 {
                     Ok(render_bundle) => PreparedTileState::Loaded(Arc::new(render_bundle)),
-                    Err(_) => PreparedTileState::Error,
+                    Err(_) => PreparedTileState::Error(SystemTime::now()),
                 }
             }
-            MvtTileState::Error() => PreparedTileState::Error,
+            MvtTileState::Error(failed_at) => PreparedTileState::Error(*failed_at),
+            MvtTileState::NotFound => PreparedTileState::NotFound,
         }
     }
 }
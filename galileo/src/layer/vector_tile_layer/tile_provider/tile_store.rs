@@ -1,4 +1,5 @@
 use crate::layer::vector_tile_layer::tile_provider::VtStyleId;
+use crate::layer::RetryPolicy;
 use crate::render::render_bundle::RenderBundle;
 use crate::render::PackedBundle;
 use crate::tile_scheme::TileIndex;
@@ -9,6 +10,7 @@ use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::sync::{Arc, Weak};
 use tokio::sync::OnceCell;
+use web_time::SystemTime;
 
 const DEFAULT_CACHE_CAPACITY: usize = 100_000_000;
 const AVG_TILE_SIZE: usize = 100_000;
@@ -17,7 +19,10 @@ const EMPTY_CELL_SIZE: u32 = 1024;
 #[derive(Debug, Clone)]
 pub enum MvtTileState {
     Loaded(Arc<MvtTile>),
-    Error(),
+    /// Loading failed at the given time.
+    Error(SystemTime),
+    /// The tile does not exist in the source, so it is never requested again.
+    NotFound,
 }
 
 #[derive(Clone)]
@@ -25,7 +30,10 @@ pub enum PreparedTileState {
     Loading,
     Loaded(Arc<RenderBundle>),
     Packed(Arc<dyn PackedBundle>),
-    Error,
+    /// Loading or processing failed at the given time.
+    Error(SystemTime),
+    /// The tile does not exist in the source, so it is never requested again.
+    NotFound,
 }
 
 impl Debug for PreparedTileState {
@@ -34,7 +42,8 @@ impl Debug for PreparedTileState {
             PreparedTileState::Loading => write!(f, "PreparedTileState::Loading"),
             PreparedTileState::Loaded(_) => write!(f, "PreparedTileState::Loaded"),
             PreparedTileState::Packed(_) => write!(f, "PreparedTileState::Packed"),
-            PreparedTileState::Error => write!(f, "PreparedTileState::Error"),
+            PreparedTileState::Error(_) => write!(f, "PreparedTileState::Error"),
+            PreparedTileState::NotFound => write!(f, "PreparedTileState::NotFound"),
         }
     }
 }
@@ -120,19 +129,37 @@ impl TileStore {
         }
     }
 
-    pub fn contains(&self, tile_index: TileIndex, style_id: VtStyleId) -> bool {
-        self.processed.peek(&(tile_index, style_id)).is_some()
+    /// Returns true if the tile is not in the store, or if it failed to load and the error
+    /// cooldown of the `retry_policy` is over. Tiles that do not exist are never loaded again.
+    pub fn needs_loading(
+        &self,
+        tile_index: TileIndex,
+        style_id: VtStyleId,
+        retry_policy: &RetryPolicy,
+    ) -> bool {
+        match self.processed.peek(&(tile_index, style_id)) {
+            None => true,
+            Some(entry) => match entry.prepared_tile {
+                PreparedTileState::Error(failed_at) => retry_policy.is_cooldown_over(failed_at),
+                _ => false,
+            },
+        }
     }
 
     pub fn start_loading_tile(
         &mut self,
         index: TileIndex,
         style_id: VtStyleId,
+        retry_policy: &RetryPolicy,
     ) -> Arc<OnceCell<MvtTileState>> {
         let tile_cell = self
             .mvt_tiles
             .get(&index)
             .and_then(|v| v.upgrade())
+            .filter(|cell| match cell.get() {
+                Some(MvtTileState::Error(failed_at)) => !retry_policy.is_cooldown_over(*failed_at),
+                _ => true,
+            })
             .unwrap_or_default();
         self.mvt_tiles.insert(index, Arc::downgrade(&tile_cell));
 
@@ -227,6 +254,7 @@ mod tests {
     use super::*;
     use crate::render::render_bundle::tessellating::TessellatingRenderBundle;
     use crate::render::render_bundle::RenderBundleType;
+    use web_time::Duration;
 
     fn render_bundle(size: usize) -> RenderBundle {
         let mut bundle = RenderBundle(RenderBundleType::Tessellating(
@@ -245,8 +273,10 @@ mod tests {
     fn returns_same_mvt_tile_for_different_styles() {
         let mut store = TileStore::with_capacity(1_000_000);
         let index = TileIndex::new(0, 0, 0);
-        let mvt_cell = store.start_loading_tile(index, VtStyleId::next_id());
-        let another_mvt_cell = store.start_loading_tile(index, VtStyleId::next_id());
+        let mvt_cell =
+            store.start_loading_tile(index, VtStyleId::next_id(), &RetryPolicy::default());
+        let another_mvt_cell =
+            store.start_loading_tile(index, VtStyleId::next_id(), &RetryPolicy::default());
 
         assert!(
             Arc::ptr_eq(&mvt_cell, &another_mvt_cell),
@@ -254,6 +284,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn reloads_failed_tiles_after_cooldown() {
+        let mut store = TileStore::with_capacity(1_000_000);
+        let index = TileIndex::new(0, 0, 0);
+        let style_id = VtStyleId::next_id();
+        let policy = RetryPolicy::default().with_error_cooldown(Duration::from_secs(10));
+
+        assert!(store.needs_loading(index, style_id, &policy));
+
+        let failed_at = SystemTime::now() - Duration::from_secs(5);
+        let mvt_cell = store.start_loading_tile(index, style_id, &policy);
+        mvt_cell.set(MvtTileState::Error(failed_at)).unwrap();
+        store.store_tile(
+            index,
+            style_id,
+            mvt_cell.clone(),
+            PreparedTileState::Error(failed_at),
+        );
+
+        assert!(!store.needs_loading(index, style_id, &policy));
+        let same_cell = store.start_loading_tile(index, VtStyleId::next_id(), &policy);
+        assert!(Arc::ptr_eq(&mvt_cell, &same_cell));
+
+        let policy = policy.with_error_cooldown(Duration::from_secs(1));
+        assert!(store.needs_loading(index, style_id, &policy));
+        let new_cell = store.start_loading_tile(index, style_id, &policy);
+        assert!(!Arc::ptr_eq(&mvt_cell, &new_cell));
+        assert!(new_cell.get().is_none());
+    }
+
+    #[test]
+    fn does_not_reload_missing_tiles() {
+        let mut store = TileStore::with_capacity(1_000_000);
+        let index = TileIndex::new(0, 0, 0);
+        let style_id = VtStyleId::next_id();
+        let policy = RetryPolicy::default().with_error_cooldown(Duration::ZERO);
+
+        let mvt_cell = store.start_loading_tile(index, style_id, &policy);
+        mvt_cell.set(MvtTileState::NotFound).unwrap();
+        store.store_tile(
+            index,
+            style_id,
+            mvt_cell.clone(),
+            PreparedTileState::NotFound,
+        );
+
+        assert!(!store.needs_loading(index, style_id, &policy));
+        let same_cell = store.start_loading_tile(index, VtStyleId::next_id(), &policy);
+        assert!(Arc::ptr_eq(&mvt_cell, &same_cell));
+    }
+
     #[test]
     fn evicts_old_tiles() {
         const CAPACITY: u64 = 1_000_000;
@@ -297,7 +378,7 @@ mod tests {
         for i in 0..20 {
             let index = TileIndex::new(i, i, 10);
 
-            let mvt_cell = store.start_loading_tile(index, style_id);
+            let mvt_cell = store.start_loading_tile(index, style_id, &RetryPolicy::default());
             let prepared_tile = tile_with_size(ITEM_SIZE);
 
             store.store_tile(index, style_id, mvt_cell, prepared_tile);
@@ -326,7 +407,7 @@ mod tests {
             for i in 0..7 {
                 let index = TileIndex::new(i, i, 10);
 
-                let mvt_cell = store.start_loading_tile(index, style_id);
+                let mvt_cell = store.start_loading_tile(index, style_id, &RetryPolicy::default());
                 let prepared_tile = tile_with_size(ITEM_SIZE);
 
                 store.store_tile(index, style_id, mvt_cell, prepared_tile);
@@ -1,18 +1,128 @@
 use crate::error::GalileoError;
 use crate::layer::data_provider::DataProcessor;
-use crate::layer::vector_tile_layer::style::VectorTileStyle;
+use crate::layer::vector_tile_layer::style::{
+    format_template, VectorTileLabelSymbol, VectorTileStyle, VectorTileSymbol,
+};
+use crate::render::collision::CollisionOptions;
+use crate::render::line_label_paint::LineLabelPaint;
 use crate::render::point_paint::{PointPaint, PointShape};
 use crate::render::render_bundle::{RenderBundle, RenderPrimitive};
-use crate::render::{LineCap, LinePaint, PolygonPaint};
+use crate::render::sprite::SpriteAtlas;
+use crate::render::{LinePaint, PatternFill, PolygonPaint};
 use crate::tile_scheme::TileIndex;
 use crate::TileSchema;
 use bytes::Bytes;
 use galileo_mvt::{MvtFeature, MvtGeometry, MvtTile};
-use galileo_types::cartesian::{CartesianPoint2d, Point3d, Rect};
+use galileo_types::cartesian::{
+    CartesianClosedContour, CartesianPoint2d, CartesianPolygon, Point3d, Rect,
+};
 use galileo_types::impls::{ClosedContour, Polygon};
 use galileo_types::Contour;
+use nalgebra::Vector2;
 use num_traits::ToPrimitive;
-use strfmt::strfmt;
+use std::borrow::Cow;
+
+/// Geometries of a tile feature with the paint to draw them with.
+enum TilePrimitive<'a> {
+    Points(Cow<'a, [galileo_mvt::Point]>, PointPaint<'a>),
+    /// Icon and label drawn at the same points that take part in collision detection together.
+    Markers(
+        Cow<'a, [galileo_mvt::Point]>,
+        [PointPaint<'a>; 2],
+        CollisionOptions,
+    ),
+    Contours(
+        &'a [galileo_types::impls::Contour<galileo_mvt::Point>],
+        LinePaint,
+    ),
+    /// Polygons with the pattern of the style. Tiles are processed in web workers that don't share
+    /// the registry of [`PatternHandle`](crate::render::PatternHandle)s, so the pattern is passed
+    /// by reference.
+    Polygons(
+        &'a [Polygon<galileo_mvt::Point>],
+        PolygonPaint,
+        Option<&'a PatternFill>,
+    ),
+    LineLabels(
+        &'a [galileo_types::impls::Contour<galileo_mvt::Point>],
+        LineLabelPaint<'a>,
+    ),
+}
+
+/// Returns collision options of an icon and a label that are placed as one item. The item is placed
+/// with the higher priority and padding of the two, and may overlap other items only if both of them
+/// may.
+fn combined_collision(a: CollisionOptions, b: CollisionOptions) -> CollisionOptions {
+    CollisionOptions {
+        priority: a.priority.max(b.priority),
+        padding: a.padding.max(b.padding),
+        allow_overlap: a.allow_overlap && b.allow_overlap,
+    }
+}
+
+/// Returns a point inside the largest of the given polygons to place a label at.
+///
+/// The centroid of the outer contour is used if it lies inside the polygon. Otherwise, the point is
+/// placed in the middle of the widest part of the polygon along the horizontal line through the
+/// center of the polygon's bounding box.
+fn label_point(polygons: &[Polygon<galileo_mvt::Point>]) -> Option<galileo_mvt::Point> {
+    let polygon = polygons.iter().max_by(|a, b| {
+        let area = |p: &Polygon<galileo_mvt::Point>| p.outer_contour.area_signed().abs();
+        area(a).total_cmp(&area(b))
+    })?;
+
+    if let Some(centroid) = contour_centroid(&polygon.outer_contour.points) {
+        if polygon.contains_point(&centroid) {
+            return Some(centroid);
+        }
+    }
+
+    let (y_min, y_max) = polygon
+        .outer_contour
+        .points
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), p| {
+            (min.min(p.y), max.max(p.y))
+        });
+    let y = (y_min + y_max) / 2.0;
+
+    let mut crossings: Vec<f32> = std::iter::once(&polygon.outer_contour)
+        .chain(&polygon.inner_contours)
+        .flat_map(|contour| {
+            let points = &contour.points;
+            points.iter().zip(points.iter().cycle().skip(1))
+        })
+        .filter(|(from, to)| (from.y > y) != (to.y > y))
+        .map(|(from, to)| from.x + (y - from.y) / (to.y - from.y) * (to.x - from.x))
+        .collect();
+    crossings.sort_by(f32::total_cmp);
+
+    crossings
+        .chunks_exact(2)
+        .max_by(|a, b| (a[1] - a[0]).total_cmp(&(b[1] - b[0])))
+        .map(|pair| galileo_mvt::Point::new((pair[0] + pair[1]) / 2.0, y))
+}
+
+fn contour_centroid(points: &[galileo_mvt::Point]) -> Option<galileo_mvt::Point> {
+    let mut area = 0.0;
+    let mut x = 0.0;
+    let mut y = 0.0;
+    for (from, to) in points.iter().zip(points.iter().cycle().skip(1)) {
+        let cross = (from.x * to.y - to.x * from.y) as f64;
+        area += cross;
+        x += (from.x + to.x) as f64 * cross;
+        y += (from.y + to.y) as f64 * cross;
+    }
+
+    if area.abs() < f64::EPSILON {
+        return None;
+    }
+
+    Some(galileo_mvt::Point::new(
+        (x / (3.0 * area)) as f32,
+        (y / (3.0 * area)) as f32,
+    ))
+}
 
 /// Data processor that decodes vector tiles.
 pub struct VtProcessor {}
@@ -92,81 +202,107 @@ impl VtProcessor {
         bundle.add(
             RenderPrimitive::<_, _, galileo_types::impls::Contour<_>, _>::new_polygon_ref(
                 &bounds,
-                PolygonPaint {
-                    color: style.background,
-                },
+                PolygonPaint::new(style.background),
             ),
             lod_resolution,
         );
 
+        // Primitives are collected first and then added to the bundle ordered by the sort key of
+        // the rule they were created with, so that the draw order holds across all the layers of
+        // the tile.
+        let mut primitives = vec![];
         for layer in &mvt_tile.layers {
+            if !style.is_layer_visible(&layer.name, lod_resolution) {
+                continue;
+            }
+
             for feature in &layer.features {
-                match &feature.geometry {
-                    MvtGeometry::Point(points) => {
-                        // let label = if feature.properties.contains_key("name") {
-                        //     feature.properties.get("name").as_ref().unwrap().to_string()
-                        // } else {
-                        //     let Some(value) = feature.properties.values().next() else {
-                        //         continue;
-                        //     };
-                        //
-                        //     value.to_string()
-                        // };
-                        //
-                        // let label: String = label.chars().take(20).collect();
-                        // let style = TextStyle {
-                        //     font_name: "Noto Sans".into(),
-                        //     font_size: 20.0,
-                        //     font_color: Color::RED,
-                        //     horizontal_alignment: HorizontalAlignment::Left,
-                        //     vertical_alignment: VerticalAlignment::Top,
-                        // };
-
-                        let Some(paint) = Self::get_point_symbol(style, &layer.name, feature)
-                        else {
-                            continue;
-                        };
-
-                        for point in points {
-                            // let paint = PointPaint::label(&label, &style);
-                            bundle.add(RenderPrimitive::<_, _, galileo_types::impls::Contour<_>, Polygon<_>>::new_point_ref(&Self::transform_point(point, bbox, tile_resolution), &paint), lod_resolution);
+                match style.rules_for_feature(&layer.name, feature, lod_resolution) {
+                    Some(rules) => {
+                        for rule in rules {
+                            Self::add_primitives(
+                                &mut primitives,
+                                rule.sort_key,
+                                &rule.symbol,
+                                style.sprite_atlas(),
+                                feature,
+                                lod_resolution,
+                            );
                         }
                     }
-                    MvtGeometry::LineString(contours) => {
-                        if let Some(paint) = Self::get_line_symbol(style, &layer.name, feature) {
-                            for contour in contours {
-                                bundle.add(
-                                    RenderPrimitive::<_, _, _, Polygon<_>>::new_contour_ref(
-                                        &galileo_types::impls::Contour::new(
-                                            contour
-                                                .iter_points()
-                                                .map(|p| {
-                                                    Self::transform_point(p, bbox, tile_resolution)
-                                                })
-                                                .collect(),
-                                            false,
-                                        ),
-                                        paint,
-                                    ),
-                                    lod_resolution,
-                                );
-                            }
-                        }
+                    None => Self::add_primitives(
+                        &mut primitives,
+                        0,
+                        &style.default_symbol,
+                        style.sprite_atlas(),
+                        feature,
+                        lod_resolution,
+                    ),
+                }
+            }
+        }
+
+        primitives.sort_by_key(|(sort_key, _)| *sort_key);
+
+        for (_, primitive) in &primitives {
+            match primitive {
+                TilePrimitive::Points(points, paint) => {
+                    for point in points.iter() {
+                        bundle.add(RenderPrimitive::<_, _, galileo_types::impls::Contour<_>, Polygon<_>>::new_point_ref(&Self::transform_point(point, bbox, tile_resolution), paint), lod_resolution);
                     }
-                    MvtGeometry::Polygon(polygons) => {
-                        if let Some(paint) = Self::get_polygon_symbol(style, &layer.name, feature) {
-                            for polygon in polygons {
-                                bundle.add(
-                                    RenderPrimitive::<_, _, galileo_types::impls::Contour<_>, _>::new_polygon_ref(
-                                        &polygon.cast_points(|p| {
-                                            Self::transform_point(p, bbox, tile_resolution)
-                                        }),
-                                        paint,
-                                    ),
-                                    lod_resolution,
-                                );
-                            }
-                        }
+                }
+                TilePrimitive::Markers(points, [icon, label], options) => {
+                    for point in points.iter() {
+                        bundle.add_point_group(
+                            &Self::transform_point(point, bbox, tile_resolution),
+                            &[icon, label],
+                            *options,
+                        );
+                    }
+                }
+                TilePrimitive::Contours(contours, paint) => {
+                    for contour in *contours {
+                        bundle.add(
+                            RenderPrimitive::<_, _, _, Polygon<_>>::new_contour_ref(
+                                &galileo_types::impls::Contour::new(
+                                    contour
+                                        .iter_points()
+                                        .map(|p| Self::transform_point(p, bbox, tile_resolution))
+                                        .collect(),
+                                    false,
+                                ),
+                                *paint,
+                            ),
+                            lod_resolution,
+                        );
+                    }
+                }
+                TilePrimitive::Polygons(polygons, paint, pattern) => {
+                    for polygon in *polygons {
+                        bundle.add_pattern_polygon(
+                            &polygon
+                                .cast_points(|p| Self::transform_point(p, bbox, tile_resolution)),
+                            *paint,
+                            *pattern,
+                            lod_resolution,
+                        );
+                    }
+                }
+                TilePrimitive::LineLabels(contours, paint) => {
+                    for contour in *contours {
+                        bundle.add(
+                            RenderPrimitive::<_, _, _, Polygon<_>>::new_line_label_ref(
+                                &galileo_types::impls::Contour::new(
+                                    contour
+                                        .iter_points()
+                                        .map(|p| Self::transform_point(p, bbox, tile_resolution))
+                                        .collect(),
+                                    false,
+                                ),
+                                paint,
+                            ),
+                            lod_resolution,
+                        );
                     }
                 }
             }
@@ -175,71 +311,158 @@ impl VtProcessor {
         Ok(())
     }
 
-    fn get_point_symbol<'a>(
-        style: &'a VectorTileStyle,
-        layer_name: &str,
+    fn add_primitives<'a>(
+        primitives: &mut Vec<(i32, TilePrimitive<'a>)>,
+        sort_key: i32,
+        symbol: &'a VectorTileSymbol,
+        sprite: Option<&SpriteAtlas>,
+        feature: &'a MvtFeature,
+        resolution: f64,
+    ) {
+        let primitive = match &feature.geometry {
+            MvtGeometry::Point(points) => Self::get_point_symbol(symbol, feature, resolution)
+                .map(|paint| TilePrimitive::Points(Cow::Borrowed(points), paint)),
+            MvtGeometry::LineString(contours) => Self::get_line_symbol(symbol, resolution)
+                .map(|paint| TilePrimitive::Contours(contours, paint)),
+            MvtGeometry::Polygon(polygons) => Self::get_polygon_symbol(symbol, resolution)
+                .map(|(paint, pattern)| TilePrimitive::Polygons(polygons, paint, pattern)),
+        };
+        primitives.extend(primitive.map(|primitive| (sort_key, primitive)));
+
+        let has_markers = symbol.label.is_some() || symbol.icon.is_some();
+        let marker_points = match &feature.geometry {
+            MvtGeometry::Point(points) => Some(Cow::Borrowed(points.as_slice())),
+            MvtGeometry::Polygon(polygons) if has_markers => {
+                label_point(polygons).map(|point| Cow::Owned(vec![point]))
+            }
+            _ => None,
+        };
+
+        let label = Self::get_label_text(symbol, feature);
+        let Some(points) = marker_points else {
+            if let (MvtGeometry::LineString(contours), Some((label, text))) =
+                (&feature.geometry, label)
+            {
+                let paint = LineLabelPaint::new(text, &label.text_style)
+                    .with_spacing(label.spacing)
+                    .with_max_angle(label.max_angle)
+                    .with_collision(label.collision);
+                primitives.push((sort_key, TilePrimitive::LineLabels(contours, paint)));
+            }
+
+            return;
+        };
+
+        let icon = Self::get_icon_paint(symbol, sprite, feature);
+        let label = label.map(|(label, text)| Self::get_point_label_paint(label, text));
+        let primitive = match (icon, label) {
+            (Some((icon, icon_collision)), Some((label, label_collision))) => {
+                TilePrimitive::Markers(
+                    points,
+                    [icon, label],
+                    combined_collision(icon_collision, label_collision),
+                )
+            }
+            (Some((paint, collision)), None) | (None, Some((paint, collision))) => {
+                TilePrimitive::Points(points, paint.with_collision(collision))
+            }
+            (None, None) => return,
+        };
+        primitives.push((sort_key, primitive));
+    }
+
+    /// Returns the icon paint without collision options and the collision options of the icon.
+    fn get_icon_paint<'a>(
+        symbol: &VectorTileSymbol,
+        sprite: Option<&SpriteAtlas>,
         feature: &MvtFeature,
-    ) -> Option<PointPaint<'a>> {
-        let mut paint = Self::get_point_paint(style, layer_name, feature)?.clone();
-        if let PointShape::Label { text, .. } = &mut paint.shape {
-            let formatted = strfmt(text, &feature.properties).ok()?;
-            *text.to_mut() = formatted;
+    ) -> Option<(PointPaint<'a>, CollisionOptions)> {
+        let symbol = symbol.icon.as_ref()?;
+        let name = format_template(&symbol.name, &feature.properties)?;
+        let icon = sprite?.icon(&name)?;
+        let anchor = Vector2::new(symbol.anchor[0], symbol.anchor[1]);
+
+        Some((
+            PointPaint::icon(&icon, anchor, symbol.scale),
+            symbol.collision,
+        ))
+    }
+
+    /// Returns the label paint without collision options and the collision options of the label.
+    fn get_point_label_paint(
+        label: &VectorTileLabelSymbol,
+        text: String,
+    ) -> (PointPaint<'_>, CollisionOptions) {
+        let mut paint = PointPaint::label_with_style(text, &label.text_style)
+            .with_offset(Vector2::new(label.offset[0], label.offset[1]));
+        if let Some([x, y]) = label.anchor {
+            paint = paint.with_label_anchor(Vector2::new(x, y));
         }
 
-        Some(paint)
+        (paint, label.collision)
     }
 
-    fn get_point_paint<'a>(
-        style: &'a VectorTileStyle,
-        layer_name: &str,
+    fn get_label_text<'a>(
+        symbol: &'a VectorTileSymbol,
         feature: &MvtFeature,
-    ) -> Option<&'a PointPaint<'a>> {
-        let Some(rule) = style.get_style_rule(layer_name, feature) else {
-            return style.default_symbol.point.as_ref();
-        };
+    ) -> Option<(&'a VectorTileLabelSymbol, String)> {
+        let symbol = symbol.label.as_ref()?;
+        let text = format_template(&symbol.pattern, &feature.properties)?;
+        if text.is_empty() {
+            return None;
+        }
 
-        rule.symbol.point.as_ref()
+        Some((symbol, text))
     }
 
-    fn get_line_symbol(
-        style: &VectorTileStyle,
-        layer_name: &str,
+    fn get_point_symbol<'a>(
+        symbol: &'a VectorTileSymbol,
         feature: &MvtFeature,
-    ) -> Option<LinePaint> {
-        let Some(rule) = style.get_style_rule(layer_name, feature) else {
-            let symbol = style.default_symbol.line.as_ref()?;
-            return Some(LinePaint {
-                width: symbol.width,
-                color: symbol.stroke_color,
-                offset: 0.0,
-                line_cap: LineCap::Butt,
-            });
-        };
+        resolution: f64,
+    ) -> Option<PointPaint<'a>> {
+        let mut paint = symbol.point.as_ref()?.clone();
+        if let PointShape::Label { text, .. } = &mut paint.shape {
+            let formatted = format_template(text, &feature.properties)?;
+            *text.to_mut() = formatted;
+        }
+
+        if let Some(scale) = &symbol.point_scale {
+            paint = paint.scaled(scale.get(resolution)? as f32);
+        }
+
+        if let Some(opacity) = &symbol.point_opacity {
+            paint = paint.with_opacity(opacity.get(resolution)? as f32);
+        }
+
+        Some(paint)
+    }
 
-        let symbol = rule.symbol.line.as_ref()?;
+    fn get_line_symbol(symbol: &VectorTileSymbol, resolution: f64) -> Option<LinePaint> {
+        let symbol = symbol.line.as_ref()?;
+        let color = symbol.stroke_color.get(resolution)?;
 
         Some(LinePaint {
-            width: symbol.width,
-            color: symbol.stroke_color,
+            width: symbol.width.get(resolution)?,
+            color: color.with_opacity(symbol.opacity.get(resolution)?),
             offset: 0.0,
-            line_cap: LineCap::Butt,
+            line_cap: symbol.line_cap,
+            line_join: symbol.line_join,
+            miter_limit: symbol.miter_limit,
+            dash_array: symbol.dash_array,
         })
     }
 
     fn get_polygon_symbol(
-        style: &VectorTileStyle,
-        layer_name: &str,
-        feature: &MvtFeature,
-    ) -> Option<PolygonPaint> {
-        let Some(rule) = style.get_style_rule(layer_name, feature) else {
-            return Some(PolygonPaint {
-                color: style.default_symbol.polygon.as_ref()?.fill_color,
-            });
-        };
+        symbol: &VectorTileSymbol,
+        resolution: f64,
+    ) -> Option<(PolygonPaint, Option<&PatternFill>)> {
+        let symbol = symbol.polygon.as_ref()?;
+        let color = symbol.fill_color.get(resolution)?;
 
-        Some(PolygonPaint {
-            color: rule.symbol.polygon.as_ref()?.fill_color,
-        })
+        Some((
+            PolygonPaint::new(color.with_opacity(symbol.opacity.get(resolution)?)),
+            symbol.pattern.as_ref(),
+        ))
     }
 
     fn transform_point<Num: num_traits::Float + ToPrimitive>(
@@ -252,3 +475,196 @@ impl VtProcessor {
         Point3d::new(x, y, 0.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoded_image::DecodedImage;
+    use crate::layer::vector_tile_layer::style::{
+        maplibre, VectorTileIconSymbol, VectorTilePolygonSymbol,
+    };
+    use crate::render::text::TextStyle;
+    use crate::Color;
+    use galileo_mvt::{MvtValue, Point};
+    use std::sync::Arc;
+
+    fn polygon(points: &[(f32, f32)]) -> Polygon<Point> {
+        Polygon::new(
+            ClosedContour::new(points.iter().map(|&(x, y)| Point::new(x, y)).collect()),
+            vec![],
+        )
+    }
+
+    #[test]
+    fn label_point_of_convex_polygon_is_centroid() {
+        let small = polygon(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        let large = polygon(&[(10.0, 10.0), (20.0, 10.0), (20.0, 30.0), (10.0, 30.0)]);
+
+        assert_eq!(label_point(&[small, large]), Some(Point::new(15.0, 20.0)));
+    }
+
+    #[test]
+    fn label_point_is_inside_concave_polygon() {
+        // U-shaped polygon, its centroid lies outside of the polygon.
+        let u_shape = polygon(&[
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (8.0, 10.0),
+            (8.0, 2.0),
+            (2.0, 2.0),
+            (2.0, 10.0),
+            (0.0, 10.0),
+        ]);
+
+        let point = label_point(std::slice::from_ref(&u_shape)).unwrap();
+        assert!(u_shape.contains_point(&point));
+    }
+
+    #[test]
+    fn no_label_point_for_empty_feature() {
+        assert_eq!(label_point(&[]), None);
+    }
+
+    #[test]
+    fn polygon_pattern_is_taken_from_style() {
+        let image = Arc::new(DecodedImage::from_raw(vec![255; 16], 2, 2).unwrap());
+        let symbol = VectorTileSymbol {
+            polygon: Some(VectorTilePolygonSymbol {
+                fill_color: Color::WHITE.into(),
+                opacity: 1.0.into(),
+                pattern: Some(PatternFill::new(image.clone())),
+            }),
+            ..Default::default()
+        };
+        let feature = MvtFeature {
+            id: None,
+            properties: Default::default(),
+            geometry: MvtGeometry::Polygon(vec![polygon(&[
+                (0.0, 0.0),
+                (1.0, 0.0),
+                (1.0, 1.0),
+                (0.0, 1.0),
+            ])]),
+        };
+
+        let mut primitives = vec![];
+        VtProcessor::add_primitives(&mut primitives, 0, &symbol, None, &feature, 1.0);
+
+        let [(_, TilePrimitive::Polygons(_, paint, Some(pattern)))] = &primitives[..] else {
+            panic!("pattern is not set");
+        };
+        assert!(paint.pattern.is_none());
+        assert!(Arc::ptr_eq(&pattern.image, &image));
+    }
+
+    #[test]
+    fn icon_and_label_are_one_collision_item() {
+        let image = DecodedImage::from_raw(vec![0; 4 * 4 * 4], 4, 4).unwrap();
+        let sprite = SpriteAtlas::from_json(
+            r#"{"bus": {"x": 0, "y": 0, "width": 4, "height": 4}}"#,
+            image,
+        )
+        .unwrap();
+        let symbol = VectorTileSymbol {
+            icon: Some(VectorTileIconSymbol {
+                name: "bus".into(),
+                scale: 1.0,
+                anchor: [0.5, 1.0],
+                collision: CollisionOptions {
+                    priority: 1.0,
+                    allow_overlap: true,
+                    ..Default::default()
+                },
+            }),
+            label: Some(VectorTileLabelSymbol {
+                pattern: "{name}".into(),
+                text_style: TextStyle {
+                    font_name: "Noto Sans".into(),
+                    font_size: 12.0,
+                    font_color: Color::BLACK,
+                    horizontal_alignment: Default::default(),
+                    vertical_alignment: Default::default(),
+                },
+                collision: CollisionOptions {
+                    padding: 2.0,
+                    ..Default::default()
+                },
+                spacing: LineLabelPaint::DEFAULT_SPACING,
+                max_angle: LineLabelPaint::DEFAULT_MAX_ANGLE,
+                offset: [0.0, -2.0],
+                anchor: Some([0.5, 0.0]),
+            }),
+            ..Default::default()
+        };
+        let feature = MvtFeature {
+            id: None,
+            properties: [("name".to_string(), MvtValue::String("Stop".into()))].into(),
+            geometry: MvtGeometry::Point(vec![Point::new(1.0, 1.0)]),
+        };
+
+        let mut primitives = vec![];
+        VtProcessor::add_primitives(&mut primitives, 0, &symbol, Some(&sprite), &feature, 1.0);
+
+        let [(_, TilePrimitive::Markers(points, [icon, label], options))] = &primitives[..] else {
+            panic!("icon and label are not grouped");
+        };
+        assert_eq!(points.len(), 1);
+        assert!(icon.collision.is_none());
+        assert!(label.collision.is_none());
+        assert_eq!(label.offset, Vector2::new(0.0, -2.0));
+        assert!(matches!(
+            label.shape,
+            PointShape::Label { anchor: Some(anchor), .. } if anchor == Vector2::new(0.5, 0.0)
+        ));
+        assert_eq!(
+            *options,
+            CollisionOptions {
+                priority: 1.0,
+                padding: 2.0,
+                allow_overlap: false,
+            }
+        );
+
+        let mut primitives = vec![];
+        VtProcessor::add_primitives(&mut primitives, 0, &symbol, None, &feature, 1.0);
+
+        let [(_, TilePrimitive::Points(_, label))] = &primitives[..] else {
+            panic!("label is not added");
+        };
+        assert_eq!(
+            label.collision,
+            Some(CollisionOptions {
+                padding: 2.0,
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn label_with_colon_in_property_name() {
+        let json = r#"{"layers": [{"id": "places", "type": "symbol", "source-layer": "place",
+            "layout": {"text-field": "{name:latin}"}}]}"#;
+        let style = maplibre::convert(json).unwrap().style;
+        let feature = MvtFeature {
+            id: None,
+            properties: [("name:latin".to_string(), MvtValue::String("Roma".into()))].into(),
+            geometry: MvtGeometry::Point(vec![Point::new(1.0, 1.0)]),
+        };
+
+        let mut primitives = vec![];
+        VtProcessor::add_primitives(
+            &mut primitives,
+            0,
+            &style.rules[0].symbol,
+            None,
+            &feature,
+            1.0,
+        );
+
+        let [(_, TilePrimitive::Points(_, label))] = &primitives[..] else {
+            panic!("label is not added");
+        };
+        assert!(matches!(&label.shape, PointShape::Label { text, .. } if text.as_str() == "Roma"));
+    }
+}
@@ -0,0 +1,1249 @@
+//! [`VectorTiler`] cuts collections of features into [vector tiles](MvtTile) of a [`TileSchema`].
+//!
+//! For every tile the features are clipped by the tile boundaries (plus a small buffer), simplified
+//! and quantised to the integer grid of the tile. The tiler can be used to build a whole tile
+//! pyramid with [`VectorTiler::generate`], or to cut single tiles on request with
+//! [`VectorTiler::tile`].
+
+use std::collections::{BTreeSet, HashMap};
+use std::ops::RangeInclusive;
+
+use galileo_mvt::{MvtFeature, MvtGeometry, MvtLayer, MvtTile, MvtValue};
+use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
+use galileo_types::contour::Contour as _;
+use galileo_types::geo::{Crs, NewGeoPoint};
+use galileo_types::geometry::{CartesianGeometry2d, Geom, Geometry};
+use galileo_types::impls::{ClosedContour, Contour, Polygon};
+use galileo_types::MultiContour as _;
+use galileo_types::MultiPoint as _;
+use nalgebra::Point2;
+
+use crate::error::GalileoError;
+use crate::tile_scheme::{TileIndex, TileSchema, VerticalDirection};
+
+/// Feature that can be cut into vector tiles by the [`VectorTiler`].
+#[derive(Debug, Clone)]
+pub struct TilerFeature {
+    /// Identifier of the feature, written into the tiles as is.
+    pub id: Option<u64>,
+    /// Geometry of the feature in the CRS of the tile schema.
+    pub geometry: Geom<Point2d>,
+    /// Attributes of the feature.
+    pub properties: HashMap<String, MvtValue>,
+}
+
+impl TilerFeature {
+    /// Creates a new feature without an id.
+    pub fn new(geometry: Geom<Point2d>, properties: HashMap<String, MvtValue>) -> Self {
+        Self {
+            id: None,
+            geometry,
+            properties,
+        }
+    }
+
+    /// Sets the id of the feature.
+    pub fn with_id(mut self, id: u64) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Creates a feature from a geometry with geographic coordinates, projecting it into the
+    /// given CRS.
+    ///
+    /// Returns `None` if the geometry cannot be projected into the CRS.
+    pub fn project<G>(
+        geometry: &G,
+        crs: &Crs,
+        properties: HashMap<String, MvtValue>,
+    ) -> Option<Self>
+    where
+        G: Geometry,
+        G::Point: NewGeoPoint + 'static,
+    {
+        let projection = crs.get_projection::<G::Point, Point2d>()?;
+        Some(Self::new(geometry.project(&*projection)?, properties))
+    }
+
+    /// Converts a GeoJSON feature into a tiler feature with geometry projected into the given CRS.
+    ///
+    /// Numeric ids are preserved. Properties with `null` values are skipped, arrays and objects
+    /// are written as JSON strings.
+    ///
+    /// Returns `None` if the feature has no geometry or it cannot be projected.
+    #[cfg(feature = "geojson")]
+    pub fn from_geojson(feature: &geojson::Feature, crs: &Crs) -> Option<Self> {
+        let properties = feature
+            .properties
+            .iter()
+            .flatten()
+            .filter_map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::Null => return None,
+                    serde_json::Value::Bool(v) => MvtValue::Bool(*v),
+                    serde_json::Value::Number(v) => {
+                        if let Some(v) = v.as_u64() {
+                            MvtValue::Uint64(v)
+                        } else if let Some(v) = v.as_i64() {
+                            MvtValue::Int64(v)
+                        } else {
+                            MvtValue::Double(v.as_f64()?)
+                        }
+                    }
+                    serde_json::Value::String(v) => MvtValue::String(v.clone()),
+                    v => MvtValue::String(v.to_string()),
+                };
+
+                Some((key.clone(), value))
+            })
+            .collect();
+
+        let mut result = Self::project(feature.geometry.as_ref()?, crs, properties)?;
+        if let Some(geojson::feature::Id::Number(id)) = &feature.id {
+            result.id = id.as_u64();
+        }
+
+        Some(result)
+    }
+}
+
+/// Parameters of tile generation.
+///
+/// Distances are given in the tile coordinate units, a tile being `extent` units wide and high.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TilerOptions {
+    /// Size of the integer grid the tile coordinates are quantised to.
+    pub extent: u32,
+    /// Width of the area around a tile that is still included into the tile. Clipping geometries
+    /// with a buffer hides the artifacts of the cut at the tile borders when the tile is rendered.
+    pub buffer: u32,
+    /// Maximum deviation of a simplified geometry from the original one. Zero disables
+    /// simplification.
+    pub tolerance: f64,
+}
+
+impl Default for TilerOptions {
+    fn default() -> Self {
+        Self {
+            extent: 4096,
+            buffer: 64,
+            tolerance: 1.0,
+        }
+    }
+}
+
+/// Destination for the tiles produced by [`VectorTiler::generate`].
+///
+/// Tiles can be collected into a `HashMap`, written into a directory with [`DirectoryTileSink`]
+/// or into a PMTiles archive with `PmTilesWriter` (requires the `pmtiles` feature). Implement this
+/// trait to write the tiles into a database or a remote storage.
+pub trait TileSink {
+    /// Stores the tile with the given index.
+    fn write_tile(&mut self, index: TileIndex, tile: MvtTile) -> Result<(), GalileoError>;
+}
+
+impl TileSink for HashMap<TileIndex, MvtTile> {
+    fn write_tile(&mut self, index: TileIndex, tile: MvtTile) -> Result<(), GalileoError> {
+        self.insert(index, tile);
+        Ok(())
+    }
+}
+
+/// Writes encoded tiles into a directory as `{z}/{x}/{y}.mvt` files.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct DirectoryTileSink {
+    path: std::path::PathBuf,
+    extension: String,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DirectoryTileSink {
+    /// Creates a new sink writing to the given directory. The directory is created when the first
+    /// tile is written.
+    pub fn new(path: impl AsRef<std::path::Path>) -> Self {
+        Self {
+            path: path.as_ref().into(),
+            extension: "mvt".into(),
+        }
+    }
+
+    /// Sets the extension of the tile files, `mvt` by default.
+    pub fn with_extension(mut self, extension: impl Into<String>) -> Self {
+        self.extension = extension.into();
+        self
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TileSink for DirectoryTileSink {
+    fn write_tile(&mut self, index: TileIndex, tile: MvtTile) -> Result<(), GalileoError> {
+        let folder = self
+            .path
+            .join(index.z.to_string())
+            .join(index.x.to_string());
+        std::fs::create_dir_all(&folder)?;
+
+        let file_path = folder.join(format!("{}.{}", index.y, self.extension));
+        std::fs::write(&file_path, tile.encode())?;
+        log::trace!("Tile {index:?} written to {file_path:?}");
+
+        Ok(())
+    }
+}
+
+struct TilerLayer {
+    name: String,
+    features: Vec<TilerFeature>,
+    bboxes: Vec<Rect>,
+    index: BboxIndex,
+}
+
+/// Number of children of a node of the [`BboxIndex`].
+const INDEX_NODE_SIZE: usize = 16;
+
+/// Static R-tree of bounding boxes packed with the Sort-Tile-Recursive algorithm.
+///
+/// The tree is stored by levels: the first level contains the bounding boxes of the items, and
+/// every node of a next level covers [`INDEX_NODE_SIZE`] consecutive nodes of the previous one.
+struct BboxIndex {
+    items: Vec<usize>,
+    levels: Vec<Vec<Rect>>,
+}
+
+impl BboxIndex {
+    fn new(bboxes: &[Rect]) -> Self {
+        let mut items: Vec<usize> = (0..bboxes.len()).collect();
+        let center_x = |index: &usize| bboxes[*index].x_min() + bboxes[*index].x_max();
+        let center_y = |index: &usize| bboxes[*index].y_min() + bboxes[*index].y_max();
+
+        let leaf_count = bboxes.len().div_ceil(INDEX_NODE_SIZE);
+        let slice_size = (leaf_count as f64).sqrt().ceil() as usize * INDEX_NODE_SIZE;
+        items.sort_by(|a, b| center_x(a).total_cmp(&center_x(b)));
+        for slice in items.chunks_mut(slice_size.max(1)) {
+            slice.sort_by(|a, b| center_y(a).total_cmp(&center_y(b)));
+        }
+
+        let mut levels = vec![items.iter().map(|index| bboxes[*index]).collect::<Vec<_>>()];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let next = level
+                .chunks(INDEX_NODE_SIZE)
+                .map(|nodes| {
+                    nodes
+                        .iter()
+                        .skip(1)
+                        .fold(nodes[0], |bbox, node| bbox.merge(*node))
+                })
+                .collect();
+            levels.push(next);
+        }
+
+        Self { items, levels }
+    }
+
+    /// Returns the indices of the items with bounding boxes intersecting the `bbox`, in the order
+    /// the items were given.
+    fn query(&self, bbox: &Rect) -> Vec<usize> {
+        let mut result = vec![];
+        let Some(top) = self.levels.len().checked_sub(1) else {
+            return result;
+        };
+
+        let mut stack: Vec<(usize, usize)> = (0..self.levels[top].len())
+            .map(|node| (top, node))
+            .collect();
+        while let Some((level, node)) = stack.pop() {
+            if !rects_intersect(bbox, &self.levels[level][node]) {
+                continue;
+            }
+
+            if level == 0 {
+                result.push(self.items[node]);
+            } else {
+                let children = node * INDEX_NODE_SIZE
+                    ..((node + 1) * INDEX_NODE_SIZE).min(self.levels[level - 1].len());
+                stack.extend(children.map(|child| (level - 1, child)));
+            }
+        }
+
+        result.sort_unstable();
+        result
+    }
+}
+
+/// Cuts features into vector tiles.
+///
+/// Features are grouped into named layers, each of which becomes an [`MvtLayer`] in every tile
+/// it has features in.
+///
+/// To render the features with a [`VectorTileLayer`](super::VectorTileLayer) without generating
+/// the tiles beforehand, use the tiler with a
+/// [`TilerVtLoader`](super::tile_provider::loader::TilerVtLoader).
+pub struct VectorTiler {
+    tile_schema: TileSchema,
+    options: TilerOptions,
+    layers: Vec<TilerLayer>,
+}
+
+impl VectorTiler {
+    /// Creates a new tiler without any features.
+    pub fn new(tile_schema: TileSchema) -> Self {
+        Self {
+            tile_schema,
+            options: TilerOptions::default(),
+            layers: vec![],
+        }
+    }
+
+    /// Sets the parameters of tile generation.
+    pub fn with_options(mut self, options: TilerOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Tile schema the tiles are generated for.
+    pub fn tile_schema(&self) -> &TileSchema {
+        &self.tile_schema
+    }
+
+    /// Parameters of tile generation.
+    pub fn options(&self) -> TilerOptions {
+        self.options
+    }
+
+    /// Adds a layer with the given name and features. Features with empty geometries are skipped.
+    pub fn add_layer(
+        &mut self,
+        name: impl Into<String>,
+        features: impl IntoIterator<Item = TilerFeature>,
+    ) {
+        let mut layer = TilerLayer {
+            name: name.into(),
+            features: vec![],
+            bboxes: vec![],
+            index: BboxIndex::new(&[]),
+        };
+
+        for feature in features {
+            if let Some(bbox) = feature.geometry.bounding_rectangle() {
+                layer.features.push(feature);
+                layer.bboxes.push(bbox);
+            }
+        }
+
+        layer.index = BboxIndex::new(&layer.bboxes);
+        self.layers.push(layer);
+    }
+
+    /// Cuts the tile with the given index. Returns `None` if the tile does not contain any
+    /// features or the index is not valid for the tile schema.
+    pub fn tile(&self, index: TileIndex) -> Option<MvtTile> {
+        let clip = TileClip::new(self.tile_schema.tile_bbox(index)?, self.options);
+        self.cut_tile(&clip, &self.candidates(&clip))
+    }
+
+    /// Cuts all non-empty tiles of the given z-levels and writes them into the `sink`. Returns the
+    /// number of written tiles.
+    ///
+    /// Tiles are generated depth-first: the features of every tile are selected only from the
+    /// features of its parent tile, and the tiles of a branch are written before the next branch
+    /// is started. Generation stops at the first z-level in the range missing from the tile
+    /// schema.
+    pub fn generate(
+        &self,
+        z_levels: RangeInclusive<u32>,
+        sink: &mut impl TileSink,
+    ) -> Result<usize, GalileoError> {
+        let max_z = *z_levels.end();
+        let Some((z, resolution)) = z_levels
+            .into_iter()
+            .find_map(|z| Some((z, self.tile_schema.lod_resolution(z)?)))
+        else {
+            return Ok(0);
+        };
+
+        let Some(data_bbox) = self
+            .layers
+            .iter()
+            .flat_map(|layer| layer.bboxes.iter().copied())
+            .collect::<Option<Rect>>()
+        else {
+            return Ok(0);
+        };
+
+        let buffer = self.options.buffer as f64 / self.options.extent as f64;
+        let Some((xs, ys)) = self.index_range(&data_bbox, resolution, buffer) else {
+            return Ok(0);
+        };
+
+        let mut count = 0;
+        for x in xs {
+            for y in ys.clone() {
+                let index = TileIndex::new(x, y, z);
+                let Some(bbox) = self.tile_schema.tile_bbox(index) else {
+                    continue;
+                };
+
+                let clip = TileClip::new(bbox, self.options);
+                let candidates = self.candidates(&clip);
+                count += self.generate_branch(index, clip, &candidates, max_z, sink)?;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Returns `(layer index, feature index)` pairs of all the features whose bounding boxes
+    /// intersect the buffered tile.
+    fn candidates(&self, clip: &TileClip) -> Vec<(usize, usize)> {
+        self.layers
+            .iter()
+            .enumerate()
+            .flat_map(|(layer_index, layer)| {
+                layer
+                    .index
+                    .query(&clip.buffered_bbox)
+                    .into_iter()
+                    .map(move |feature_index| (layer_index, feature_index))
+            })
+            .collect()
+    }
+
+    /// Cuts the tile and its descendants up to `max_z`. The `candidates` must include all the
+    /// features intersecting the buffered tile.
+    fn generate_branch(
+        &self,
+        index: TileIndex,
+        clip: TileClip,
+        candidates: &[(usize, usize)],
+        max_z: u32,
+        sink: &mut impl TileSink,
+    ) -> Result<usize, GalileoError> {
+        if candidates.is_empty() {
+            return Ok(0);
+        }
+
+        let mut count = 0;
+        if let Some(tile) = self.cut_tile(&clip, candidates) {
+            sink.write_tile(index, tile)?;
+            count += 1;
+        }
+
+        if index.z >= max_z {
+            return Ok(count);
+        }
+
+        let Some(resolution) = self.tile_schema.lod_resolution(index.z + 1) else {
+            return Ok(count);
+        };
+
+        // Child tiles are the ones with centers inside the tile.
+        let Some((xs, ys)) = self.index_range(&clip.bbox, resolution, -0.5) else {
+            return Ok(count);
+        };
+
+        for x in xs {
+            for y in ys.clone() {
+                let child = TileIndex::new(x, y, index.z + 1);
+                let Some(bbox) = self.tile_schema.tile_bbox(child) else {
+                    continue;
+                };
+
+                let child_clip = TileClip::new(bbox, self.options);
+                let child_candidates: Vec<_> = candidates
+                    .iter()
+                    .copied()
+                    .filter(|&(layer_index, feature_index)| {
+                        child_clip.intersects(&self.layers[layer_index].bboxes[feature_index])
+                    })
+                    .collect();
+
+                count += self.generate_branch(child, child_clip, &child_candidates, max_z, sink)?;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Returns the ranges of X and Y indices of the tiles at the given resolution, the bounds of
+    /// which extended by `buffer` (given as a part of the tile size) intersect with the `bbox`.
+    fn index_range(
+        &self,
+        bbox: &Rect,
+        resolution: f64,
+        buffer: f64,
+    ) -> Option<(RangeInclusive<i32>, RangeInclusive<i32>)> {
+        let schema = &self.tile_schema;
+        let tile_w = resolution * schema.tile_width as f64;
+        let tile_h = resolution * schema.tile_height as f64;
+
+        let x_index = |x: f64| (x - schema.origin.x()) / tile_w;
+        let y_index = |y: f64| match schema.y_direction {
+            VerticalDirection::TopToBottom => (schema.origin.y() - y) / tile_h,
+            VerticalDirection::BottomToTop => (y - schema.origin.y()) / tile_h,
+        };
+        let index_range = |a: f64, b: f64, bound_a: f64, bound_b: f64| {
+            let (min, max) = (a.min(b), a.max(b));
+            let (bound_min, bound_max) = (bound_a.min(bound_b), bound_a.max(bound_b));
+            let from = (min - buffer).floor().max(bound_min.floor()) as i32;
+            let to = (max + buffer).floor().min(bound_max.ceil() - 1.0) as i32;
+            (from <= to).then_some(from..=to)
+        };
+
+        let xs = index_range(
+            x_index(bbox.x_min()),
+            x_index(bbox.x_max()),
+            x_index(schema.bounds.x_min()),
+            x_index(schema.bounds.x_max()),
+        )?;
+        let ys = index_range(
+            y_index(bbox.y_min()),
+            y_index(bbox.y_max()),
+            y_index(schema.bounds.y_min()),
+            y_index(schema.bounds.y_max()),
+        )?;
+
+        Some((xs, ys))
+    }
+
+    fn cut_tile(&self, clip: &TileClip, candidates: &[(usize, usize)]) -> Option<MvtTile> {
+        let mut layers: Vec<MvtLayer> = vec![];
+        for &(layer_index, feature_index) in candidates {
+            let layer = &self.layers[layer_index];
+            let feature = &layer.features[feature_index];
+            let Some(geometry) = clip.cut_geometry(&feature.geometry) else {
+                continue;
+            };
+
+            let mvt_feature = MvtFeature {
+                id: feature.id,
+                properties: feature.properties.clone(),
+                geometry,
+            };
+
+            match layers.last_mut() {
+                Some(mvt_layer) if mvt_layer.name == layer.name => {
+                    mvt_layer.features.push(mvt_feature)
+                }
+                _ => layers.push(MvtLayer {
+                    name: layer.name.clone(),
+                    features: vec![mvt_feature],
+                    properties: vec![],
+                    size: self.options.extent,
+                }),
+            }
+        }
+
+        for layer in &mut layers {
+            let keys: BTreeSet<&String> = layer
+                .features
+                .iter()
+                .flat_map(|feature| feature.properties.keys())
+                .collect();
+            layer.properties = keys.into_iter().cloned().collect();
+        }
+
+        (!layers.is_empty()).then_some(MvtTile { layers })
+    }
+}
+
+/// Converts geometries into the coordinates of a single tile.
+struct TileClip {
+    bbox: Rect,
+    buffered_bbox: Rect,
+    extent: f64,
+    min: f64,
+    max: f64,
+    tolerance: f64,
+}
+
+impl TileClip {
+    fn new(bbox: Rect, options: TilerOptions) -> Self {
+        let extent = options.extent as f64;
+        let buffer = options.buffer as f64;
+        let dx = bbox.width() * buffer / extent;
+        let dy = bbox.height() * buffer / extent;
+
+        Self {
+            bbox,
+            buffered_bbox: Rect::new(
+                bbox.x_min() - dx,
+                bbox.y_min() - dy,
+                bbox.x_max() + dx,
+                bbox.y_max() + dy,
+            ),
+            extent,
+            min: -buffer,
+            max: extent + buffer,
+            tolerance: options.tolerance,
+        }
+    }
+
+    fn intersects(&self, bbox: &Rect) -> bool {
+        rects_intersect(bbox, &self.buffered_bbox)
+    }
+
+    /// Converts the point into tile coordinates, with the Y axis pointing down.
+    fn to_tile(&self, point: &Point2d) -> Point2d {
+        Point2d::new(
+            (point.x() - self.bbox.x_min()) / self.bbox.width() * self.extent,
+            (self.bbox.y_max() - point.y()) / self.bbox.height() * self.extent,
+        )
+    }
+
+    fn cut_geometry(&self, geometry: &Geom<Point2d>) -> Option<MvtGeometry> {
+        match geometry {
+            Geom::Point(point) => self.cut_points(std::iter::once(point)),
+            Geom::MultiPoint(points) => self.cut_points(points.iter_points()),
+            Geom::Contour(contour) => self.cut_lines(std::iter::once(contour)),
+            Geom::MultiContour(contours) => self.cut_lines(contours.contours()),
+            Geom::Polygon(polygon) => self.cut_polygons(std::iter::once(polygon)),
+            Geom::MultiPolygon(polygons) => self.cut_polygons(polygons.parts().iter()),
+        }
+    }
+
+    fn cut_points<'a>(&self, points: impl Iterator<Item = &'a Point2d>) -> Option<MvtGeometry> {
+        let points: Vec<_> = points
+            .map(|point| self.to_tile(point))
+            .filter(|point| self.contains(point))
+            .map(|point| self.quantise(&point))
+            .collect();
+
+        (!points.is_empty()).then_some(MvtGeometry::Point(points))
+    }
+
+    fn cut_lines<'a>(
+        &self,
+        contours: impl Iterator<Item = &'a Contour<Point2d>>,
+    ) -> Option<MvtGeometry> {
+        let mut lines = vec![];
+        for contour in contours {
+            let points: Vec<_> = contour
+                .iter_points_closing()
+                .map(|point| self.to_tile(point))
+                .collect();
+
+            for part in clip_line(&points, self.min, self.max) {
+                let part = self.quantise_all(&simplify(&part, self.tolerance));
+                if part.len() >= 2 {
+                    lines.push(Contour::open(part));
+                }
+            }
+        }
+
+        (!lines.is_empty()).then_some(MvtGeometry::LineString(lines))
+    }
+
+    fn cut_polygons<'a>(
+        &self,
+        polygons: impl Iterator<Item = &'a Polygon<Point2d>>,
+    ) -> Option<MvtGeometry> {
+        let mut result = vec![];
+        for polygon in polygons {
+            let Some(outer_contour) = self.cut_ring(&polygon.outer_contour, true) else {
+                continue;
+            };
+
+            let inner_contours = polygon
+                .inner_contours
+                .iter()
+                .filter_map(|contour| self.cut_ring(contour, false))
+                .collect();
+
+            result.push(Polygon::new(outer_contour, inner_contours));
+        }
+
+        (!result.is_empty()).then_some(MvtGeometry::Polygon(result))
+    }
+
+    /// Clips, simplifies and quantises the ring. Outer rings are oriented to have positive area
+    /// in tile coordinates, inner rings - negative, as the MVT specification requires.
+    fn cut_ring(
+        &self,
+        contour: &ClosedContour<Point2d>,
+        is_outer: bool,
+    ) -> Option<ClosedContour<Point2<f32>>> {
+        let points: Vec<_> = contour
+            .points
+            .iter()
+            .map(|point| self.to_tile(point))
+            .collect();
+        let mut ring = clip_ring(points, self.min, self.max);
+        if ring.len() < 3 {
+            return None;
+        }
+
+        ring.push(ring[0]);
+        let mut ring = self.quantise_all(&simplify(&ring, self.tolerance));
+        if ring.len() > 1 && ring[0] == ring[ring.len() - 1] {
+            ring.pop();
+        }
+
+        let area = signed_area(&ring);
+        if ring.len() < 3 || area == 0.0 {
+            return None;
+        }
+
+        if (area > 0.0) != is_outer {
+            ring.reverse();
+        }
+
+        Some(ClosedContour::new(ring))
+    }
+
+    fn contains(&self, point: &Point2d) -> bool {
+        point.x() >= self.min
+            && point.x() <= self.max
+            && point.y() >= self.min
+            && point.y() <= self.max
+    }
+
+    /// Rounds the point to the tile grid and normalizes it to the `[0, 1]` range, which is used
+    /// by [`MvtTile`] for tile coordinates.
+    fn quantise(&self, point: &Point2d) -> Point2<f32> {
+        Point2::new(
+            (point.x().round() / self.extent) as f32,
+            (point.y().round() / self.extent) as f32,
+        )
+    }
+
+    /// Quantises the points removing consecutive duplicates.
+    fn quantise_all(&self, points: &[Point2d]) -> Vec<Point2<f32>> {
+        let mut quantised: Vec<Point2<f32>> = Vec::with_capacity(points.len());
+        for point in points {
+            let point = self.quantise(point);
+            if quantised.last() != Some(&point) {
+                quantised.push(point);
+            }
+        }
+
+        quantised
+    }
+}
+
+/// Cuts the `region` out of the tile, scaling it to the full tile size. This is used to display
+/// tiles at z-levels higher than the data is available for.
+///
+/// The region is given in the normalized tile coordinates. Geometries are clipped by the region
+/// extended by `buffer`, which is given as a part of the region size.
+pub(crate) fn cut_sub_tile(tile: &MvtTile, region: Rect, buffer: f64) -> MvtTile {
+    let to_region = |point: &Point2<f32>| {
+        Point2d::new(
+            (point.x as f64 - region.x_min()) / region.width(),
+            (point.y as f64 - region.y_min()) / region.height(),
+        )
+    };
+    let to_tile = |points: Vec<Point2d>| -> Vec<Point2<f32>> {
+        points
+            .into_iter()
+            .map(|point| Point2::new(point.x as f32, point.y as f32))
+            .collect()
+    };
+    let (min, max) = (-buffer, 1.0 + buffer);
+
+    let cut_ring = |contour: &ClosedContour<Point2<f32>>| {
+        let ring = clip_ring(contour.points.iter().map(to_region).collect(), min, max);
+        (ring.len() >= 3).then(|| ClosedContour::new(to_tile(ring)))
+    };
+
+    let cut_geometry = |geometry: &MvtGeometry| match geometry {
+        MvtGeometry::Point(points) => {
+            let points: Vec<_> = points
+                .iter()
+                .map(to_region)
+                .filter(|p| p.x >= min && p.x <= max && p.y >= min && p.y <= max)
+                .collect();
+            (!points.is_empty()).then(|| MvtGeometry::Point(to_tile(points)))
+        }
+        MvtGeometry::LineString(contours) => {
+            let lines: Vec<_> = contours
+                .iter()
+                .flat_map(|contour| {
+                    let points: Vec<_> = contour.iter_points().map(to_region).collect();
+                    clip_line(&points, min, max)
+                })
+                .map(|part| Contour::open(to_tile(part)))
+                .collect();
+            (!lines.is_empty()).then_some(MvtGeometry::LineString(lines))
+        }
+        MvtGeometry::Polygon(polygons) => {
+            let polygons: Vec<_> = polygons
+                .iter()
+                .filter_map(|polygon| {
+                    Some(Polygon::new(
+                        cut_ring(&polygon.outer_contour)?,
+                        polygon.inner_contours.iter().filter_map(cut_ring).collect(),
+                    ))
+                })
+                .collect();
+            (!polygons.is_empty()).then_some(MvtGeometry::Polygon(polygons))
+        }
+    };
+
+    let layers = tile
+        .layers
+        .iter()
+        .map(|layer| MvtLayer {
+            name: layer.name.clone(),
+            features: layer
+                .features
+                .iter()
+                .filter_map(|feature| {
+                    Some(MvtFeature {
+                        id: feature.id,
+                        properties: feature.properties.clone(),
+                        geometry: cut_geometry(&feature.geometry)?,
+                    })
+                })
+                .collect(),
+            properties: layer.properties.clone(),
+            size: layer.size,
+        })
+        .filter(|layer| !layer.features.is_empty())
+        .collect();
+
+    MvtTile { layers }
+}
+
+fn rects_intersect(a: &Rect, b: &Rect) -> bool {
+    a.x_max() >= b.x_min()
+        && a.x_min() <= b.x_max()
+        && a.y_max() >= b.y_min()
+        && a.y_min() <= b.y_max()
+}
+
+/// Clips the line by the square `[min, max]`. Returns the parts of the line inside the square.
+fn clip_line(points: &[Point2d], min: f64, max: f64) -> Vec<Vec<Point2d>> {
+    let mut parts = vec![];
+    let mut current = vec![];
+
+    for segment in points.windows(2) {
+        match clip_segment(segment[0], segment[1], min, max) {
+            Some((from, to)) => {
+                if current.is_empty() {
+                    current.push(from);
+                }
+
+                current.push(to);
+
+                if to != segment[1] {
+                    parts.push(std::mem::take(&mut current));
+                }
+            }
+            None => {
+                if !current.is_empty() {
+                    parts.push(std::mem::take(&mut current));
+                }
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        parts.push(current);
+    }
+
+    parts.retain(|part| part.len() >= 2);
+    parts
+}
+
+/// Liang-Barsky segment clipping.
+fn clip_segment(a: Point2d, b: Point2d, min: f64, max: f64) -> Option<(Point2d, Point2d)> {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    let mut t_from = 0.0f64;
+    let mut t_to = 1.0f64;
+
+    for (p, q) in [
+        (-dx, a.x - min),
+        (dx, max - a.x),
+        (-dy, a.y - min),
+        (dy, max - a.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+
+            continue;
+        }
+
+        let t = q / p;
+        if p < 0.0 {
+            if t > t_to {
+                return None;
+            }
+
+            t_from = t_from.max(t);
+        } else {
+            if t < t_from {
+                return None;
+            }
+
+            t_to = t_to.min(t);
+        }
+    }
+
+    let from = if t_from > 0.0 {
+        a + (b - a) * t_from
+    } else {
+        a
+    };
+    let to = if t_to < 1.0 { a + (b - a) * t_to } else { b };
+
+    Some((from, to))
+}
+
+/// Sutherland-Hodgman clipping of a closed ring by the square `[min, max]`.
+fn clip_ring(mut ring: Vec<Point2d>, min: f64, max: f64) -> Vec<Point2d> {
+    for (axis, bound, keep_greater) in [
+        (0, min, true),
+        (0, max, false),
+        (1, min, true),
+        (1, max, false),
+    ] {
+        let is_inside = |point: &Point2d| {
+            if keep_greater {
+                point[axis] >= bound
+            } else {
+                point[axis] <= bound
+            }
+        };
+        let intersection = |a: &Point2d, b: &Point2d| {
+            let t = (bound - a[axis]) / (b[axis] - a[axis]);
+            let mut point = a + (b - a) * t;
+            point[axis] = bound;
+            point
+        };
+
+        let mut clipped = Vec::with_capacity(ring.len());
+        for (index, point) in ring.iter().enumerate() {
+            let prev = &ring[(index + ring.len() - 1) % ring.len()];
+            match (is_inside(prev), is_inside(point)) {
+                (true, true) => clipped.push(*point),
+                (true, false) => clipped.push(intersection(prev, point)),
+                (false, true) => {
+                    clipped.push(intersection(prev, point));
+                    clipped.push(*point);
+                }
+                (false, false) => {}
+            }
+        }
+
+        ring = clipped;
+        if ring.is_empty() {
+            break;
+        }
+    }
+
+    ring
+}
+
+/// Douglas-Peucker simplification. The first and the last points are always kept.
+fn simplify(points: &[Point2d], tolerance: f64) -> Vec<Point2d> {
+    if tolerance <= 0.0 || points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let sq_tolerance = tolerance * tolerance;
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        let mut max_distance = 0.0;
+        let mut max_index = first;
+        for index in first + 1..last {
+            let distance = sq_segment_distance(&points[index], &points[first], &points[last]);
+            if distance > max_distance {
+                max_distance = distance;
+                max_index = index;
+            }
+        }
+
+        if max_distance > sq_tolerance {
+            keep[max_index] = true;
+            stack.push((first, max_index));
+            stack.push((max_index, last));
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(point, keep)| keep.then_some(*point))
+        .collect()
+}
+
+fn sq_segment_distance(point: &Point2d, a: &Point2d, b: &Point2d) -> f64 {
+    let segment = b - a;
+    let length = segment.norm_squared();
+    let projection = if length > 0.0 {
+        a + segment * ((point - a).dot(&segment) / length).clamp(0.0, 1.0)
+    } else {
+        *a
+    };
+
+    (point - projection).norm_squared()
+}
+
+fn signed_area(points: &[Point2<f32>]) -> f64 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.x as f64 * b.y as f64 - b.x as f64 * a.y as f64)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lod::Lod;
+    use galileo_types::geo::impls::GeoPoint2d;
+
+    fn schema() -> TileSchema {
+        TileSchema {
+            origin: Point2d::new(0.0, 2048.0),
+            bounds: Rect::new(0.0, 0.0, 2048.0, 2048.0),
+            lods: [
+                Lod::new(8.0, 0).unwrap(),
+                Lod::new(4.0, 1).unwrap(),
+                Lod::new(2.0, 2).unwrap(),
+            ]
+            .into(),
+            tile_width: 256,
+            tile_height: 256,
+            y_direction: VerticalDirection::TopToBottom,
+            crs: Crs::EPSG3857,
+        }
+    }
+
+    fn tiler(features: Vec<TilerFeature>) -> VectorTiler {
+        let mut tiler = VectorTiler::new(schema()).with_options(TilerOptions {
+            extent: 256,
+            buffer: 16,
+            tolerance: 1.0,
+        });
+        tiler.add_layer("layer", features);
+        tiler
+    }
+
+    fn feature(geometry: impl Into<Geom<Point2d>>) -> TilerFeature {
+        TilerFeature::new(
+            geometry.into(),
+            [("name".to_string(), MvtValue::String("test".into()))].into(),
+        )
+    }
+
+    fn tile_points(tile: &MvtTile) -> Vec<[f32; 2]> {
+        match &tile.layers[0].features[0].geometry {
+            MvtGeometry::Point(points) => {
+                points.iter().map(|p| [p.x * 256.0, p.y * 256.0]).collect()
+            }
+            MvtGeometry::LineString(lines) => lines[0]
+                .iter_points()
+                .map(|p| [p.x * 256.0, p.y * 256.0])
+                .collect(),
+            MvtGeometry::Polygon(polygons) => polygons[0]
+                .outer_contour
+                .points
+                .iter()
+                .map(|p| [p.x * 256.0, p.y * 256.0])
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn point_is_quantised_in_its_tile() {
+        let tiler = tiler(vec![feature(Point2d::new(1100.7, 1000.0))]);
+
+        let tile = tiler.tile(TileIndex::new(1, 1, 1)).unwrap();
+        assert_eq!(tile_points(&tile), vec![[19.0, 6.0]]);
+        assert_eq!(tile.layers[0].properties, vec!["name".to_string()]);
+
+        // Inside the buffer of the neighbour tile.
+        let tile = tiler.tile(TileIndex::new(1, 0, 1)).unwrap();
+        assert_eq!(tile_points(&tile), vec![[19.0, 262.0]]);
+
+        assert!(tiler.tile(TileIndex::new(0, 0, 1)).is_none());
+    }
+
+    #[test]
+    fn lines_are_clipped_with_buffer() {
+        let line = Contour::open(vec![
+            Point2d::new(512.0, 1536.0),
+            Point2d::new(1536.0, 1536.0),
+        ]);
+        let tiler = tiler(vec![feature(line)]);
+
+        let tile = tiler.tile(TileIndex::new(0, 0, 1)).unwrap();
+        assert_eq!(tile_points(&tile), vec![[128.0, 128.0], [272.0, 128.0]]);
+
+        let tile = tiler.tile(TileIndex::new(1, 0, 1)).unwrap();
+        assert_eq!(tile_points(&tile), vec![[-16.0, 128.0], [128.0, 128.0]]);
+
+        assert!(tiler.tile(TileIndex::new(0, 1, 1)).is_none());
+    }
+
+    #[test]
+    fn polygons_are_clipped_and_oriented() {
+        let polygon = Polygon::from(vec![
+            Point2d::new(-100.0, -100.0),
+            Point2d::new(3000.0, -100.0),
+            Point2d::new(3000.0, 3000.0),
+            Point2d::new(-100.0, 3000.0),
+        ]);
+        let tiler = tiler(vec![feature(polygon)]);
+
+        let tile = tiler.tile(TileIndex::new(1, 2, 2)).unwrap();
+        let points = tile_points(&tile);
+        assert_eq!(points.len(), 4);
+        for point in &points {
+            assert!(point[0] == -16.0 || point[0] == 272.0);
+            assert!(point[1] == -16.0 || point[1] == 272.0);
+        }
+
+        let MvtGeometry::Polygon(polygons) = &tile.layers[0].features[0].geometry else {
+            panic!("invalid geometry type");
+        };
+        assert!(signed_area(&polygons[0].outer_contour.points) > 0.0);
+    }
+
+    #[test]
+    fn simplification_removes_small_deviations() {
+        let points = vec![
+            Point2d::new(0.0, 0.0),
+            Point2d::new(10.0, 0.3),
+            Point2d::new(20.0, 0.0),
+            Point2d::new(20.0, 20.0),
+        ];
+
+        assert_eq!(
+            simplify(&points, 1.0),
+            vec![points[0], points[2], points[3]]
+        );
+        assert_eq!(simplify(&points, 0.0), points);
+    }
+
+    #[test]
+    fn geographic_features_are_projected() {
+        let point = GeoPoint2d::latlon(10.0, 10.0);
+        let feature = TilerFeature::project(&point, &Crs::EPSG3857, HashMap::new()).unwrap();
+
+        let mut tiler = VectorTiler::new(TileSchema::web(2));
+        tiler.add_layer("layer", vec![feature]);
+
+        assert!(tiler.tile(TileIndex::new(1, 0, 1)).is_some());
+        assert!(tiler.tile(TileIndex::new(0, 0, 1)).is_none());
+        assert!(tiler.tile(TileIndex::new(1, 1, 1)).is_none());
+    }
+
+    #[test]
+    fn sub_tile_is_scaled_and_clipped() {
+        let line = Contour::open(vec![
+            Point2d::new(0.0, 1536.0),
+            Point2d::new(2048.0, 1536.0),
+        ]);
+        let tile = tiler(vec![feature(line)])
+            .tile(TileIndex::new(0, 0, 0))
+            .unwrap();
+
+        let sub_tile = cut_sub_tile(&tile, Rect::new(0.5, 0.0, 1.0, 0.5), 0.0625);
+        let points = tile_points(&sub_tile);
+        assert_eq!(points, vec![[-16.0, 128.0], [256.0, 128.0]]);
+
+        let sub_tile = cut_sub_tile(&tile, Rect::new(0.5, 0.5, 1.0, 1.0), 0.0625);
+        assert!(sub_tile.layers.is_empty());
+    }
+
+    #[test]
+    fn index_finds_intersecting_bboxes() {
+        let bboxes: Vec<_> = (0..1000)
+            .map(|i| {
+                let x = (i * 37 % 1000) as f64;
+                let y = (i * 91 % 1000) as f64;
+                let size = (i % 7 * 10) as f64;
+                Rect::new(x, y, x + size, y + size)
+            })
+            .collect();
+        let index = BboxIndex::new(&bboxes);
+
+        for query in [
+            Rect::new(0.0, 0.0, 100.0, 100.0),
+            Rect::new(450.0, 300.0, 460.0, 800.0),
+            Rect::new(-10.0, -10.0, 2000.0, 2000.0),
+            Rect::new(2000.0, 2000.0, 3000.0, 3000.0),
+        ] {
+            let expected: Vec<_> = (0..bboxes.len())
+                .filter(|i| rects_intersect(&bboxes[*i], &query))
+                .collect();
+            assert_eq!(index.query(&query), expected);
+        }
+
+        assert!(BboxIndex::new(&[])
+            .query(&Rect::new(0.0, 0.0, 1.0, 1.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn generate_writes_non_empty_tiles() {
+        let line = Contour::open(vec![
+            Point2d::new(100.0, 1900.0),
+            Point2d::new(900.0, 1900.0),
+        ]);
+        let tiler = tiler(vec![feature(line).with_id(7)]);
+
+        let mut tiles = HashMap::new();
+        let count = tiler.generate(0..=2, &mut tiles).unwrap();
+
+        let mut indices: Vec<_> = tiles.keys().map(|i| (i.z, i.x, i.y)).collect();
+        indices.sort();
+        assert_eq!(indices, vec![(0, 0, 0), (1, 0, 0), (2, 0, 0), (2, 1, 0)]);
+        assert_eq!(count, 4);
+
+        let tile = &tiles[&TileIndex::new(0, 0, 1)];
+        let decoded = MvtTile::decode(bytes::Bytes::from(tile.encode()), false).unwrap();
+        assert_eq!(decoded.layers[0].name, "layer");
+        assert_eq!(decoded.layers[0].features[0].id, Some(7));
+        assert_eq!(tile_points(&decoded), tile_points(tile));
+    }
+
+    #[test]
+    fn generated_tiles_match_single_tiles() {
+        let features = vec![
+            feature(Polygon::from(vec![
+                Point2d::new(300.0, 300.0),
+                Point2d::new(1100.0, 300.0),
+                Point2d::new(1100.0, 700.0),
+                Point2d::new(300.0, 700.0),
+            ])),
+            feature(Point2d::new(1530.0, 1530.0)),
+            feature(Contour::open(vec![
+                Point2d::new(0.0, 2000.0),
+                Point2d::new(2048.0, 1200.0),
+            ])),
+        ];
+        let tiler = tiler(features);
+
+        let mut tiles = HashMap::new();
+        let count = tiler.generate(0..=2, &mut tiles).unwrap();
+        assert_eq!(count, tiles.len());
+
+        let mut expected = 0;
+        for z in 0..=2 {
+            for x in 0..1 << z {
+                for y in 0..1 << z {
+                    let index = TileIndex::new(x, y, z);
+                    match tiler.tile(index) {
+                        Some(tile) => {
+                            expected += 1;
+                            assert_eq!(tiles[&index].encode(), tile.encode());
+                        }
+                        None => assert!(!tiles.contains_key(&index)),
+                    }
+                }
+            }
+        }
+        assert_eq!(count, expected);
+    }
+}
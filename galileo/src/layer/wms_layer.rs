@@ -0,0 +1,560 @@
+//! [`WmsLayer`] draws images requested from a WMS server for the current map view.
+
+use crate::decoded_image::DecodedImage;
+use crate::error::GalileoError;
+use crate::layer::data_provider::{percent_encode, UrlSource};
+use crate::layer::{Layer, RetryPolicy};
+use crate::messenger::Messenger;
+use crate::platform::{PlatformService, PlatformServiceImpl};
+use crate::render::{Canvas, ImagePaint, PackedBundle, RenderOptions};
+use crate::tile_scheme::{TileIndex, TileSchema};
+use crate::view::MapView;
+use galileo_types::cartesian::Rect;
+use galileo_types::geo::Crs;
+use maybe_sync::Mutex;
+use std::any::Any;
+use std::sync::Arc;
+use web_time::{Duration, Instant, SystemTime};
+
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+const DEFAULT_MAX_IMAGE_SIZE: u32 = 4096;
+
+/// Parameters of WMS `GetMap` requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WmsParameters {
+    /// Url of the WMS endpoint. May already contain query parameters.
+    pub url: String,
+    /// Names of the layers to request.
+    pub layers: Vec<String>,
+    /// Styles of the layers. If empty, default styles are used.
+    pub styles: Vec<String>,
+    /// Format of the images, e.g. `image/png`.
+    pub format: String,
+    /// Version of the WMS protocol: `1.3.0` or `1.1.1`.
+    pub version: String,
+    /// Code of the CRS to request the images in, e.g. `EPSG:3857`.
+    ///
+    /// If not set, the code is derived from the CRS of the map (only `EPSG:3857` and `EPSG:4326`
+    /// are supported this way).
+    pub crs: Option<String>,
+    /// Whether the images should be requested with transparent background.
+    pub transparent: bool,
+    /// Additional vendor-specific parameters added to the requests.
+    pub extra_parameters: Vec<(String, String)>,
+}
+
+impl WmsParameters {
+    /// Creates parameters for requesting the given layers as transparent PNG images with WMS 1.3.0.
+    pub fn new(
+        url: impl Into<String>,
+        layers: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            url: url.into(),
+            layers: layers.into_iter().map(Into::into).collect(),
+            styles: vec![],
+            format: "image/png".into(),
+            version: "1.3.0".into(),
+            crs: None,
+            transparent: true,
+            extra_parameters: vec![],
+        }
+    }
+
+    /// Returns the code of the CRS to be used for requesting data for a map in the given `crs`.
+    pub fn crs_code(&self, crs: &Crs) -> Option<String> {
+        if let Some(code) = &self.crs {
+            return Some(code.clone());
+        }
+
+        if *crs == Crs::EPSG3857 {
+            Some("EPSG:3857".into())
+        } else if *crs == Crs::WGS84 {
+            Some("EPSG:4326".into())
+        } else {
+            None
+        }
+    }
+
+    /// Creates the url of the `GetMap` request for an image of the given area and size.
+    ///
+    /// For WMS 1.3.0 and `EPSG:4326` the coordinates of the bbox are given in latitude-longitude
+    /// order as required by the standard.
+    pub fn get_map_url(&self, bbox: Rect, width: u32, height: u32, crs_code: &str) -> String {
+        let is_1_3 = self.version.starts_with("1.3");
+        let bbox = if is_1_3 && crs_code.eq_ignore_ascii_case("EPSG:4326") {
+            [bbox.y_min(), bbox.x_min(), bbox.y_max(), bbox.x_max()]
+        } else {
+            [bbox.x_min(), bbox.y_min(), bbox.x_max(), bbox.y_max()]
+        };
+
+        let separator = if self.url.ends_with('?') || self.url.ends_with('&') {
+            ""
+        } else if self.url.contains('?') {
+            "&"
+        } else {
+            "?"
+        };
+
+        let layers: Vec<String> = self.layers.iter().map(|l| percent_encode(l)).collect();
+        let styles: Vec<String> = self.styles.iter().map(|s| percent_encode(s)).collect();
+        let mut url = format!(
+            "{}{separator}SERVICE=WMS&VERSION={}&REQUEST=GetMap&LAYERS={}&STYLES={}&FORMAT={}\
+            &TRANSPARENT={}&{}={}&BBOX={},{},{},{}&WIDTH={width}&HEIGHT={height}",
+            self.url,
+            percent_encode(&self.version),
+            layers.join(","),
+            styles.join(","),
+            percent_encode(&self.format),
+            if self.transparent { "TRUE" } else { "FALSE" },
+            if is_1_3 { "CRS" } else { "SRS" },
+            percent_encode(crs_code),
+            bbox[0],
+            bbox[1],
+            bbox[2],
+            bbox[3],
+        );
+
+        for (key, value) in &self.extra_parameters {
+            url.push_str(&format!(
+                "&{}={}",
+                percent_encode(key),
+                percent_encode(value)
+            ));
+        }
+
+        url
+    }
+
+    /// Creates a url source requesting one image per tile of the schema.
+    ///
+    /// The source can be used with a [`RasterTileLayer`](super::RasterTileLayer) to load WMS
+    /// images as pseudo-tiles, which makes use of the tile caching of the layer.
+    pub fn tile_url_source(&self, tile_schema: TileSchema) -> impl UrlSource<TileIndex> {
+        let crs_code = self.crs_code(&tile_schema.crs).unwrap_or_else(|| {
+            log::warn!("CRS code for WMS requests is not set, using EPSG:3857");
+            "EPSG:3857".into()
+        });
+        let parameters = self.clone();
+
+        move |index: &TileIndex| {
+            let bbox = tile_schema
+                .tile_bbox(*index)
+                .unwrap_or_else(|| Rect::new(0.0, 0.0, 0.0, 0.0));
+            parameters.get_map_url(
+                bbox,
+                tile_schema.tile_width,
+                tile_schema.tile_height,
+                &crs_code,
+            )
+        }
+    }
+}
+
+/// Layer showing a single image requested from a WMS server for the whole map view.
+///
+/// A new image is requested when the view stops changing for the [debounce
+/// duration](WmsLayer::set_debounce_duration). Until the new image is loaded, the previous image
+/// is drawn at its original position, so it is moved and stretched together with the map.
+///
+/// Failed requests are repeated according to the [retry policy](WmsLayer::set_retry_policy). If
+/// all attempts fail, the image for the same view is requested again after the error cooldown.
+///
+/// To request images in tiles instead, use [`WmsParameters::tile_url_source`] with a
+/// [`RasterTileLayer`](super::RasterTileLayer).
+pub struct WmsLayer {
+    parameters: Arc<WmsParameters>,
+    platform_service: Arc<PlatformServiceImpl>,
+    debounce: Duration,
+    max_image_size: u32,
+    retry_policy: RetryPolicy,
+    state: Arc<Mutex<WmsState>>,
+    messenger: Option<Arc<dyn Messenger>>,
+}
+
+#[derive(Default)]
+struct WmsState {
+    requested: Option<ImageRequest>,
+    generation: u64,
+    // Time when the requested image is to be loaded if the view does not change until then.
+    deadline: Option<Instant>,
+    // Whether the task waiting for the deadline is running.
+    debouncing: bool,
+    // Time when loading of the requested image failed.
+    failed_at: Option<SystemTime>,
+    loaded_generation: u64,
+    loaded: Option<(ImageRequest, DecodedImage)>,
+    rendered: Option<Box<dyn PackedBundle>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ImageRequest {
+    bbox: Rect,
+    width: u32,
+    height: u32,
+    crs_code: String,
+}
+
+impl ImageRequest {
+    fn from_view(view: &MapView, parameters: &WmsParameters, max_image_size: u32) -> Option<Self> {
+        let bbox = view.get_bbox()?;
+        let width = (bbox.width() / view.resolution()).round();
+        let height = (bbox.height() / view.resolution()).round();
+        if !(width >= 1.0 && height >= 1.0) {
+            return None;
+        }
+
+        let scale = (max_image_size as f64 / width.max(height)).min(1.0);
+        let Some(crs_code) = parameters.crs_code(view.crs()) else {
+            log::warn!("Cannot request WMS image: CRS code for the map CRS is not set");
+            return None;
+        };
+
+        Some(Self {
+            bbox,
+            width: ((width * scale).round() as u32).max(1),
+            height: ((height * scale).round() as u32).max(1),
+            crs_code,
+        })
+    }
+}
+
+impl WmsLayer {
+    /// Creates a new layer.
+    pub fn new(parameters: WmsParameters, messenger: Option<Arc<dyn Messenger>>) -> Self {
+        Self::new_with_platform_service(parameters, PlatformServiceImpl::new(), messenger)
+    }
+
+    /// Creates a new layer that requests the images with the given platform service, e.g. one
+    /// created with custom headers by [`PlatformService::with_config`].
+    pub fn new_with_platform_service(
+        parameters: WmsParameters,
+        platform_service: PlatformServiceImpl,
+        messenger: Option<Arc<dyn Messenger>>,
+    ) -> Self {
+        Self {
+            parameters: Arc::new(parameters),
+            platform_service: Arc::new(platform_service),
+            debounce: DEFAULT_DEBOUNCE,
+            max_image_size: DEFAULT_MAX_IMAGE_SIZE,
+            retry_policy: RetryPolicy::default(),
+            state: Arc::new(Mutex::new(WmsState::default())),
+            messenger,
+        }
+    }
+
+    /// Parameters of the requests.
+    pub fn parameters(&self) -> &WmsParameters {
+        &self.parameters
+    }
+
+    /// Sets the time the view must stay unchanged before a new image is requested.
+    pub fn set_debounce_duration(&mut self, duration: Duration) {
+        self.debounce = duration;
+    }
+
+    /// Sets the maximum width and height of requested images in pixels. Larger views are requested
+    /// with lower resolution.
+    pub fn set_max_image_size(&mut self, size: u32) {
+        self.max_image_size = size.max(1);
+    }
+
+    /// Sets the policy of repeating failed image requests.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    fn image_loader(&self) -> ImageLoader {
+        ImageLoader {
+            parameters: self.parameters.clone(),
+            platform_service: self.platform_service.clone(),
+            retry_policy: self.retry_policy,
+            state: self.state.clone(),
+            messenger: self.messenger.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ImageLoader {
+    parameters: Arc<WmsParameters>,
+    platform_service: Arc<PlatformServiceImpl>,
+    retry_policy: RetryPolicy,
+    state: Arc<Mutex<WmsState>>,
+    messenger: Option<Arc<dyn Messenger>>,
+}
+
+impl ImageLoader {
+    /// Waits until the view stops changing and loads the image for the last requested view.
+    ///
+    /// Only one such task is running at a time. Changes of the view move the deadline it waits for.
+    async fn debounce(self) {
+        let (generation, request) = loop {
+            let wait = {
+                let mut state = self.state.lock();
+                let now = Instant::now();
+                match state.deadline {
+                    Some(deadline) if deadline > now => deadline - now,
+                    _ => {
+                        state.deadline = None;
+                        state.debouncing = false;
+                        let Some(request) = state.requested.clone() else {
+                            return;
+                        };
+                        break (state.generation, request);
+                    }
+                }
+            };
+
+            crate::async_runtime::sleep(wait).await;
+        };
+
+        self.load_image(generation, request).await;
+    }
+
+    async fn load_image(&self, generation: u64, request: ImageRequest) {
+        let url = self.parameters.get_map_url(
+            request.bbox,
+            request.width,
+            request.height,
+            &request.crs_code,
+        );
+        let result = self
+            .retry_policy
+            .run(
+                || self.platform_service.load_image_url(&url),
+                GalileoError::is_retryable,
+            )
+            .await;
+
+        let mut state = self.state.lock();
+        match result {
+            Ok(image) => {
+                if state.loaded_generation > generation {
+                    return;
+                }
+
+                state.loaded_generation = generation;
+                state.loaded = Some((request, image));
+                drop(state);
+
+                if let Some(messenger) = &self.messenger {
+                    messenger.request_redraw();
+                }
+            }
+            Err(err) => {
+                log::warn!("Failed to load WMS image from {url}: {err}");
+                if state.generation == generation {
+                    state.failed_at = Some(SystemTime::now());
+                }
+            }
+        }
+    }
+}
+
+impl Layer for WmsLayer {
+    fn render(&self, _view: &MapView, canvas: &mut dyn Canvas) {
+        let mut state = self.state.lock();
+        if let Some((request, image)) = state.loaded.take() {
+            let mut bundle = canvas.create_bundle();
+            bundle.add_image(
+                image,
+                request.bbox.into_quadrangle(),
+                ImagePaint { opacity: 255 },
+            );
+            state.rendered = Some(canvas.pack_bundle(&bundle));
+        }
+
+        if let Some(packed) = &state.rendered {
+            canvas.draw_bundles(&[&**packed], RenderOptions::default());
+        }
+    }
+
+    fn prepare(&self, view: &MapView) {
+        let Some(request) = ImageRequest::from_view(view, &self.parameters, self.max_image_size)
+        else {
+            return;
+        };
+
+        let mut state = self.state.lock();
+        if state.requested.as_ref() == Some(&request) {
+            match state.failed_at {
+                Some(failed_at) if self.retry_policy.is_cooldown_over(failed_at) => {}
+                _ => return,
+            }
+        }
+
+        state.requested = Some(request);
+        state.generation += 1;
+        state.failed_at = None;
+        state.deadline = Some(Instant::now() + self.debounce);
+        if state.debouncing {
+            return;
+        }
+
+        state.debouncing = true;
+        drop(state);
+
+        crate::async_runtime::spawn(self.image_loader().debounce());
+    }
+
+    fn set_messenger(&mut self, messenger: Box<dyn Messenger>) {
+        self.messenger = Some(Arc::from(messenger));
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::cartesian::{Point2d, Size};
+    use nalgebra::Vector2;
+
+    fn approx_eq(a: Rect, b: Rect) -> bool {
+        (a.x_min() - b.x_min()).abs() < 1e-3
+            && (a.y_min() - b.y_min()).abs() < 1e-3
+            && (a.x_max() - b.x_max()).abs() < 1e-3
+            && (a.y_max() - b.y_max()).abs() < 1e-3
+    }
+
+    fn parameters() -> WmsParameters {
+        WmsParameters::new("https://example.com/wms", ["roads", "rivers"])
+    }
+
+    #[test]
+    fn get_map_url() {
+        let url =
+            parameters().get_map_url(Rect::new(-100.0, -50.0, 100.0, 50.0), 400, 200, "EPSG:3857");
+        assert_eq!(
+            url,
+            "https://example.com/wms?SERVICE=WMS&VERSION=1.3.0&REQUEST=GetMap&LAYERS=roads,rivers\
+            &STYLES=&FORMAT=image%2Fpng&TRANSPARENT=TRUE&CRS=EPSG%3A3857&BBOX=-100,-50,100,50\
+            &WIDTH=400&HEIGHT=200"
+        );
+    }
+
+    #[test]
+    fn get_map_url_axis_order() {
+        let bbox = Rect::new(10.0, 40.0, 20.0, 50.0);
+        let url = parameters().get_map_url(bbox, 100, 100, "EPSG:4326");
+        assert!(url.contains("&CRS=EPSG%3A4326&BBOX=40,10,50,20&"));
+
+        let mut parameters = parameters();
+        parameters.version = "1.1.1".into();
+        parameters.url = "https://example.com/wms?map=test".into();
+        parameters.extra_parameters = vec![("TIME".into(), "2024-01-01".into())];
+        let url = parameters.get_map_url(bbox, 100, 100, "EPSG:4326");
+        assert!(url.starts_with("https://example.com/wms?map=test&SERVICE=WMS&VERSION=1.1.1&"));
+        assert!(url.contains("&SRS=EPSG%3A4326&BBOX=10,40,20,50&"));
+        assert!(url.ends_with("&TIME=2024-01-01"));
+    }
+
+    #[test]
+    fn crs_code() {
+        let mut parameters = parameters();
+        assert_eq!(
+            parameters.crs_code(&Crs::EPSG3857).as_deref(),
+            Some("EPSG:3857")
+        );
+        assert_eq!(
+            parameters.crs_code(&Crs::WGS84).as_deref(),
+            Some("EPSG:4326")
+        );
+
+        parameters.crs = Some("EPSG:2056".into());
+        assert_eq!(
+            parameters.crs_code(&Crs::EPSG3857).as_deref(),
+            Some("EPSG:2056")
+        );
+    }
+
+    #[test]
+    fn request_from_view() {
+        let view = MapView::new_projected(&Point2d::new(0.0, 0.0), 10.0)
+            .with_size(Size::new(800.0, 600.0));
+        let request = ImageRequest::from_view(&view, &parameters(), 4096).unwrap();
+        assert_eq!(request.width, 800);
+        assert_eq!(request.height, 600);
+        assert!(approx_eq(
+            request.bbox,
+            Rect::new(-4000.0, -3000.0, 4000.0, 3000.0)
+        ));
+        assert_eq!(request.crs_code, "EPSG:3857");
+
+        let request = ImageRequest::from_view(&view, &parameters(), 400).unwrap();
+        assert_eq!(request.width, 400);
+        assert_eq!(request.height, 300);
+
+        let empty = view.with_size(Size::new(0.0, 0.0));
+        assert!(ImageRequest::from_view(&empty, &parameters(), 4096).is_none());
+    }
+
+    #[tokio::test]
+    async fn debounces_and_retries_failed_requests() {
+        // Nothing listens on the port, so all requests fail.
+        let parameters = WmsParameters::new("http://127.0.0.1:9/wms", ["roads"]);
+        let mut layer = WmsLayer::new(parameters, None);
+        layer.set_debounce_duration(Duration::from_millis(20));
+        layer.set_retry_policy(
+            RetryPolicy::no_retry().with_error_cooldown(Duration::from_secs(3600)),
+        );
+
+        let view = MapView::new_projected(&Point2d::new(0.0, 0.0), 10.0)
+            .with_size(Size::new(800.0, 600.0));
+        layer.prepare(&view);
+        layer.prepare(&view.translate(Vector2::new(100.0, 0.0)));
+        {
+            let state = layer.state.lock();
+            assert_eq!(state.generation, 2);
+            assert!(state.debouncing);
+        }
+
+        let failed_view = view.translate(Vector2::new(100.0, 0.0));
+        for _ in 0..500 {
+            if layer.state.lock().failed_at.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(layer.state.lock().failed_at.is_some());
+        assert!(!layer.state.lock().debouncing);
+
+        // The failed request is not repeated until the cooldown is over.
+        layer.prepare(&failed_view);
+        assert_eq!(layer.state.lock().generation, 2);
+
+        layer.retry_policy = layer.retry_policy.with_error_cooldown(Duration::ZERO);
+        layer.prepare(&failed_view);
+        let state = layer.state.lock();
+        assert_eq!(state.generation, 3);
+        assert!(state.failed_at.is_none());
+        assert!(state.debouncing);
+    }
+
+    #[test]
+    fn tile_url_source() {
+        let source = parameters().tile_url_source(TileSchema::web(18));
+        let url = source(&TileIndex::new(0, 0, 1));
+        assert!(url.contains("&CRS=EPSG%3A3857&"));
+        assert!(url.ends_with("&WIDTH=256&HEIGHT=256"));
+
+        let bbox: Vec<f64> = url
+            .split('&')
+            .find_map(|param| param.strip_prefix("BBOX="))
+            .unwrap()
+            .split(',')
+            .map(|v| v.parse().unwrap())
+            .collect();
+        assert!(approx_eq(
+            Rect::new(bbox[0], bbox[1], bbox[2], bbox[3]),
+            Rect::new(-20037508.342787, 0.0, 0.0, 20037508.342787)
+        ));
+    }
+}
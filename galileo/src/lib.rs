@@ -79,8 +79,11 @@ mod map;
 mod messenger;
 pub mod platform;
 pub mod render;
+pub mod tile_json;
 pub mod tile_scheme;
 mod view;
+#[cfg(feature = "wmts")]
+pub mod wmts;
 
 #[cfg(feature = "winit")]
 pub mod winit;
@@ -91,7 +94,7 @@ mod galileo_map;
 pub use galileo_map::{GalileoMap, MapBuilder};
 
 pub use color::Color;
-pub use error::{GalileoError, GalileoResult};
+pub use error::{GalileoError, GalileoResult, NetworkError, NetworkErrorKind};
 pub use layer::feature_layer::symbol;
 pub use lod::Lod;
 pub use map::{LayerCollection, Map};
@@ -6,7 +6,8 @@ use std::ops::{Index, IndexMut, RangeBounds};
 /// When a map is rendered, it draws all visible layers in the order they are stored in the
 /// collection. Any layer can be temporary hidden with the [`LayerCollection::hide`] or
 /// [`LayerCollection::show_by`] methods. These layers will be ignored by the renderer, but
-/// retain their place in the collection.
+/// retain their place in the collection. Visible layers can also be drawn semi-transparent with
+/// [`LayerCollection::set_opacity`].
 ///
 /// Since a map should be able to render anything implementing the [`Layer`] trait, this
 /// collection stores layers as trait objects. You can use downcasting through `Any` trait
@@ -37,6 +38,7 @@ pub struct LayerCollection(Vec<LayerEntry>);
 struct LayerEntry {
     layer: Box<dyn Layer>,
     is_hidden: bool,
+    opacity: f32,
 }
 
 impl LayerCollection {
@@ -556,6 +558,61 @@ impl LayerCollection {
             .filter(|entry| !entry.is_hidden)
             .map(|entry| &*entry.layer)
     }
+
+    /// Sets the opacity of the layer at `index`. The value is clamped to the `[0.0, 1.0]` range,
+    /// where `0.0` is fully transparent and `1.0` (the default) is fully opaque. `NaN` is treated
+    /// as `1.0`.
+    ///
+    /// The layer is drawn as a whole first and then blended into the map with the given opacity,
+    /// so overlapping features of the layer do not shine through each other. Layers with zero
+    /// opacity are not rendered at all.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use galileo::LayerCollection;
+    /// use galileo::layer::TestLayer;
+    ///
+    /// let mut collection = LayerCollection::from(vec![
+    ///     TestLayer("Layer A"),
+    ///     TestLayer("Layer B"),
+    /// ]);
+    ///
+    /// collection.set_opacity(1, 0.5);
+    /// assert_eq!(collection.opacity(1), 0.5);
+    /// collection.set_opacity(1, 2.0);
+    /// assert_eq!(collection.opacity(1), 1.0);
+    /// assert_eq!(collection.opacity(0), 1.0);
+    /// ```
+    pub fn set_opacity(&mut self, index: usize, opacity: f32) {
+        self.0[index].opacity = if opacity.is_nan() {
+            1.0
+        } else {
+            opacity.clamp(0.0, 1.0)
+        };
+    }
+
+    /// Returns the opacity of the layer at `index`. See [`LayerCollection::set_opacity`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn opacity(&self, index: usize) -> f32 {
+        self.0[index].opacity
+    }
+
+    /// Iterates over all visible layers in the collection that are not fully transparent, together
+    /// with their opacity.
+    pub(crate) fn iter_rendered(&self) -> impl Iterator<Item = (&dyn Layer, f32)> + '_ {
+        self.0
+            .iter()
+            .filter(|entry| !entry.is_hidden && entry.opacity > 0.0)
+            .map(|entry| (&*entry.layer, entry.opacity))
+    }
 }
 
 impl Index<usize> for LayerCollection {
@@ -583,6 +640,7 @@ impl<T: Layer + 'static> From<T> for LayerEntry {
         Self {
             layer: Box::new(value),
             is_hidden: false,
+            opacity: 1.0,
         }
     }
 }
@@ -592,6 +650,60 @@ impl From<Box<dyn Layer>> for LayerEntry {
         Self {
             layer: value,
             is_hidden: false,
+            opacity: 1.0,
         }
     }
 }
+
+#[cfg(all(test, feature = "_tests"))]
+mod tests {
+    use super::*;
+    use crate::layer::TestLayer;
+
+    fn layer_name(layer: &dyn Layer) -> &'static str {
+        layer
+            .as_any()
+            .downcast_ref::<TestLayer>()
+            .expect("layer is a test layer")
+            .0
+    }
+
+    #[test]
+    fn set_opacity_clamps_value() {
+        let mut collection = LayerCollection::from(vec![TestLayer("A"), TestLayer("B")]);
+        assert_eq!(collection.opacity(0), 1.0);
+
+        collection.set_opacity(0, 0.25);
+        assert_eq!(collection.opacity(0), 0.25);
+
+        collection.set_opacity(0, 1.5);
+        assert_eq!(collection.opacity(0), 1.0);
+
+        collection.set_opacity(0, -0.5);
+        assert_eq!(collection.opacity(0), 0.0);
+
+        collection.set_opacity(0, f32::NAN);
+        assert_eq!(collection.opacity(0), 1.0);
+
+        assert_eq!(collection.opacity(1), 1.0);
+    }
+
+    #[test]
+    fn iter_rendered_skips_hidden_and_transparent_layers() {
+        let mut collection = LayerCollection::from(vec![
+            TestLayer("A"),
+            TestLayer("B"),
+            TestLayer("C"),
+            TestLayer("D"),
+        ]);
+        collection.hide(0);
+        collection.set_opacity(1, 0.0);
+        collection.set_opacity(2, 0.5);
+
+        let rendered: Vec<_> = collection
+            .iter_rendered()
+            .map(|(layer, opacity)| (layer_name(layer), opacity))
+            .collect();
+        assert_eq!(rendered, vec![("C", 0.5), ("D", 1.0)]);
+    }
+}
@@ -0,0 +1,156 @@
+use maybe_sync::{MaybeSend, MaybeSync};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use web_time::Duration;
+
+/// User agent used by the platform services if none is configured.
+pub const DEFAULT_USER_AGENT: &str = "galileo/0.1";
+
+/// Function called before every request with the url of the request. The returned headers are
+/// added to the request.
+///
+/// Use it for the values that change over time, e.g. short-living access tokens.
+pub trait RequestHook: (Fn(&str) -> Vec<(String, String)>) + MaybeSend + MaybeSync {}
+impl<T: Fn(&str) -> Vec<(String, String)>> RequestHook for T where T: MaybeSend + MaybeSync {}
+
+/// Configuration of the HTTP requests made by a [`PlatformService`](super::PlatformService).
+///
+/// ```
+/// use galileo::platform::PlatformServiceConfig;
+/// use std::time::Duration;
+///
+/// let config = PlatformServiceConfig::new()
+///     .with_bearer_token("secret")
+///     .with_header("Referer", "https://example.com/")
+///     .with_request_hook(|url: &str| vec![("X-Request-Url".to_string(), url.to_string())])
+///     .with_timeout(Duration::from_secs(10));
+/// ```
+///
+/// In browsers the user agent, the proxy and the connection timeout are controlled by the browser
+/// and these settings are ignored.
+#[derive(Clone)]
+pub struct PlatformServiceConfig {
+    /// Value of the `User-Agent` header.
+    pub user_agent: Option<String>,
+    /// Headers added to every request.
+    pub headers: Vec<(String, String)>,
+    /// Function providing additional headers for each request.
+    pub request_hook: Option<Arc<dyn RequestHook>>,
+    /// Time limit for the whole request, including reading of the response body.
+    pub timeout: Option<Duration>,
+    /// Time limit for establishing a connection to the server.
+    pub connect_timeout: Option<Duration>,
+    /// Url of the proxy server used for all requests, e.g. `http://proxy.local:8080`.
+    pub proxy: Option<String>,
+}
+
+impl Default for PlatformServiceConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: Some(DEFAULT_USER_AGENT.to_string()),
+            headers: vec![],
+            request_hook: None,
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+        }
+    }
+}
+
+impl Debug for PlatformServiceConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let header_names: Vec<&str> = self.headers.iter().map(|(name, _)| name.as_str()).collect();
+        f.debug_struct("PlatformServiceConfig")
+            .field("user_agent", &self.user_agent)
+            .field("headers", &header_names)
+            .field("request_hook", &self.request_hook.is_some())
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("proxy", &self.proxy)
+            .finish()
+    }
+}
+
+impl PlatformServiceConfig {
+    /// Creates a configuration with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a header to every request.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds the `Authorization: Bearer <token>` header to every request.
+    pub fn with_bearer_token(self, token: impl AsRef<str>) -> Self {
+        let value = format!("Bearer {}", token.as_ref());
+        self.with_header("Authorization", value)
+    }
+
+    /// Sets the value of the `User-Agent` header.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets the function providing additional headers for each request.
+    pub fn with_request_hook(mut self, hook: impl RequestHook + 'static) -> Self {
+        self.request_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Sets the time limit for the whole request.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the time limit for establishing a connection.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sends all requests through the proxy server at the given url.
+    pub fn with_proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Returns all the headers to be added to the request to the given url: the configured headers
+    /// followed by the ones provided by the request hook.
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub(crate) fn request_headers(&self, url: &str) -> Vec<(String, String)> {
+        let mut headers = self.headers.clone();
+        if let Some(hook) = &self.request_hook {
+            headers.extend(hook(url));
+        }
+
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_request_headers() {
+        let config = PlatformServiceConfig::new()
+            .with_bearer_token("token")
+            .with_header("X-Api-Key", "key")
+            .with_request_hook(|url: &str| vec![("X-Url".to_string(), url.to_string())]);
+
+        assert_eq!(
+            config.request_headers("https://example.com"),
+            vec![
+                ("Authorization".to_string(), "Bearer token".to_string()),
+                ("X-Api-Key".to_string(), "key".to_string()),
+                ("X-Url".to_string(), "https://example.com".to_string()),
+            ]
+        );
+        assert_eq!(config.user_agent.as_deref(), Some(DEFAULT_USER_AGENT));
+    }
+}
@@ -4,17 +4,57 @@ use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
 use async_trait::async_trait;
 
+mod config;
+pub use config::{PlatformServiceConfig, RequestHook, DEFAULT_USER_AGENT};
+
 /// Service providing some platform specific functions in a generic way.
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 pub trait PlatformService {
-    /// Creates a new instance of the service. This method is a part of the trait to allow other
-    /// types be agnostic of the specific type of the platform service they work with.
-    fn new() -> Self;
+    /// Creates a new instance of the service with the default configuration. This method is a part
+    /// of the trait to allow other types be agnostic of the specific type of the platform service
+    /// they work with.
+    fn new() -> Self
+    where
+        Self: Sized,
+    {
+        Self::with_config(PlatformServiceConfig::default())
+            .expect("default platform service configuration is valid")
+    }
+    /// Creates a new instance of the service with the given configuration.
+    ///
+    /// Returns an error if the configuration is invalid, e.g. it contains a malformed header or
+    /// proxy url.
+    fn with_config(config: PlatformServiceConfig) -> Result<Self, GalileoError>
+    where
+        Self: Sized;
     /// Loads and decodes an image from the given url.
     async fn load_image_url(&self, url: &str) -> Result<DecodedImage, GalileoError>;
     /// Loads a byte array from the given url.
     async fn load_bytes_from_url(&self, url: &str) -> Result<bytes::Bytes, GalileoError>;
+    /// Loads the given byte range of the resource at the url using an HTTP range request.
+    ///
+    /// If the server ignores the range, the required part is cut from the full response.
+    async fn load_bytes_range(
+        &self,
+        url: &str,
+        range: std::ops::Range<u64>,
+    ) -> Result<bytes::Bytes, GalileoError>;
+}
+
+/// Cuts the `range` out of the response body if the server returned the whole resource.
+pub(crate) fn slice_full_response(
+    bytes: bytes::Bytes,
+    range: std::ops::Range<u64>,
+    is_partial: bool,
+) -> bytes::Bytes {
+    if is_partial {
+        return bytes;
+    }
+
+    let end = (range.end as usize).min(bytes.len());
+    let start = (range.start as usize).min(end);
+    bytes.slice(start..end)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -1,11 +1,13 @@
 //! Types for native applications.
 
 use crate::decoded_image::DecodedImage;
-use crate::error::GalileoError;
-use crate::platform::PlatformService;
+use crate::error::{GalileoError, NetworkError};
+use crate::platform::{slice_full_response, PlatformService, PlatformServiceConfig};
 use async_trait::async_trait;
 use bytes::Bytes;
 use log::info;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::ops::Range;
 
 pub mod map_builder;
 pub mod vt_processor;
@@ -14,17 +16,37 @@ pub mod vt_processor;
 #[derive(Debug, Clone)]
 pub struct NativePlatformService {
     http_client: reqwest::Client,
+    config: PlatformServiceConfig,
 }
 
 #[async_trait]
 impl PlatformService for NativePlatformService {
-    fn new() -> Self {
-        let http_client = reqwest::Client::builder()
-            .user_agent("galileo/0.1")
-            .build()
-            .expect("Failed to initialize http client");
+    fn with_config(config: PlatformServiceConfig) -> Result<Self, GalileoError> {
+        let mut builder = reqwest::Client::builder().default_headers(header_map(&config.headers)?);
+        if let Some(user_agent) = &config.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        if let Some(timeout) = config.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = config.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(proxy) = &config.proxy {
+            let proxy = reqwest::Proxy::all(proxy).map_err(|err| {
+                GalileoError::Generic(format!("invalid proxy url {proxy}: {err}"))
+            })?;
+            builder = builder.proxy(proxy);
+        }
+
+        let http_client = builder.build().map_err(|err| {
+            GalileoError::Generic(format!("failed to initialize http client: {err}"))
+        })?;
 
-        Self { http_client }
+        Ok(Self {
+            http_client,
+            config,
+        })
     }
 
     async fn load_image_url(&self, url: &str) -> Result<DecodedImage, GalileoError> {
@@ -35,20 +57,124 @@ impl PlatformService for NativePlatformService {
     async fn load_bytes_from_url(&self, url: &str) -> Result<Bytes, GalileoError> {
         self.load_from_web(url).await
     }
+
+    async fn load_bytes_range(&self, url: &str, range: Range<u64>) -> Result<Bytes, GalileoError> {
+        if range.is_empty() {
+            return Ok(Bytes::new());
+        }
+
+        let request = self.http_client.get(url).header(
+            reqwest::header::RANGE,
+            format!("bytes={}-{}", range.start, range.end - 1),
+        );
+        let response = self.send(url, request).await?;
+        let is_partial = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+
+        Ok(slice_full_response(
+            response.bytes().await?,
+            range,
+            is_partial,
+        ))
+    }
 }
 
 impl NativePlatformService {
+    /// Configuration the service was created with.
+    pub fn config(&self) -> &PlatformServiceConfig {
+        &self.config
+    }
+
     async fn load_from_web(&self, url: &str) -> Result<Bytes, GalileoError> {
-        let response = self.http_client.get(url).send().await?;
-        if !response.status().is_success() {
+        let response = self.send(url, self.http_client.get(url)).await?;
+        Ok(response.bytes().await?)
+    }
+
+    async fn send(
+        &self,
+        url: &str,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, GalileoError> {
+        if let Some(hook) = &self.config.request_hook {
+            for (name, value) in hook(url) {
+                request = request.header(name, value);
+            }
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
             info!(
-                "Failed to load {url}: {}, {:?}",
-                response.status(),
+                "Failed to load {url}: {status}, {:?}",
                 response.text().await
             );
-            return Err(GalileoError::IO);
+            return Err(NetworkError::status(url, status.as_u16()).into());
         }
 
-        Ok(response.bytes().await?)
+        Ok(response)
+    }
+}
+
+fn header_map(headers: &[(String, String)]) -> Result<HeaderMap, GalileoError> {
+    let mut map = HeaderMap::with_capacity(headers.len());
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|err| GalileoError::Generic(format!("invalid header name {name}: {err}")))?;
+        let mut header_value = HeaderValue::from_str(value).map_err(|err| {
+            GalileoError::Generic(format!("invalid value of header {name}: {err}"))
+        })?;
+        if header_name == reqwest::header::AUTHORIZATION {
+            header_value.set_sensitive(true);
+        }
+
+        map.append(header_name, header_value);
+    }
+
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn rejects_invalid_config() {
+        let config = PlatformServiceConfig::new().with_header("Bad Header", "value");
+        assert!(NativePlatformService::with_config(config).is_err());
+
+        let config = PlatformServiceConfig::new().with_header("X-Value", "line\nbreak");
+        assert!(NativePlatformService::with_config(config).is_err());
+
+        let config = PlatformServiceConfig::new().with_proxy("not a url");
+        assert!(NativePlatformService::with_config(config).is_err());
+    }
+
+    #[test]
+    fn sends_configured_headers() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/tile.pbf", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![0; 4096];
+            let len = stream.read(&mut request).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok")
+                .unwrap();
+            String::from_utf8_lossy(&request[..len]).to_lowercase()
+        });
+
+        let config = PlatformServiceConfig::new()
+            .with_user_agent("test-agent")
+            .with_bearer_token("secret")
+            .with_request_hook(|url: &str| vec![("X-Tile".to_string(), url.len().to_string())]);
+        let service = NativePlatformService::with_config(config).unwrap();
+        let bytes = tokio_test::block_on(service.load_bytes_from_url(&url)).unwrap();
+        assert_eq!(&bytes[..], b"ok");
+
+        let request = server.join().unwrap();
+        assert!(request.contains("user-agent: test-agent"));
+        assert!(request.contains("authorization: bearer secret"));
+        assert!(request.contains(&format!("x-tile: {}", url.len())));
     }
 }
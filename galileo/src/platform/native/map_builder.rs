@@ -24,6 +24,19 @@ impl MapBuilder {
     pub fn create_raster_tile_layer(
         tile_source: impl UrlSource<TileIndex> + 'static,
         tile_scheme: TileSchema,
+    ) -> RasterTileLayer<UrlImageProvider<TileIndex, FileCacheController>> {
+        Self::create_raster_tile_layer_with_platform_service(
+            tile_source,
+            tile_scheme,
+            PlatformServiceImpl::new(),
+        )
+    }
+
+    /// Create a new raster tile layer that loads tiles with the given platform service.
+    pub fn create_raster_tile_layer_with_platform_service(
+        tile_source: impl UrlSource<TileIndex> + 'static,
+        tile_scheme: TileSchema,
+        platform_service: PlatformServiceImpl,
     ) -> RasterTileLayer<UrlImageProvider<TileIndex, FileCacheController>> {
         #[cfg(not(target_os = "android"))]
         let cache_controller = FileCacheController::new(".tile_cache");
@@ -32,7 +45,11 @@ impl MapBuilder {
         let cache_controller =
             FileCacheController::new("/data/data/com.example.rastertilesandroid/.tile_cache");
 
-        let tile_provider = UrlImageProvider::new_cached(tile_source, cache_controller);
+        let tile_provider = UrlImageProvider::new_cached_with_platform_service(
+            tile_source,
+            cache_controller,
+            platform_service,
+        );
         RasterTileLayer::new(tile_scheme, tile_provider, None)
     }
 
@@ -42,10 +59,12 @@ impl MapBuilder {
         tile_source: impl UrlSource<TileIndex> + 'static,
         tile_scheme: TileSchema,
     ) -> Self {
-        self.layers.push(Box::new(Self::create_raster_tile_layer(
+        let layer = Self::create_raster_tile_layer_with_platform_service(
             tile_source,
             tile_scheme,
-        )));
+            self.platform_service.clone(),
+        );
+        self.layers.push(Box::new(layer));
         self
     }
 
@@ -55,7 +74,27 @@ impl MapBuilder {
         tile_schema: TileSchema,
         style: VectorTileStyle,
     ) -> VectorTileLayer<WebVtLoader<FileCacheController>, ThreadVtProcessor> {
-        let tile_provider = Self::create_vector_tile_provider(tile_source, tile_schema.clone());
+        Self::create_vector_tile_layer_with_platform_service(
+            tile_source,
+            tile_schema,
+            style,
+            PlatformServiceImpl::new(),
+        )
+        .await
+    }
+
+    /// Create a new vector tile layer that loads tiles with the given platform service.
+    pub async fn create_vector_tile_layer_with_platform_service(
+        tile_source: impl UrlSource<TileIndex> + 'static,
+        tile_schema: TileSchema,
+        style: VectorTileStyle,
+        platform_service: PlatformServiceImpl,
+    ) -> VectorTileLayer<WebVtLoader<FileCacheController>, ThreadVtProcessor> {
+        let tile_provider = Self::create_vector_tile_provider_with_platform_service(
+            tile_source,
+            tile_schema.clone(),
+            platform_service,
+        );
         VectorTileLayer::from_url(tile_provider, style, tile_schema).await
     }
 
@@ -64,8 +103,21 @@ impl MapBuilder {
         tile_source: impl UrlSource<TileIndex> + 'static,
         tile_schema: TileSchema,
     ) -> VectorTileProvider<WebVtLoader<FileCacheController>, ThreadVtProcessor> {
-        let loader = WebVtLoader::new(
+        Self::create_vector_tile_provider_with_platform_service(
+            tile_source,
+            tile_schema,
             PlatformServiceImpl::new(),
+        )
+    }
+
+    /// Returns a vector tile provider that loads tiles with the given platform service.
+    pub fn create_vector_tile_provider_with_platform_service(
+        tile_source: impl UrlSource<TileIndex> + 'static,
+        tile_schema: TileSchema,
+        platform_service: PlatformServiceImpl,
+    ) -> VectorTileProvider<WebVtLoader<FileCacheController>, ThreadVtProcessor> {
+        let loader = WebVtLoader::new(
+            platform_service,
             FileCacheController::new(".tile_cache"),
             tile_source,
         );
@@ -1,10 +1,11 @@
 //! Platform specific stuff for WASM32 (web) targets.
 
 use crate::decoded_image::DecodedImage;
-use crate::error::GalileoError;
-use crate::platform::PlatformService;
+use crate::error::{GalileoError, NetworkError, NetworkErrorKind};
+use crate::platform::{slice_full_response, PlatformService, PlatformServiceConfig};
 use async_trait::async_trait;
-use js_sys::Uint8Array;
+use futures::future::Either;
+use js_sys::{Array, Uint8Array};
 use std::cell::Cell;
 use std::future::Future;
 use std::pin::Pin;
@@ -14,25 +15,47 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{
-    CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, Request, RequestInit,
-    RequestMode, Response, WorkerGlobalScope,
+    AbortController, Blob, CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, Request,
+    RequestInit, RequestMode, Response, Url, WorkerGlobalScope,
 };
 
 pub mod map_builder;
 pub mod vt_processor;
 pub mod web_workers;
 
+/// `Accept` header of image requests.
+const IMAGE_ACCEPT: &str = "image/*";
+/// `Accept` header of the whole file requests, which are mostly vector tile requests.
+const VECTOR_TILE_ACCEPT: &str = "application/vnd.mapbox-vector-tile";
+
 /// Platform service for Web target.
-pub struct WebPlatformService {}
+///
+/// The user agent, proxy and connection timeout of the requests are controlled by the browser, so
+/// these values of the [`PlatformServiceConfig`] are ignored.
+#[derive(Debug, Clone)]
+pub struct WebPlatformService {
+    config: PlatformServiceConfig,
+}
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 impl PlatformService for WebPlatformService {
-    fn new() -> Self {
-        Self {}
+    fn with_config(config: PlatformServiceConfig) -> Result<Self, GalileoError> {
+        Ok(Self { config })
     }
 
     async fn load_image_url(&self, url: &str) -> Result<DecodedImage, GalileoError> {
-        let image = ImageFuture::new(url).await?;
+        // The image is fetched first and then given to the image element as a blob. Image
+        // elements can neither send custom headers nor report the reason a load failed, while the
+        // fetch error tells if the request can be retried.
+        let bytes = self.fetch_bytes(url, None, Some(IMAGE_ACCEPT)).await?;
+        let parts = Array::of1(&Uint8Array::from(&bytes[..]));
+        let blob = Blob::new_with_u8_array_sequence(&parts)?;
+        let object_url = Url::create_object_url_with_blob(&blob)?;
+        let image = ImageFuture::new(&object_url).await;
+        let _ = Url::revoke_object_url(&object_url);
+        let image = image.map_err(|_| {
+            GalileoError::Wasm(Some(format!("failed to decode image loaded from {url}")))
+        })?;
 
         let canvas: HtmlCanvasElement = web_sys::window()
             .ok_or(GalileoError::Wasm(Some(
@@ -72,22 +95,93 @@ impl PlatformService for WebPlatformService {
     }
 
     async fn load_bytes_from_url(&self, url: &str) -> Result<bytes::Bytes, GalileoError> {
+        self.fetch_bytes(url, None, Some(VECTOR_TILE_ACCEPT)).await
+    }
+
+    async fn load_bytes_range(
+        &self,
+        url: &str,
+        range: std::ops::Range<u64>,
+    ) -> Result<bytes::Bytes, GalileoError> {
+        if range.is_empty() {
+            return Ok(bytes::Bytes::new());
+        }
+
+        // Range requests read parts of archives, so the content type of the response is unknown.
+        self.fetch_bytes(url, Some(range), None).await
+    }
+}
+
+impl WebPlatformService {
+    /// Configuration the service was created with.
+    pub fn config(&self) -> &PlatformServiceConfig {
+        &self.config
+    }
+
+    async fn fetch_bytes(
+        &self,
+        url: &str,
+        range: Option<std::ops::Range<u64>>,
+        accept: Option<&str>,
+    ) -> Result<bytes::Bytes, GalileoError> {
+        let Some(timeout) = self.config.timeout else {
+            return self.fetch_bytes_inner(url, range, accept, None).await;
+        };
+
+        let controller = AbortController::new()?;
+        let fetch = Box::pin(self.fetch_bytes_inner(url, range, accept, Some(&controller)));
+        let timer = Box::pin(crate::async_runtime::sleep(timeout));
+        match futures::future::select(fetch, timer).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => {
+                controller.abort();
+                Err(NetworkError::new(url, NetworkErrorKind::Timeout).into())
+            }
+        }
+    }
+
+    async fn fetch_bytes_inner(
+        &self,
+        url: &str,
+        range: Option<std::ops::Range<u64>>,
+        accept: Option<&str>,
+        controller: Option<&AbortController>,
+    ) -> Result<bytes::Bytes, GalileoError> {
         let mut opts = RequestInit::new();
         opts.method("GET");
         opts.mode(RequestMode::Cors);
+        if let Some(controller) = controller {
+            opts.signal(Some(&controller.signal()));
+        }
 
         let request =
             Request::new_with_str_and_init(url, &opts).expect("failed to create a request object");
-        request
-            .headers()
-            .set("Accept", "application/vnd.mapbox-vector-tile")?;
+        if let Some(accept) = accept {
+            request.headers().set("Accept", accept)?;
+        }
+        if let Some(range) = &range {
+            request
+                .headers()
+                .set("Range", &format!("bytes={}-{}", range.start, range.end - 1))?;
+        }
+        for (name, value) in self.config.request_headers(url) {
+            request.headers().set(&name, &value)?;
+        }
+
+        let fetch_error = |err: JsValue| {
+            NetworkError::new(url, NetworkErrorKind::Connect).with_source(GalileoError::from(err))
+        };
 
         use wasm_bindgen::JsCast;
         let resp_value = {
             if let Some(window) = web_sys::window() {
-                JsFuture::from(window.fetch_with_request(&request)).await?
+                JsFuture::from(window.fetch_with_request(&request))
+                    .await
+                    .map_err(fetch_error)?
             } else if let Ok(global) = js_sys::global().dyn_into::<WorkerGlobalScope>() {
-                JsFuture::from(global.fetch_with_request(&request)).await?
+                JsFuture::from(global.fetch_with_request(&request))
+                    .await
+                    .map_err(fetch_error)?
             } else {
                 return Err(GalileoError::Wasm(Some(
                     "Global object is not available".into(),
@@ -97,15 +191,26 @@ impl PlatformService for WebPlatformService {
 
         assert!(resp_value.is_instance_of::<Response>());
         let resp: Response = resp_value.dyn_into()?;
+        if !resp.ok() {
+            return Err(NetworkError::status(url, resp.status()).into());
+        }
 
-        let bytes_val = JsFuture::from(resp.array_buffer()?).await?;
+        let bytes_val = JsFuture::from(resp.array_buffer()?).await.map_err(|err| {
+            NetworkError::new(url, NetworkErrorKind::Body).with_source(GalileoError::from(err))
+        })?;
         let array = Uint8Array::new(&bytes_val);
-        Ok(array.to_vec().into())
+        let bytes: bytes::Bytes = array.to_vec().into();
+
+        Ok(match range {
+            Some(range) => slice_full_response(bytes, range, resp.status() == 206),
+            None => bytes,
+        })
     }
 }
 
 /// Future for getting image with browser API
 pub struct ImageFuture {
+    url: String,
     image: Option<HtmlImageElement>,
     load_failed: Rc<Cell<bool>>,
 }
@@ -117,6 +222,7 @@ impl ImageFuture {
         image.set_cross_origin(Some("anonymous"));
         image.set_src(path);
         ImageFuture {
+            url: path.to_string(),
             image: Some(image),
             load_failed: Rc::new(Cell::new(false)),
         }
@@ -133,7 +239,12 @@ impl Future for ImageFuture {
                 let failed = self.load_failed.get();
 
                 if failed {
-                    Poll::Ready(Err(GalileoError::IO))
+                    // Browsers do not expose the reason an image failed to load.
+                    Poll::Ready(Err(NetworkError::new(
+                        self.url.clone(),
+                        NetworkErrorKind::Other,
+                    )
+                    .into()))
                 } else {
                     Poll::Ready(Ok(image))
                 }
@@ -157,7 +268,9 @@ impl Future for ImageFuture {
 
                 Poll::Pending
             }
-            _ => Poll::Ready(Err(GalileoError::IO)),
+            _ => Poll::Ready(Err(GalileoError::Generic(
+                "image future polled after completion".into(),
+            ))),
         }
     }
 }
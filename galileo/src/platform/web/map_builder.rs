@@ -26,7 +26,21 @@ impl MapBuilder {
         tile_source: impl UrlSource<TileIndex> + 'static,
         tile_scheme: TileSchema,
     ) -> RasterTileLayer<UrlImageProvider<TileIndex>> {
-        let tile_provider = UrlImageProvider::new(tile_source);
+        Self::create_raster_tile_layer_with_platform_service(
+            tile_source,
+            tile_scheme,
+            PlatformServiceImpl::new(),
+        )
+    }
+
+    /// Creates a raster tile layer that loads tiles with the given platform service.
+    pub fn create_raster_tile_layer_with_platform_service(
+        tile_source: impl UrlSource<TileIndex> + 'static,
+        tile_scheme: TileSchema,
+        platform_service: PlatformServiceImpl,
+    ) -> RasterTileLayer<UrlImageProvider<TileIndex>> {
+        let tile_provider =
+            UrlImageProvider::new_with_platform_service(tile_source, platform_service);
         RasterTileLayer::new(tile_scheme, tile_provider, None)
     }
 
@@ -36,7 +50,27 @@ impl MapBuilder {
         tile_schema: TileSchema,
         style: VectorTileStyle,
     ) -> VectorTileLayer<WebVtLoader<DummyCacheController>, WebWorkerVtProcessor> {
-        let tile_provider = Self::create_vector_tile_provider(tile_source, tile_schema.clone());
+        Self::create_vector_tile_layer_with_platform_service(
+            tile_source,
+            tile_schema,
+            style,
+            PlatformServiceImpl::new(),
+        )
+        .await
+    }
+
+    /// Create a new vector tile layer that loads tiles with the given platform service.
+    pub async fn create_vector_tile_layer_with_platform_service(
+        tile_source: impl UrlSource<TileIndex> + 'static,
+        tile_schema: TileSchema,
+        style: VectorTileStyle,
+        platform_service: PlatformServiceImpl,
+    ) -> VectorTileLayer<WebVtLoader<DummyCacheController>, WebWorkerVtProcessor> {
+        let tile_provider = Self::create_vector_tile_provider_with_platform_service(
+            tile_source,
+            tile_schema.clone(),
+            platform_service,
+        );
         VectorTileLayer::from_url(tile_provider, style, tile_schema).await
     }
 
@@ -45,11 +79,20 @@ impl MapBuilder {
         tile_source: impl UrlSource<TileIndex> + 'static,
         tile_schema: TileSchema,
     ) -> VectorTileProvider<WebVtLoader<DummyCacheController>, WebWorkerVtProcessor> {
-        let loader = WebVtLoader::new(
-            PlatformServiceImpl::new(),
-            DummyCacheController {},
+        Self::create_vector_tile_provider_with_platform_service(
             tile_source,
-        );
+            tile_schema,
+            PlatformServiceImpl::new(),
+        )
+    }
+
+    /// Create a new vector tile provider that loads tiles with the given platform service.
+    pub fn create_vector_tile_provider_with_platform_service(
+        tile_source: impl UrlSource<TileIndex> + 'static,
+        tile_schema: TileSchema,
+        platform_service: PlatformServiceImpl,
+    ) -> VectorTileProvider<WebVtLoader<DummyCacheController>, WebWorkerVtProcessor> {
+        let loader = WebVtLoader::new(platform_service, DummyCacheController {}, tile_source);
         let ww_service = WebWorkerService::new(4);
         let processor = WebWorkerVtProcessor::new(tile_schema, ww_service);
 
@@ -116,7 +159,10 @@ impl MapBuilder {
                 .unwrap()
         };
 
-        let tile_provider = UrlImageProvider::new(tile_source_int);
+        let tile_provider = UrlImageProvider::new_with_platform_service(
+            tile_source_int,
+            self.platform_service.clone(),
+        );
         self.layers.push(Box::new(RasterTileLayer::new(
             TileSchema::web(18),
             tile_provider,
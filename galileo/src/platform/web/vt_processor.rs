@@ -44,6 +44,10 @@ impl VectorTileProcessor for WebWorkerVtProcessor {
     }
 
     async fn add_style(&self, style_id: VtStyleId, style: VectorTileStyle) {
+        if let (Some(id), Some(atlas)) = (&style.sprite, style.sprite_atlas()) {
+            self.ww_service.add_sprite(id, atlas).await;
+        }
+
         self.styles.borrow_mut().insert(style_id, Arc::new(style));
     }
 
@@ -5,6 +5,7 @@ use crate::layer::vector_tile_layer::tile_provider::processor::TileProcessingErr
 use crate::render::render_bundle::tessellating::serialization::TessellatingRenderBundleBytes;
 use crate::render::render_bundle::tessellating::TessellatingRenderBundle;
 use crate::render::render_bundle::{RenderBundle, RenderBundleType};
+use crate::render::sprite::SpriteAtlas;
 use crate::tile_scheme::TileIndex;
 use crate::TileSchema;
 use futures::channel::oneshot;
@@ -74,6 +75,8 @@ enum WebWorkerRequestPayload {
         style: VectorTileStyle,
         tile_schema: TileSchema,
     },
+    /// Stores the sprite atlas in the worker, so that it is not sent with every tile.
+    AddSprite { id: String, atlas: SpriteAtlas },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -85,6 +88,7 @@ struct WebWorkerResponse {
 #[derive(Debug, Serialize, Deserialize)]
 enum WebWorkerResponsePayload {
     Ready,
+    SpriteAdded,
     ProcessVtTile {
         result: Result<Vec<u8>, TileProcessingError>,
     },
@@ -152,23 +156,67 @@ impl WebWorkerService {
         response.try_into()
     }
 
-    async fn request_operation(
-        &self,
-        payload: WebWorkerRequestPayload,
-    ) -> Result<WebWorkerResponsePayload, WebWorkerError> {
+    /// Sends the sprite atlas to all the workers. Tiles of the styles with the sprite `id` are
+    /// processed with this atlas.
+    pub async fn add_sprite(&self, id: &str, atlas: &SpriteAtlas) {
+        self.wait_ready().await;
+
+        let receivers: Vec<_> = self
+            .worker_pool
+            .iter()
+            .map(|worker_state| {
+                let (sender, receiver) = oneshot::channel();
+                let request_id = WebWorkerRequestId::next();
+                self.add_request(request_id, sender);
+                Self::send_request_to(
+                    &worker_state.worker,
+                    &WebWorkerRequest {
+                        request_id,
+                        payload: WebWorkerRequestPayload::AddSprite {
+                            id: id.to_string(),
+                            atlas: atlas.clone(),
+                        },
+                    },
+                );
+
+                receiver
+            })
+            .collect();
+
+        for receiver in receivers {
+            if !matches!(
+                receiver.await,
+                Ok(Ok(WebWorkerResponsePayload::SpriteAdded))
+            ) {
+                log::error!("Failed to add sprite {id} to a web worker");
+            }
+        }
+    }
+
+    async fn wait_ready(&self) {
         self.is_ready
             .borrow_mut()
             .wait_for(|v| *v)
             .await
             .expect("failed to read is_ready channel");
+    }
+
+    async fn request_operation(
+        &self,
+        payload: WebWorkerRequestPayload,
+    ) -> Result<WebWorkerResponsePayload, WebWorkerError> {
+        self.wait_ready().await;
 
         let (sender, receiver) = oneshot::channel();
         let request_id = WebWorkerRequestId::next();
         self.add_request(request_id, sender);
-        self.send_request(&WebWorkerRequest {
-            request_id,
-            payload,
-        });
+        Self::send_request_to(
+            self.next_worker(),
+            &WebWorkerRequest {
+                request_id,
+                payload,
+            },
+        );
 
         log::debug!("Sent request {request_id} to web worker");
 
@@ -185,8 +233,7 @@ impl WebWorkerService {
             .insert(request_id, result_channel);
     }
 
-    fn send_request(&self, request: &WebWorkerRequest) {
-        let worker = self.next_worker();
+    fn send_request_to(worker: &web_sys::Worker, request: &WebWorkerRequest) {
         worker
             .post_message(
                 &serde_wasm_bindgen::to_value(request).expect("failed to serialize ww request"),
@@ -262,6 +309,9 @@ impl WebWorkerService {
 
 mod worker {
     use galileo_mvt::MvtTile;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::Arc;
     use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
 
     use crate::{
@@ -271,6 +321,7 @@ mod worker {
         },
         platform::web::web_workers::WebWorkerResponsePayload,
         render::render_bundle::RenderBundle,
+        render::sprite::SpriteAtlas,
         tile_scheme::TileIndex,
         TileSchema,
     };
@@ -280,6 +331,11 @@ mod worker {
         WebWorkerRequestPayload, WebWorkerResponse,
     };
 
+    thread_local! {
+        /// Sprite atlases added to the worker by their ids.
+        static SPRITES: RefCell<HashMap<String, Arc<SpriteAtlas>>> = RefCell::new(HashMap::new());
+    }
+
     #[wasm_bindgen]
     pub fn init_vt_worker() {
         std::panic::set_hook(Box::new(console_error_panic_hook::hook));
@@ -341,15 +397,23 @@ mod worker {
                 style,
                 tile_schema,
             } => process_vt_tile(tile, index, style, tile_schema),
+            WebWorkerRequestPayload::AddSprite { id, atlas } => {
+                SPRITES.with(|sprites| sprites.borrow_mut().insert(id, Arc::new(atlas)));
+                WebWorkerResponsePayload::SpriteAdded
+            }
         }
     }
 
     fn process_vt_tile(
         tile: MvtTile,
         index: TileIndex,
-        style: VectorTileStyle,
+        mut style: VectorTileStyle,
         tile_schema: TileSchema,
     ) -> WebWorkerResponsePayload {
+        if let Some(id) = &style.sprite {
+            style.sprite_atlas = SPRITES.with(|sprites| sprites.borrow().get(id).cloned());
+        }
+
         let mut bundle = RenderBundle(RenderBundleType::Tessellating(
             TessellatingRenderBundle::new(),
         ));
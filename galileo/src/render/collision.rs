@@ -0,0 +1,395 @@
+//! Screen-space collision detection for labels and icons.
+//!
+//! Every frame, the renderer collects labels and icons of all the layers drawn to the map, places
+//! them into a [`CollisionIndex`] in the order of their priority, and hides the ones that would
+//! overlap already placed items. See [`CollisionOptions`] for the parameters of the placement.
+//!
+//! Features crossing tile borders are included into every tile they touch, so the same label can
+//! be added by several tiles. Copies of a label with the same text at the same screen position
+//! are drawn only once.
+
+use galileo_types::cartesian::Rect;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+/// Parameters of collision detection for a label or an icon.
+///
+/// Point symbols and labels take part in collision detection only if the options are set with
+/// [`PointPaint::with_collision`](crate::render::point_paint::PointPaint::with_collision). Line
+/// labels use collision detection with the default options unless disabled with
+/// [`LineLabelPaint::without_collision`](crate::render::line_label_paint::LineLabelPaint::without_collision).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CollisionOptions {
+    /// Items with higher priority are placed first. When priorities are equal, items of the
+    /// layers higher in the layer collection are placed first.
+    #[serde(default)]
+    pub priority: f32,
+    /// Additional space in pixels around the item that must not be occupied by other items.
+    #[serde(default)]
+    pub padding: f32,
+    /// If set to true, the item is drawn even if it overlaps items placed before it. It still
+    /// occupies the space on the screen, so the items placed after it can be hidden.
+    #[serde(default)]
+    pub allow_overlap: bool,
+}
+
+/// Size of a cell of the collision grid in pixels.
+const CELL_SIZE: f32 = 64.0;
+
+/// Maximum distance in pixels between copies of the same label from different tiles.
+const DUPLICATE_DISTANCE: f32 = 4.0;
+
+/// Grid of the screen space occupied by placed items.
+pub(crate) struct CollisionIndex {
+    width: f32,
+    height: f32,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+    boxes: Vec<Rect<f32>>,
+}
+
+impl CollisionIndex {
+    /// Creates an empty index for the screen of the given size in pixels.
+    pub fn new(width: f32, height: f32) -> Self {
+        let width = width.max(0.0);
+        let height = height.max(0.0);
+        let columns = ((width / CELL_SIZE).ceil() as usize).max(1);
+        let rows = ((height / CELL_SIZE).ceil() as usize).max(1);
+
+        Self {
+            width,
+            height,
+            columns,
+            rows,
+            cells: vec![vec![]; columns * rows],
+            boxes: vec![],
+        }
+    }
+
+    /// Tries to place an item occupying the `bbox` on the screen. Returns true if the item should
+    /// be drawn.
+    pub fn place(&mut self, bbox: Rect<f32>, options: &CollisionOptions) -> bool {
+        let bbox = bbox.shrink(-options.padding.max(0.0));
+        if !options.allow_overlap && self.collides(&bbox) {
+            return false;
+        }
+
+        self.insert(bbox);
+        true
+    }
+
+    fn collides(&self, bbox: &Rect<f32>) -> bool {
+        let Some((columns, rows)) = self.cell_range(bbox) else {
+            return false;
+        };
+
+        for row in rows {
+            for column in columns.clone() {
+                let overlaps = self.cells[row * self.columns + column]
+                    .iter()
+                    .any(|&index| overlaps(&self.boxes[index], bbox));
+                if overlaps {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn insert(&mut self, bbox: Rect<f32>) {
+        let Some((columns, rows)) = self.cell_range(&bbox) else {
+            // Item is outside of the screen, so it cannot hide anything.
+            return;
+        };
+
+        let index = self.boxes.len();
+        self.boxes.push(bbox);
+
+        for row in rows {
+            for column in columns.clone() {
+                self.cells[row * self.columns + column].push(index);
+            }
+        }
+    }
+
+    fn cell_range(
+        &self,
+        bbox: &Rect<f32>,
+    ) -> Option<(std::ops::Range<usize>, std::ops::Range<usize>)> {
+        if bbox.x_max() < 0.0
+            || bbox.y_max() < 0.0
+            || bbox.x_min() > self.width
+            || bbox.y_min() > self.height
+        {
+            return None;
+        }
+
+        let cell = |value: f32, count: usize| ((value / CELL_SIZE) as usize).min(count - 1);
+        let columns = cell(bbox.x_min().max(0.0), self.columns)
+            ..cell(bbox.x_max().max(0.0), self.columns) + 1;
+        let rows =
+            cell(bbox.y_min().max(0.0), self.rows)..cell(bbox.y_max().max(0.0), self.rows) + 1;
+
+        Some((columns, rows))
+    }
+}
+
+/// Identifier of a collision item: id of the packed bundle and index of the item in the bundle.
+pub(crate) type CollisionKey = (usize, usize);
+
+/// Placement of labels and icons for one frame.
+///
+/// Candidates for placement are collected from all the layers first, and then placed together
+/// with [`LabelPlacement::resolve`], so that the priority of the items is respected across
+/// layers and tiles.
+#[derive(Default)]
+pub(crate) struct LabelPlacement {
+    candidates: Vec<PlacementCandidate>,
+    hidden: HashSet<CollisionKey>,
+}
+
+struct PlacementCandidate {
+    key: CollisionKey,
+    layer_index: usize,
+    bbox: Option<Rect<f32>>,
+    options: CollisionOptions,
+    text_key: Option<u64>,
+}
+
+impl LabelPlacement {
+    /// Removes the results of the previous frame.
+    pub fn reset(&mut self) {
+        self.candidates.clear();
+        self.hidden.clear();
+    }
+
+    /// Adds a candidate for placement. If `bbox` is `None`, the item cannot be displayed at all
+    /// (e.g. it is behind the camera) and is always hidden.
+    ///
+    /// `text_key` identifies the text of a label. Only the first of the candidates of a layer with
+    /// the same key at the same position is placed, the others are copies of it from other tiles.
+    pub fn add_candidate(
+        &mut self,
+        key: CollisionKey,
+        layer_index: usize,
+        bbox: Option<Rect<f32>>,
+        options: CollisionOptions,
+        text_key: Option<u64>,
+    ) {
+        self.candidates.push(PlacementCandidate {
+            key,
+            layer_index,
+            bbox,
+            options,
+            text_key,
+        });
+    }
+
+    /// Places all the collected candidates on the screen of the given size.
+    pub fn resolve(&mut self, width: f32, height: f32) {
+        let mut candidates = std::mem::take(&mut self.candidates);
+        candidates.sort_by(|a, b| {
+            b.options
+                .priority
+                .partial_cmp(&a.options.priority)
+                .unwrap_or(Ordering::Equal)
+                .then(b.layer_index.cmp(&a.layer_index))
+                // Keeps the same copy of a duplicated label regardless of the order of the tiles.
+                .then(a.key.cmp(&b.key))
+        });
+
+        let mut index = CollisionIndex::new(width, height);
+        let mut texts: HashMap<(u64, usize), Vec<[f32; 2]>> = HashMap::new();
+        for candidate in candidates {
+            let is_duplicate = match (candidate.text_key, candidate.bbox) {
+                (Some(text_key), Some(bbox)) => {
+                    let center = [
+                        (bbox.x_min() + bbox.x_max()) / 2.0,
+                        (bbox.y_min() + bbox.y_max()) / 2.0,
+                    ];
+                    let copies = texts.entry((text_key, candidate.layer_index)).or_default();
+                    let is_duplicate = copies.iter().any(|copy| {
+                        (copy[0] - center[0]).abs() <= DUPLICATE_DISTANCE
+                            && (copy[1] - center[1]).abs() <= DUPLICATE_DISTANCE
+                    });
+                    if !is_duplicate {
+                        copies.push(center);
+                    }
+
+                    is_duplicate
+                }
+                _ => false,
+            };
+
+            let is_placed = !is_duplicate
+                && candidate
+                    .bbox
+                    .is_some_and(|bbox| index.place(bbox, &candidate.options));
+            if !is_placed {
+                self.hidden.insert(candidate.key);
+            }
+        }
+    }
+
+    /// Returns true if the item must not be drawn in this frame.
+    ///
+    /// Items that were not added as candidates before the placement was resolved are not hidden.
+    pub fn is_hidden(&self, key: CollisionKey) -> bool {
+        self.hidden.contains(&key)
+    }
+}
+
+fn overlaps(a: &Rect<f32>, b: &Rect<f32>) -> bool {
+    a.x_min() < b.x_max() && b.x_min() < a.x_max() && a.y_min() < b.y_max() && b.y_min() < a.y_max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect<f32> {
+        Rect::new(x, y, x + width, y + height)
+    }
+
+    #[test]
+    fn overlapping_items_are_hidden() {
+        let mut index = CollisionIndex::new(200.0, 200.0);
+        let options = CollisionOptions::default();
+
+        assert!(index.place(rect(10.0, 10.0, 50.0, 20.0), &options));
+        assert!(!index.place(rect(40.0, 20.0, 50.0, 20.0), &options));
+        assert!(index.place(rect(60.0, 10.0, 50.0, 20.0), &options));
+        assert!(index.place(rect(100.0, 150.0, 50.0, 20.0), &options));
+    }
+
+    #[test]
+    fn padding_increases_occupied_space() {
+        let mut index = CollisionIndex::new(200.0, 200.0);
+        let padded = CollisionOptions {
+            padding: 5.0,
+            ..Default::default()
+        };
+
+        assert!(index.place(rect(10.0, 10.0, 50.0, 20.0), &padded));
+        assert!(!index.place(rect(63.0, 10.0, 50.0, 20.0), &Default::default()));
+        assert!(index.place(rect(66.0, 10.0, 50.0, 20.0), &Default::default()));
+    }
+
+    #[test]
+    fn allow_overlap_is_drawn_and_occupies_space() {
+        let mut index = CollisionIndex::new(200.0, 200.0);
+        let overlap = CollisionOptions {
+            allow_overlap: true,
+            ..Default::default()
+        };
+
+        assert!(index.place(rect(10.0, 10.0, 50.0, 20.0), &Default::default()));
+        assert!(index.place(rect(30.0, 10.0, 50.0, 20.0), &overlap));
+        assert!(!index.place(rect(70.0, 10.0, 20.0, 20.0), &Default::default()));
+    }
+
+    #[test]
+    fn items_outside_of_screen_do_not_collide() {
+        let mut index = CollisionIndex::new(100.0, 100.0);
+        let options = CollisionOptions::default();
+
+        assert!(index.place(rect(-80.0, 10.0, 50.0, 20.0), &options));
+        assert!(index.place(rect(-80.0, 10.0, 50.0, 20.0), &options));
+        assert!(index.place(rect(90.0, 90.0, 50.0, 50.0), &options));
+        assert!(!index.place(rect(95.0, 95.0, 10.0, 10.0), &options));
+    }
+
+    #[test]
+    fn placement_respects_priority_and_layer_order() {
+        let mut placement = LabelPlacement::default();
+        let bbox = Some(rect(10.0, 10.0, 50.0, 20.0));
+        let low = CollisionOptions::default();
+        let high = CollisionOptions {
+            priority: 1.0,
+            ..Default::default()
+        };
+
+        placement.add_candidate((0, 0), 0, bbox, low, None);
+        placement.add_candidate((0, 1), 0, bbox, high, None);
+        placement.add_candidate((1, 0), 1, bbox, low, None);
+        placement.add_candidate((2, 0), 1, None, high, None);
+        placement.resolve(200.0, 200.0);
+
+        assert!(placement.is_hidden((0, 0)));
+        assert!(!placement.is_hidden((0, 1)));
+        assert!(placement.is_hidden((1, 0)));
+        assert!(placement.is_hidden((2, 0)));
+        assert!(!placement.is_hidden((3, 0)));
+    }
+
+    #[test]
+    fn items_of_upper_layers_are_placed_first() {
+        let mut placement = LabelPlacement::default();
+        let bbox = Some(rect(10.0, 10.0, 50.0, 20.0));
+
+        placement.add_candidate((0, 0), 0, bbox, Default::default(), None);
+        placement.add_candidate((1, 0), 1, bbox, Default::default(), None);
+        placement.resolve(200.0, 200.0);
+
+        assert!(placement.is_hidden((0, 0)));
+        assert!(!placement.is_hidden((1, 0)));
+    }
+
+    #[test]
+    fn copies_of_label_are_drawn_once() {
+        let mut placement = LabelPlacement::default();
+        let overlap = CollisionOptions {
+            allow_overlap: true,
+            ..Default::default()
+        };
+
+        // The same label of two tiles, added in a different order than the bundles were created.
+        placement.add_candidate(
+            (2, 0),
+            0,
+            Some(rect(10.0, 10.0, 50.0, 20.0)),
+            overlap,
+            Some(1),
+        );
+        placement.add_candidate(
+            (1, 0),
+            0,
+            Some(rect(11.0, 10.0, 50.0, 20.0)),
+            overlap,
+            Some(1),
+        );
+        // Other text or other position.
+        placement.add_candidate(
+            (1, 1),
+            0,
+            Some(rect(10.0, 10.0, 50.0, 20.0)),
+            overlap,
+            Some(2),
+        );
+        placement.add_candidate(
+            (1, 2),
+            0,
+            Some(rect(100.0, 10.0, 50.0, 20.0)),
+            overlap,
+            Some(1),
+        );
+        // Same label of another layer.
+        placement.add_candidate(
+            (3, 0),
+            1,
+            Some(rect(10.0, 10.0, 50.0, 20.0)),
+            overlap,
+            Some(1),
+        );
+        placement.resolve(200.0, 200.0);
+
+        assert!(!placement.is_hidden((1, 0)));
+        assert!(placement.is_hidden((2, 0)));
+        assert!(!placement.is_hidden((1, 1)));
+        assert!(!placement.is_hidden((1, 2)));
+        assert!(!placement.is_hidden((3, 0)));
+    }
+}
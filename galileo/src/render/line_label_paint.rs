@@ -0,0 +1,82 @@
+//! [`LineLabelPaint`] specifies the way a text label should be drawn along a line.
+
+use crate::render::collision::CollisionOptions;
+use crate::render::text::TextStyle;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Specifies the way a text label should be drawn along a line, e.g. a name of a street or a river.
+///
+/// Every glyph of the label is placed on the line and rotated to follow its direction. The label is
+/// repeated along the line every `spacing` pixels, but is drawn at least once if the line is long
+/// enough to fit the text. Placements where the line turns too sharply for the text to be read are
+/// dropped.
+///
+/// The glyphs keep their size and spacing in pixels at any resolution and rotate together with the
+/// map. Their positions along the line are calculated for the resolution the primitive is added to
+/// a bundle with, so on curved lines the text can deviate from the line when the map is displayed
+/// at other resolutions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineLabelPaint<'a> {
+    pub(crate) text: Cow<'a, str>,
+    pub(crate) style: Cow<'a, TextStyle>,
+    pub(crate) spacing: f32,
+    pub(crate) max_angle: f32,
+    #[serde(default)]
+    pub(crate) collision: Option<CollisionOptions>,
+}
+
+impl<'a> LineLabelPaint<'a> {
+    /// Default distance in pixels between repeated labels of a line.
+    pub const DEFAULT_SPACING: f32 = 250.0;
+    /// Default maximum angle in degrees between two adjacent glyphs of a label.
+    pub const DEFAULT_MAX_ANGLE: f32 = 45.0;
+
+    /// Creates a paint that draws the given text along a line with the specified style.
+    pub fn new(text: impl Into<Cow<'a, str>>, style: &'a TextStyle) -> Self {
+        Self {
+            text: text.into(),
+            style: Cow::Borrowed(style),
+            spacing: Self::DEFAULT_SPACING,
+            max_angle: Self::DEFAULT_MAX_ANGLE,
+            collision: Some(CollisionOptions::default()),
+        }
+    }
+
+    /// Creates a paint that draws the given text along a line with the owned style.
+    pub fn new_owned(text: String, style: TextStyle) -> Self {
+        Self {
+            text: Cow::Owned(text),
+            style: Cow::Owned(style),
+            spacing: Self::DEFAULT_SPACING,
+            max_angle: Self::DEFAULT_MAX_ANGLE,
+            collision: Some(CollisionOptions::default()),
+        }
+    }
+
+    /// Sets the distance in pixels between the centers of repeated labels.
+    pub fn with_spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Sets the maximum angle in degrees between the directions of two adjacent glyphs. Placements
+    /// of the label on the parts of the line that turn sharper than this are dropped.
+    pub fn with_max_angle(mut self, max_angle: f32) -> Self {
+        self.max_angle = max_angle;
+        self
+    }
+
+    /// Sets the parameters of collision detection for the label. Every placement of the label
+    /// along the line is checked for collisions separately.
+    pub fn with_collision(mut self, options: CollisionOptions) -> Self {
+        self.collision = Some(options);
+        self
+    }
+
+    /// Disables collision detection for the label.
+    pub fn without_collision(mut self) -> Self {
+        self.collision = None;
+        self
+    }
+}
@@ -4,20 +4,28 @@
 //!
 //! At this point only [`WgpuRenderer`] is implemented.
 
+use crate::decoded_image::DecodedImage;
 use crate::Color;
 use galileo_types::cartesian::Size;
+use lazy_static::lazy_static;
 use maybe_sync::{MaybeSend, MaybeSync};
 use render_bundle::RenderBundle;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 
 #[cfg(feature = "wgpu")]
 mod wgpu;
 #[cfg(feature = "wgpu")]
 pub use wgpu::WgpuRenderer;
 
+pub mod collision;
+pub mod line_label_paint;
 pub mod point_paint;
 pub mod render_bundle;
+pub mod sprite;
 pub mod text;
 
 /// Id of a rendering primitive
@@ -69,8 +77,112 @@ impl Default for RenderOptions {
 /// Parameters to draw a polygon primitive with.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct PolygonPaint {
-    /// Fill color of the polygon.
+    /// Fill color of the polygon. If a pattern is set, the colors of the pattern image are
+    /// multiplied by this color, so white color draws the image as is.
     pub color: Color,
+    /// If set, the polygon is filled with the repeated image instead of the solid color.
+    #[serde(default)]
+    pub pattern: Option<PatternHandle>,
+}
+
+impl PolygonPaint {
+    /// Creates a paint that fills a polygon with the solid color.
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            pattern: None,
+        }
+    }
+
+    /// Fills the polygon with the given pattern.
+    pub fn with_pattern(mut self, pattern: PatternHandle) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+}
+
+/// Image repeated over the area of a polygon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternFill {
+    /// Image of one tile of the pattern.
+    pub image: Arc<DecodedImage>,
+    /// The space the pattern is attached to.
+    #[serde(default)]
+    pub space: PatternSpace,
+}
+
+impl PatternFill {
+    /// Creates a pattern of the given image tiled in screen space.
+    pub fn new(image: Arc<DecodedImage>) -> Self {
+        Self {
+            image,
+            space: PatternSpace::default(),
+        }
+    }
+
+    /// Sets the space the pattern is attached to.
+    pub fn with_space(mut self, space: PatternSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    /// Registers the pattern and returns the handle to use it with [`PolygonPaint::with_pattern`].
+    ///
+    /// The pattern is kept in memory until the handle is [released](PatternHandle::release).
+    pub fn register(self) -> PatternHandle {
+        let handle = PatternHandle(NEXT_PATTERN_ID.fetch_add(1, Ordering::Relaxed));
+        PATTERNS
+            .write()
+            .expect("lock is poisoned")
+            .insert(handle.0, Arc::new(self));
+
+        handle
+    }
+}
+
+lazy_static! {
+    static ref PATTERNS: RwLock<HashMap<u64, Arc<PatternFill>>> = RwLock::new(HashMap::new());
+}
+
+static NEXT_PATTERN_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Copyable reference to a [`PatternFill`] registered with [`PatternFill::register`].
+///
+/// Handles are only valid in the process they were created in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PatternHandle(u64);
+
+impl PatternHandle {
+    /// Returns the registered pattern, or `None` if the handle was released.
+    pub fn pattern(&self) -> Option<Arc<PatternFill>> {
+        PATTERNS
+            .read()
+            .expect("lock is poisoned")
+            .get(&self.0)
+            .cloned()
+    }
+
+    /// Removes the pattern from the registry. Polygons already added to render bundles keep
+    /// their pattern, but new polygons with this handle are drawn with the solid color.
+    pub fn release(self) {
+        PATTERNS.write().expect("lock is poisoned").remove(&self.0);
+    }
+}
+
+/// The space a [`PatternFill`] is tiled in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PatternSpace {
+    /// One pixel of the image is one pixel of the screen, and the pattern does not move when the
+    /// map is panned or zoomed.
+    #[default]
+    Screen,
+    /// The pattern is attached to the map, so it moves and scales together with the map.
+    Map {
+        /// Size of one pixel of the image in map units. If not set, the resolution the polygon
+        /// is rendered with is used, so the image is drawn in its own size at that resolution.
+        #[serde(default)]
+        resolution: Option<f64>,
+    },
 }
 
 /// Parameter to draw a line primitive with.
@@ -85,15 +197,108 @@ pub struct LinePaint {
     pub offset: f64,
     /// Type of the cap of the line.
     pub line_cap: LineCap,
+    /// Type of the joins between the segments of the line.
+    #[serde(default)]
+    pub line_join: LineJoin,
+    /// Maximum ratio of the miter length to the line width for [`LineJoin::Miter`] joins. Joins
+    /// exceeding the limit are drawn as [`LineJoin::Bevel`]. Values less than `1.0` are treated as
+    /// `1.0`.
+    #[serde(default = "default_miter_limit")]
+    pub miter_limit: f64,
+    /// If set, the line is drawn dashed with the given pattern.
+    #[serde(default)]
+    pub dash_array: Option<DashArray>,
+}
+
+impl LinePaint {
+    /// Default value of [`LinePaint::miter_limit`].
+    pub const DEFAULT_MITER_LIMIT: f64 = 4.0;
+}
+
+fn default_miter_limit() -> f64 {
+    LinePaint::DEFAULT_MITER_LIMIT
+}
+
+/// Dash pattern of a line.
+///
+/// The pattern consists of lengths in pixels of alternating dashes and gaps, starting with a dash. If
+/// an odd number of lengths is given, the list is repeated to make it even, e.g. `[5]` is the same
+/// as `[5, 5]`. The pattern can contain up to 4 values after that.
+///
+/// Since the lengths are given in pixels, the pattern looks the same at any map resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<f32>", into = "Vec<f32>")]
+pub struct DashArray {
+    lengths: [f32; DashArray::MAX_LENGTHS],
+    count: usize,
+}
+
+impl DashArray {
+    /// Maximum number of values in the pattern.
+    pub const MAX_LENGTHS: usize = 4;
+
+    /// Creates a new dash pattern.
+    ///
+    /// Returns `None` if the pattern has too many values, or if any of the values is negative, or
+    /// if the total length of the pattern is zero.
+    pub fn new(lengths: &[f32]) -> Option<Self> {
+        let count = if lengths.len() % 2 == 1 {
+            lengths.len() * 2
+        } else {
+            lengths.len()
+        };
+
+        if count > Self::MAX_LENGTHS
+            || lengths.iter().any(|v| !v.is_finite() || *v < 0.0)
+            || lengths.iter().sum::<f32>() <= 0.0
+        {
+            return None;
+        }
+
+        let mut array = [0.0; Self::MAX_LENGTHS];
+        for (i, value) in array.iter_mut().take(count).enumerate() {
+            *value = lengths[i % lengths.len()];
+        }
+
+        Some(Self {
+            lengths: array,
+            count,
+        })
+    }
+
+    /// Lengths of dashes and gaps of the pattern.
+    pub fn lengths(&self) -> &[f32] {
+        &self.lengths[..self.count]
+    }
+
+    pub(crate) fn to_array(self) -> [f32; DashArray::MAX_LENGTHS] {
+        self.lengths
+    }
+}
+
+impl TryFrom<Vec<f32>> for DashArray {
+    type Error = &'static str;
+
+    fn try_from(value: Vec<f32>) -> Result<Self, Self::Error> {
+        Self::new(&value).ok_or("invalid dash array")
+    }
+}
+
+impl From<DashArray> for Vec<f32> {
+    fn from(value: DashArray) -> Self {
+        value.lengths().to_vec()
+    }
 }
 
 /// Cap (end point) style of the line.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LineCap {
     /// Half-circle cap.
     Round,
     /// Strait rectangular cap.
     Butt,
+    /// Rectangular cap extending beyond the end point by half of the line width.
+    Square,
 }
 
 impl From<LineCap> for lyon::path::LineCap {
@@ -101,6 +306,30 @@ impl From<LineCap> for lyon::path::LineCap {
         match val {
             LineCap::Round => lyon::lyon_tessellation::LineCap::Round,
             LineCap::Butt => lyon::lyon_tessellation::LineCap::Butt,
+            LineCap::Square => lyon::lyon_tessellation::LineCap::Square,
+        }
+    }
+}
+
+/// Style of the joins between the segments of the line.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineJoin {
+    /// Sharp corner made by extending the outer edges of the segments until they meet. If the corner
+    /// is longer than allowed by [`LinePaint::miter_limit`], a bevel join is used instead.
+    Miter,
+    /// Corner cut off by a straight line between the outer edges of the segments.
+    Bevel,
+    /// Rounded corner.
+    #[default]
+    Round,
+}
+
+impl From<LineJoin> for lyon::path::LineJoin {
+    fn from(val: LineJoin) -> Self {
+        match val {
+            LineJoin::Miter => lyon::lyon_tessellation::LineJoin::Miter,
+            LineJoin::Bevel => lyon::lyon_tessellation::LineJoin::Bevel,
+            LineJoin::Round => lyon::lyon_tessellation::LineJoin::Round,
         }
     }
 }
@@ -113,3 +342,47 @@ pub struct ImagePaint {
     /// opacity and this value represented in percents.
     pub opacity: u8,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dash_array_validation() {
+        assert_eq!(DashArray::new(&[4.0, 2.0]).unwrap().lengths(), &[4.0, 2.0]);
+        assert!(DashArray::new(&[4.0, 2.0, 1.0]).is_none());
+        assert_eq!(DashArray::new(&[3.0]).unwrap().lengths(), &[3.0, 3.0]);
+        assert!(DashArray::new(&[]).is_none());
+        assert!(DashArray::new(&[0.0, 0.0]).is_none());
+        assert!(DashArray::new(&[1.0, -1.0]).is_none());
+        assert!(DashArray::new(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).is_none());
+    }
+
+    #[test]
+    fn pattern_handle_resolves_until_released() {
+        let image = Arc::new(DecodedImage::from_raw(vec![255; 16], 2, 2).unwrap());
+        let handle = PatternFill::new(image.clone())
+            .with_space(PatternSpace::Map { resolution: None })
+            .register();
+        let paint = PolygonPaint::new(Color::WHITE).with_pattern(handle);
+        let copy = paint;
+
+        let pattern = copy.pattern.unwrap().pattern().unwrap();
+        assert!(Arc::ptr_eq(&pattern.image, &image));
+        assert_eq!(pattern.space, PatternSpace::Map { resolution: None });
+
+        handle.release();
+        assert!(paint.pattern.unwrap().pattern().is_none());
+    }
+
+    #[test]
+    fn dash_array_serialization() {
+        let dash_array: DashArray = serde_json::from_str("[5, 2, 1, 2]").unwrap();
+        assert_eq!(dash_array.to_array(), [5.0, 2.0, 1.0, 2.0]);
+        assert_eq!(
+            serde_json::to_string(&dash_array).unwrap(),
+            "[5.0,2.0,1.0,2.0]"
+        );
+        assert!(serde_json::from_str::<DashArray>("[-1]").is_err());
+    }
+}
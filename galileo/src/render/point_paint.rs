@@ -1,8 +1,10 @@
 //! [`PointPaint`] specifies the way a point should be drawn to the map.
 
 use crate::decoded_image::DecodedImage;
+use crate::render::collision::CollisionOptions;
+use crate::render::sprite::{SpriteIcon, SpriteRegion};
 use crate::render::text::TextStyle;
-use crate::render::{LineCap, LinePaint};
+use crate::render::{LineCap, LineJoin, LinePaint};
 use crate::Color;
 use galileo_types::impls::ClosedContour;
 use nalgebra::{Point2, Vector2};
@@ -15,6 +17,8 @@ use std::sync::Arc;
 pub struct PointPaint<'a> {
     pub(crate) shape: PointShape<'a>,
     pub(crate) offset: Vector2<f32>,
+    #[serde(default)]
+    pub(crate) collision: Option<CollisionOptions>,
 }
 
 impl<'a> PointPaint<'a> {
@@ -22,6 +26,7 @@ impl<'a> PointPaint<'a> {
     pub fn circle(color: Color, diameter: f32) -> Self {
         Self {
             offset: Vector2::default(),
+            collision: None,
             shape: PointShape::Circle {
                 fill: color.into(),
                 radius: diameter / 2.0,
@@ -34,6 +39,7 @@ impl<'a> PointPaint<'a> {
     pub fn sector(color: Color, diameter: f32, start_angle: f32, end_angle: f32) -> Self {
         Self {
             offset: Vector2::default(),
+            collision: None,
             shape: PointShape::Sector(SectorParameters {
                 fill: color.into(),
                 radius: diameter / 2.0,
@@ -48,6 +54,7 @@ impl<'a> PointPaint<'a> {
     pub fn square(color: Color, size: f32) -> Self {
         Self {
             offset: Vector2::default(),
+            collision: None,
             shape: PointShape::Square {
                 fill: color,
                 size,
@@ -60,6 +67,7 @@ impl<'a> PointPaint<'a> {
     pub fn dot(color: Color) -> Self {
         Self {
             offset: Vector2::default(),
+            collision: None,
             shape: PointShape::Dot { color },
         }
     }
@@ -68,6 +76,7 @@ impl<'a> PointPaint<'a> {
     pub fn shape(color: Color, contour: &'a ClosedContour<Point2<f32>>, scale: f32) -> Self {
         Self {
             offset: Vector2::default(),
+            collision: None,
             shape: PointShape::FreeShape {
                 fill: color,
                 scale,
@@ -84,8 +93,10 @@ impl<'a> PointPaint<'a> {
         let height = image.height() as f32 * scale;
         Self {
             offset,
+            collision: None,
             shape: PointShape::Image {
                 image,
+                region: None,
                 opacity: 255,
                 width,
                 height,
@@ -93,13 +104,33 @@ impl<'a> PointPaint<'a> {
         }
     }
 
+    /// Creates a paint that draws a point as an icon of a [sprite atlas](crate::render::sprite::SpriteAtlas).
+    /// Offset is given as a portion of icon size, the same way as for [`PointPaint::image`].
+    ///
+    /// Icons of the same atlas share one image, so they are stored in a render bundle only once.
+    pub fn icon(icon: &SpriteIcon, offset: Vector2<f32>, scale: f32) -> Self {
+        Self {
+            offset,
+            collision: None,
+            shape: PointShape::Image {
+                image: icon.image.clone(),
+                region: Some(icon.region),
+                opacity: 255,
+                width: icon.width() * scale,
+                height: icon.height() * scale,
+            },
+        }
+    }
+
     /// Creates a paint that draws given text label with the specified style.
     pub fn label(text: &'a String, style: &'a TextStyle) -> Self {
         Self {
             offset: Vector2::new(0.0, 0.0),
+            collision: None,
             shape: PointShape::Label {
                 text: Cow::Borrowed(text),
                 style: Cow::Borrowed(style),
+                anchor: None,
             },
         }
     }
@@ -108,9 +139,24 @@ impl<'a> PointPaint<'a> {
     pub fn label_owed(text: String, style: TextStyle) -> Self {
         Self {
             offset: Vector2::new(0.0, 0.0),
+            collision: None,
             shape: PointShape::Label {
                 text: Cow::Owned(text),
                 style: Cow::Owned(style),
+                anchor: None,
+            },
+        }
+    }
+
+    /// Creates a paint that draws given text label with the borrowed style.
+    pub(crate) fn label_with_style(text: String, style: &'a TextStyle) -> Self {
+        Self {
+            offset: Vector2::new(0.0, 0.0),
+            collision: None,
+            shape: PointShape::Label {
+                text: Cow::Owned(text),
+                style: Cow::Borrowed(style),
+                anchor: None,
             },
         }
     }
@@ -126,6 +172,9 @@ impl<'a> PointPaint<'a> {
                     width: width as f64,
                     offset: 0.0,
                     line_cap: LineCap::Round,
+                    line_join: LineJoin::default(),
+                    miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+                    dash_array: None,
                 })
             }
             _ => {}
@@ -134,6 +183,24 @@ impl<'a> PointPaint<'a> {
         self
     }
 
+    /// Sets the position of the anchor point of a label as a portion of the size of the label
+    /// text, the same way as the offset of [`PointPaint::image`]. E.g. anchor `[0.5, 1.0]` places
+    /// the center-bottom point of the text at the labeled point.
+    ///
+    /// Without an anchor the text starts at the labeled point on its baseline. Does nothing for
+    /// other shapes.
+    pub fn with_label_anchor(mut self, anchor: Vector2<f32>) -> Self {
+        if let PointShape::Label {
+            anchor: label_anchor,
+            ..
+        } = &mut self.shape
+        {
+            *label_anchor = Some(anchor);
+        }
+
+        self
+    }
+
     /// Sets offset of the paint.
     ///
     /// Offset is the distance in pixels from the base point the object will be drawn at. E.g.
@@ -145,6 +212,81 @@ impl<'a> PointPaint<'a> {
         self.offset = offset;
         self
     }
+
+    /// Sets the parameters of collision detection for the symbol.
+    ///
+    /// Symbols with collision options are hidden if they overlap other labels or icons on the screen.
+    /// No symbols, including labels, take part in collision detection unless this method is
+    /// called.
+    pub fn with_collision(mut self, options: CollisionOptions) -> Self {
+        self.collision = Some(options);
+        self
+    }
+
+    /// Disables collision detection for the symbol, so it is always drawn and does not hide
+    /// other symbols.
+    pub fn without_collision(mut self) -> Self {
+        self.collision = None;
+        self
+    }
+
+    /// Multiplies the size of the symbol by `scale`. Offset and outline width are not changed.
+    pub(crate) fn scaled(mut self, scale: f32) -> Self {
+        match &mut self.shape {
+            PointShape::Dot { .. } => {}
+            PointShape::Circle { radius, .. } => *radius *= scale,
+            PointShape::Sector(parameters) => parameters.radius *= scale,
+            PointShape::Square { size, .. } => *size *= scale,
+            PointShape::FreeShape {
+                scale: shape_scale, ..
+            } => *shape_scale *= scale,
+            PointShape::Image { width, height, .. } => {
+                *width *= scale;
+                *height *= scale;
+            }
+            PointShape::Label { style, .. } => style.to_mut().font_size *= scale,
+        }
+
+        self
+    }
+
+    /// Multiplies the opacity of all the colors of the symbol by `opacity`.
+    pub(crate) fn with_opacity(mut self, opacity: f32) -> Self {
+        let apply = |color: &mut Color| {
+            *color = color.with_opacity(opacity as f64);
+        };
+        let apply_outline = |outline: &mut Option<LinePaint>| {
+            if let Some(outline) = outline {
+                apply(&mut outline.color);
+            }
+        };
+
+        match &mut self.shape {
+            PointShape::Dot { color } => apply(color),
+            PointShape::Circle { fill, outline, .. } => {
+                apply(&mut fill.center_color);
+                apply(&mut fill.side_color);
+                apply_outline(outline);
+            }
+            PointShape::Sector(parameters) => {
+                apply(&mut parameters.fill.center_color);
+                apply(&mut parameters.fill.side_color);
+                apply_outline(&mut parameters.outline);
+            }
+            PointShape::Square { fill, outline, .. }
+            | PointShape::FreeShape { fill, outline, .. } => {
+                apply(fill);
+                apply_outline(outline);
+            }
+            PointShape::Image {
+                opacity: image_opacity,
+                ..
+            } => *image_opacity = (*image_opacity as f32 * opacity).round() as u8,
+            PointShape::Label { style, .. } => apply(&mut style.to_mut().font_color),
+        }
+
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -172,6 +314,8 @@ pub(crate) enum PointShape<'a> {
     },
     Image {
         image: Arc<DecodedImage>,
+        #[serde(default)]
+        region: Option<SpriteRegion>,
         opacity: u8,
         width: f32,
         height: f32,
@@ -179,6 +323,8 @@ pub(crate) enum PointShape<'a> {
     Label {
         text: Cow<'a, String>,
         style: Cow<'a, TextStyle>,
+        #[serde(default)]
+        anchor: Option<Vector2<f32>>,
     },
 }
 
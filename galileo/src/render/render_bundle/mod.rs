@@ -2,9 +2,11 @@
 
 use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
+use crate::render::collision::CollisionOptions;
+use crate::render::line_label_paint::LineLabelPaint;
 use crate::render::point_paint::PointPaint;
 use crate::render::render_bundle::tessellating::TessellatingRenderBundle;
-use crate::render::{ImagePaint, LinePaint, PolygonPaint, PrimitiveId};
+use crate::render::{ImagePaint, LinePaint, PatternFill, PolygonPaint, PrimitiveId};
 use crate::view::MapView;
 use galileo_types::cartesian::{CartesianPoint3d, Point2d};
 use galileo_types::contour::Contour;
@@ -88,6 +90,49 @@ impl RenderBundle {
         }
     }
 
+    /// Adds a polygon filled with the given pattern, or with the solid color if the pattern is
+    /// `None`. The pattern handle of the paint is ignored.
+    pub(crate) fn add_pattern_polygon<N, P, Poly>(
+        &mut self,
+        polygon: &Poly,
+        paint: PolygonPaint,
+        pattern: Option<&PatternFill>,
+        min_resolution: f64,
+    ) -> PrimitiveId
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        Poly: Polygon,
+        Poly::Contour: Contour<Point = P>,
+    {
+        match &mut self.0 {
+            RenderBundleType::Tessellating(inner) => {
+                inner.add_pattern_polygon(polygon, paint, pattern, min_resolution)
+            }
+        }
+    }
+
+    /// Adds several point symbols drawn at the same point, e.g. an icon and its label, that take
+    /// part in collision detection as one item with the given options. The item occupies the
+    /// space of all the symbols and they are shown or hidden together. Collision options of the
+    /// paints are ignored.
+    ///
+    /// Returns the ids of the primitives in the order of the paints.
+    pub fn add_point_group<N, P>(
+        &mut self,
+        point: &P,
+        paints: &[&PointPaint],
+        options: CollisionOptions,
+    ) -> Vec<PrimitiveId>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        match &mut self.0 {
+            RenderBundleType::Tessellating(inner) => inner.add_point_group(point, paints, options),
+        }
+    }
+
     /// Removes the primitive from the bundle.
     pub fn remove(&mut self, primitive_id: PrimitiveId) -> Result<(), GalileoError> {
         match &mut self.0 {
@@ -158,6 +203,8 @@ where
     Contour(Cow<'a, C>, LinePaint),
     /// Polygon primitive
     Polygon(Cow<'a, Poly>, PolygonPaint),
+    /// Text label placed along a contour (line)
+    LineLabel(Cow<'a, C>, Cow<'a, LineLabelPaint<'a>>),
 }
 
 impl<'a, N, P, C, Poly> RenderPrimitive<'a, N, P, C, Poly>
@@ -197,4 +244,15 @@ where
     pub fn new_polygon_ref(polygon: &'a Poly, paint: PolygonPaint) -> Self {
         Self::Polygon(Cow::Borrowed(polygon), paint)
     }
+
+    /// Creates a new primitive of a text label along the contour
+    pub fn new_line_label(contour: C, paint: LineLabelPaint<'a>) -> Self {
+        Self::LineLabel(Cow::Owned(contour), Cow::Owned(paint))
+    }
+
+    /// Creates a new primitive of a text label along the contour with the references of the
+    /// contour and the paint
+    pub fn new_line_label_ref(contour: &'a C, paint: &'a LineLabelPaint<'a>) -> Self {
+        Self::LineLabel(Cow::Borrowed(contour), Cow::Borrowed(paint))
+    }
 }
@@ -1,18 +1,22 @@
 use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
+use crate::render::collision::CollisionOptions;
+use crate::render::line_label_paint::LineLabelPaint;
 use crate::render::point_paint::{CircleFill, PointPaint, PointShape, SectorParameters};
+use crate::render::render_bundle::tessellating::line_label::LinePath;
 use crate::render::render_bundle::RenderPrimitive;
+use crate::render::sprite::SpriteRegion;
 use crate::render::text::{FontService, TextShaping, TextStyle};
-use crate::render::{ImagePaint, LinePaint, PolygonPaint, PrimitiveId};
+use crate::render::{ImagePaint, LinePaint, PatternFill, PatternSpace, PolygonPaint, PrimitiveId};
 use crate::view::MapView;
 use crate::Color;
-use galileo_types::cartesian::{CartesianPoint2d, CartesianPoint3d, Point2d, Point3d};
+use galileo_types::cartesian::{CartesianPoint2d, CartesianPoint3d, Point2d, Point3d, Rect};
 use galileo_types::contour::Contour;
 use galileo_types::impls::ClosedContour;
 use galileo_types::Polygon;
 use lyon::lyon_tessellation::{
-    BuffersBuilder, FillOptions, FillTessellator, FillVertex, FillVertexConstructor, LineJoin,
-    Side, StrokeOptions, StrokeTessellator, StrokeVertex, StrokeVertexConstructor, VertexBuffers,
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, FillVertexConstructor, Side,
+    StrokeOptions, StrokeTessellator, StrokeVertex, StrokeVertexConstructor, VertexBuffers,
 };
 use lyon::math::point;
 use lyon::path::builder::PathBuilder;
@@ -23,6 +27,9 @@ use nalgebra::{Point2, Vector2};
 use num_traits::AsPrimitive;
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::mem::size_of;
 use std::ops::Range;
 use std::sync::Arc;
@@ -30,12 +37,17 @@ use std::sync::Arc;
 #[derive(Debug, Clone)]
 pub(crate) struct TessellatingRenderBundle {
     pub poly_tessellation: VertexBuffers<PolyVertex, u32>,
+    pub dash_tessellation: VertexBuffers<DashVertex, u32>,
+    pub pattern_tessellation: VertexBuffers<PatternVertex, u32>,
     pub points: Vec<PointInstance>,
     pub screen_ref: ScreenRefTessellation,
     pub images: Vec<ImageInfo>,
     pub clip_area: Option<VertexBuffers<PolyVertex, u32>>,
     pub image_store: Vec<ImageStoreInfo>,
     pub primitives: Vec<PrimitiveInfo>,
+    pub collidables: Vec<Collidable>,
+    /// Ids of the map reference primitives in the order they are drawn.
+    pub map_ref_order: Vec<usize>,
     vacant_ids: Vec<usize>,
     vacant_image_ids: Vec<usize>,
     vacant_image_store_ids: Vec<usize>,
@@ -62,16 +74,83 @@ pub(crate) struct ScreenRefVertex {
     position: [f32; 3],
     normal: [f32; 2],
     color: [u8; 4],
+    /// If not zero, the normal is rotated together with the map instead of staying aligned with
+    /// the screen.
+    map_oriented: u32,
+}
+
+/// Label or icon that takes part in the collision detection.
+///
+/// An item can consist of several primitives drawn at the same anchor, e.g. an icon with a label,
+/// which are shown or hidden together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Collidable {
+    pub primitive_ids: Vec<usize>,
+    /// Position of the item in map coordinates.
+    pub anchor: [f32; 3],
+    /// Bounding box of the item in pixels relative to the anchor, with `y` axis pointing up.
+    pub bbox: Rect<f32>,
+    /// If true, the item is rotated together with the map, so its bounding box must be rotated
+    /// too before checking for collisions.
+    #[serde(default)]
+    pub map_oriented: bool,
+    /// Hash of the label text, used to draw copies of the same label from different tiles only
+    /// once.
+    #[serde(default)]
+    pub text_key: Option<u64>,
+    pub options: CollisionOptions,
+    pub targets: Vec<CollidableTarget>,
+}
+
+/// Kind of a map reference primitive, which defines the tessellation its vertices are stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum MapRefKind {
+    /// Solid line or polygon stored in the `poly_tessellation`.
+    Plain,
+    /// Dashed line stored in the `dash_tessellation`.
+    Dashed,
+    /// Polygon filled with the pattern image stored in the `pattern_tessellation`.
+    Pattern { image_store_index: usize },
+}
+
+impl MapRefKind {
+    /// Returns true if primitives of both kinds are stored in the same tessellation.
+    fn same_tessellation(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// Range of the indices of one of the map reference tessellations that are drawn together.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MapRefRange {
+    pub kind: MapRefKind,
+    pub index_range: Range<u32>,
+}
+
+/// Part of the bundle buffers that is drawn for a [`Collidable`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum CollidableTarget {
+    ScreenRef { index_range: Range<usize> },
+    Image { image_index: usize },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub(crate) enum PrimitiveInfo {
     None,
     Vacant,
-    MapRef { vertex_range: Range<usize> },
-    ScreenRef { vertex_range: Range<usize> },
-    Dot { point_index: usize },
-    Image { image_index: usize },
+    MapRef {
+        vertex_range: Range<usize>,
+        kind: MapRefKind,
+    },
+    ScreenRef {
+        vertex_range: Range<usize>,
+    },
+    Dot {
+        point_index: usize,
+    },
+    Image {
+        image_index: usize,
+    },
 }
 
 impl Default for TessellatingRenderBundle {
@@ -84,10 +163,14 @@ impl TessellatingRenderBundle {
     pub fn new() -> Self {
         Self {
             poly_tessellation: VertexBuffers::new(),
+            dash_tessellation: VertexBuffers::new(),
+            pattern_tessellation: VertexBuffers::new(),
             points: Vec::new(),
             screen_ref: VertexBuffers::new(),
             images: Vec::new(),
             primitives: Vec::new(),
+            collidables: Vec::new(),
+            map_ref_order: Vec::new(),
             clip_area: None,
             image_store: Vec::new(),
             vacant_ids: vec![],
@@ -115,10 +198,10 @@ impl TessellatingRenderBundle {
         let mut tessellation = VertexBuffers::new();
         Self::tessellate_polygon(
             polygon,
-            PolygonPaint {
-                color: Color::BLACK,
-            },
             &mut tessellation,
+            PolygonVertexConstructor {
+                color: Color::BLACK.to_f32_array(),
+            },
         );
 
         self.buffer_size += tessellation.vertices.len() * std::mem::size_of::<PolyVertex>()
@@ -135,7 +218,7 @@ impl TessellatingRenderBundle {
     ) -> PrimitiveId {
         let opacity = paint.opacity as f32 / 255.0;
 
-        self.buffer_size += image.bytes().len() + std::mem::size_of::<ImageVertex>() * 4;
+        self.buffer_size += std::mem::size_of::<ImageVertex>() * 4;
 
         let index = self.add_image_to_store(Arc::new(image));
         let vertices = [
@@ -173,10 +256,12 @@ impl TessellatingRenderBundle {
         PrimitiveId(id)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn add_image_point<N, P>(
         &mut self,
         position: &P,
         image: Arc<DecodedImage>,
+        region: Option<SpriteRegion>,
         opacity: u8,
         width: f32,
         height: f32,
@@ -188,36 +273,50 @@ impl TessellatingRenderBundle {
     {
         let opacity = opacity as f32 / 255.0;
 
-        self.buffer_size += image.bytes().len() + size_of::<ImageVertex>() * 4;
+        self.buffer_size += size_of::<ImageVertex>() * 4;
 
         let position = [position.x().as_(), position.y().as_()];
         let offset_x = -offset[0] * width;
         let offset_y = offset[1] * height;
 
+        let [u_min, v_min, u_max, v_max] = match region {
+            Some(region) => {
+                let image_width = image.width() as f32;
+                let image_height = image.height() as f32;
+                [
+                    region.x as f32 / image_width,
+                    region.y as f32 / image_height,
+                    (region.x + region.width) as f32 / image_width,
+                    (region.y + region.height) as f32 / image_height,
+                ]
+            }
+            None => [0.0, 0.0, 1.0, 1.0],
+        };
+
         let index = self.add_image_to_store(image);
         let vertices = [
             ImageVertex {
                 position,
                 opacity,
-                tex_coords: [0.0, 1.0],
+                tex_coords: [u_min, v_max],
                 offset: [offset_x, offset_y - height],
             },
             ImageVertex {
                 position,
                 opacity,
-                tex_coords: [0.0, 0.0],
+                tex_coords: [u_min, v_min],
                 offset: [offset_x, offset_y],
             },
             ImageVertex {
                 position,
                 opacity,
-                tex_coords: [1.0, 1.0],
+                tex_coords: [u_max, v_max],
                 offset: [offset_x + width, offset_y - height],
             },
             ImageVertex {
                 position,
                 opacity,
-                tex_coords: [1.0, 0.0],
+                tex_coords: [u_max, v_min],
                 offset: [offset_x + width, offset_y],
             },
         ];
@@ -250,6 +349,8 @@ impl TessellatingRenderBundle {
         }
     }
 
+    /// Adds the image to the image store, unless the same image is already stored there. Images
+    /// are compared by pointer, so all the icons of a sprite atlas share one store entry.
     fn add_image_to_store(&mut self, image: Arc<DecodedImage>) -> usize {
         for (i, stored) in self.image_store.iter().enumerate() {
             match stored {
@@ -262,6 +363,8 @@ impl TessellatingRenderBundle {
             }
         }
 
+        self.buffer_size += image.bytes().len();
+
         if let Some(id) = self.vacant_image_store_ids.pop() {
             self.image_store[id] = ImageStoreInfo::Image(image);
             id
@@ -292,6 +395,9 @@ impl TessellatingRenderBundle {
             RenderPrimitive::Polygon(polygon, paint) => {
                 self.add_polygon::<N, P, Poly>(polygon.borrow(), paint, min_resolution)
             }
+            RenderPrimitive::LineLabel(line, paint) => {
+                self.add_line_label::<N, P, C>(line.borrow(), &paint, min_resolution)
+            }
         }
     }
 
@@ -316,8 +422,8 @@ impl TessellatingRenderBundle {
         let info = &self.primitives[primitive_id.0];
 
         match info {
-            PrimitiveInfo::MapRef { vertex_range } => {
-                self.update_map_ref(vertex_range.clone(), primitive)
+            PrimitiveInfo::MapRef { vertex_range, kind } => {
+                self.update_map_ref(vertex_range.clone(), *kind, primitive)
             }
             PrimitiveInfo::Vacant => Ok(()),
             _ => todo!(),
@@ -332,9 +438,14 @@ impl TessellatingRenderBundle {
         }
 
         let info = std::mem::replace(&mut self.primitives[primitive_id.0], PrimitiveInfo::Vacant);
+        self.collidables
+            .retain(|collidable| !collidable.primitive_ids.contains(&primitive_id.0));
 
         match info {
-            PrimitiveInfo::MapRef { vertex_range } => self.remove_map_ref(vertex_range),
+            PrimitiveInfo::MapRef { vertex_range, kind } => {
+                self.map_ref_order.retain(|id| *id != primitive_id.0);
+                self.remove_map_ref(vertex_range, kind)
+            }
             PrimitiveInfo::ScreenRef { vertex_range } => self.remove_screen_ref(vertex_range),
             PrimitiveInfo::Dot { point_index } => self.remove_dot(point_index),
             PrimitiveInfo::Image { image_index } => self.remove_image(image_index),
@@ -360,22 +471,9 @@ impl TessellatingRenderBundle {
                 }
             };
 
-            let stored_image_unused = self.images.iter().all(|info| match info {
-                ImageInfo::Vacant => false,
-                ImageInfo::Image((i, _)) => *i != image_id,
-            });
-
-            if stored_image_unused {
-                match std::mem::replace(&mut self.image_store[image_id], ImageStoreInfo::Vacant) {
-                    ImageStoreInfo::Vacant => {
-                        // this should not happen
-                    }
-                    ImageStoreInfo::Image(image) => {
-                        self.vacant_image_store_ids.push(image_id);
-
-                        self.buffer_size -= image.bytes.len() + size_of::<ImageVertex>() * 4;
-                    }
-                }
+            self.buffer_size -= size_of::<ImageVertex>() * 4;
+            if let Some(image) = self.release_stored_image(image_id) {
+                self.buffer_size -= image.bytes.len();
             }
 
             for info in &mut self.primitives {
@@ -393,6 +491,35 @@ impl TessellatingRenderBundle {
         }
     }
 
+    /// Removes the image from the image store if it is not used by any image or pattern anymore.
+    /// Returns the removed image.
+    fn release_stored_image(&mut self, image_id: usize) -> Option<Arc<DecodedImage>> {
+        let used_by_images = self.images.iter().any(|info| match info {
+            ImageInfo::Vacant => false,
+            ImageInfo::Image((i, _)) => *i == image_id,
+        });
+        let used_by_patterns = self.primitives.iter().any(|info| {
+            matches!(
+                info,
+                PrimitiveInfo::MapRef {
+                    kind: MapRefKind::Pattern { image_store_index },
+                    ..
+                } if *image_store_index == image_id
+            )
+        });
+        if used_by_images || used_by_patterns {
+            return None;
+        }
+
+        match std::mem::replace(self.image_store.get_mut(image_id)?, ImageStoreInfo::Vacant) {
+            ImageStoreInfo::Vacant => None,
+            ImageStoreInfo::Image(image) => {
+                self.vacant_image_store_ids.push(image_id);
+                Some(image)
+            }
+        }
+    }
+
     fn remove_dot(&mut self, index: usize) -> Result<(), GalileoError> {
         if index >= self.points.len() {
             Err(GalileoError::Generic("index out of bounds".into()))
@@ -417,6 +544,11 @@ impl TessellatingRenderBundle {
     }
 
     fn remove_screen_ref(&mut self, range: Range<usize>) -> Result<(), GalileoError> {
+        let removed_indices_start = self
+            .screen_ref
+            .indices
+            .iter()
+            .position(|index| range.contains(&(*index as usize)));
         let removed_index_count =
             Self::remove_from_tessellation(&mut self.screen_ref, range.clone())?;
         let len = range.len();
@@ -435,20 +567,61 @@ impl TessellatingRenderBundle {
             }
         }
 
+        if let Some(removed_start) = removed_indices_start {
+            for target in self
+                .collidables
+                .iter_mut()
+                .flat_map(|collidable| &mut collidable.targets)
+            {
+                match target {
+                    CollidableTarget::ScreenRef { index_range }
+                        if index_range.start >= removed_start =>
+                    {
+                        index_range.start -= removed_index_count;
+                        index_range.end -= removed_index_count;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
         Ok(())
     }
 
-    fn remove_map_ref(&mut self, range: Range<usize>) -> Result<(), GalileoError> {
-        let removed_index_count =
-            Self::remove_from_tessellation(&mut self.poly_tessellation, range.clone())?;
+    fn remove_map_ref(
+        &mut self,
+        range: Range<usize>,
+        kind: MapRefKind,
+    ) -> Result<(), GalileoError> {
+        let (removed_index_count, vertex_size) = match kind {
+            MapRefKind::Plain => (
+                Self::remove_from_tessellation(&mut self.poly_tessellation, range.clone())?,
+                size_of::<PolyVertex>(),
+            ),
+            MapRefKind::Dashed => (
+                Self::remove_from_tessellation(&mut self.dash_tessellation, range.clone())?,
+                size_of::<DashVertex>(),
+            ),
+            MapRefKind::Pattern { image_store_index } => {
+                if let Some(image) = self.release_stored_image(image_store_index) {
+                    self.buffer_size -= image.bytes.len();
+                }
+
+                (
+                    Self::remove_from_tessellation(&mut self.pattern_tessellation, range.clone())?,
+                    size_of::<PatternVertex>(),
+                )
+            }
+        };
         let len = range.len();
-        self.buffer_size -= size_of::<PolyVertex>() * len + size_of::<u32>() * removed_index_count;
+        self.buffer_size -= vertex_size * len + size_of::<u32>() * removed_index_count;
 
         for info in &mut self.primitives {
             match info {
                 PrimitiveInfo::MapRef {
                     ref mut vertex_range,
-                } if vertex_range.start >= range.end => {
+                    kind: other_kind,
+                } if other_kind.same_tessellation(&kind) && vertex_range.start >= range.end => {
                     vertex_range.start -= len;
                     vertex_range.end -= len;
                 }
@@ -492,6 +665,71 @@ impl TessellatingRenderBundle {
     }
 
     pub fn add_point<N, P>(&mut self, point: &P, paint: &PointPaint) -> PrimitiveId
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        let start_index_count = self.screen_ref.indices.len();
+        let id = self.add_point_primitive(point, paint);
+        if let Some(options) = paint.collision {
+            if let Some((bbox, target)) = self.collidable_part(id, start_index_count) {
+                self.collidables.push(Collidable {
+                    primitive_ids: vec![id.0],
+                    anchor: [point.x().as_(), point.y().as_(), point.z().as_()],
+                    bbox,
+                    map_oriented: false,
+                    text_key: label_text_key(&[paint]),
+                    options,
+                    targets: vec![target],
+                });
+            }
+        }
+
+        id
+    }
+
+    /// Adds several symbols at the same point that take part in the collision detection as one
+    /// item with the given options, e.g. an icon with its label. Collision options of the paints
+    /// are ignored.
+    pub fn add_point_group<N, P>(
+        &mut self,
+        point: &P,
+        paints: &[&PointPaint],
+        options: CollisionOptions,
+    ) -> Vec<PrimitiveId>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        let mut ids = vec![];
+        let mut parts = vec![];
+        for paint in paints {
+            let start_index_count = self.screen_ref.indices.len();
+            let id = self.add_point_primitive(point, paint);
+            parts.extend(self.collidable_part(id, start_index_count));
+            ids.push(id);
+        }
+
+        let bbox = parts
+            .iter()
+            .map(|(bbox, _)| *bbox)
+            .collect::<Option<Rect<f32>>>();
+        if let Some(bbox) = bbox {
+            self.collidables.push(Collidable {
+                primitive_ids: ids.iter().map(|id| id.0).collect(),
+                anchor: [point.x().as_(), point.y().as_(), point.z().as_()],
+                bbox,
+                map_oriented: false,
+                text_key: label_text_key(paints),
+                options,
+                targets: parts.into_iter().map(|(_, target)| target).collect(),
+            });
+        }
+
+        ids
+    }
+
+    fn add_point_primitive<N, P>(&mut self, point: &P, paint: &PointPaint) -> PrimitiveId
     where
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
@@ -506,12 +744,14 @@ impl TessellatingRenderBundle {
             }
             PointShape::Image {
                 image,
+                region,
                 opacity,
                 width,
                 height,
             } => self.add_image_point(
                 point,
                 image.clone(),
+                *region,
                 *opacity,
                 *width,
                 *height,
@@ -554,12 +794,53 @@ impl TessellatingRenderBundle {
                     vertex_range: start_index..self.screen_ref.vertices.len(),
                 }
             }
-            PointShape::Label { text, style } => self.add_label(point, text, style, paint.offset),
+            PointShape::Label {
+                text,
+                style,
+                anchor,
+            } => self.add_label(point, text, style, paint.offset, *anchor),
         };
 
         self.add_primitive_info(info)
     }
 
+    /// Returns the bounding box in pixels and the drawn part of the point primitive, which was
+    /// added when the screen reference tessellation had `start_index_count` indices.
+    fn collidable_part(
+        &self,
+        id: PrimitiveId,
+        start_index_count: usize,
+    ) -> Option<(Rect<f32>, CollidableTarget)> {
+        let (offsets, target): (Vec<[f32; 2]>, _) = match &self.primitives[id.0] {
+            PrimitiveInfo::ScreenRef { vertex_range } => (
+                self.screen_ref.vertices[vertex_range.clone()]
+                    .iter()
+                    .map(|vertex| vertex.normal)
+                    .collect(),
+                CollidableTarget::ScreenRef {
+                    index_range: start_index_count..self.screen_ref.indices.len(),
+                },
+            ),
+            PrimitiveInfo::Image { image_index } => match &self.images[*image_index] {
+                ImageInfo::Image((_, vertices)) => (
+                    vertices.iter().map(|vertex| vertex.offset).collect(),
+                    CollidableTarget::Image {
+                        image_index: *image_index,
+                    },
+                ),
+                ImageInfo::Vacant => return None,
+            },
+            _ => return None,
+        };
+
+        let bbox = offsets
+            .iter()
+            .map(|offset| Rect::new(offset[0], offset[1], offset[0], offset[1]))
+            .collect::<Option<Rect<f32>>>()?;
+
+        Some((bbox, target))
+    }
+
     pub fn add_line<N, P, C>(
         &mut self,
         line: &C,
@@ -572,10 +853,19 @@ impl TessellatingRenderBundle {
         C: Contour<Point = P>,
     {
         let range = self.add_line_lod(line, paint, min_resolution);
+        let kind = match paint.dash_array {
+            Some(_) => MapRefKind::Dashed,
+            None => MapRefKind::Plain,
+        };
+
+        self.add_map_ref_info(range, kind)
+    }
 
-        self.add_primitive_info(PrimitiveInfo::MapRef {
-            vertex_range: range,
-        })
+    fn add_map_ref_info(&mut self, vertex_range: Range<usize>, kind: MapRefKind) -> PrimitiveId {
+        let id = self.add_primitive_info(PrimitiveInfo::MapRef { vertex_range, kind });
+        self.map_ref_order.push(id.0);
+
+        id
     }
 
     fn add_line_lod<N, P, C>(
@@ -589,7 +879,6 @@ impl TessellatingRenderBundle {
         P: CartesianPoint3d<Num = N>,
         C: Contour<Point = P>,
     {
-        let tessellation = &mut self.poly_tessellation;
         let mut path_builder = BuilderWithAttributes::new(1);
         let mut iterator = line.iter_points();
 
@@ -625,19 +914,48 @@ impl TessellatingRenderBundle {
             resolution: min_resolution as f32,
             path: &path,
         };
+        let options = StrokeOptions::DEFAULT
+            .with_line_cap(paint.line_cap.into())
+            .with_line_width(paint.width as f32)
+            .with_miter_limit(paint.miter_limit.max(1.0) as f32)
+            .with_tolerance(0.1)
+            .with_line_join(paint.line_join.into());
+
+        match paint.dash_array {
+            Some(dash_array) => Self::stroke_path(
+                &path,
+                &options,
+                &mut self.dash_tessellation,
+                &mut self.buffer_size,
+                DashedLineVertexConstructor {
+                    line: vertex_constructor,
+                    dash_array: dash_array.to_array(),
+                },
+            ),
+            None => Self::stroke_path(
+                &path,
+                &options,
+                &mut self.poly_tessellation,
+                &mut self.buffer_size,
+                vertex_constructor,
+            ),
+        }
+    }
 
+    fn stroke_path<V>(
+        path: &Path,
+        options: &StrokeOptions,
+        tessellation: &mut VertexBuffers<V, u32>,
+        buffer_size: &mut usize,
+        vertex_constructor: impl StrokeVertexConstructor<V>,
+    ) -> Range<usize> {
         let mut tesselator = StrokeTessellator::new();
         let start_index = tessellation.vertices.len();
         let start_index_count = tessellation.indices.len();
 
         if let Err(err) = tesselator.tessellate_path(
-            &path,
-            &StrokeOptions::DEFAULT
-                .with_line_cap(paint.line_cap.into())
-                .with_line_width(paint.width as f32)
-                .with_miter_limit(1.0)
-                .with_tolerance(0.1)
-                .with_line_join(LineJoin::Round),
+            path,
+            options,
             &mut BuffersBuilder::new(tessellation, vertex_constructor),
         ) {
             log::error!("Tessellation failed: {err}");
@@ -646,12 +964,179 @@ impl TessellatingRenderBundle {
 
         let end_index = tessellation.vertices.len();
 
-        self.buffer_size += (end_index - start_index) * size_of::<PolyVertex>();
-        self.buffer_size += (tessellation.indices.len() - start_index_count) * size_of::<u32>();
+        *buffer_size += (end_index - start_index) * size_of::<V>();
+        *buffer_size += (tessellation.indices.len() - start_index_count) * size_of::<u32>();
 
         start_index..end_index
     }
 
+    pub fn add_line_label<N, P, C>(
+        &mut self,
+        line: &C,
+        paint: &LineLabelPaint,
+        min_resolution: f64,
+    ) -> PrimitiveId
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        let start_vertex_count = self.screen_ref.vertices.len();
+        let start_index_count = self.screen_ref.indices.len();
+
+        let labels = self.add_line_label_glyphs(line, paint, min_resolution);
+
+        self.buffer_size += (self.screen_ref.vertices.len() - start_vertex_count)
+            * size_of::<ScreenRefVertex>()
+            + (self.screen_ref.indices.len() - start_index_count) * size_of::<u32>();
+
+        let id = self.add_primitive_info(PrimitiveInfo::ScreenRef {
+            vertex_range: start_vertex_count..self.screen_ref.vertices.len(),
+        });
+
+        if let Some(options) = paint.collision {
+            for (anchor, bbox, index_range) in labels {
+                self.collidables.push(Collidable {
+                    primitive_ids: vec![id.0],
+                    anchor,
+                    bbox,
+                    map_oriented: true,
+                    text_key: Some(text_key(&paint.text)),
+                    options,
+                    targets: vec![CollidableTarget::ScreenRef { index_range }],
+                });
+            }
+        }
+
+        id
+    }
+
+    /// Adds glyphs of all the placements of the label along the line. Returns anchor, bounding box
+    /// and the range of indices of every placement.
+    fn add_line_label_glyphs<N, P, C>(
+        &mut self,
+        line: &C,
+        paint: &LineLabelPaint,
+        resolution: f64,
+    ) -> Vec<([f32; 3], Rect<f32>, Range<usize>)>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        C: Contour<Point = P>,
+    {
+        let glyphs = FontService::with(|font_service| {
+            match font_service.shape(&paint.text, &paint.style, Vector2::zeros()) {
+                Ok(TextShaping::Tessellation { glyphs, .. }) => glyphs,
+                Err(err) => {
+                    log::error!("Error shaping text label: {err:?}");
+                    vec![]
+                }
+                _ => {
+                    log::error!("Not supported font type");
+                    vec![]
+                }
+            }
+        });
+
+        // Whitespace glyphs have no vertices and need not be placed.
+        let glyphs: Vec<_> = glyphs
+            .into_iter()
+            .filter_map(|glyph| {
+                let bounds = glyph
+                    .vertices
+                    .iter()
+                    .map(|v| Rect::new(v[0], v[1], v[0], v[1]))
+                    .collect::<Option<Rect<f32>>>()?;
+                Some((glyph, bounds))
+            })
+            .collect();
+        let Some(text_bounds) = glyphs
+            .iter()
+            .map(|(_, bounds)| *bounds)
+            .collect::<Option<_>>()
+        else {
+            return vec![];
+        };
+
+        let text_center = text_bounds.center();
+        let glyph_offsets: Vec<f64> = glyphs
+            .iter()
+            .map(|(_, bounds)| (bounds.center().x - text_center.x) as f64)
+            .collect();
+
+        let path = LinePath::new(line.iter_points().map(|p| {
+            [
+                p.x().as_() as f64 / resolution,
+                p.y().as_() as f64 / resolution,
+                p.z().as_() as f64,
+            ]
+        }));
+        let to_map = |position: [f64; 3]| {
+            [
+                (position[0] * resolution) as f32,
+                (position[1] * resolution) as f32,
+                position[2] as f32,
+            ]
+        };
+
+        let width = text_bounds.width() as f64;
+        let color = paint.style.font_color.to_u8_array();
+        let mut labels = vec![];
+        for center in path.label_centers(width, paint.spacing as f64) {
+            let Some(label) = path.place(
+                center,
+                width,
+                &glyph_offsets,
+                (paint.max_angle as f64).to_radians(),
+            ) else {
+                continue;
+            };
+
+            // All glyphs are anchored at the label center on the line and offset from it in pixels.
+            // The offsets are rotated together with the map when it is rendered.
+            let position = to_map(label.anchor);
+            let indices_start = self.screen_ref.indices.len();
+            let mut bbox: Option<Rect<f32>> = None;
+            for ((glyph, bounds), placement) in glyphs.iter().zip(&label.glyphs) {
+                let (sin, cos) = (placement.angle as f32).sin_cos();
+                let glyph_center = [placement.offset[0] as f32, placement.offset[1] as f32];
+
+                let vertices_start = self.screen_ref.vertices.len() as u32;
+                for vertex in &glyph.vertices {
+                    let x = vertex[0] - bounds.center().x;
+                    let y = vertex[1] - text_center.y;
+                    let normal = [
+                        glyph_center[0] + x * cos - y * sin,
+                        glyph_center[1] + x * sin + y * cos,
+                    ];
+
+                    let point_rect = Rect::new(normal[0], normal[1], normal[0], normal[1]);
+                    bbox = Some(match bbox {
+                        Some(bbox) => bbox.merge(point_rect),
+                        None => point_rect,
+                    });
+
+                    self.screen_ref.vertices.push(ScreenRefVertex {
+                        position,
+                        normal,
+                        color,
+                        map_oriented: 1,
+                    });
+                }
+
+                for index in &glyph.indices {
+                    self.screen_ref.indices.push(index + vertices_start);
+                }
+            }
+
+            if let Some(bbox) = bbox {
+                labels.push((position, bbox, indices_start..self.screen_ref.indices.len()));
+            }
+        }
+
+        labels
+    }
+
     pub fn add_polygon<N, P, Poly>(
         &mut self,
         polygon: &Poly,
@@ -664,8 +1149,131 @@ impl TessellatingRenderBundle {
         Poly: Polygon,
         Poly::Contour: Contour<Point = P>,
     {
-        let vertex_range = self.add_polygon_lod(polygon, paint, min_resolution as f32);
-        self.add_primitive_info(PrimitiveInfo::MapRef { vertex_range })
+        let pattern = paint.pattern.and_then(|handle| handle.pattern());
+        self.add_pattern_polygon(polygon, paint, pattern.as_deref(), min_resolution)
+    }
+
+    /// Adds a polygon filled with the given pattern instead of the one registered for the paint.
+    pub fn add_pattern_polygon<N, P, Poly>(
+        &mut self,
+        polygon: &Poly,
+        paint: PolygonPaint,
+        pattern: Option<&PatternFill>,
+        min_resolution: f64,
+    ) -> PrimitiveId
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+        Poly: Polygon,
+        Poly::Contour: Contour<Point = P>,
+    {
+        let color = paint.color.to_f32_array();
+        let coordinates =
+            pattern.and_then(|pattern| pattern_coordinates(polygon, pattern, min_resolution));
+
+        match (pattern, coordinates) {
+            (Some(pattern), Some(coordinates)) => {
+                let vertex_range = Self::add_to_tessellation(
+                    polygon,
+                    &mut self.pattern_tessellation,
+                    &mut self.buffer_size,
+                    PatternVertexConstructor { color, coordinates },
+                );
+                let image_store_index = self.add_image_to_store(pattern.image.clone());
+
+                self.add_map_ref_info(vertex_range, MapRefKind::Pattern { image_store_index })
+            }
+            _ => {
+                let vertex_range = Self::add_to_tessellation(
+                    polygon,
+                    &mut self.poly_tessellation,
+                    &mut self.buffer_size,
+                    PolygonVertexConstructor { color },
+                );
+
+                self.add_map_ref_info(vertex_range, MapRefKind::Plain)
+            }
+        }
+    }
+
+    /// Splits the indices of the map reference tessellations into consecutive ranges that are
+    /// drawn together, keeping the order in which the primitives were added.
+    pub fn map_ref_ranges(&self) -> Vec<MapRefRange> {
+        let plain = self.primitive_index_ranges(MapRefKind::Plain, &self.poly_tessellation.indices);
+        let dashed =
+            self.primitive_index_ranges(MapRefKind::Dashed, &self.dash_tessellation.indices);
+        let pattern = self.primitive_index_ranges(
+            MapRefKind::Pattern {
+                image_store_index: 0,
+            },
+            &self.pattern_tessellation.indices,
+        );
+
+        let mut ranges: Vec<MapRefRange> = vec![];
+        for id in &self.map_ref_order {
+            let Some(PrimitiveInfo::MapRef { kind, .. }) = self.primitives.get(*id) else {
+                continue;
+            };
+            let index_ranges = match kind {
+                MapRefKind::Plain => &plain,
+                MapRefKind::Dashed => &dashed,
+                MapRefKind::Pattern { .. } => &pattern,
+            };
+            let Some(index_range) = index_ranges.get(id).cloned() else {
+                continue;
+            };
+
+            match ranges.last_mut() {
+                Some(last) if last.kind == *kind && last.index_range.end == index_range.start => {
+                    last.index_range.end = index_range.end
+                }
+                _ => ranges.push(MapRefRange {
+                    kind: *kind,
+                    index_range,
+                }),
+            }
+        }
+
+        ranges
+    }
+
+    /// Returns the ranges of the `indices` used by the primitives of the given kind of
+    /// tessellation. Indices of every primitive are consecutive in the buffer.
+    fn primitive_index_ranges(
+        &self,
+        kind: MapRefKind,
+        indices: &[u32],
+    ) -> HashMap<usize, Range<u32>> {
+        let mut owners = vec![];
+        for (id, info) in self.primitives.iter().enumerate() {
+            if let PrimitiveInfo::MapRef {
+                vertex_range,
+                kind: primitive_kind,
+            } = info
+            {
+                if primitive_kind.same_tessellation(&kind) {
+                    owners.push((vertex_range.clone(), id));
+                }
+            }
+        }
+        owners.sort_by_key(|(vertex_range, _)| vertex_range.start);
+
+        let mut ranges: HashMap<usize, Range<u32>> = HashMap::new();
+        for (position, index) in indices.iter().enumerate() {
+            let index = *index as usize;
+            let owner = owners.partition_point(|(vertex_range, _)| vertex_range.end <= index);
+            let Some((_, id)) = owners.get(owner) else {
+                continue;
+            };
+
+            let position = position as u32;
+            ranges
+                .entry(*id)
+                .and_modify(|range| range.end = position + 1)
+                .or_insert(position..position + 1);
+        }
+
+        ranges
     }
 
     pub fn modify_image(&mut self, id: PrimitiveId, paint: ImagePaint) -> Result<(), GalileoError> {
@@ -699,6 +1307,7 @@ impl TessellatingRenderBundle {
     fn update_map_ref<N, P, C, Poly>(
         &mut self,
         range: Range<usize>,
+        kind: MapRefKind,
         primitive: RenderPrimitive<N, P, C, Poly>,
     ) -> Result<(), GalileoError>
     where
@@ -710,7 +1319,7 @@ impl TessellatingRenderBundle {
     {
         let color = match primitive {
             RenderPrimitive::Contour(_, LinePaint { color, .. })
-            | RenderPrimitive::Polygon(_, PolygonPaint { color }) => color,
+            | RenderPrimitive::Polygon(_, PolygonPaint { color, .. }) => color,
             _ => {
                 return Err(GalileoError::Generic(
                     "expected line or polygon primitive, but got a point".into(),
@@ -718,18 +1327,27 @@ impl TessellatingRenderBundle {
             }
         };
 
-        for vertex in &mut self.poly_tessellation.vertices[range] {
-            vertex.color = color.to_f32_array();
+        let color = color.to_f32_array();
+        match kind {
+            MapRefKind::Plain => self.poly_tessellation.vertices[range]
+                .iter_mut()
+                .for_each(|vertex| vertex.color = color),
+            MapRefKind::Dashed => self.dash_tessellation.vertices[range]
+                .iter_mut()
+                .for_each(|vertex| vertex.color = color),
+            MapRefKind::Pattern { .. } => self.pattern_tessellation.vertices[range]
+                .iter_mut()
+                .for_each(|vertex| vertex.color = color),
         }
 
         Ok(())
     }
 
-    fn add_polygon_lod<N, P, Poly>(
-        &mut self,
+    fn add_to_tessellation<N, P, Poly, V>(
         polygon: &Poly,
-        paint: PolygonPaint,
-        _min_resolution: f32,
+        tessellation: &mut VertexBuffers<V, u32>,
+        buffer_size: &mut usize,
+        vertex_constructor: impl FillVertexConstructor<V>,
     ) -> Range<usize>
     where
         N: AsPrimitive<f32>,
@@ -737,16 +1355,15 @@ impl TessellatingRenderBundle {
         Poly: Polygon,
         Poly::Contour: Contour<Point = P>,
     {
-        let lod = &mut self.poly_tessellation;
-        let start_index = lod.vertices.len();
-        let start_index_count = lod.indices.len();
+        let start_index = tessellation.vertices.len();
+        let start_index_count = tessellation.indices.len();
 
-        Self::tessellate_polygon(polygon, paint, lod);
+        Self::tessellate_polygon(polygon, tessellation, vertex_constructor);
 
-        let end_index = lod.vertices.len();
+        let end_index = tessellation.vertices.len();
 
-        self.buffer_size += (end_index - start_index) * size_of::<PolyVertex>();
-        self.buffer_size += (lod.indices.len() - start_index_count) * size_of::<u32>();
+        *buffer_size += (end_index - start_index) * size_of::<V>();
+        *buffer_size += (tessellation.indices.len() - start_index_count) * size_of::<u32>();
 
         start_index..end_index
     }
@@ -755,10 +1372,10 @@ impl TessellatingRenderBundle {
         self.primitives.is_empty()
     }
 
-    fn tessellate_polygon<N, P, Poly>(
+    fn tessellate_polygon<N, P, Poly, V>(
         polygon: &Poly,
-        paint: PolygonPaint,
-        tessellation: &mut VertexBuffers<PolyVertex, u32>,
+        tessellation: &mut VertexBuffers<V, u32>,
+        vertex_constructor: impl FillVertexConstructor<V>,
     ) where
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
@@ -786,10 +1403,6 @@ impl TessellatingRenderBundle {
         }
 
         let path = path_builder.build();
-
-        let vertex_constructor = PolygonVertexConstructor {
-            color: paint.color.to_f32_array(),
-        };
         let mut tesselator = FillTessellator::new();
 
         if let Err(err) = tesselator.tessellate(
@@ -908,6 +1521,7 @@ impl TessellatingRenderBundle {
             position: [position.x().as_(), position.y().as_(), position.z().as_()],
             normal: [offset.x, offset.y],
             color: fill.center_color.to_u8_array(),
+            map_oriented: 0,
         };
 
         let is_full_circle = (dr - std::f32::consts::PI * 2.0).abs() < TOLERANCE;
@@ -931,6 +1545,7 @@ impl TessellatingRenderBundle {
                 position: [position.x().as_(), position.y().as_(), position.z().as_()],
                 normal: (point + offset).coords.into(),
                 color: fill.side_color.to_u8_array(),
+                map_oriented: 0,
             });
         }
 
@@ -1022,15 +1637,37 @@ impl TessellatingRenderBundle {
         text: &str,
         style: &TextStyle,
         offset: Vector2<f32>,
+        anchor: Option<Vector2<f32>>,
     ) -> PrimitiveInfo
     where
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
     {
+        let shaping_offset = if anchor.is_some() {
+            Vector2::default()
+        } else {
+            offset
+        };
         FontService::with(
-            |font_service| match font_service.shape(text, style, offset) {
-                Ok(TextShaping::Tessellation { glyphs, .. }) => {
-                    let indices_start = self.screen_ref.indices.len();
+            |font_service| match font_service.shape(text, style, shaping_offset) {
+                Ok(TextShaping::Tessellation { mut glyphs, .. }) => {
+                    let vertices_start = self.screen_ref.vertices.len();
+
+                    if let Some(anchor) = anchor {
+                        let bbox = glyphs
+                            .iter()
+                            .flat_map(|glyph| &glyph.vertices)
+                            .map(|v| Rect::new(v[0], v[1], v[0], v[1]))
+                            .collect::<Option<Rect<f32>>>();
+                        if let Some(bbox) = bbox {
+                            let dx = offset.x - bbox.x_min() - bbox.width() * anchor.x;
+                            let dy = offset.y - bbox.y_max() + bbox.height() * anchor.y;
+                            for vertex in glyphs.iter_mut().flat_map(|glyph| &mut glyph.vertices) {
+                                vertex[0] += dx;
+                                vertex[1] += dy;
+                            }
+                        }
+                    }
 
                     for glyph in glyphs {
                         let vertices_start = self.screen_ref.vertices.len() as u32;
@@ -1043,6 +1680,7 @@ impl TessellatingRenderBundle {
                                 ],
                                 normal: vertex,
                                 color: style.font_color.to_u8_array(),
+                                map_oriented: 0,
                             });
                         }
                         for index in glyph.indices {
@@ -1051,7 +1689,7 @@ impl TessellatingRenderBundle {
                     }
 
                     PrimitiveInfo::ScreenRef {
-                        vertex_range: indices_start..self.screen_ref.indices.len(),
+                        vertex_range: vertices_start..self.screen_ref.vertices.len(),
                     }
                 }
                 Err(err) => {
@@ -1067,6 +1705,47 @@ impl TessellatingRenderBundle {
     }
 }
 
+/// Returns the way to calculate texture coordinates of the pattern for the polygon, or `None` if
+/// the pattern cannot be drawn.
+fn pattern_coordinates<N, P, Poly>(
+    polygon: &Poly,
+    pattern: &PatternFill,
+    min_resolution: f64,
+) -> Option<PatternCoordinates>
+where
+    N: AsPrimitive<f32>,
+    P: CartesianPoint3d<Num = N>,
+    Poly: Polygon,
+    Poly::Contour: Contour<Point = P>,
+{
+    let origin = polygon.iter_contours().next()?.iter_points().next()?;
+
+    match pattern.space {
+        PatternSpace::Screen => Some(PatternCoordinates::Screen),
+        PatternSpace::Map { resolution } => {
+            let resolution = resolution.unwrap_or(min_resolution);
+            let size = [
+                pattern.image.width() as f64 * resolution,
+                pattern.image.height() as f64 * resolution,
+            ];
+            if size[0] <= 0.0 || size[1] <= 0.0 {
+                return None;
+            }
+
+            // Origin is aligned to the pattern grid, so that patterns of adjacent polygons match
+            // each other, while texture coordinates stay small.
+            let origin = [origin.x().as_() as f64, origin.y().as_() as f64];
+            Some(PatternCoordinates::Map {
+                origin: [
+                    (origin[0] / size[0]).floor() * size[0],
+                    (origin[1] / size[1]).floor() * size[1],
+                ],
+                size,
+            })
+        }
+    }
+}
+
 fn get_circle_sector(radius: f32, start_angle: f32, end_angle: f32) -> Vec<Point2<f32>> {
     const TOLERANCE: f32 = 0.1;
 
@@ -1183,14 +1862,45 @@ impl<'a> StrokeVertexConstructor<PolyVertex> for LineVertexConstructor<'a> {
     }
 }
 
+struct DashedLineVertexConstructor<'a> {
+    line: LineVertexConstructor<'a>,
+    dash_array: [f32; 4],
+}
+
+impl<'a> StrokeVertexConstructor<DashVertex> for DashedLineVertexConstructor<'a> {
+    fn new_vertex(&mut self, vertex: StrokeVertex) -> DashVertex {
+        // The path is built in pixels at `resolution`, so the advancement is the distance from
+        // the start of the line in pixels too. It stays small enough for f32 precision, unlike the
+        // distance in map units.
+        let line_distance = vertex.advancement();
+        let PolyVertex {
+            position,
+            color,
+            normal,
+            norm_limit,
+        } = self.line.new_vertex(vertex);
+
+        DashVertex {
+            position,
+            color,
+            normal,
+            norm_limit,
+            line_distance,
+            resolution: self.line.resolution,
+            dash_array: self.dash_array,
+        }
+    }
+}
+
 struct PolygonVertexConstructor {
     color: [f32; 4],
 }
 
 impl FillVertexConstructor<PolyVertex> for PolygonVertexConstructor {
     fn new_vertex(&mut self, vertex: FillVertex) -> PolyVertex {
+        let position = vertex.position();
         PolyVertex {
-            position: [vertex.position().x, vertex.position().y, 0.0],
+            position: [position.x, position.y, 0.0],
             color: self.color,
             normal: Default::default(),
             norm_limit: 1.0,
@@ -1198,6 +1908,57 @@ impl FillVertexConstructor<PolyVertex> for PolygonVertexConstructor {
     }
 }
 
+struct PatternVertexConstructor {
+    color: [f32; 4],
+    coordinates: PatternCoordinates,
+}
+
+/// Way to calculate texture coordinates of a pattern fill.
+enum PatternCoordinates {
+    /// Texture coordinates are calculated in the shader from the screen position.
+    Screen,
+    /// Texture coordinates are calculated from the map position. `origin` is the map point where
+    /// the top-left corner of one of the pattern tiles is, `size` is the size of a tile in map units.
+    Map { origin: [f64; 2], size: [f64; 2] },
+}
+
+impl FillVertexConstructor<PatternVertex> for PatternVertexConstructor {
+    fn new_vertex(&mut self, vertex: FillVertex) -> PatternVertex {
+        let position = vertex.position();
+        let (tex_coord, pattern_mode) = match self.coordinates {
+            PatternCoordinates::Screen => ([0.0; 2], 1.0),
+            PatternCoordinates::Map { origin, size } => (
+                [
+                    ((position.x as f64 - origin[0]) / size[0]) as f32,
+                    ((origin[1] - position.y as f64) / size[1]) as f32,
+                ],
+                0.0,
+            ),
+        };
+
+        PatternVertex {
+            position: [position.x, position.y, 0.0],
+            color: self.color,
+            tex_coord,
+            pattern_mode,
+        }
+    }
+}
+
+fn text_key(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns the text key of the first label of the `paints`.
+fn label_text_key(paints: &[&PointPaint]) -> Option<u64> {
+    paints.iter().find_map(|paint| match &paint.shape {
+        PointShape::Label { text, .. } => Some(text_key(text)),
+        _ => None,
+    })
+}
+
 struct ScreenRefVertexConstructor {
     color: [u8; 4],
     position: [f32; 3],
@@ -1210,6 +1971,7 @@ impl ScreenRefVertexConstructor {
             position: self.position,
             normal: [position.x + self.offset.x, position.y + self.offset.y],
             color: self.color,
+            map_oriented: 0,
         }
     }
 }
@@ -1235,6 +1997,35 @@ pub(crate) struct PolyVertex {
     pub norm_limit: f32,
 }
 
+/// Vertex of a dashed line.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct DashVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+    pub normal: [f32; 2],
+    pub norm_limit: f32,
+    /// Distance from the start of the line in pixels at `resolution`.
+    pub line_distance: f32,
+    /// Resolution the line was tessellated with.
+    pub resolution: f32,
+    /// Dash pattern of the line in pixels.
+    pub dash_array: [f32; 4],
+}
+
+/// Vertex of a polygon filled with a pattern.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct PatternVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+    /// Texture coordinates of the pattern fill for map space patterns.
+    pub tex_coord: [f32; 2],
+    /// `1.0` if the texture coordinates of the pattern must be calculated from the screen position
+    /// of the fragment, `0.0` otherwise.
+    pub pattern_mode: f32,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub(crate) struct PointInstance {
@@ -1251,12 +2042,14 @@ pub(crate) struct ImageVertex {
     pub offset: [f32; 2],
 }
 
+mod line_label;
 #[cfg(target_arch = "wasm32")]
 pub(crate) mod serialization;
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::render::LineCap;
 
     type C = galileo_types::impls::Contour<Point3d>;
 
@@ -1269,10 +2062,8 @@ mod tests {
             Point3d::new(1.0, 1.0, 0.0),
             Point3d::new(0.0, 1.0, 0.0),
         ]);
-        let paint1 = PolygonPaint {
-            color: Color::BLACK,
-        };
-        let paint2 = PolygonPaint { color: Color::RED };
+        let paint1 = PolygonPaint::new(Color::BLACK);
+        let paint2 = PolygonPaint::new(Color::RED);
 
         let _id0 = bundle.add(
             RenderPrimitive::<_, _, C, _>::new_polygon_ref(&polygon, paint1),
@@ -1303,10 +2094,370 @@ mod tests {
             .all(|v| vertex_range.contains(&(*v as usize))));
 
         let vertex_count = bundle.poly_tessellation.vertices.len();
-        let PrimitiveInfo::MapRef { vertex_range } = bundle.primitives[id2.0].clone() else {
+        let PrimitiveInfo::MapRef { vertex_range, .. } = bundle.primitives[id2.0].clone() else {
             panic!("invalid primitive type");
         };
 
         assert_eq!(vertex_range.end, vertex_count);
     }
+
+    fn square() -> galileo_types::impls::Polygon<Point3d> {
+        galileo_types::impls::Polygon::from(vec![
+            Point3d::new(0.0, 0.0, 0.0),
+            Point3d::new(1.0, 0.0, 0.0),
+            Point3d::new(1.0, 1.0, 0.0),
+            Point3d::new(0.0, 1.0, 0.0),
+        ])
+    }
+
+    fn line() -> C {
+        C::new(
+            vec![Point3d::new(0.0, 0.0, 0.0), Point3d::new(10.0, 0.0, 0.0)],
+            false,
+        )
+    }
+
+    #[test]
+    fn map_ref_vertices_have_no_pattern_or_dash_attributes() {
+        assert_eq!(size_of::<PolyVertex>(), 40);
+        assert_eq!(size_of::<PatternVertex>(), 40);
+    }
+
+    #[test]
+    fn pattern_fills_split_map_ref_ranges() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let polygon = square();
+        let image = Arc::new(DecodedImage::from_raw(vec![255; 16], 2, 2).unwrap());
+        let handle = PatternFill::new(image)
+            .with_space(PatternSpace::Map { resolution: None })
+            .register();
+        let solid = PolygonPaint::new(Color::RED);
+        let pattern = PolygonPaint::new(Color::WHITE).with_pattern(handle);
+
+        let mut add = |paint: PolygonPaint| {
+            bundle.add(
+                RenderPrimitive::<_, _, C, _>::new_polygon_ref(&polygon, paint),
+                0.5,
+            )
+        };
+        let _solid0 = add(solid);
+        let pattern0 = add(pattern);
+        let pattern1 = add(pattern);
+        let _solid1 = add(solid);
+        handle.release();
+
+        // Every square is tessellated into 2 triangles.
+        assert_eq!(bundle.poly_tessellation.indices.len(), 12);
+        assert_eq!(bundle.pattern_tessellation.indices.len(), 12);
+        assert_eq!(
+            bundle.map_ref_ranges(),
+            vec![
+                MapRefRange {
+                    kind: MapRefKind::Plain,
+                    index_range: 0..6,
+                },
+                MapRefRange {
+                    kind: MapRefKind::Pattern {
+                        image_store_index: 0
+                    },
+                    index_range: 0..12,
+                },
+                MapRefRange {
+                    kind: MapRefKind::Plain,
+                    index_range: 6..12,
+                },
+            ]
+        );
+
+        // The image is 1 map unit wide at the given resolution.
+        let tex_coords: Vec<_> = bundle.pattern_tessellation.vertices[0..4]
+            .iter()
+            .map(|v| v.tex_coord)
+            .collect();
+        assert!(tex_coords.contains(&[1.0, -1.0]));
+        assert!(tex_coords.contains(&[0.0, 0.0]));
+
+        bundle.remove(pattern0).unwrap();
+        assert!(matches!(bundle.image_store[0], ImageStoreInfo::Image(_)));
+        assert_eq!(bundle.pattern_tessellation.vertices.len(), 4);
+        assert!(bundle
+            .pattern_tessellation
+            .indices
+            .iter()
+            .all(|index| *index < 4));
+
+        bundle.remove(pattern1).unwrap();
+        assert!(matches!(bundle.image_store[0], ImageStoreInfo::Vacant));
+        assert_eq!(
+            bundle.map_ref_ranges(),
+            vec![MapRefRange {
+                kind: MapRefKind::Plain,
+                index_range: 0..12,
+            }]
+        );
+    }
+
+    #[test]
+    fn released_pattern_is_drawn_with_solid_color() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let image = Arc::new(DecodedImage::from_raw(vec![255; 16], 2, 2).unwrap());
+        let handle = PatternFill::new(image).register();
+        handle.release();
+
+        bundle.add(
+            RenderPrimitive::<_, _, C, _>::new_polygon(
+                square(),
+                PolygonPaint::new(Color::RED).with_pattern(handle),
+            ),
+            1.0,
+        );
+
+        assert!(bundle.pattern_tessellation.vertices.is_empty());
+        assert!(bundle.image_store.is_empty());
+        assert_eq!(bundle.poly_tessellation.vertices.len(), 4);
+    }
+
+    #[test]
+    fn pattern_polygon_uses_given_pattern() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let image = Arc::new(DecodedImage::from_raw(vec![255; 16], 2, 2).unwrap());
+        let pattern = PatternFill::new(image.clone());
+
+        bundle.add_pattern_polygon(
+            &square(),
+            PolygonPaint::new(Color::WHITE),
+            Some(&pattern),
+            1.0,
+        );
+
+        assert_eq!(bundle.pattern_tessellation.vertices.len(), 4);
+        assert!(bundle
+            .pattern_tessellation
+            .vertices
+            .iter()
+            .all(|v| v.pattern_mode == 1.0));
+        assert!(matches!(
+            &bundle.image_store[0],
+            ImageStoreInfo::Image(stored) if Arc::ptr_eq(stored, &image)
+        ));
+    }
+
+    #[test]
+    fn dashed_lines_are_drawn_in_order_with_solid_lines() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let solid = LinePaint {
+            color: Color::RED,
+            width: 2.0,
+            offset: 0.0,
+            line_cap: LineCap::Butt,
+            line_join: Default::default(),
+            miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+            dash_array: None,
+        };
+        let dashed = LinePaint {
+            dash_array: crate::render::DashArray::new(&[4.0, 2.0]),
+            ..solid
+        };
+
+        let solid0 = bundle.add_line(&line(), solid, 1.0);
+        let dashed0 = bundle.add_line(&line(), dashed, 1.0);
+        let solid1 = bundle.add_line(&line(), solid, 1.0);
+
+        let solid_index_count = bundle.poly_tessellation.indices.len() as u32;
+        let dashed_index_count = bundle.dash_tessellation.indices.len() as u32;
+        assert!(dashed_index_count > 0);
+        assert_eq!(
+            bundle.map_ref_ranges(),
+            vec![
+                MapRefRange {
+                    kind: MapRefKind::Plain,
+                    index_range: 0..solid_index_count / 2,
+                },
+                MapRefRange {
+                    kind: MapRefKind::Dashed,
+                    index_range: 0..dashed_index_count,
+                },
+                MapRefRange {
+                    kind: MapRefKind::Plain,
+                    index_range: solid_index_count / 2..solid_index_count,
+                },
+            ]
+        );
+        assert!(bundle
+            .dash_tessellation
+            .vertices
+            .iter()
+            .all(|v| v.dash_array == [4.0, 2.0, 0.0, 0.0]));
+
+        // Changing the color of the dashed line does not touch the solid lines.
+        let blue = LinePaint {
+            color: Color::BLUE,
+            ..dashed
+        };
+        bundle
+            .update(
+                dashed0,
+                RenderPrimitive::<_, _, C, galileo_types::impls::Polygon<_>>::new_contour(
+                    line(),
+                    blue,
+                ),
+            )
+            .unwrap();
+        assert!(bundle
+            .dash_tessellation
+            .vertices
+            .iter()
+            .all(|v| v.color == Color::BLUE.to_f32_array()));
+        assert!(bundle
+            .poly_tessellation
+            .vertices
+            .iter()
+            .all(|v| v.color == Color::RED.to_f32_array()));
+
+        // Removing the dashed line keeps the vertex ranges of the solid lines and merges their
+        // index ranges.
+        let ranges_before: Vec<_> = [solid0, solid1]
+            .iter()
+            .map(|id| bundle.primitives[id.0].clone())
+            .collect();
+        bundle.remove(dashed0).unwrap();
+        assert!(bundle.dash_tessellation.vertices.is_empty());
+        for (id, before) in [solid0, solid1].iter().zip(ranges_before) {
+            let (
+                PrimitiveInfo::MapRef {
+                    vertex_range: after,
+                    ..
+                },
+                PrimitiveInfo::MapRef {
+                    vertex_range: before,
+                    ..
+                },
+            ) = (&bundle.primitives[id.0], before)
+            else {
+                panic!("invalid primitive type");
+            };
+            assert_eq!(*after, before);
+        }
+        assert_eq!(
+            bundle.map_ref_ranges(),
+            vec![MapRefRange {
+                kind: MapRefKind::Plain,
+                index_range: 0..solid_index_count,
+            }]
+        );
+    }
+
+    #[test]
+    fn dash_distance_is_in_pixels_from_line_start() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let paint = LinePaint {
+            color: Color::RED,
+            width: 2.0,
+            offset: 0.0,
+            line_cap: LineCap::Butt,
+            line_join: Default::default(),
+            miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+            dash_array: crate::render::DashArray::new(&[4.0, 2.0]),
+        };
+
+        // Line is 10 map units long, which is 20 pixels at the given resolution.
+        bundle.add_line(&line(), paint, 0.5);
+
+        let vertices = &bundle.dash_tessellation.vertices;
+        assert!(vertices.iter().all(|v| v.resolution == 0.5));
+        let mut distances: Vec<_> = vertices.iter().map(|v| v.line_distance).collect();
+        distances.sort_by(f32::total_cmp);
+        assert_eq!(distances.first(), Some(&0.0));
+        assert_eq!(distances.last(), Some(&20.0));
+    }
+
+    #[test]
+    fn sprite_icons_share_stored_image() {
+        let json = r#"{
+            "a": {"x": 0, "y": 0, "width": 2, "height": 4},
+            "b": {"x": 2, "y": 2, "width": 2, "height": 2}
+        }"#;
+        let image = DecodedImage::from_raw(vec![255; 4 * 4 * 4], 4, 4).unwrap();
+        let atlas = crate::render::sprite::SpriteAtlas::from_json(json, image).unwrap();
+        let point = Point3d::new(1.0, 2.0, 0.0);
+
+        let mut bundle = TessellatingRenderBundle::new();
+        let icon_a = PointPaint::icon(&atlas.icon("a").unwrap(), Vector2::new(0.5, 0.5), 1.0);
+        let icon_b = PointPaint::icon(&atlas.icon("b").unwrap(), Vector2::new(0.5, 0.5), 2.0);
+        let id_a = bundle.add_point(&point, &icon_a);
+        let size_with_one_icon = bundle.buffer_size;
+        bundle.add_point(&point, &icon_b);
+
+        assert_eq!(bundle.image_store.len(), 1);
+        assert_eq!(
+            bundle.buffer_size - size_with_one_icon,
+            size_of::<ImageVertex>() * 4
+        );
+
+        let ImageInfo::Image((_, vertices)) = &bundle.images[1] else {
+            panic!("image is not added");
+        };
+        assert_eq!(vertices[0].tex_coords, [0.5, 1.0]);
+        assert_eq!(vertices[3].tex_coords, [1.0, 0.5]);
+        assert_eq!(vertices[3].offset, [2.0, 2.0]);
+
+        bundle.remove(id_a).unwrap();
+        assert_eq!(bundle.buffer_size, size_with_one_icon);
+        assert!(matches!(bundle.image_store[0], ImageStoreInfo::Image(_)));
+    }
+
+    #[test]
+    fn remove_collidable_point() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let paint = PointPaint::circle(Color::BLACK, 10.0).with_collision(Default::default());
+        let point = Point3d::new(1.0, 2.0, 0.0);
+
+        let id0 = bundle.add_point(&point, &paint);
+        let id1 = bundle.add_point(&point, &paint);
+        let index_count = bundle.screen_ref.indices.len();
+
+        assert_eq!(bundle.collidables.len(), 2);
+        let bbox = bundle.collidables[0].bbox;
+        assert!((bbox.width() - 10.0).abs() < 0.5);
+        assert!((bbox.x_min() + 5.0).abs() < 0.5);
+
+        bundle.remove(id0).unwrap();
+
+        assert_eq!(bundle.collidables.len(), 1);
+        let collidable = &bundle.collidables[0];
+        assert_eq!(collidable.primitive_ids, vec![id1.0]);
+        assert_eq!(collidable.anchor, [1.0, 2.0, 0.0]);
+        let [CollidableTarget::ScreenRef { index_range }] = &collidable.targets[..] else {
+            panic!("invalid collidable target");
+        };
+        assert_eq!(index_range.clone(), 0..bundle.screen_ref.indices.len());
+        assert_eq!(index_range.len() * 2, index_count);
+    }
+
+    #[test]
+    fn point_group_is_one_collidable() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let circle = PointPaint::circle(Color::BLACK, 10.0).with_collision(Default::default());
+        let square = PointPaint::square(Color::BLACK, 4.0).with_offset(Vector2::new(10.0, 0.0));
+        let point = Point3d::new(1.0, 2.0, 0.0);
+        let options = CollisionOptions {
+            priority: 2.0,
+            ..Default::default()
+        };
+
+        let ids = bundle.add_point_group(&point, &[&circle, &square], options);
+
+        assert_eq!(ids.len(), 2);
+        assert_eq!(bundle.collidables.len(), 1);
+        let collidable = &bundle.collidables[0];
+        assert_eq!(collidable.primitive_ids, vec![ids[0].0, ids[1].0]);
+        assert_eq!(collidable.options, options);
+        assert_eq!(collidable.targets.len(), 2);
+        assert!((collidable.bbox.x_min() + 5.0).abs() < 0.5);
+        assert!((collidable.bbox.x_max() - 12.0).abs() < 0.5);
+        assert!((collidable.bbox.y_max() - 5.0).abs() < 0.5);
+
+        bundle.remove(ids[1]).unwrap();
+        assert!(bundle.collidables.is_empty());
+    }
 }
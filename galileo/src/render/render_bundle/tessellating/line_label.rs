@@ -0,0 +1,252 @@
+//! Placement of text labels along lines.
+
+use std::f64::consts::PI;
+
+/// Line with the coordinates of the points given in pixels.
+pub(super) struct LinePath {
+    points: Vec<[f64; 3]>,
+    /// Distance from the start of the line to every point.
+    distances: Vec<f64>,
+}
+
+/// Position of a label placed along a [`LinePath`].
+#[derive(Debug)]
+pub(super) struct PathLabel {
+    /// Position of the center of the label on the path.
+    pub anchor: [f64; 3],
+    /// Offset and direction of every glyph of the label.
+    pub glyphs: Vec<GlyphPlacement>,
+}
+
+/// Offset of the glyph center from the label anchor in pixels and the angle of the glyph in
+/// radians.
+///
+/// Glyphs are drawn at the screen offsets from the anchor, like point labels, so the letter
+/// spacing does not change with the map resolution.
+#[derive(Debug)]
+pub(super) struct GlyphPlacement {
+    pub offset: [f64; 2],
+    pub angle: f64,
+}
+
+impl LinePath {
+    /// Creates a new path. Repeating points are skipped.
+    pub fn new(points: impl IntoIterator<Item = [f64; 3]>) -> Self {
+        let mut path = Self {
+            points: vec![],
+            distances: vec![],
+        };
+
+        for point in points {
+            match path.points.last() {
+                None => path.distances.push(0.0),
+                Some(prev) => {
+                    let segment_length = (point[0] - prev[0]).hypot(point[1] - prev[1]);
+                    if segment_length == 0.0 {
+                        continue;
+                    }
+
+                    let distance = path.length() + segment_length;
+                    path.distances.push(distance);
+                }
+            }
+
+            path.points.push(point);
+        }
+
+        path
+    }
+
+    /// Length of the path.
+    pub fn length(&self) -> f64 {
+        self.distances.last().copied().unwrap_or_default()
+    }
+
+    fn reversed(&self) -> Self {
+        Self::new(self.points.iter().rev().copied())
+    }
+
+    /// Returns the position of the point at the `distance` from the start of the path and the
+    /// direction of the path at that point.
+    fn point_at(&self, distance: f64) -> Option<([f64; 3], f64)> {
+        if distance < 0.0 || distance > self.length() || self.points.len() < 2 {
+            return None;
+        }
+
+        let segment = match self.distances.partition_point(|&d| d <= distance) {
+            index if index >= self.points.len() => self.points.len() - 1,
+            index => index,
+        };
+        let from = self.points[segment - 1];
+        let to = self.points[segment];
+        let from_distance = self.distances[segment - 1];
+        let k = (distance - from_distance) / (self.distances[segment] - from_distance);
+
+        let position = [
+            from[0] + (to[0] - from[0]) * k,
+            from[1] + (to[1] - from[1]) * k,
+            from[2] + (to[2] - from[2]) * k,
+        ];
+        let angle = (to[1] - from[1]).atan2(to[0] - from[0]);
+
+        Some((position, angle))
+    }
+
+    /// Distances from the start of the path to the centers of the labels of the given width,
+    /// repeated every `spacing` pixels. If the path is long enough, at least one label is placed.
+    pub fn label_centers(&self, width: f64, spacing: f64) -> Vec<f64> {
+        let length = self.length();
+        if length < width {
+            return vec![];
+        }
+
+        let count = if spacing > 0.0 {
+            ((length / spacing).floor() as usize).max(1)
+        } else {
+            1
+        };
+
+        (0..count)
+            .map(|i| (i as f64 + 0.5) * length / count as f64)
+            .collect()
+    }
+
+    /// Places a label of the given width with the center at the `center` distance from the start
+    /// of the path. `glyph_offsets` are distances from the center of the label to the centers of
+    /// the glyphs.
+    ///
+    /// The label is placed so that it is read from left to right. Returns `None` if the label does
+    /// not fit into the path or if the direction of the path changes by more than `max_angle`
+    /// (in radians) between two adjacent glyphs.
+    pub fn place(
+        &self,
+        center: f64,
+        width: f64,
+        glyph_offsets: &[f64],
+        max_angle: f64,
+    ) -> Option<PathLabel> {
+        let (start, _) = self.point_at(center - width / 2.0)?;
+        let (end, _) = self.point_at(center + width / 2.0)?;
+        if end[0] < start[0] {
+            return self
+                .reversed()
+                .place_forward(self.length() - center, glyph_offsets, max_angle);
+        }
+
+        self.place_forward(center, glyph_offsets, max_angle)
+    }
+
+    fn place_forward(
+        &self,
+        center: f64,
+        glyph_offsets: &[f64],
+        max_angle: f64,
+    ) -> Option<PathLabel> {
+        let (anchor, _) = self.point_at(center)?;
+        let mut glyphs: Vec<GlyphPlacement> = Vec::with_capacity(glyph_offsets.len());
+        for offset in glyph_offsets {
+            let (position, angle) = self.point_at(center + offset)?;
+            if let Some(prev) = glyphs.last() {
+                if angle_difference(prev.angle, angle) > max_angle {
+                    return None;
+                }
+            }
+
+            glyphs.push(GlyphPlacement {
+                offset: [position[0] - anchor[0], position[1] - anchor[1]],
+                angle,
+            });
+        }
+
+        Some(PathLabel { anchor, glyphs })
+    }
+}
+
+/// Absolute difference between two angles in radians, in the range `[0, PI]`.
+fn angle_difference(a: f64, b: f64) -> f64 {
+    let diff = (b - a).rem_euclid(2.0 * PI);
+    if diff > PI {
+        2.0 * PI - diff
+    } else {
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn label_centers_are_repeated_with_spacing() {
+        let path = LinePath::new([[0.0, 0.0, 0.0], [100.0, 0.0, 0.0], [100.0, 0.0, 0.0]]);
+        assert_close(path.length(), 100.0);
+
+        assert_eq!(path.label_centers(120.0, 30.0), Vec::<f64>::new());
+        assert_eq!(path.label_centers(50.0, 250.0), vec![50.0]);
+        assert_eq!(path.label_centers(20.0, 40.0), vec![25.0, 75.0]);
+    }
+
+    #[test]
+    fn glyphs_follow_the_path() {
+        let path = LinePath::new([[0.0, 0.0, 0.0], [10.0, 0.0, 0.0], [10.0, 10.0, 0.0]]);
+        let label = path
+            .place(10.0, 8.0, &[-3.0, 3.0], PI)
+            .expect("label must be placed");
+
+        assert_eq!(label.anchor, [10.0, 0.0, 0.0]);
+        assert_eq!(label.glyphs[0].offset, [-3.0, 0.0]);
+        assert_close(label.glyphs[0].angle, 0.0);
+        assert_eq!(label.glyphs[1].offset, [0.0, 3.0]);
+        assert_close(label.glyphs[1].angle, PI / 2.0);
+    }
+
+    #[test]
+    fn sharp_turns_are_skipped() {
+        let path = LinePath::new([[0.0, 0.0, 0.0], [10.0, 0.0, 0.0], [10.0, 10.0, 0.0]]);
+        assert!(path.place(10.0, 8.0, &[-3.0, 3.0], PI / 4.0).is_none());
+        assert!(path.place(4.0, 8.0, &[-3.0, 3.0], PI / 4.0).is_some());
+    }
+
+    #[test]
+    fn labels_are_read_from_left_to_right() {
+        let path = LinePath::new([[20.0, 0.0, 0.0], [0.0, 0.0, 0.0]]);
+        let label = path
+            .place(5.0, 4.0, &[-1.0, 1.0], PI)
+            .expect("label must be placed");
+
+        assert_eq!(label.anchor, [15.0, 0.0, 0.0]);
+        assert_eq!(label.glyphs[0].offset, [-1.0, 0.0]);
+        assert_eq!(label.glyphs[1].offset, [1.0, 0.0]);
+        assert_close(label.glyphs[0].angle, 0.0);
+    }
+
+    #[test]
+    fn glyph_offsets_do_not_depend_on_path_position() {
+        let near = LinePath::new([[0.0, 0.0, 0.0], [10.0, 0.0, 0.0], [10.0, 10.0, 0.0]]);
+        let far = LinePath::new([
+            [1.0e6, 1.0e6, 5.0],
+            [1.0e6 + 10.0, 1.0e6, 5.0],
+            [1.0e6 + 10.0, 1.0e6 + 10.0, 5.0],
+        ]);
+
+        let near = near.place(10.0, 8.0, &[-3.0, 3.0], PI).unwrap();
+        let far = far.place(10.0, 8.0, &[-3.0, 3.0], PI).unwrap();
+
+        assert_eq!(far.anchor, [1.0e6 + 10.0, 1.0e6, 5.0]);
+        for (near, far) in near.glyphs.iter().zip(&far.glyphs) {
+            assert_eq!(near.offset, far.offset);
+            assert_close(near.angle, far.angle);
+        }
+    }
+
+    #[test]
+    fn labels_not_fitting_the_path_are_skipped() {
+        let path = LinePath::new([[0.0, 0.0, 0.0], [10.0, 0.0, 0.0]]);
+        assert!(path.place(3.0, 8.0, &[], PI).is_none());
+        assert!(path.place(5.0, 8.0, &[], PI).is_some());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::decoded_image::DecodedImage;
 use crate::render::render_bundle::tessellating::{
-    ImageInfo, ImageStoreInfo, PolyVertex, PrimitiveInfo, ScreenRefVertex, TessellatingRenderBundle,
+    Collidable, DashVertex, ImageInfo, ImageStoreInfo, PatternVertex, PolyVertex, PrimitiveInfo,
+    ScreenRefVertex, TessellatingRenderBundle,
 };
 use lyon::lyon_tessellation::VertexBuffers;
 use serde::{Deserialize, Serialize};
@@ -10,10 +11,14 @@ use std::sync::Arc;
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct TessellatingRenderBundleBytes {
     pub poly_tessellation: PolyVertexBuffersBytes,
+    pub dash_tessellation: DashVertexBuffersBytes,
+    pub pattern_tessellation: PatternVertexBuffersBytes,
     pub points: Vec<u32>,
     pub screen_ref: ScreenRefVertexBuffersBytes,
     pub images: Vec<Option<ImageBytes>>,
     pub primitives: Vec<PrimitiveInfo>,
+    pub collidables: Vec<Collidable>,
+    pub map_ref_order: Vec<usize>,
     pub image_store: Vec<Option<(u32, u32, Vec<u8>)>>,
     pub vacant_image_ids: Vec<usize>,
     pub vacant_image_store_ids: Vec<usize>,
@@ -55,6 +60,66 @@ impl PolyVertexBuffersBytes {
     }
 }
 
+const DASH_VERTEX_BLOCKS: usize = size_of::<DashVertex>() / size_of::<u32>();
+
+type DashVertexShim = [u32; DASH_VERTEX_BLOCKS];
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct DashVertexBuffersBytes {
+    vertices: Vec<DashVertexShim>,
+    indices: Vec<u32>,
+}
+
+impl From<VertexBuffers<DashVertex, u32>> for DashVertexBuffersBytes {
+    fn from(value: VertexBuffers<DashVertex, u32>) -> Self {
+        Self {
+            vertices: bytemuck::cast_vec(value.vertices),
+            indices: value.indices,
+        }
+    }
+}
+
+impl DashVertexBuffersBytes {
+    fn into_typed_unchecked(self) -> VertexBuffers<DashVertex, u32> {
+        let vertices = bytemuck::cast_vec(self.vertices);
+
+        VertexBuffers {
+            vertices,
+            indices: self.indices,
+        }
+    }
+}
+
+const PATTERN_VERTEX_BLOCKS: usize = size_of::<PatternVertex>() / size_of::<u32>();
+
+type PatternVertexShim = [u32; PATTERN_VERTEX_BLOCKS];
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct PatternVertexBuffersBytes {
+    vertices: Vec<PatternVertexShim>,
+    indices: Vec<u32>,
+}
+
+impl From<VertexBuffers<PatternVertex, u32>> for PatternVertexBuffersBytes {
+    fn from(value: VertexBuffers<PatternVertex, u32>) -> Self {
+        Self {
+            vertices: bytemuck::cast_vec(value.vertices),
+            indices: value.indices,
+        }
+    }
+}
+
+impl PatternVertexBuffersBytes {
+    fn into_typed_unchecked(self) -> VertexBuffers<PatternVertex, u32> {
+        let vertices = bytemuck::cast_vec(self.vertices);
+
+        VertexBuffers {
+            vertices,
+            indices: self.indices,
+        }
+    }
+}
+
 const SCREEN_REF_VERTEX_BLOCKS: usize = size_of::<ScreenRefVertex>() / size_of::<u32>();
 type ScreenRefVertexShim = [u32; SCREEN_REF_VERTEX_BLOCKS];
 
@@ -86,6 +151,8 @@ impl TessellatingRenderBundle {
     pub(crate) fn into_bytes(self) -> TessellatingRenderBundleBytes {
         TessellatingRenderBundleBytes {
             poly_tessellation: self.poly_tessellation.into(),
+            dash_tessellation: self.dash_tessellation.into(),
+            pattern_tessellation: self.pattern_tessellation.into(),
             points: bytemuck::cast_vec(self.points),
             screen_ref: self.screen_ref.into(),
             images: self
@@ -100,6 +167,8 @@ impl TessellatingRenderBundle {
                 })
                 .collect(),
             primitives: self.primitives,
+            collidables: self.collidables,
+            map_ref_order: self.map_ref_order,
             image_store: self
                 .image_store
                 .into_iter()
@@ -120,6 +189,8 @@ impl TessellatingRenderBundle {
     pub(crate) fn from_bytes_unchecked(bundle: TessellatingRenderBundleBytes) -> Self {
         Self {
             poly_tessellation: bundle.poly_tessellation.into_typed_unchecked(),
+            dash_tessellation: bundle.dash_tessellation.into_typed_unchecked(),
+            pattern_tessellation: bundle.pattern_tessellation.into_typed_unchecked(),
             points: bytemuck::cast_vec(bundle.points),
             screen_ref: bundle.screen_ref.into_typed_unchecked(),
             images: bundle
@@ -140,6 +211,8 @@ impl TessellatingRenderBundle {
                 })
                 .collect(),
             primitives: bundle.primitives,
+            collidables: bundle.collidables,
+            map_ref_order: bundle.map_ref_order,
             image_store: bundle
                 .image_store
                 .into_iter()
@@ -0,0 +1,273 @@
+//! Sprite atlases: sets of named icons stored in a single image. See [`SpriteAtlas`].
+
+use crate::decoded_image::DecodedImage;
+use crate::error::GalileoError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Empty space in pixels left around every icon when icons are packed into an atlas, so that
+/// texture filtering does not mix the pixels of adjacent icons.
+const PACKING_PADDING: u32 = 1;
+
+/// Position of an icon in the image of a [`SpriteAtlas`].
+///
+/// The structure has the same format as the entries of the MapLibre `sprite.json` index file.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpriteRegion {
+    /// Horizontal position of the left side of the icon in pixels.
+    pub x: u32,
+    /// Vertical position of the top side of the icon in pixels.
+    pub y: u32,
+    /// Width of the icon in pixels.
+    pub width: u32,
+    /// Height of the icon in pixels.
+    pub height: u32,
+    /// Number of image pixels per one screen pixel. High-DPI sprites have the value of `2.0`, so
+    /// their icons are drawn at half of their pixel size.
+    #[serde(default = "default_pixel_ratio", rename = "pixelRatio")]
+    pub pixel_ratio: f32,
+}
+
+fn default_pixel_ratio() -> f32 {
+    1.0
+}
+
+/// Set of named icons stored in a single image.
+///
+/// Icons of an atlas are drawn from the same image, so all of them use one GPU texture. An atlas
+/// can be loaded from a MapLibre sprite (a `sprite.json` index and a `sprite.png` image) with
+/// [`SpriteAtlas::from_json`], or created from separate images with [`SpriteAtlas::pack`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpriteAtlas {
+    image: Arc<DecodedImage>,
+    icons: HashMap<String, SpriteRegion>,
+}
+
+/// Icon of a [`SpriteAtlas`].
+#[derive(Debug, Clone)]
+pub struct SpriteIcon {
+    /// Image of the whole atlas.
+    pub image: Arc<DecodedImage>,
+    /// Position of the icon in the atlas image.
+    pub region: SpriteRegion,
+}
+
+impl SpriteIcon {
+    /// Width of the icon in screen pixels.
+    pub fn width(&self) -> f32 {
+        self.region.width as f32 / self.region.pixel_ratio
+    }
+
+    /// Height of the icon in screen pixels.
+    pub fn height(&self) -> f32 {
+        self.region.height as f32 / self.region.pixel_ratio
+    }
+}
+
+impl SpriteAtlas {
+    /// Creates a new atlas with the given icons.
+    ///
+    /// Returns an error if any of the icons lies outside of the image, or has zero size.
+    pub fn new(
+        image: Arc<DecodedImage>,
+        icons: HashMap<String, SpriteRegion>,
+    ) -> Result<Self, GalileoError> {
+        for (name, region) in &icons {
+            let fits = region
+                .x
+                .checked_add(region.width)
+                .map(|right| right <= image.width())
+                == Some(true)
+                && region
+                    .y
+                    .checked_add(region.height)
+                    .map(|bottom| bottom <= image.height())
+                    == Some(true);
+            if !fits || region.width == 0 || region.height == 0 || region.pixel_ratio <= 0.0 {
+                return Err(GalileoError::Generic(format!(
+                    "invalid sprite icon '{name}': {region:?}"
+                )));
+            }
+        }
+
+        Ok(Self { image, icons })
+    }
+
+    /// Loads an atlas from a MapLibre sprite: the content of the `sprite.json` index file and the
+    /// decoded `sprite.png` image.
+    pub fn from_json(json: &str, image: DecodedImage) -> Result<Self, GalileoError> {
+        let icons: HashMap<String, SpriteRegion> = serde_json::from_str(json)
+            .map_err(|err| GalileoError::Generic(format!("invalid sprite index: {err}")))?;
+        Self::new(Arc::new(image), icons)
+    }
+
+    /// Packs separate images into a single atlas image.
+    ///
+    /// Icons are placed in rows ordered by their height. If several images have the same name,
+    /// the last one is used.
+    pub fn pack(
+        images: impl IntoIterator<Item = (String, Arc<DecodedImage>)>,
+    ) -> Result<Self, GalileoError> {
+        let images: HashMap<String, Arc<DecodedImage>> = images.into_iter().collect();
+        let mut sorted: Vec<_> = images.iter().collect();
+        sorted.sort_by(|(name_a, a), (name_b, b)| {
+            b.height().cmp(&a.height()).then(name_a.cmp(name_b))
+        });
+
+        let total_area: u64 = sorted
+            .iter()
+            .map(|(_, image)| {
+                (image.width() + PACKING_PADDING) as u64 * (image.height() + PACKING_PADDING) as u64
+            })
+            .sum();
+        let max_width = sorted
+            .iter()
+            .map(|(_, image)| image.width() + PACKING_PADDING)
+            .max()
+            .unwrap_or_default();
+        let atlas_width = max_width
+            .max((total_area as f64).sqrt().ceil() as u32)
+            .max(1);
+
+        let mut icons = HashMap::new();
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for (name, image) in &sorted {
+            if x + image.width() > atlas_width {
+                x = 0;
+                y += row_height;
+                row_height = 0;
+            }
+
+            icons.insert(
+                (*name).clone(),
+                SpriteRegion {
+                    x,
+                    y,
+                    width: image.width(),
+                    height: image.height(),
+                    pixel_ratio: 1.0,
+                },
+            );
+
+            x += image.width() + PACKING_PADDING;
+            row_height = row_height.max(image.height() + PACKING_PADDING);
+        }
+
+        let atlas_height = (y + row_height).max(1);
+        let mut bytes = vec![0; atlas_width as usize * atlas_height as usize * 4];
+        for (name, region) in &icons {
+            let image = &images[name];
+            let row_length = region.width as usize * 4;
+            for row in 0..region.height as usize {
+                let source = row * row_length;
+                let target =
+                    ((region.y as usize + row) * atlas_width as usize + region.x as usize) * 4;
+                bytes[target..target + row_length]
+                    .copy_from_slice(&image.bytes()[source..source + row_length]);
+            }
+        }
+
+        let image = DecodedImage::from_raw(bytes, atlas_width, atlas_height)?;
+        Self::new(Arc::new(image), icons)
+    }
+
+    /// Image containing all the icons of the atlas.
+    pub fn image(&self) -> &Arc<DecodedImage> {
+        &self.image
+    }
+
+    /// Names of all the icons of the atlas.
+    pub fn icon_names(&self) -> impl Iterator<Item = &str> {
+        self.icons.keys().map(|name| name.as_str())
+    }
+
+    /// Returns the icon with the given name.
+    pub fn icon(&self, name: &str) -> Option<SpriteIcon> {
+        self.icons.get(name).map(|region| SpriteIcon {
+            image: self.image.clone(),
+            region: *region,
+        })
+    }
+
+    /// Copies the pixels of the icon with the given name into a separate image.
+    pub fn slice(&self, name: &str) -> Option<DecodedImage> {
+        let region = self.icons.get(name)?;
+        let atlas_row_length = self.image.width() as usize * 4;
+        let row_length = region.width as usize * 4;
+
+        let mut bytes = Vec::with_capacity(row_length * region.height as usize);
+        for row in region.y as usize..(region.y + region.height) as usize {
+            let start = row * atlas_row_length + region.x as usize * 4;
+            bytes.extend_from_slice(&self.image.bytes()[start..start + row_length]);
+        }
+
+        DecodedImage::from_raw(bytes, region.width, region.height).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_image(width: u32, height: u32, value: u8) -> Arc<DecodedImage> {
+        Arc::new(
+            DecodedImage::from_raw(vec![value; (width * height * 4) as usize], width, height)
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn loads_maplibre_sprite() {
+        let json = r#"{
+            "airport": {"x": 0, "y": 0, "width": 2, "height": 2, "pixelRatio": 2},
+            "bus": {"x": 2, "y": 0, "width": 1, "height": 1, "sdf": false}
+        }"#;
+        let mut bytes = vec![0; 3 * 2 * 4];
+        bytes[2 * 4..3 * 4].copy_from_slice(&[255, 0, 0, 255]);
+        let atlas =
+            SpriteAtlas::from_json(json, DecodedImage::from_raw(bytes, 3, 2).unwrap()).unwrap();
+
+        let airport = atlas.icon("airport").unwrap();
+        assert_eq!(airport.width(), 1.0);
+        assert_eq!(airport.region.pixel_ratio, 2.0);
+
+        let bus = atlas.slice("bus").unwrap();
+        assert_eq!(bus.bytes(), &[255, 0, 0, 255]);
+        assert!(atlas.icon("ferry").is_none());
+    }
+
+    #[test]
+    fn rejects_icons_outside_of_image() {
+        let json = r#"{"airport": {"x": 2, "y": 0, "width": 2, "height": 2}}"#;
+        let image = DecodedImage::from_raw(vec![0; 3 * 2 * 4], 3, 2).unwrap();
+        assert!(SpriteAtlas::from_json(json, image).is_err());
+    }
+
+    #[test]
+    fn packed_icons_do_not_overlap() {
+        let images = [
+            ("a".to_string(), solid_image(4, 4, 1)),
+            ("b".to_string(), solid_image(3, 2, 2)),
+            ("c".to_string(), solid_image(5, 1, 3)),
+            ("d".to_string(), solid_image(2, 6, 4)),
+        ];
+        let atlas = SpriteAtlas::pack(images.clone()).unwrap();
+
+        for (name, image) in &images {
+            let sliced = atlas.slice(name).unwrap();
+            assert_eq!(sliced.bytes(), image.bytes(), "icon {name}");
+        }
+
+        let regions: Vec<_> = atlas.icons.values().collect();
+        for (i, a) in regions.iter().enumerate() {
+            for b in &regions[i + 1..] {
+                let overlaps = a.x < b.x + b.width
+                    && b.x < a.x + a.width
+                    && a.y < b.y + b.height
+                    && b.y < a.y + a.height;
+                assert!(!overlaps, "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+}
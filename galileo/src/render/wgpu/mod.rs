@@ -1,28 +1,33 @@
 use cfg_if::cfg_if;
+use galileo_types::cartesian::Rect;
 use galileo_types::cartesian::Size;
 use lyon::tessellation::VertexBuffers;
-use nalgebra::{Rotation3, Vector3};
+use nalgebra::{Matrix4, Rotation3, Vector3, Vector4};
 use std::any::Any;
 use std::mem::size_of;
-use std::sync::Arc;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use wgpu::util::DeviceExt;
 use wgpu::{
-    Adapter, Buffer, BufferAddress, BufferDescriptor, BufferUsages, Device, Extent3d,
-    ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, Origin3d, Queue,
+    Adapter, BindGroup, Buffer, BufferAddress, BufferDescriptor, BufferUsages, CommandEncoder,
+    Device, Extent3d, ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, Origin3d, Queue,
     RenderPassDepthStencilAttachment, StoreOp, Surface, SurfaceConfiguration, SurfaceError,
     SurfaceTexture, Texture, TextureAspect, TextureDescriptor, TextureDimension, TextureFormat,
     TextureUsages, TextureView, TextureViewDescriptor, WasmNotSendSync,
 };
 
 use crate::error::GalileoError;
-use crate::layer::Layer;
 use crate::map::Map;
+use crate::render::collision::{CollisionOptions, LabelPlacement};
 use crate::render::render_bundle::tessellating::{
-    PointInstance, PolyVertex, TessellatingRenderBundle,
+    CollidableTarget, DashVertex, MapRefKind, PatternVertex, PointInstance, PolyVertex,
+    TessellatingRenderBundle,
 };
 use crate::render::render_bundle::{RenderBundle, RenderBundleType};
+use crate::render::wgpu::pipelines::composite::{CompositePipeline, LayerOpacities};
 use crate::render::wgpu::pipelines::image::WgpuImage;
-use crate::render::wgpu::pipelines::Pipelines;
+use crate::render::wgpu::pipelines::{Pipelines, TargetBlending};
 use crate::view::MapView;
 use crate::Color;
 
@@ -41,14 +46,27 @@ pub struct WgpuRenderer {
     queue: Arc<Queue>,
     render_set: Option<RenderSet>,
     background: Color,
+    placement: Mutex<LabelPlacement>,
 }
 
 struct RenderSet {
     render_target: RenderTarget,
     pipelines: Pipelines,
+    /// Pipelines drawing to the offscreen layer target. Created when the first semi-transparent
+    /// layer is drawn.
+    layer_pipelines: OnceLock<Pipelines>,
+    composite: CompositePipeline,
     multisampling_view: TextureView,
     stencil_view_multisample: TextureView,
     stencil_view: TextureView,
+    layer_target: LayerTarget,
+}
+
+/// Offscreen target semi-transparent layers are drawn to before they are blended into the map.
+struct LayerTarget {
+    view: TextureView,
+    multisampling_view: TextureView,
+    bind_group: BindGroup,
 }
 
 enum RenderTarget {
@@ -126,6 +144,7 @@ impl WgpuRenderer {
             queue: Arc::new(queue),
             render_set: None,
             background: DEFAULT_BACKGROUND,
+            placement: Mutex::default(),
         })
     }
 
@@ -168,22 +187,24 @@ impl WgpuRenderer {
             Some(RenderSet {
                 render_target,
                 pipelines,
+                layer_pipelines,
+                composite,
                 multisampling_view,
                 stencil_view_multisample,
                 stencil_view,
-            }) if new_target.size() == render_target.size() => {
-                let pipelines = if new_target.format() == render_target.format() {
-                    pipelines
-                } else {
-                    Pipelines::create(&self.device, new_target.format())
-                };
-
+                layer_target,
+            }) if new_target.size() == render_target.size()
+                && new_target.format() == render_target.format() =>
+            {
                 self.render_set = Some(RenderSet {
                     render_target: new_target,
                     pipelines,
+                    layer_pipelines,
+                    composite,
                     multisampling_view,
                     stencil_view_multisample,
                     stencil_view,
+                    layer_target,
                 })
             }
             _ => self.render_set = Some(self.create_render_set(new_target)),
@@ -198,14 +219,19 @@ impl WgpuRenderer {
         let stencil_view_multisample = Self::create_stencil_texture(&self.device, size, 4);
         let stencil_view = Self::create_stencil_texture(&self.device, size, 1);
 
-        let pipelines = Pipelines::create(&self.device, format);
+        let pipelines = Pipelines::create(&self.device, format, TargetBlending::Map);
+        let composite = CompositePipeline::create(&self.device, format);
+        let layer_target = Self::create_layer_target(&self.device, &composite, size, format);
 
         RenderSet {
             render_target,
             pipelines,
+            layer_pipelines: OnceLock::new(),
+            composite,
             multisampling_view,
             stencil_view_multisample,
             stencil_view,
+            layer_target,
         }
     }
 
@@ -304,6 +330,7 @@ impl WgpuRenderer {
             queue,
             render_set: None,
             background: DEFAULT_BACKGROUND,
+            placement: Mutex::default(),
         };
         renderer.init_render_set(render_target);
 
@@ -401,6 +428,36 @@ impl WgpuRenderer {
         texture.create_view(&TextureViewDescriptor::default())
     }
 
+    fn create_layer_target(
+        device: &Device,
+        composite: &CompositePipeline,
+        size: Size<u32>,
+        format: TextureFormat,
+    ) -> LayerTarget {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Layer texture"),
+            size: Extent3d {
+                width: size.width(),
+                height: size.height(),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let bind_group = composite.create_bind_group(device, &view);
+
+        LayerTarget {
+            view,
+            multisampling_view: Self::create_multisample_texture(device, size, format),
+            bind_group,
+        }
+    }
+
     /// De-initializes the renderer.
     pub fn clear_render_target(&mut self) {
         self.render_set = None;
@@ -476,6 +533,8 @@ impl WgpuRenderer {
             render_set.stencil_view_multisample =
                 Self::create_stencil_texture(&self.device, new_size, 4);
             render_set.stencil_view = Self::create_stencil_texture(&self.device, new_size, 1);
+            render_set.layer_target =
+                Self::create_layer_target(&self.device, &render_set.composite, new_size, format);
         }
     }
 
@@ -562,44 +621,44 @@ impl WgpuRenderer {
 
     /// Renders the map to the given texture.
     pub fn render_to_texture_view(&self, map: &Map, view: &TextureView) {
-        if let Some(render_set) = &self.render_set {
-            let mut encoder = self
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
+        let Some(render_set) = &self.render_set else {
+            return;
+        };
 
-            {
-                let background = self.background.to_f32_array();
-                let _ = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: Some("Render Pass"),
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &render_set.multisampling_view,
-                        resolve_target: Some(view),
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(wgpu::Color {
-                                r: background[0] as f64,
-                                g: background[1] as f64,
-                                b: background[2] as f64,
-                                a: background[3] as f64,
-                            }),
-                            store: StoreOp::Store,
-                        },
-                    })],
-                    depth_stencil_attachment: None,
-                    timestamp_writes: None,
-                    occlusion_query_set: None,
-                });
-            }
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
 
-            self.queue.submit(std::iter::once(encoder.finish()));
-        } else {
-            return;
+        {
+            let background = self.background.to_f32_array();
+            let _ = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &render_set.multisampling_view,
+                    resolve_target: Some(view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: background[0] as f64,
+                            g: background[1] as f64,
+                            b: background[2] as f64,
+                            a: background[3] as f64,
+                        }),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
         }
 
         log::info!("View: {:#?}", view);
 
-        self.render_map(map, view);
+        self.render_map(map, render_set, view, &mut encoder);
+
+        self.queue.submit(std::iter::once(encoder.finish()));
     }
 
     /// Renders the map.
@@ -618,23 +677,250 @@ impl WgpuRenderer {
         Ok(())
     }
 
-    fn render_map(&self, map: &Map, texture_view: &TextureView) {
+    /// Records the commands rendering the map into the encoder.
+    ///
+    /// Every layer is rendered once, recording the bundles it draws. Labels and icons of the
+    /// recorded bundles are then given to the label placement, which hides the ones colliding on
+    /// the screen, and after that the same bundles are drawn.
+    fn render_map(
+        &self,
+        map: &Map,
+        render_set: &RenderSet,
+        texture_view: &TextureView,
+        encoder: &mut CommandEncoder,
+    ) {
         let view = map.view();
-        for layer in map.layers().iter_visible() {
-            self.render_layer(layer, view, texture_view);
+        let Some(transform) = view.map_to_scene_transform() else {
+            log::warn!("Map cannot be rendered to the map view.");
+            return;
+        };
+
+        let mut layers = vec![];
+        for (layer, opacity) in map.layers().iter_rendered() {
+            let mut canvas = WgpuCanvas::new(self, render_set);
+            layer.render(view, &mut canvas);
+            layers.push(RecordedLayer {
+                draws: canvas.draws,
+                opacity,
+            });
         }
-    }
 
-    fn render_layer(&self, layer: &dyn Layer, view: &MapView, texture_view: &TextureView) {
-        let Some(render_set) = &self.render_set else {
+        let size = self.size();
+        let (width, height) = (size.width() as f32, size.height() as f32);
+        let rotation = view_rotation(view);
+
+        let mut placement = self.placement.lock().expect("mutex is poisoned");
+        placement.reset();
+        for (layer_index, layer) in layers.iter().enumerate() {
+            for bundle in layer.draws.iter().flat_map(|draw| &draw.bundles) {
+                bundle.add_placement_candidates(
+                    &mut placement,
+                    &transform,
+                    &rotation,
+                    width,
+                    height,
+                    layer_index,
+                );
+            }
+        }
+        placement.resolve(width, height);
+
+        // Each layer has its own slot in the opacity buffer, since all layers are composited
+        // within one submission.
+        let opacities: Vec<f32> = layers.iter().map(|layer| layer.opacity).collect();
+        let layer_resources = opacities.iter().any(|opacity| *opacity < 1.0).then(|| {
+            let pipelines = render_set.layer_pipelines.get_or_init(|| {
+                Pipelines::create(
+                    &self.device,
+                    render_set.render_target.format(),
+                    TargetBlending::Layer,
+                )
+            });
+            let opacities = render_set
+                .composite
+                .create_opacities(&self.device, &opacities);
+            (pipelines, opacities)
+        });
+
+        if self.write_view_uniform(render_set, view).is_none() {
             return;
+        }
+
+        for (index, layer) in layers.iter().enumerate() {
+            match &layer_resources {
+                Some((pipelines, opacities)) if layer.opacity < 1.0 => self.draw_offscreen_layer(
+                    encoder,
+                    render_set,
+                    pipelines,
+                    &placement,
+                    layer,
+                    texture_view,
+                    opacities,
+                    index,
+                ),
+                _ => {
+                    for draw in &layer.draws {
+                        self.draw_bundles(
+                            encoder,
+                            render_set,
+                            &render_set.pipelines,
+                            &placement,
+                            draw,
+                            texture_view,
+                            &render_set.multisampling_view,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_view_uniform(&self, render_set: &RenderSet, map_view: &MapView) -> Option<()> {
+        let rotation_mtx = view_rotation(map_view).to_homogeneous();
+        let uniform = ViewUniform {
+            view_proj: map_view.map_to_scene_mtx()?,
+            view_rotation: rotation_mtx.cast::<f32>().data.0,
+            inv_screen_size: [
+                1.0 / self.size().width() as f32,
+                1.0 / self.size().height() as f32,
+            ],
+            resolution: map_view.resolution() as f32,
+            _padding: [0.0; 1],
         };
-        let Some(mut canvas) = WgpuCanvas::new(self, render_set, texture_view, view.clone()) else {
-            log::warn!("Layer cannot be rendered to the map view.");
-            return;
+
+        let pipelines =
+            std::iter::once(&render_set.pipelines).chain(render_set.layer_pipelines.get());
+        for pipelines in pipelines {
+            self.queue.write_buffer(
+                pipelines.map_view_buffer(),
+                0,
+                bytemuck::cast_slice(&[uniform]),
+            );
+        }
+
+        Some(())
+    }
+
+    /// Draws a semi-transparent layer to the offscreen layer target, and then blends the target
+    /// into the texture view with the opacity of the layer. This way overlapping primitives of the
+    /// layer do not shine through each other.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_offscreen_layer(
+        &self,
+        encoder: &mut CommandEncoder,
+        render_set: &RenderSet,
+        pipelines: &Pipelines,
+        placement: &LabelPlacement,
+        layer: &RecordedLayer,
+        texture_view: &TextureView,
+        opacities: &LayerOpacities,
+        index: usize,
+    ) {
+        let layer_target = &render_set.layer_target;
+        let _ = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Layer clear pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &layer_target.multisampling_view,
+                resolve_target: Some(&layer_target.view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        for draw in &layer.draws {
+            self.draw_bundles(
+                encoder,
+                render_set,
+                pipelines,
+                placement,
+                draw,
+                &layer_target.view,
+                &layer_target.multisampling_view,
+            );
+        }
+
+        // The layer is blended into the multisample target, which is then resolved into the
+        // texture view. Otherwise, the next antialiased draw would resolve the multisample target
+        // without the layer over the texture view and erase it.
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Layer composite pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &render_set.multisampling_view,
+                resolve_target: Some(texture_view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_set
+            .composite
+            .render(&layer_target.bind_group, opacities, index, &mut render_pass);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_bundles(
+        &self,
+        encoder: &mut CommandEncoder,
+        render_set: &RenderSet,
+        pipelines: &Pipelines,
+        placement: &LabelPlacement,
+        draw: &RecordedDraw,
+        target_view: &TextureView,
+        multisampling_view: &TextureView,
+    ) {
+        let visibilities: Vec<_> = draw
+            .bundles
+            .iter()
+            .map(|bundle| bundle.visibility(placement))
+            .collect();
+
+        let (view, resolve_target, depth_view) = if draw.options.antialias {
+            (
+                multisampling_view,
+                Some(target_view),
+                &render_set.stencil_view_multisample,
+            )
+        } else {
+            (target_view, None, &render_set.stencil_view)
         };
 
-        layer.render(view, &mut canvas);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: StoreOp::Discard,
+                }),
+                stencil_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0),
+                    store: StoreOp::Discard,
+                }),
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        for (bundle, visibility) in draw.bundles.iter().zip(&visibilities) {
+            pipelines.render(&mut render_pass, bundle, visibility, draw.options);
+        }
     }
 
     /// Returns the size of the rendering area.
@@ -654,46 +940,33 @@ impl WgpuRenderer {
     }
 }
 
-#[allow(dead_code)]
+/// Canvas that records the bundles drawn by a layer, so that they can be drawn after the labels
+/// of all layers are placed.
 struct WgpuCanvas<'a> {
     renderer: &'a WgpuRenderer,
     render_set: &'a RenderSet,
-    view: &'a TextureView,
+    draws: Vec<RecordedDraw>,
 }
 
-impl<'a> WgpuCanvas<'a> {
-    fn new(
-        renderer: &'a WgpuRenderer,
-        render_set: &'a RenderSet,
-        view: &'a TextureView,
-        map_view: MapView,
-    ) -> Option<Self> {
-        let rotation_mtx = Rotation3::new(Vector3::new(
-            map_view.rotation_x(),
-            0.0,
-            -map_view.rotation_z(),
-        ))
-        .to_homogeneous();
-        renderer.queue.write_buffer(
-            render_set.pipelines.map_view_buffer(),
-            0,
-            bytemuck::cast_slice(&[ViewUniform {
-                view_proj: map_view.map_to_scene_mtx()?,
-                view_rotation: rotation_mtx.cast::<f32>().data.0,
-                inv_screen_size: [
-                    1.0 / renderer.size().width() as f32,
-                    1.0 / renderer.size().height() as f32,
-                ],
-                resolution: map_view.resolution() as f32,
-                _padding: [0.0; 1],
-            }]),
-        );
+/// Bundles drawn by a layer in one [`Canvas::draw_bundles`] call.
+struct RecordedDraw {
+    bundles: Vec<Arc<WgpuPackedBundle>>,
+    options: RenderOptions,
+}
 
-        Some(Self {
+/// All bundles drawn by a layer in the current frame.
+struct RecordedLayer {
+    draws: Vec<RecordedDraw>,
+    opacity: f32,
+}
+
+impl<'a> WgpuCanvas<'a> {
+    fn new(renderer: &'a WgpuRenderer, render_set: &'a RenderSet) -> Self {
+        Self {
             renderer,
             render_set,
-            view,
-        })
+            draws: vec![],
+        }
     }
 }
 
@@ -708,77 +981,69 @@ impl<'a> Canvas for WgpuCanvas<'a> {
 
     fn pack_bundle(&self, bundle: &RenderBundle) -> Box<dyn PackedBundle> {
         match bundle {
-            RenderBundle(RenderBundleType::Tessellating(inner)) => {
-                Box::new(WgpuPackedBundle::new(inner, self.renderer, self.render_set))
-            }
+            RenderBundle(RenderBundleType::Tessellating(inner)) => Box::new(SharedPackedBundle(
+                Arc::new(WgpuPackedBundle::new(inner, self.renderer, self.render_set)),
+            )),
         }
     }
 
     fn draw_bundles(&mut self, bundles: &[&dyn PackedBundle], options: RenderOptions) {
-        let mut encoder =
-            self.renderer
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
-
-        {
-            let (view, resolve_target, depth_view) = if options.antialias {
-                (
-                    &self.render_set.multisampling_view,
-                    Some(self.view),
-                    &self.render_set.stencil_view_multisample,
-                )
-            } else {
-                (self.view, None, &self.render_set.stencil_view)
-            };
-
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view,
-                    resolve_target,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
-                        store: StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-                    view: depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: StoreOp::Discard,
-                    }),
-                    stencil_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(0),
-                        store: StoreOp::Discard,
-                    }),
-                }),
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-
-            for bundle in bundles {
-                if let Some(cast) = bundle.as_any().downcast_ref::<WgpuPackedBundle>() {
-                    self.render_set
-                        .pipelines
-                        .render(&mut render_pass, cast, options);
-                }
-            }
-        }
-
-        self.renderer
-            .queue
-            .submit(std::iter::once(encoder.finish()));
+        let bundles = bundles
+            .iter()
+            .filter_map(|bundle| bundle.as_any().downcast_ref::<SharedPackedBundle>())
+            .map(|bundle| bundle.0.clone())
+            .collect();
+        self.draws.push(RecordedDraw { bundles, options });
     }
 }
 
+/// Counter used to give unique ids to the packed bundles.
+static NEXT_BUNDLE_ID: AtomicUsize = AtomicUsize::new(0);
+
 struct WgpuPackedBundle {
+    id: usize,
     clip_area_buffers: Option<WgpuPolygonBuffers>,
     map_ref_buffers: WgpuPolygonBuffers,
+    dash_buffers: WgpuPolygonBuffers,
+    pattern_buffers: WgpuPolygonBuffers,
+    map_ref_ranges: Vec<PackedMapRefRange>,
     screen_ref_buffers: Option<ScreenRefBuffers>,
     dot_buffers: Option<WgpuDotBuffers>,
     image_buffers: Vec<WgpuImage>,
+    collidables: Vec<PackedCollidable>,
+}
+
+/// Range of the indices of one of the map reference buffers that is drawn with one draw call.
+struct PackedMapRefRange {
+    index_range: Range<u32>,
+    buffer: MapRefBuffer,
+}
+
+/// Map reference buffer of a [`PackedMapRefRange`].
+enum MapRefBuffer {
+    Plain,
+    Dashed,
+    Pattern(Arc<BindGroup>),
+}
+
+struct PackedCollidable {
+    anchor: [f32; 3],
+    bbox: Rect<f32>,
+    map_oriented: bool,
+    text_key: Option<u64>,
+    options: CollisionOptions,
+    targets: Vec<PackedCollidableTarget>,
+}
+
+enum PackedCollidableTarget {
+    ScreenRef(Range<u32>),
+    Image(usize),
+}
+
+/// Parts of a packed bundle that are drawn in the current frame.
+struct BundleVisibility {
+    screen_ref_ranges: Vec<Range<u32>>,
+    hidden_images: Vec<bool>,
 }
 
 struct WgpuPolygonBuffers {
@@ -806,11 +1071,14 @@ impl WgpuPackedBundle {
     ) -> Self {
         let TessellatingRenderBundle {
             poly_tessellation,
+            dash_tessellation,
+            pattern_tessellation,
             points,
             screen_ref,
             images,
             clip_area,
             image_store,
+            collidables,
             ..
         } = bundle;
 
@@ -819,6 +1087,44 @@ impl WgpuPackedBundle {
             .map(|v| Self::write_poly_buffers(v, renderer));
 
         let poly_buffers = Self::write_poly_buffers(poly_tessellation, renderer);
+        let dash_buffers = Self::write_poly_buffers(dash_tessellation, renderer);
+        let pattern_buffers = Self::write_poly_buffers(pattern_tessellation, renderer);
+
+        let mut pattern_textures: Vec<Option<Arc<BindGroup>>> = vec![None; image_store.len()];
+        let map_ref_ranges = bundle
+            .map_ref_ranges()
+            .into_iter()
+            .filter_map(|range| {
+                let buffer = match range.kind {
+                    MapRefKind::Plain => MapRefBuffer::Plain,
+                    MapRefKind::Dashed => MapRefBuffer::Dashed,
+                    MapRefKind::Pattern { image_store_index } => {
+                        let ImageStoreInfo::Image(image) = image_store.get(image_store_index)?
+                        else {
+                            return None;
+                        };
+
+                        let texture =
+                            pattern_textures[image_store_index].get_or_insert_with(|| {
+                                render_set
+                                    .pipelines
+                                    .pattern_pipeline()
+                                    .create_pattern_texture(
+                                        &renderer.device,
+                                        &renderer.queue,
+                                        image,
+                                    )
+                            });
+                        MapRefBuffer::Pattern(texture.clone())
+                    }
+                };
+
+                Some(PackedMapRefRange {
+                    index_range: range.index_range,
+                    buffer,
+                })
+            })
+            .collect();
 
         let screen_ref_buffers = if !screen_ref.vertices.is_empty() {
             let index = renderer
@@ -879,8 +1185,10 @@ impl WgpuPackedBundle {
             .collect();
 
         let mut image_buffers = vec![];
-        for image_info in images {
+        let mut image_buffer_indices = vec![None; images.len()];
+        for (index, image_info) in images.iter().enumerate() {
             if let ImageInfo::Image((image_index, vertices)) = image_info {
+                image_buffer_indices[index] = Some(image_buffers.len());
                 let image = render_set.pipelines.image_pipeline().create_image(
                     &renderer.device,
                     textures
@@ -897,17 +1205,122 @@ impl WgpuPackedBundle {
             }
         }
 
+        let collidables = collidables
+            .iter()
+            .filter_map(|collidable| {
+                let targets = collidable
+                    .targets
+                    .iter()
+                    .map(|target| match target {
+                        CollidableTarget::ScreenRef { index_range } => {
+                            Some(PackedCollidableTarget::ScreenRef(
+                                index_range.start as u32..index_range.end as u32,
+                            ))
+                        }
+                        CollidableTarget::Image { image_index } => {
+                            Some(PackedCollidableTarget::Image(
+                                (*image_buffer_indices.get(*image_index)?)?,
+                            ))
+                        }
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+
+                Some(PackedCollidable {
+                    anchor: collidable.anchor,
+                    bbox: collidable.bbox,
+                    map_oriented: collidable.map_oriented,
+                    text_key: collidable.text_key,
+                    options: collidable.options,
+                    targets,
+                })
+            })
+            .collect();
+
         Self {
+            id: NEXT_BUNDLE_ID.fetch_add(1, Ordering::Relaxed),
             clip_area_buffers,
             map_ref_buffers: poly_buffers,
+            dash_buffers,
+            pattern_buffers,
+            map_ref_ranges,
             image_buffers,
             screen_ref_buffers,
             dot_buffers,
+            collidables,
+        }
+    }
+
+    fn add_placement_candidates(
+        &self,
+        placement: &mut LabelPlacement,
+        transform: &Matrix4<f64>,
+        rotation: &Rotation3<f64>,
+        width: f32,
+        height: f32,
+        layer_index: usize,
+    ) {
+        for (index, collidable) in self.collidables.iter().enumerate() {
+            let [x, y, z] = collidable.anchor;
+            let projected = transform * Vector4::new(x as f64, y as f64, z as f64, 1.0);
+
+            // Items behind the camera cannot be displayed.
+            let bbox = (projected.w > 0.0).then(|| {
+                let screen_x = ((projected.x / projected.w + 1.0) / 2.0) as f32 * width;
+                let screen_y = ((projected.y / projected.w + 1.0) / 2.0) as f32 * height;
+                let bbox = if collidable.map_oriented {
+                    rotate_bbox(collidable.bbox, rotation)
+                } else {
+                    collidable.bbox
+                };
+                Rect::new(
+                    screen_x + bbox.x_min(),
+                    screen_y + bbox.y_min(),
+                    screen_x + bbox.x_max(),
+                    screen_y + bbox.y_max(),
+                )
+            });
+
+            placement.add_candidate(
+                (self.id, index),
+                layer_index,
+                bbox,
+                collidable.options,
+                collidable.text_key,
+            );
         }
     }
 
-    fn write_poly_buffers(
-        tessellation: &VertexBuffers<PolyVertex, u32>,
+    fn visibility(&self, placement: &LabelPlacement) -> BundleVisibility {
+        let mut hidden_ranges = vec![];
+        let mut hidden_images = vec![false; self.image_buffers.len()];
+        for (index, collidable) in self.collidables.iter().enumerate() {
+            if !placement.is_hidden((self.id, index)) {
+                continue;
+            }
+
+            for target in &collidable.targets {
+                match target {
+                    PackedCollidableTarget::ScreenRef(range) => hidden_ranges.push(range.clone()),
+                    PackedCollidableTarget::Image(image_index) => {
+                        hidden_images[*image_index] = true
+                    }
+                }
+            }
+        }
+
+        let index_count = self
+            .screen_ref_buffers
+            .as_ref()
+            .map(|buffers| buffers.index_count)
+            .unwrap_or_default();
+        BundleVisibility {
+            screen_ref_ranges: visible_ranges(index_count, hidden_ranges),
+            hidden_images,
+        }
+    }
+
+    fn write_poly_buffers<V: bytemuck::Pod>(
+        tessellation: &VertexBuffers<V, u32>,
         renderer: &WgpuRenderer,
     ) -> WgpuPolygonBuffers {
         let index_bytes = bytemuck::cast_slice(&tessellation.indices);
@@ -937,7 +1350,68 @@ impl WgpuPackedBundle {
     }
 }
 
-impl PackedBundle for WgpuPackedBundle {
+/// Returns the ranges of `0..count` not covered by the `hidden` ranges.
+fn visible_ranges(count: u32, mut hidden: Vec<Range<u32>>) -> Vec<Range<u32>> {
+    hidden.sort_by_key(|range| range.start);
+
+    let mut visible = vec![];
+    let mut start = 0;
+    for range in hidden {
+        if range.start > start {
+            visible.push(start..range.start.min(count));
+        }
+        start = start.max(range.end);
+    }
+
+    if start < count {
+        visible.push(start..count);
+    }
+
+    visible
+}
+
+/// Rotation of the map relative to the screen, applied to the pixel offsets that follow the map.
+fn view_rotation(map_view: &MapView) -> Rotation3<f64> {
+    Rotation3::new(Vector3::new(
+        map_view.rotation_x(),
+        0.0,
+        -map_view.rotation_z(),
+    ))
+}
+
+/// Returns the bounding box of the `bbox` rotated the same way as the map oriented offsets are
+/// rotated by the shaders.
+fn rotate_bbox(bbox: Rect<f32>, rotation: &Rotation3<f64>) -> Rect<f32> {
+    // Shaders multiply the offset vector by the rotation matrix from the left, which is the
+    // inverse rotation.
+    let rotation = rotation.inverse();
+    let corners = [
+        (bbox.x_min(), bbox.y_min()),
+        (bbox.x_min(), bbox.y_max()),
+        (bbox.x_max(), bbox.y_min()),
+        (bbox.x_max(), bbox.y_max()),
+    ];
+
+    corners
+        .into_iter()
+        .map(|(x, y)| {
+            let rotated = rotation * Vector3::new(x as f64, y as f64, 0.0);
+            Rect::new(
+                rotated.x as f32,
+                rotated.y as f32,
+                rotated.x as f32,
+                rotated.y as f32,
+            )
+        })
+        .reduce(|acc, rect| acc.merge(rect))
+        .unwrap_or(bbox)
+}
+
+/// Packed bundle given to the layers. The bundle data is shared with the canvas, which keeps it
+/// until the frame is drawn.
+struct SharedPackedBundle(Arc<WgpuPackedBundle>);
+
+impl PackedBundle for SharedPackedBundle {
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -1005,3 +1479,211 @@ impl PolyVertex {
         }
     }
 }
+
+impl DashVertex {
+    fn wgpu_desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<DashVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: (size_of::<[f32; 3]>() + size_of::<[f32; 4]>()) as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: (size_of::<[f32; 3]>() + size_of::<[f32; 4]>() + size_of::<[f32; 2]>())
+                        as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: (size_of::<[f32; 3]>()
+                        + size_of::<[f32; 4]>()
+                        + size_of::<[f32; 2]>()
+                        + size_of::<f32>()) as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: (size_of::<[f32; 3]>()
+                        + size_of::<[f32; 4]>()
+                        + size_of::<[f32; 2]>()
+                        + size_of::<f32>() * 2) as wgpu::BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: (size_of::<[f32; 3]>()
+                        + size_of::<[f32; 4]>()
+                        + size_of::<[f32; 2]>()
+                        + size_of::<f32>() * 3) as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+impl PatternVertex {
+    fn wgpu_desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<PatternVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: (size_of::<[f32; 3]>() + size_of::<[f32; 4]>()) as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: (size_of::<[f32; 3]>() + size_of::<[f32; 4]>() + size_of::<[f32; 2]>())
+                        as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::Layer;
+    use crate::messenger::{DummyMessenger, Messenger};
+    use crate::render::render_bundle::RenderPrimitive;
+    use crate::render::PolygonPaint;
+    use galileo_types::cartesian::Point3d;
+    use galileo_types::geo::impls::GeoPoint2d;
+    use galileo_types::geo::NewGeoPoint;
+    use galileo_types::impls::{ClosedContour, Contour, Polygon};
+
+    /// Layer drawing one rectangle.
+    struct RectLayer {
+        rect: Rect,
+        color: Color,
+        antialias: bool,
+    }
+
+    impl Layer for RectLayer {
+        fn render(&self, view: &MapView, canvas: &mut dyn Canvas) {
+            let mut bundle = canvas.create_bundle();
+            let polygon = Polygon::new(
+                ClosedContour::new(vec![
+                    Point3d::new(self.rect.x_min(), self.rect.y_min(), 0.0),
+                    Point3d::new(self.rect.x_min(), self.rect.y_max(), 0.0),
+                    Point3d::new(self.rect.x_max(), self.rect.y_max(), 0.0),
+                    Point3d::new(self.rect.x_max(), self.rect.y_min(), 0.0),
+                ]),
+                vec![],
+            );
+            bundle.add(
+                RenderPrimitive::<_, _, Contour<_>, _>::new_polygon(
+                    polygon,
+                    PolygonPaint::new(self.color),
+                ),
+                view.resolution(),
+            );
+
+            let packed = canvas.pack_bundle(&bundle);
+            canvas.draw_bundles(
+                &[&*packed],
+                RenderOptions {
+                    antialias: self.antialias,
+                },
+            );
+        }
+
+        fn prepare(&self, _view: &MapView) {}
+
+        fn set_messenger(&mut self, _messenger: Box<dyn Messenger>) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    #[tokio::test]
+    async fn faded_layer_is_kept_under_antialiased_layer() {
+        // Image rows must be aligned to 256 bytes to be copied into a buffer.
+        let size = Size::new(64, 64);
+        let Some(mut renderer) = WgpuRenderer::new_with_texture_rt(size).await else {
+            eprintln!("No GPU adapter available, skipping the test");
+            return;
+        };
+        renderer.set_background(Color::WHITE);
+
+        // The view is 64 map units wide with the center at the origin.
+        let view =
+            MapView::new(&GeoPoint2d::latlon(0.0, 0.0), 1.0).with_size(Size::new(64.0, 64.0));
+        let faded = RectLayer {
+            rect: Rect::new(-32.0, -32.0, 0.0, 32.0),
+            color: Color::RED,
+            antialias: false,
+        };
+        let antialiased = RectLayer {
+            rect: Rect::new(0.0, -32.0, 32.0, 32.0),
+            color: Color::BLUE,
+            antialias: true,
+        };
+        let mut map = Map::new(
+            view,
+            vec![Box::new(faded), Box::new(antialiased)],
+            None::<DummyMessenger>,
+        );
+        map.layers_mut().set_opacity(0, 0.5);
+
+        renderer.render(&map).unwrap();
+        let image = renderer.get_image().await.unwrap();
+        let pixel = |x: usize, y: usize| {
+            let offset = (y * size.width() as usize + x) * 4;
+            [image[offset], image[offset + 1], image[offset + 2]]
+        };
+
+        let faded_pixel = pixel(16, 32);
+        assert_eq!(faded_pixel[0], 255);
+        assert!(
+            faded_pixel[1] > 100 && faded_pixel[1] < 230,
+            "{faded_pixel:?}"
+        );
+        assert_eq!(pixel(48, 32), [0, 0, 255]);
+    }
+
+    #[test]
+    fn map_oriented_bbox_is_rotated_with_map() {
+        let view = MapView::new(&GeoPoint2d::latlon(0.0, 0.0), 1.0)
+            .with_rotation_z(std::f64::consts::FRAC_PI_2);
+        let bbox = rotate_bbox(Rect::new(0.0, 0.0, 10.0, 2.0), &view_rotation(&view));
+
+        assert!((bbox.x_min() + 2.0).abs() < 1e-4, "{bbox:?}");
+        assert!(bbox.x_max().abs() < 1e-4, "{bbox:?}");
+        assert!(bbox.y_min().abs() < 1e-4, "{bbox:?}");
+        assert!((bbox.y_max() - 10.0).abs() < 1e-4, "{bbox:?}");
+    }
+}
@@ -1,10 +1,10 @@
 use crate::render::render_bundle::tessellating::PolyVertex;
-use crate::render::wgpu::pipelines::{default_pipeline_descriptor, default_targets};
+use crate::render::wgpu::pipelines::default_pipeline_descriptor;
 use crate::render::wgpu::{WgpuPolygonBuffers, DEPTH_FORMAT};
 use crate::render::RenderOptions;
 use wgpu::{
-    BindGroupLayout, CompareFunction, DepthStencilState, Device, RenderPass, RenderPipeline,
-    RenderPipelineDescriptor, StencilFaceState, StencilOperation, StencilState, TextureFormat,
+    BindGroupLayout, ColorTargetState, CompareFunction, DepthStencilState, Device, RenderPass,
+    RenderPipeline, RenderPipelineDescriptor, StencilFaceState, StencilOperation, StencilState,
 };
 
 pub struct ClipPipeline {
@@ -18,7 +18,7 @@ impl ClipPipeline {
 
     pub fn create(
         device: &Device,
-        format: TextureFormat,
+        targets: &[Option<ColorTargetState>],
         map_view_layout: &BindGroupLayout,
     ) -> Self {
         let buffers = [PolyVertex::wgpu_desc()];
@@ -30,7 +30,6 @@ impl ClipPipeline {
             depth_fail_op: StencilOperation::Keep,
             pass_op: StencilOperation::Keep,
         };
-        let targets = default_targets(format);
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &[map_view_layout],
@@ -52,11 +51,11 @@ impl ClipPipeline {
 
         let wgpu_pipeline_antialias = device.create_render_pipeline(&RenderPipelineDescriptor {
             depth_stencil: depth_stencil.clone(),
-            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, true)
+            ..default_pipeline_descriptor(&layout, &shader, targets, &buffers, true)
         });
         let wgpu_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
             depth_stencil,
-            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, false)
+            ..default_pipeline_descriptor(&layout, &shader, targets, &buffers, false)
         });
 
         Self {
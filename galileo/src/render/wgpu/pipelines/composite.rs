@@ -0,0 +1,193 @@
+use std::mem::size_of;
+use std::num::NonZeroU64;
+use wgpu::util::DeviceExt;
+use wgpu::{
+    BindGroup, BindGroupLayout, BufferBinding, Device, RenderPass, RenderPipeline, Sampler,
+    TextureFormat, TextureView,
+};
+
+/// Size of the opacity uniform. Uniform structs are padded to 16 bytes.
+const OPACITY_SIZE: u64 = 16;
+
+/// Blends an offscreen layer texture into the multisample render target with the opacity of the
+/// layer.
+pub struct CompositePipeline {
+    wgpu_pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    opacity_bind_group_layout: BindGroupLayout,
+    sampler: Sampler,
+}
+
+/// Opacities of all the layers composited in a frame, stored in one uniform buffer.
+pub struct LayerOpacities {
+    bind_group: BindGroup,
+    stride: u32,
+}
+
+impl CompositePipeline {
+    pub fn create(device: &Device, format: TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("./shaders/composite.wgsl"));
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("composite_bind_group_layout"),
+        });
+
+        let opacity_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: NonZeroU64::new(OPACITY_SIZE),
+                    },
+                    count: None,
+                }],
+                label: Some("composite_opacity_bind_group_layout"),
+            });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout, &opacity_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // Layer texture contains colors premultiplied by alpha.
+        let premultiplied = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let wgpu_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Composite pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState {
+                        color: premultiplied,
+                        alpha: premultiplied,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: Default::default(),
+            depth_stencil: None,
+            // Layers are composited into the multisample target of the map.
+            multisample: wgpu::MultisampleState {
+                count: 4,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            wgpu_pipeline,
+            bind_group_layout,
+            opacity_bind_group_layout,
+            sampler,
+        }
+    }
+
+    /// Creates a bind group for the given offscreen layer texture.
+    pub fn create_bind_group(&self, device: &Device, layer_view: &TextureView) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(layer_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+            label: Some("composite_bind_group"),
+        })
+    }
+
+    /// Creates a uniform buffer with the given layer opacities. The layer at index `i` is then
+    /// composited by calling [`CompositePipeline::render`] with the same index.
+    pub fn create_opacities(&self, device: &Device, opacities: &[f32]) -> LayerOpacities {
+        let stride = device
+            .limits()
+            .min_uniform_buffer_offset_alignment
+            .max(OPACITY_SIZE as u32);
+        let floats_per_layer = stride as usize / size_of::<f32>();
+
+        let mut contents = vec![0.0f32; floats_per_layer * opacities.len().max(1)];
+        for (index, opacity) in opacities.iter().enumerate() {
+            contents[index * floats_per_layer] = *opacity;
+        }
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Layer opacity buffer"),
+            contents: bytemuck::cast_slice(&contents),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.opacity_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(OPACITY_SIZE),
+                }),
+            }],
+            label: Some("composite_opacity_bind_group"),
+        });
+
+        LayerOpacities { bind_group, stride }
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        layer_bind_group: &'a BindGroup,
+        opacities: &'a LayerOpacities,
+        index: usize,
+        render_pass: &mut RenderPass<'a>,
+    ) {
+        render_pass.set_pipeline(&self.wgpu_pipeline);
+        render_pass.set_bind_group(0, layer_bind_group, &[]);
+        render_pass.set_bind_group(1, &opacities.bind_group, &[index as u32 * opacities.stride]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::render::render_bundle::tessellating::DashVertex;
+use crate::render::wgpu::{pipelines, WgpuPolygonBuffers};
+use crate::render::RenderOptions;
+use std::ops::Range;
+use wgpu::{BindGroupLayout, ColorTargetState, Device, RenderPass, RenderPipeline};
+
+/// Draws map reference lines with a dash pattern.
+pub struct DashPipeline {
+    wgpu_pipeline: RenderPipeline,
+    wgpu_pipeline_antialias: RenderPipeline,
+}
+
+impl DashPipeline {
+    pub fn create(
+        device: &Device,
+        targets: &[Option<ColorTargetState>],
+        map_view_layout: &BindGroupLayout,
+    ) -> Self {
+        let buffers = [DashVertex::wgpu_desc()];
+        let shader = device.create_shader_module(wgpu::include_wgsl!("./shaders/dash.wgsl"));
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[map_view_layout],
+            push_constant_ranges: &[],
+        });
+        let mut desc =
+            pipelines::default_pipeline_descriptor(&layout, &shader, targets, &buffers, false);
+        let wgpu_pipeline = device.create_render_pipeline(&desc);
+
+        desc.multisample.count = 4;
+        let wgpu_pipeline_antialias = device.create_render_pipeline(&desc);
+
+        Self {
+            wgpu_pipeline,
+            wgpu_pipeline_antialias,
+        }
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        buffers: &'a WgpuPolygonBuffers,
+        index_range: Range<u32>,
+        render_pass: &mut RenderPass<'a>,
+        render_options: RenderOptions,
+    ) {
+        if render_options.antialias {
+            render_pass.set_pipeline(&self.wgpu_pipeline_antialias);
+        } else {
+            render_pass.set_pipeline(&self.wgpu_pipeline);
+        }
+        render_pass.set_vertex_buffer(0, buffers.vertex.slice(..));
+        render_pass.set_index_buffer(buffers.index.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(index_range, 0, 0..1);
+    }
+}
@@ -1,10 +1,10 @@
 use crate::render::render_bundle::tessellating::PointInstance;
-use crate::render::wgpu::pipelines::{default_pipeline_descriptor, default_targets};
+use crate::render::wgpu::pipelines::default_pipeline_descriptor;
 use crate::render::wgpu::{WgpuDotBuffers, DEPTH_FORMAT};
 use crate::render::RenderOptions;
 use wgpu::{
-    BindGroupLayout, CompareFunction, DepthStencilState, Device, RenderPass, RenderPipeline,
-    RenderPipelineDescriptor, StencilFaceState, StencilOperation, StencilState, TextureFormat,
+    BindGroupLayout, ColorTargetState, CompareFunction, DepthStencilState, Device, RenderPass,
+    RenderPipeline, RenderPipelineDescriptor, StencilFaceState, StencilOperation, StencilState,
     VertexStepMode,
 };
 
@@ -16,7 +16,7 @@ pub struct DotPipeline {
 impl DotPipeline {
     pub fn create(
         device: &Device,
-        format: TextureFormat,
+        targets: &[Option<ColorTargetState>],
         map_view_layout: &BindGroupLayout,
     ) -> Self {
         let mut desc = PointInstance::wgpu_desc();
@@ -25,7 +25,6 @@ impl DotPipeline {
         let buffers = [desc];
         let shader = device.create_shader_module(wgpu::include_wgsl!("./shaders/dot.wgsl"));
 
-        let targets = default_targets(format);
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &[map_view_layout],
@@ -62,12 +61,12 @@ impl DotPipeline {
         let wgpu_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
             primitive,
             depth_stencil: depth_stencil.clone(),
-            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, false)
+            ..default_pipeline_descriptor(&layout, &shader, targets, &buffers, false)
         });
         let wgpu_pipeline_antialias = device.create_render_pipeline(&RenderPipelineDescriptor {
             primitive,
             depth_stencil,
-            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, true)
+            ..default_pipeline_descriptor(&layout, &shader, targets, &buffers, true)
         });
         Self {
             wgpu_pipeline,
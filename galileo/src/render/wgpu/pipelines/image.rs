@@ -1,12 +1,11 @@
 use crate::decoded_image::DecodedImage;
 use crate::render::render_bundle::tessellating::ImageVertex;
 use crate::render::wgpu::pipelines;
-use crate::render::wgpu::pipelines::default_targets;
 use crate::render::RenderOptions;
 use std::sync::Arc;
 use wgpu::util::{DeviceExt, TextureDataOrder};
 use wgpu::{
-    BindGroup, BindGroupLayout, Device, Queue, RenderPass, RenderPipeline,
+    BindGroup, BindGroupLayout, ColorTargetState, Device, Queue, RenderPass, RenderPipeline,
     RenderPipelineDescriptor, TextureFormat,
 };
 
@@ -27,7 +26,7 @@ pub struct ImagePipeline {
 impl ImagePipeline {
     pub fn create(
         device: &Device,
-        format: TextureFormat,
+        targets: &[Option<ColorTargetState>],
         map_view_layout: &BindGroupLayout,
     ) -> Self {
         let shader = device.create_shader_module(wgpu::include_wgsl!("./shaders/image.wgsl"));
@@ -62,10 +61,8 @@ impl ImagePipeline {
             push_constant_ranges: &[],
         });
 
-        let targets = default_targets(format);
-
         let mut desc = RenderPipelineDescriptor {
-            ..pipelines::default_pipeline_descriptor(&layout, &shader, &targets, &buffers, false)
+            ..pipelines::default_pipeline_descriptor(&layout, &shader, targets, &buffers, false)
         };
 
         let wgpu_pipeline = device.create_render_pipeline(&desc);
@@ -1,8 +1,8 @@
 use crate::render::render_bundle::tessellating::PolyVertex;
-use crate::render::wgpu::pipelines::default_targets;
 use crate::render::wgpu::{pipelines, WgpuPolygonBuffers};
 use crate::render::RenderOptions;
-use wgpu::{BindGroupLayout, Device, RenderPass, RenderPipeline, TextureFormat};
+use std::ops::Range;
+use wgpu::{BindGroupLayout, ColorTargetState, Device, RenderPass, RenderPipeline};
 
 pub struct MapRefPipeline {
     wgpu_pipeline: RenderPipeline,
@@ -12,20 +12,19 @@ pub struct MapRefPipeline {
 impl MapRefPipeline {
     pub fn create(
         device: &Device,
-        format: TextureFormat,
+        targets: &[Option<ColorTargetState>],
         map_view_layout: &BindGroupLayout,
     ) -> Self {
         let buffers = [PolyVertex::wgpu_desc()];
         let shader = device.create_shader_module(wgpu::include_wgsl!("./shaders/map_ref.wgsl"));
 
-        let targets = default_targets(format);
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &[map_view_layout],
             push_constant_ranges: &[],
         });
         let mut desc =
-            pipelines::default_pipeline_descriptor(&layout, &shader, &targets, &buffers, false);
+            pipelines::default_pipeline_descriptor(&layout, &shader, targets, &buffers, false);
         let wgpu_pipeline = device.create_render_pipeline(&desc);
 
         desc.multisample.count = 4;
@@ -40,6 +39,7 @@ impl MapRefPipeline {
     pub fn render<'a>(
         &'a self,
         buffers: &'a WgpuPolygonBuffers,
+        index_range: Range<u32>,
         render_pass: &mut RenderPass<'a>,
         render_options: RenderOptions,
     ) {
@@ -50,6 +50,6 @@ impl MapRefPipeline {
         }
         render_pass.set_vertex_buffer(0, buffers.vertex.slice(..));
         render_pass.set_index_buffer(buffers.index.slice(..), wgpu::IndexFormat::Uint32);
-        render_pass.draw_indexed(0..buffers.index_count, 0, 0..1);
+        render_pass.draw_indexed(index_range, 0, 0..1);
     }
 }
@@ -1,9 +1,13 @@
 use crate::render::wgpu::pipelines::clip::ClipPipeline;
+use crate::render::wgpu::pipelines::dash::DashPipeline;
 use crate::render::wgpu::pipelines::dot::DotPipeline;
 use crate::render::wgpu::pipelines::image::ImagePipeline;
 use crate::render::wgpu::pipelines::map_ref::MapRefPipeline;
+use crate::render::wgpu::pipelines::pattern::PatternPipeline;
 use crate::render::wgpu::pipelines::screen_ref::ScreenRefPipeline;
-use crate::render::wgpu::{ViewUniform, WgpuPackedBundle, DEPTH_FORMAT};
+use crate::render::wgpu::{
+    BundleVisibility, MapRefBuffer, ViewUniform, WgpuPackedBundle, DEPTH_FORMAT,
+};
 use crate::render::RenderOptions;
 use std::mem::size_of;
 use wgpu::{
@@ -13,9 +17,12 @@ use wgpu::{
 };
 
 mod clip;
+pub mod composite;
+mod dash;
 mod dot;
 pub mod image;
 mod map_ref;
+mod pattern;
 mod screen_ref;
 
 pub struct Pipelines {
@@ -25,12 +32,24 @@ pub struct Pipelines {
     image: ImagePipeline,
     screen_ref: ScreenRefPipeline,
     map_ref: MapRefPipeline,
+    dash: DashPipeline,
+    pattern: PatternPipeline,
     clip: ClipPipeline,
     dot: DotPipeline,
 }
 
+/// Blending of the colors drawn to a render target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetBlending {
+    /// Primitives are blended into the map.
+    Map,
+    /// Primitives are drawn to a transparent offscreen layer texture, which is later composited
+    /// into the map. The alpha channel of the texture accumulates the coverage of the primitives.
+    Layer,
+}
+
 impl Pipelines {
-    pub fn create(device: &Device, format: TextureFormat) -> Self {
+    pub fn create(device: &Device, format: TextureFormat, blending: TargetBlending) -> Self {
         let map_view_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Map view buffer"),
             size: size_of::<ViewUniform>() as wgpu::BufferAddress,
@@ -42,7 +61,7 @@ impl Pipelines {
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -62,14 +81,17 @@ impl Pipelines {
             label: Some("view_bind_group"),
         });
 
+        let targets = default_targets(format, blending);
         Self {
             map_view_binding,
             map_view_buffer,
-            image: ImagePipeline::create(device, format, &map_view_bind_group_layout),
-            map_ref: MapRefPipeline::create(device, format, &map_view_bind_group_layout),
-            screen_ref: ScreenRefPipeline::create(device, format, &map_view_bind_group_layout),
-            clip: ClipPipeline::create(device, format, &map_view_bind_group_layout),
-            dot: DotPipeline::create(device, format, &map_view_bind_group_layout),
+            image: ImagePipeline::create(device, &targets, &map_view_bind_group_layout),
+            map_ref: MapRefPipeline::create(device, &targets, &map_view_bind_group_layout),
+            dash: DashPipeline::create(device, &targets, &map_view_bind_group_layout),
+            pattern: PatternPipeline::create(device, &targets, &map_view_bind_group_layout),
+            screen_ref: ScreenRefPipeline::create(device, &targets, &map_view_bind_group_layout),
+            clip: ClipPipeline::create(device, &targets, &map_view_bind_group_layout),
+            dot: DotPipeline::create(device, &targets, &map_view_bind_group_layout),
         }
     }
 
@@ -77,6 +99,7 @@ impl Pipelines {
         &'a self,
         render_pass: &mut RenderPass<'a>,
         bundle: &'a WgpuPackedBundle,
+        visibility: &BundleVisibility,
         render_options: RenderOptions,
     ) {
         self.set_bindings(render_pass);
@@ -85,13 +108,34 @@ impl Pipelines {
             self.clip.clip(clip, render_pass, render_options);
         }
 
-        for image in &bundle.image_buffers {
-            self.image.render(image, render_pass, render_options);
+        for (image, is_hidden) in bundle.image_buffers.iter().zip(&visibility.hidden_images) {
+            if !is_hidden {
+                self.image.render(image, render_pass, render_options);
+            }
         }
 
-        if bundle.map_ref_buffers.index_count > 0 {
-            self.map_ref
-                .render(&bundle.map_ref_buffers, render_pass, render_options);
+        for range in &bundle.map_ref_ranges {
+            match &range.buffer {
+                MapRefBuffer::Plain => self.map_ref.render(
+                    &bundle.map_ref_buffers,
+                    range.index_range.clone(),
+                    render_pass,
+                    render_options,
+                ),
+                MapRefBuffer::Dashed => self.dash.render(
+                    &bundle.dash_buffers,
+                    range.index_range.clone(),
+                    render_pass,
+                    render_options,
+                ),
+                MapRefBuffer::Pattern(texture) => self.pattern.render(
+                    &bundle.pattern_buffers,
+                    range.index_range.clone(),
+                    texture,
+                    render_pass,
+                    render_options,
+                ),
+            }
         }
 
         if let Some(clip) = &bundle.clip_area_buffers {
@@ -99,8 +143,12 @@ impl Pipelines {
         }
 
         if let Some(screen_ref_buffers) = &bundle.screen_ref_buffers {
-            self.screen_ref
-                .render(screen_ref_buffers, render_pass, render_options);
+            self.screen_ref.render(
+                screen_ref_buffers,
+                &visibility.screen_ref_ranges,
+                render_pass,
+                render_options,
+            );
         }
 
         if let Some(dot_buffers) = &bundle.dot_buffers {
@@ -116,15 +164,36 @@ impl Pipelines {
         &self.image
     }
 
+    pub fn pattern_pipeline(&self) -> &PatternPipeline {
+        &self.pattern
+    }
+
     fn set_bindings<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
         render_pass.set_bind_group(0, &self.map_view_binding, &[]);
     }
 }
 
-fn default_targets(format: TextureFormat) -> [Option<wgpu::ColorTargetState>; 1] {
+fn default_targets(
+    format: TextureFormat,
+    blending: TargetBlending,
+) -> [Option<wgpu::ColorTargetState>; 1] {
+    let blend = match blending {
+        TargetBlending::Map => wgpu::BlendState::ALPHA_BLENDING,
+        // Alpha channel is accumulated as `src + dst * (1 - src)`, so that the layer texture
+        // gets correct coverage and can be composited into the map.
+        TargetBlending::Layer => wgpu::BlendState {
+            color: wgpu::BlendState::ALPHA_BLENDING.color,
+            alpha: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                operation: wgpu::BlendOperation::Add,
+            },
+        },
+    };
+
     [Some(wgpu::ColorTargetState {
         format,
-        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+        blend: Some(blend),
         write_mask: wgpu::ColorWrites::ALL,
     })]
 }
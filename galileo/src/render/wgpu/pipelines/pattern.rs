@@ -0,0 +1,149 @@
+use crate::decoded_image::DecodedImage;
+use crate::render::render_bundle::tessellating::PatternVertex;
+use crate::render::wgpu::{pipelines, WgpuPolygonBuffers};
+use crate::render::RenderOptions;
+use std::ops::Range;
+use std::sync::Arc;
+use wgpu::util::{DeviceExt, TextureDataOrder};
+use wgpu::{
+    BindGroup, BindGroupLayout, ColorTargetState, Device, Queue, RenderPass, RenderPipeline,
+    TextureFormat,
+};
+
+/// Draws map reference polygons filled with a repeated image.
+pub struct PatternPipeline {
+    wgpu_pipeline: RenderPipeline,
+    wgpu_pipeline_antialias: RenderPipeline,
+    texture_bind_group_layout: BindGroupLayout,
+}
+
+impl PatternPipeline {
+    pub fn create(
+        device: &Device,
+        targets: &[Option<ColorTargetState>],
+        map_view_layout: &BindGroupLayout,
+    ) -> Self {
+        let buffers = [PatternVertex::wgpu_desc()];
+        let shader = device.create_shader_module(wgpu::include_wgsl!("./shaders/pattern.wgsl"));
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+                label: Some("pattern_bind_group_layout"),
+            });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[map_view_layout, &texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let mut desc =
+            pipelines::default_pipeline_descriptor(&layout, &shader, targets, &buffers, false);
+        let wgpu_pipeline = device.create_render_pipeline(&desc);
+
+        desc.multisample.count = 4;
+        let wgpu_pipeline_antialias = device.create_render_pipeline(&desc);
+
+        Self {
+            wgpu_pipeline,
+            wgpu_pipeline_antialias,
+            texture_bind_group_layout,
+        }
+    }
+
+    /// Creates a texture for the pattern image. Unlike image textures, pattern textures are
+    /// repeated outside of the `[0, 1]` range of texture coordinates.
+    pub fn create_pattern_texture(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        image: &DecodedImage,
+    ) -> Arc<BindGroup> {
+        let texture_size = wgpu::Extent3d {
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                size: texture_size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                label: None,
+                view_formats: &[],
+            },
+            TextureDataOrder::default(),
+            image.bytes(),
+        );
+
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("pattern_bind_group"),
+        });
+
+        Arc::new(texture_bind_group)
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        buffers: &'a WgpuPolygonBuffers,
+        index_range: Range<u32>,
+        texture: &'a BindGroup,
+        render_pass: &mut RenderPass<'a>,
+        render_options: RenderOptions,
+    ) {
+        if render_options.antialias {
+            render_pass.set_pipeline(&self.wgpu_pipeline_antialias);
+        } else {
+            render_pass.set_pipeline(&self.wgpu_pipeline);
+        }
+        render_pass.set_bind_group(1, texture, &[]);
+        render_pass.set_vertex_buffer(0, buffers.vertex.slice(..));
+        render_pass.set_index_buffer(buffers.index.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(index_range, 0, 0..1);
+    }
+}
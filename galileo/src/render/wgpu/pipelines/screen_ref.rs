@@ -1,11 +1,12 @@
 use crate::render::render_bundle::tessellating::ScreenRefVertex;
-use crate::render::wgpu::pipelines::{default_pipeline_descriptor, default_targets};
+use crate::render::wgpu::pipelines::default_pipeline_descriptor;
 use crate::render::wgpu::{ScreenRefBuffers, DEPTH_FORMAT};
 use crate::render::RenderOptions;
 use std::mem::size_of;
+use std::ops::Range;
 use wgpu::{
-    BindGroupLayout, CompareFunction, DepthStencilState, Device, RenderPass, RenderPipeline,
-    RenderPipelineDescriptor, StencilFaceState, StencilOperation, StencilState, TextureFormat,
+    BindGroupLayout, ColorTargetState, CompareFunction, DepthStencilState, Device, RenderPass,
+    RenderPipeline, RenderPipelineDescriptor, StencilFaceState, StencilOperation, StencilState,
 };
 
 pub struct ScreenRefPipeline {
@@ -16,13 +17,12 @@ pub struct ScreenRefPipeline {
 impl ScreenRefPipeline {
     pub fn create(
         device: &Device,
-        format: TextureFormat,
+        targets: &[Option<ColorTargetState>],
         map_view_layout: &BindGroupLayout,
     ) -> Self {
         let buffers = [ScreenRefVertex::wgpu_desc()];
         let shader = device.create_shader_module(wgpu::include_wgsl!("./shaders/screen_ref.wgsl"));
 
-        let targets = default_targets(format);
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &[map_view_layout],
@@ -47,7 +47,7 @@ impl ScreenRefPipeline {
                 },
                 bias: Default::default(),
             }),
-            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, false)
+            ..default_pipeline_descriptor(&layout, &shader, targets, &buffers, false)
         };
 
         let wgpu_pipeline = device.create_render_pipeline(&desc);
@@ -64,6 +64,7 @@ impl ScreenRefPipeline {
     pub fn render<'a>(
         &'a self,
         buffers: &'a ScreenRefBuffers,
+        index_ranges: &[Range<u32>],
         render_pass: &mut RenderPass<'a>,
         render_options: RenderOptions,
     ) {
@@ -74,7 +75,9 @@ impl ScreenRefPipeline {
         }
         render_pass.set_vertex_buffer(0, buffers.vertex.slice(..));
         render_pass.set_index_buffer(buffers.index.slice(..), wgpu::IndexFormat::Uint32);
-        render_pass.draw_indexed(0..buffers.index_count, 0, 0..1);
+        for range in index_ranges {
+            render_pass.draw_indexed(range.clone(), 0, 0..1);
+        }
     }
 }
 
@@ -99,6 +102,12 @@ impl ScreenRefVertex {
                     shader_location: 2,
                     format: wgpu::VertexFormat::Uint8x4,
                 },
+                wgpu::VertexAttribute {
+                    offset: (size_of::<[f32; 3]>() + size_of::<[f32; 2]>() + size_of::<[u8; 4]>())
+                        as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Uint32,
+                },
             ],
         }
     }
@@ -0,0 +1,39 @@
+// Vertex shader
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+};
+
+// Draws a single triangle covering the whole screen.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let x = f32((index << 1u) & 2u);
+    let y = f32(index & 2u);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(x * 2.0 - 1.0, 1.0 - y * 2.0, 0.0, 1.0);
+    out.tex_coord = vec2<f32>(x, y);
+
+    return out;
+}
+
+
+// Fragment shader
+
+struct LayerUniform {
+    opacity: f32,
+}
+
+@group(0) @binding(0)
+var t_layer: texture_2d<f32>;
+@group(0) @binding(1)
+var s_layer: sampler;
+@group(1) @binding(0)
+var<uniform> layer: LayerUniform;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Colors of the layer texture are premultiplied by alpha.
+    return textureSample(t_layer, s_layer, in.tex_coord) * layer.opacity;
+}
@@ -0,0 +1,60 @@
+// Vertex shader
+
+struct ViewUniform {
+    view_proj: mat4x4<f32>,
+    view_rotation: mat4x4<f32>,
+    inv_screen_size: vec2<f32>,
+    resolution: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> transform: ViewUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) tex_coord: vec2<f32>,
+    @location(3) pattern_mode: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) tex_coord: vec2<f32>,
+    @location(3) pattern_mode: f32,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = model.color;
+    out.tex_coord = model.tex_coord;
+    out.pattern_mode = model.pattern_mode;
+    out.clip_position = transform.view_proj * vec4<f32>(model.position, 1.0);
+
+    return out;
+}
+
+
+// Fragment shader
+
+@group(1) @binding(0)
+var t_pattern: texture_2d<f32>;
+@group(1) @binding(1)
+var s_pattern: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Screen space patterns are tiled from the fragment position in pixels.
+    let screen_coord = in.clip_position.xy / vec2<f32>(textureDimensions(t_pattern));
+    let tex_coord = select(in.tex_coord, screen_coord, in.pattern_mode > 0.5);
+
+    let color = textureSample(t_pattern, s_pattern, tex_coord) * in.color;
+    if color[3] == 0.0 {
+        discard;
+    }
+
+    return color;
+}
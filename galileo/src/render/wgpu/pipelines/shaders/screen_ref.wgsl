@@ -14,6 +14,7 @@ struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec2<f32>,
     @location(2) color: vec4<u32>,
+    @location(3) map_oriented: u32,
 }
 
 struct VertexOutput {
@@ -28,7 +29,12 @@ fn vs_main(
     var out: VertexOutput;
     out.color = vec4<f32>(model.color) / 255.0;
     var point_position = transform.view_proj * vec4<f32>(model.position, 1.0);
-    var vertex_delta = vec4<f32>(model.normal * transform.inv_screen_size * point_position[3] * 2.0, 0.0, 0.0);
+    var normal = model.normal;
+    if (model.map_oriented != 0u) {
+        normal = (vec4<f32>(normal, 0.0, 0.0) * transform.view_rotation).xy;
+    }
+
+    var vertex_delta = vec4<f32>(normal * transform.inv_screen_size * point_position[3] * 2.0, 0.0, 0.0);
 
     out.clip_position = point_position + vertex_delta;
 
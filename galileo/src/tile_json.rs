@@ -0,0 +1,283 @@
+//! Parsing of [TileJSON](https://github.com/mapbox/tilejson-spec) documents describing tile sources.
+
+use crate::error::GalileoError;
+use crate::layer::data_provider::UrlSource;
+use crate::tile_scheme::{TileIndex, TileSchema};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const DEFAULT_BOUNDS: [f64; 4] = [-180.0, -85.05112877980659, 180.0, 85.0511287798066];
+const DEFAULT_MAX_ZOOM: u32 = 30;
+
+/// Row numbering scheme of the tiles of a TileJSON source.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TileJsonScheme {
+    /// Rows are numbered from the top of the map (OSM, Google).
+    #[default]
+    Xyz,
+    /// Rows are numbered from the bottom of the map.
+    Tms,
+}
+
+/// Description of a layer of a vector tile source.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TileJsonVectorLayer {
+    /// Name of the layer in the tiles.
+    pub id: String,
+    /// Names of the feature attributes of the layer and their descriptions.
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
+    /// Description of the layer.
+    pub description: Option<String>,
+    /// Lowest z-level the layer is present at.
+    pub minzoom: Option<u32>,
+    /// Highest z-level the layer is present at.
+    pub maxzoom: Option<u32>,
+}
+
+/// TileJSON document.
+///
+/// Missing optional values are replaced with the defaults given by the specification, so the
+/// fields can be used directly.
+///
+/// ```
+/// use galileo::tile_json::TileJson;
+///
+/// let tile_json: TileJson = r#"{
+///     "tilejson": "3.0.0",
+///     "tiles": ["https://example.com/{z}/{x}/{y}.png"],
+///     "maxzoom": 14
+/// }"#.parse().unwrap();
+///
+/// assert_eq!(tile_json.minzoom, 0);
+/// assert_eq!(tile_json.tile_schema().lods.len(), 15);
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TileJson {
+    /// Version of the TileJSON specification the document follows.
+    pub tilejson: String,
+    /// Tile URL templates. If more than one template is given, they are used as mirrors.
+    pub tiles: Vec<String>,
+    /// Values for the `{s}` placeholder of the templates.
+    ///
+    /// This is not a part of the specification, but is used by some tile services.
+    #[serde(default)]
+    pub subdomains: Vec<String>,
+    /// Name of the tileset.
+    pub name: Option<String>,
+    /// Description of the tileset.
+    pub description: Option<String>,
+    /// Version of the tileset.
+    pub version: Option<String>,
+    /// Attribution to be displayed with the map. May contain HTML.
+    pub attribution: Option<String>,
+    /// Extent of the tileset as `[west, south, east, north]` in degrees.
+    #[serde(default = "default_bounds")]
+    pub bounds: [f64; 4],
+    /// Default position of the map as `[longitude, latitude, zoom]`.
+    pub center: Option<[f64; 3]>,
+    /// Lowest z-level of the tiles.
+    #[serde(default)]
+    pub minzoom: u32,
+    /// Highest z-level of the tiles.
+    #[serde(default = "default_max_zoom")]
+    pub maxzoom: u32,
+    /// Row numbering scheme of the tiles.
+    #[serde(default)]
+    pub scheme: TileJsonScheme,
+    /// Layers of a vector tileset.
+    #[serde(default)]
+    pub vector_layers: Vec<TileJsonVectorLayer>,
+}
+
+fn default_bounds() -> [f64; 4] {
+    DEFAULT_BOUNDS
+}
+
+fn default_max_zoom() -> u32 {
+    DEFAULT_MAX_ZOOM
+}
+
+impl FromStr for TileJson {
+    type Err = GalileoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tile_json: Self = serde_json::from_str(s)
+            .map_err(|err| GalileoError::Generic(format!("invalid TileJSON document: {err}")))?;
+        tile_json.validate()?;
+
+        Ok(tile_json)
+    }
+}
+
+impl TileJson {
+    /// Reads the document from a local file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, GalileoError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    fn validate(&self) -> Result<(), GalileoError> {
+        if self.tiles.is_empty() {
+            return Err(GalileoError::Generic(
+                "TileJSON document does not contain tile urls".into(),
+            ));
+        }
+
+        if self.minzoom > self.maxzoom || self.maxzoom > DEFAULT_MAX_ZOOM {
+            return Err(GalileoError::Generic(format!(
+                "invalid TileJSON zoom range: {}..{}",
+                self.minzoom, self.maxzoom
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Creates a url source substituting the tile index into the templates of the document.
+    ///
+    /// Tiles are spread between the mirror templates and the subdomains. For the TMS scheme the
+    /// row index is flipped, so the source can be used with the [`tile schema`](TileJson::tile_schema)
+    /// of the document.
+    pub fn url_source(&self) -> impl UrlSource<TileIndex> {
+        let templates = self.tiles.clone();
+        let subdomains = self.subdomains.clone();
+        let scheme = self.scheme;
+
+        move |index: &TileIndex| {
+            let flipped_y = (1i64 << index.z) - 1 - index.y as i64;
+            let y = match scheme {
+                TileJsonScheme::Xyz => index.y as i64,
+                TileJsonScheme::Tms => flipped_y,
+            };
+
+            let hash = (index.x as i64 + index.y as i64).unsigned_abs() as usize;
+            let template = &templates[hash % templates.len()];
+            let mut url = template
+                .replace("{z}", &index.z.to_string())
+                .replace("{x}", &index.x.to_string())
+                .replace("{y}", &y.to_string())
+                .replace("{-y}", &flipped_y.to_string());
+
+            if !subdomains.is_empty() {
+                url = url.replace("{s}", &subdomains[hash % subdomains.len()]);
+            }
+
+            url
+        }
+    }
+
+    /// Creates a Web Mercator tile schema with the z-levels and bounds of the document.
+    pub fn tile_schema(&self) -> TileSchema {
+        TileSchema::web_for_area(self.minzoom, self.maxzoom, self.bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"{
+        "tilejson": "3.0.0",
+        "name": "test",
+        "tiles": [
+            "https://a.example.com/tiles/{z}/{x}/{y}.pbf",
+            "https://b.example.com/tiles/{z}/{x}/{y}.pbf"
+        ],
+        "attribution": "(c) test",
+        "bounds": [-10, -20, 30, 40],
+        "center": [10, 10, 5],
+        "minzoom": 2,
+        "maxzoom": 12,
+        "scheme": "tms",
+        "vector_layers": [
+            { "id": "roads", "fields": { "class": "String" }, "maxzoom": 10 }
+        ]
+    }"#;
+
+    #[test]
+    fn parses_document() {
+        let tile_json: TileJson = DOCUMENT.parse().unwrap();
+        assert_eq!(tile_json.name.as_deref(), Some("test"));
+        assert_eq!(tile_json.attribution.as_deref(), Some("(c) test"));
+        assert_eq!(tile_json.bounds, [-10.0, -20.0, 30.0, 40.0]);
+        assert_eq!(tile_json.center, Some([10.0, 10.0, 5.0]));
+        assert_eq!(tile_json.minzoom, 2);
+        assert_eq!(tile_json.maxzoom, 12);
+        assert_eq!(tile_json.scheme, TileJsonScheme::Tms);
+        assert_eq!(tile_json.vector_layers.len(), 1);
+        assert_eq!(tile_json.vector_layers[0].id, "roads");
+        assert_eq!(tile_json.vector_layers[0].maxzoom, Some(10));
+    }
+
+    #[test]
+    fn applies_defaults() {
+        let tile_json: TileJson = r#"{"tilejson": "3.0.0", "tiles": ["{z}/{x}/{y}"]}"#
+            .parse()
+            .unwrap();
+        assert_eq!(tile_json.minzoom, 0);
+        assert_eq!(tile_json.maxzoom, DEFAULT_MAX_ZOOM);
+        assert_eq!(tile_json.bounds, DEFAULT_BOUNDS);
+        assert_eq!(tile_json.scheme, TileJsonScheme::Xyz);
+        assert!(tile_json.attribution.is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        assert!(r#"{"tilejson": "3.0.0", "tiles": []}"#.parse::<TileJson>().is_err());
+        assert!(
+            r#"{"tilejson": "3.0.0", "tiles": ["{z}/{x}/{y}"], "minzoom": 5, "maxzoom": 3}"#
+                .parse::<TileJson>()
+                .is_err()
+        );
+        assert!("not json".parse::<TileJson>().is_err());
+    }
+
+    #[test]
+    fn url_source_substitutes_index() {
+        let tile_json: TileJson = DOCUMENT.parse().unwrap();
+        let source = tile_json.url_source();
+        assert_eq!(
+            source(&TileIndex::new(0, 0, 2)),
+            "https://a.example.com/tiles/2/0/3.pbf"
+        );
+        assert_eq!(
+            source(&TileIndex::new(1, 0, 2)),
+            "https://b.example.com/tiles/2/1/3.pbf"
+        );
+
+        let tile_json: TileJson = r#"{
+            "tilejson": "3.0.0",
+            "tiles": ["https://{s}.example.com/{z}/{x}/{y}.png?tms={-y}"],
+            "subdomains": ["a", "b", "c"]
+        }"#
+        .parse()
+        .unwrap();
+        let source = tile_json.url_source();
+        assert_eq!(
+            source(&TileIndex::new(1, 1, 3)),
+            "https://c.example.com/3/1/1.png?tms=6"
+        );
+    }
+
+    #[test]
+    fn creates_tile_schema() {
+        let tile_json: TileJson = DOCUMENT.parse().unwrap();
+        let schema = tile_json.tile_schema();
+        let mut levels: Vec<u32> = schema.lods.iter().map(|lod| lod.z_index()).collect();
+        levels.sort();
+        assert_eq!(levels, (2..=12).collect::<Vec<_>>());
+        assert!(schema.bounds.x_min() > -2_000_000.0);
+        assert!(schema.bounds.x_max() < 4_000_000.0);
+    }
+
+    #[test]
+    fn reads_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), DOCUMENT).unwrap();
+        let tile_json = TileJson::from_file(file.path()).unwrap();
+        assert_eq!(tile_json.tiles.len(), 2);
+    }
+}
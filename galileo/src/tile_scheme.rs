@@ -1,7 +1,8 @@
 //! [`TileSchema`] is used by tile layers to calculate [tile indices](TileIndex) needed for a given ['MapView'].
 
 use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
-use galileo_types::geo::Crs;
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::{Crs, NewGeoPoint};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 
@@ -175,6 +176,21 @@ impl TileSchema {
         )
     }
 
+    /// Returns the index of the tile at the z-level `z` that contains the tile with the given
+    /// index.
+    pub(crate) fn get_ancestor(&self, index: TileIndex, z: u32) -> Option<TileIndex> {
+        if z > index.z {
+            return None;
+        }
+
+        let center = self.tile_bbox(index)?.center();
+        self.iter_tiles_over_bbox(
+            self.lod_resolution(z)?,
+            Rect::new(center.x(), center.y(), center.x(), center.y()),
+        )?
+        .next()
+    }
+
     /// Returns lod one z-level over the given.
     fn lod_over(&self, z: u32) -> Option<&Lod> {
         let mut lod_iter = self.lods.iter();
@@ -227,6 +243,27 @@ impl TileSchema {
         }
     }
 
+    /// Web Mercator tile scheme with levels from `min_zoom` to `max_zoom` (inclusive), limited to the area given
+    /// as `[west, south, east, north]` in degrees.
+    pub(crate) fn web_for_area(min_zoom: u32, max_zoom: u32, bounds: [f64; 4]) -> Self {
+        let mut schema = Self::web(max_zoom + 1);
+        schema.lods.retain(|lod| lod.z_index() >= min_zoom);
+
+        let [west, south, east, north] = bounds;
+        if let Some(projection) = Crs::EPSG3857.get_projection::<GeoPoint2d, Point2d>() {
+            let min = projection.project(&GeoPoint2d::latlon(south, west));
+            let max = projection.project(&GeoPoint2d::latlon(north, east));
+            if let (Some(min), Some(max)) = (min, max) {
+                let bounds = Rect::new(min.x(), min.y(), max.x(), max.y());
+                if bounds.width() > 0.0 && bounds.height() > 0.0 {
+                    schema.bounds = schema.bounds.limit(bounds);
+                }
+            }
+        }
+
+        schema
+    }
+
     pub(crate) fn tile_bbox(&self, index: TileIndex) -> Option<Rect> {
         let resolution = self
             .lods
@@ -432,6 +469,16 @@ mod tests {
         assert_eq!(schema.iter_tiles(&view).unwrap().count(), 16);
     }
 
+    #[test]
+    fn get_ancestor() {
+        let schema = simple_schema();
+        let index = TileIndex::new(3, 2, 2);
+        assert_eq!(schema.get_ancestor(index, 2), Some(index));
+        assert_eq!(schema.get_ancestor(index, 1), Some(TileIndex::new(1, 1, 1)));
+        assert_eq!(schema.get_ancestor(index, 0), Some(TileIndex::new(0, 0, 0)));
+        assert_eq!(schema.get_ancestor(TileIndex::new(0, 0, 1), 2), None);
+    }
+
     #[test]
     fn lod_over() {
         let schema = simple_schema();
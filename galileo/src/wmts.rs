@@ -0,0 +1,942 @@
+//! Parsing of [WMTS](https://www.ogc.org/standard/wmts/) `GetCapabilities` documents.
+//!
+//! The capabilities describe the layers of the service and the tile matrix sets the layers are
+//! available in. A [`TileSchema`] can be created from a tile matrix set, and a url source for the
+//! tiles of a layer is created with [`WmtsCapabilities::url_source`].
+
+use crate::error::GalileoError;
+use crate::layer::data_provider::{percent_encode, UrlSource};
+use crate::lod::Lod;
+use crate::tile_scheme::{TileIndex, TileSchema, VerticalDirection};
+use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
+use galileo_types::geo::Crs;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, XmlVersion};
+use std::str::FromStr;
+
+/// Size of a pixel in meters as defined by the WMTS standard.
+const PIXEL_SIZE: f64 = 0.00028;
+/// Length of one degree along the equator of the WGS84 ellipsoid in meters.
+const METERS_PER_DEGREE: f64 = 6378137.0 * 2.0 * std::f64::consts::PI / 360.0;
+
+/// Geographic CRSs (coordinates in degrees).
+const GEOGRAPHIC_CRS: &[u32] = &[4326, 4258, 4269, 4171, 4674];
+/// CRSs with the northing (or latitude) axis first. Corner coordinates of tile matrices in these
+/// CRSs are given in `y x` order.
+const NORTHING_FIRST_CRS: &[u32] = &[
+    4326, 4258, 4269, 4171, 4674, 3034, 3035, 3844, 2180, 2193, 3006, 3007, 3008, 3009, 3010, 3011,
+    3012, 3013, 3014, 3015, 3016, 3017, 3018, 31466, 31467, 31468, 31469,
+];
+/// Projected CRSs with the easting axis first. Corner coordinates of tile matrices in these CRSs
+/// are given in `x y` order.
+const EASTING_FIRST_CRS: &[u32] = &[
+    3857, 900913, 102100, 3395, 2056, 21781, 2154, 3067, 27700, 28992,
+];
+/// UTM zones of ETRS89 and WGS84, all of them have the easting axis first.
+const EASTING_FIRST_CRS_RANGES: &[std::ops::RangeInclusive<u32>] =
+    &[25828..=25838, 32601..=32660, 32701..=32760];
+
+/// Order of the axes of a CRS, which defines the order of the corner coordinates of tile matrices.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AxisOrder {
+    /// The first coordinate is easting (or longitude), the second is northing (or latitude).
+    EastingFirst,
+    /// The first coordinate is northing (or latitude), the second is easting (or longitude).
+    NorthingFirst,
+}
+
+/// Contents of a WMTS `GetCapabilities` response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WmtsCapabilities {
+    /// Layers of the service.
+    pub layers: Vec<WmtsLayer>,
+    /// Tile matrix sets the layers are available in.
+    pub tile_matrix_sets: Vec<WmtsTileMatrixSet>,
+    /// Base url for the key-value-pair encoded `GetTile` requests, if the service supports them.
+    pub get_tile_kvp_url: Option<String>,
+}
+
+/// Layer of a WMTS service.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WmtsLayer {
+    /// Identifier of the layer used in the requests.
+    pub identifier: String,
+    /// Human-readable name of the layer.
+    pub title: Option<String>,
+    /// Image formats the tiles are available in.
+    pub formats: Vec<String>,
+    /// Identifiers of the styles of the layer.
+    pub styles: Vec<String>,
+    /// Identifier of the default style.
+    pub default_style: Option<String>,
+    /// Identifiers of the tile matrix sets the layer is available in.
+    pub tile_matrix_sets: Vec<String>,
+    /// Templates for the RESTful requests.
+    pub resource_urls: Vec<WmtsResourceUrl>,
+    /// Additional dimensions of the layer, like time.
+    pub dimensions: Vec<WmtsDimension>,
+}
+
+/// Url template for RESTful requests of a layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WmtsResourceUrl {
+    /// Format of the resource.
+    pub format: String,
+    /// Type of the resource: `tile` or `FeatureInfo`.
+    pub resource_type: String,
+    /// Url template with placeholders in curly braces, e.g. `{TileMatrix}`.
+    pub template: String,
+}
+
+/// Additional dimension of a layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WmtsDimension {
+    /// Name of the dimension used as a placeholder in url templates.
+    pub identifier: String,
+    /// Value used if no other value is requested.
+    pub default: Option<String>,
+}
+
+/// Tile matrix set of a WMTS service.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WmtsTileMatrixSet {
+    /// Identifier of the set used in the requests.
+    pub identifier: String,
+    /// CRS of the set as given in the capabilities, e.g. `urn:ogc:def:crs:EPSG::3035`.
+    pub supported_crs: String,
+    /// Tile matrices of the set, ordered from the smallest scale to the largest one.
+    ///
+    /// The position of a matrix in this list is the z-index of its level in the
+    /// [tile schema](WmtsTileMatrixSet::tile_schema).
+    pub tile_matrices: Vec<WmtsTileMatrix>,
+}
+
+/// Single level of a tile matrix set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WmtsTileMatrix {
+    /// Identifier of the matrix used in the requests.
+    pub identifier: String,
+    /// Scale denominator of the level.
+    pub scale_denominator: f64,
+    /// Top left corner of the matrix in the axis order of the CRS.
+    pub top_left_corner: [f64; 2],
+    /// Width of a tile in pixels.
+    pub tile_width: u32,
+    /// Height of a tile in pixels.
+    pub tile_height: u32,
+    /// Number of tiles in a row of the matrix.
+    pub matrix_width: u32,
+    /// Number of tiles in a column of the matrix.
+    pub matrix_height: u32,
+}
+
+impl FromStr for WmtsCapabilities {
+    type Err = GalileoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let root = XmlElement::parse(s)?;
+        if root.name != "Capabilities" {
+            return Err(GalileoError::Generic(format!(
+                "expected WMTS capabilities document, but got <{}> element",
+                root.name
+            )));
+        }
+
+        let contents = root.child("Contents");
+        let layers = contents
+            .map(|contents| contents.children("Layer").map(WmtsLayer::parse).collect())
+            .transpose()?
+            .unwrap_or_default();
+        let tile_matrix_sets = contents
+            .map(|contents| {
+                contents
+                    .children("TileMatrixSet")
+                    .map(WmtsTileMatrixSet::parse)
+                    .collect()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Self {
+            layers,
+            tile_matrix_sets,
+            get_tile_kvp_url: get_tile_kvp_url(&root),
+        })
+    }
+}
+
+impl WmtsCapabilities {
+    /// Reads the capabilities from a local file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, GalileoError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// Returns the layer with the given identifier.
+    pub fn layer(&self, identifier: &str) -> Option<&WmtsLayer> {
+        self.layers
+            .iter()
+            .find(|layer| layer.identifier == identifier)
+    }
+
+    /// Returns the tile matrix set with the given identifier.
+    pub fn tile_matrix_set(&self, identifier: &str) -> Option<&WmtsTileMatrixSet> {
+        self.tile_matrix_sets
+            .iter()
+            .find(|set| set.identifier == identifier)
+    }
+
+    /// Creates a url source for the tiles of the layer in the given tile matrix set.
+    ///
+    /// If `style` or `format` are not given, the default style and the first format of the layer
+    /// are used. RESTful urls are preferred if the layer provides a template for the format,
+    /// otherwise key-value-pair requests are used. Dimensions of the layer are set to their
+    /// default values.
+    ///
+    /// The z-index of the tile indices is the position of the tile matrix in the set, as in the
+    /// [tile schema](WmtsTileMatrixSet::tile_schema) of the set.
+    pub fn url_source(
+        &self,
+        layer: &str,
+        tile_matrix_set: &str,
+        style: Option<&str>,
+        format: Option<&str>,
+    ) -> Result<impl UrlSource<TileIndex>, GalileoError> {
+        let layer = self
+            .layer(layer)
+            .ok_or_else(|| GalileoError::Generic(format!("WMTS layer {layer} not found")))?;
+        if !layer
+            .tile_matrix_sets
+            .iter()
+            .any(|id| id == tile_matrix_set)
+        {
+            return Err(GalileoError::Generic(format!(
+                "WMTS layer {} is not available in tile matrix set {tile_matrix_set}",
+                layer.identifier
+            )));
+        }
+        let matrix_set = self.tile_matrix_set(tile_matrix_set).ok_or_else(|| {
+            GalileoError::Generic(format!("WMTS tile matrix set {tile_matrix_set} not found"))
+        })?;
+
+        let style = style
+            .or(layer.default_style.as_deref())
+            .or(layer.styles.first().map(|s| s.as_str()))
+            .unwrap_or("default");
+        let format = format
+            .or(layer.formats.first().map(|f| f.as_str()))
+            .or(layer.resource_urls.first().map(|r| r.format.as_str()))
+            .ok_or_else(|| {
+                GalileoError::Generic(format!(
+                    "WMTS layer {} does not declare any formats",
+                    layer.identifier
+                ))
+            })?;
+
+        let rest_template = layer.resource_urls.iter().find(|resource| {
+            resource.resource_type.eq_ignore_ascii_case("tile") && resource.format == format
+        });
+
+        let (template, encode): (String, fn(&str) -> String) = match rest_template {
+            Some(resource) => (resource.template.clone(), |v| v.to_string()),
+            None => {
+                let base = self.get_tile_kvp_url.as_deref().ok_or_else(|| {
+                    GalileoError::Generic(format!(
+                        "WMTS layer {} has no RESTful template for {format}, and the service \
+                        does not support KVP requests",
+                        layer.identifier
+                    ))
+                })?;
+                (
+                    kvp_template(base, &layer.identifier, format),
+                    percent_encode,
+                )
+            }
+        };
+
+        let template = fill_template(&template, |key| {
+            if key.eq_ignore_ascii_case("Style") {
+                return Some(encode(style));
+            }
+            if key.eq_ignore_ascii_case("TileMatrixSet") {
+                return Some(encode(&matrix_set.identifier));
+            }
+            layer
+                .dimensions
+                .iter()
+                .find(|dimension| dimension.identifier.eq_ignore_ascii_case(key))
+                .and_then(|dimension| dimension.default.as_deref())
+                .map(encode)
+        });
+        let matrix_ids: Vec<String> = matrix_set
+            .tile_matrices
+            .iter()
+            .map(|matrix| encode(&matrix.identifier))
+            .collect();
+
+        Ok(move |index: &TileIndex| {
+            fill_template(&template, |key| {
+                if key.eq_ignore_ascii_case("TileMatrix") {
+                    matrix_ids.get(index.z as usize).cloned()
+                } else if key.eq_ignore_ascii_case("TileRow") {
+                    Some(index.y.to_string())
+                } else if key.eq_ignore_ascii_case("TileCol") {
+                    Some(index.x.to_string())
+                } else {
+                    None
+                }
+            })
+        })
+    }
+}
+
+impl WmtsLayer {
+    fn parse(element: &XmlElement) -> Result<Self, GalileoError> {
+        let styles: Vec<(String, bool)> = element
+            .children("Style")
+            .filter_map(|style| {
+                let id = style.child_text("Identifier")?;
+                let is_default = style.attribute("isDefault") == Some("true");
+                Some((id.to_string(), is_default))
+            })
+            .collect();
+
+        Ok(Self {
+            identifier: element.required_text("Identifier")?.to_string(),
+            title: element.child_text("Title").map(str::to_string),
+            formats: element
+                .children("Format")
+                .map(|format| format.text().to_string())
+                .collect(),
+            default_style: styles
+                .iter()
+                .find(|(_, is_default)| *is_default)
+                .map(|(id, _)| id.clone()),
+            styles: styles.into_iter().map(|(id, _)| id).collect(),
+            tile_matrix_sets: element
+                .children("TileMatrixSetLink")
+                .filter_map(|link| link.child_text("TileMatrixSet"))
+                .map(str::to_string)
+                .collect(),
+            resource_urls: element
+                .children("ResourceURL")
+                .filter_map(|resource| {
+                    Some(WmtsResourceUrl {
+                        format: resource.attribute("format")?.to_string(),
+                        resource_type: resource.attribute("resourceType")?.to_string(),
+                        template: resource.attribute("template")?.to_string(),
+                    })
+                })
+                .collect(),
+            dimensions: element
+                .children("Dimension")
+                .filter_map(|dimension| {
+                    Some(WmtsDimension {
+                        identifier: dimension.child_text("Identifier")?.to_string(),
+                        default: dimension.child_text("Default").map(str::to_string),
+                    })
+                })
+                .collect(),
+        })
+    }
+}
+
+impl WmtsTileMatrixSet {
+    fn parse(element: &XmlElement) -> Result<Self, GalileoError> {
+        let mut tile_matrices = element
+            .children("TileMatrix")
+            .map(WmtsTileMatrix::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        tile_matrices.sort_by(|a, b| b.scale_denominator.total_cmp(&a.scale_denominator));
+
+        Ok(Self {
+            identifier: element.required_text("Identifier")?.to_string(),
+            supported_crs: element.required_text("SupportedCRS")?.to_string(),
+            tile_matrices,
+        })
+    }
+
+    /// EPSG code of the CRS of the set, if the CRS is given as an EPSG reference.
+    pub fn epsg_code(&self) -> Option<u32> {
+        let crs = self.supported_crs.trim();
+        if crs.ends_with("CRS84") {
+            return None;
+        }
+
+        if !crs.to_ascii_uppercase().contains("EPSG") {
+            return None;
+        }
+
+        crs.rsplit([':', '/']).next()?.parse().ok()
+    }
+
+    /// Returns the CRS of the set if it is known to Galileo (Web Mercator or WGS84).
+    ///
+    /// For other CRSs the caller has to provide the CRS definition to
+    /// [`WmtsTileMatrixSet::tile_schema`].
+    pub fn crs(&self) -> Option<Crs> {
+        if self.supported_crs.trim().ends_with("CRS84") {
+            return Some(Crs::WGS84);
+        }
+
+        match self.epsg_code()? {
+            3857 | 900913 | 102100 => Some(Crs::EPSG3857),
+            4326 => Some(Crs::WGS84),
+            _ => None,
+        }
+    }
+
+    /// Returns the axis order of the CRS of the set if it is known to Galileo.
+    pub fn axis_order(&self) -> Option<AxisOrder> {
+        if self.supported_crs.trim().ends_with("CRS84") {
+            return Some(AxisOrder::EastingFirst);
+        }
+
+        let code = self.epsg_code()?;
+        if NORTHING_FIRST_CRS.contains(&code) {
+            Some(AxisOrder::NorthingFirst)
+        } else if EASTING_FIRST_CRS.contains(&code)
+            || EASTING_FIRST_CRS_RANGES
+                .iter()
+                .any(|range| range.contains(&code))
+        {
+            Some(AxisOrder::EastingFirst)
+        } else {
+            None
+        }
+    }
+
+    /// Creates a tile schema from the set.
+    ///
+    /// Scale denominators of the matrices are converted into resolutions, the top left corner of
+    /// the matrices becomes the origin of the schema. `crs` is the CRS the set is defined in, see
+    /// [`WmtsTileMatrixSet::crs`].
+    ///
+    /// All matrices of the set must have the same tile size and top left corner. Returns an error
+    /// if the axis order of the CRS of the set is unknown (see [`WmtsTileMatrixSet::axis_order`]),
+    /// use [`WmtsTileMatrixSet::tile_schema_with_axis_order`] for such sets.
+    pub fn tile_schema(&self, crs: Crs) -> Result<TileSchema, GalileoError> {
+        let axis_order = self.axis_order().ok_or_else(|| {
+            GalileoError::Generic(format!(
+                "axis order of CRS {} of WMTS tile matrix set {} is unknown",
+                self.supported_crs, self.identifier
+            ))
+        })?;
+
+        self.tile_schema_with_axis_order(crs, axis_order)
+    }
+
+    /// Creates a tile schema from the set, reading the top left corner of the matrices in the
+    /// given axis order.
+    ///
+    /// See [`WmtsTileMatrixSet::tile_schema`].
+    pub fn tile_schema_with_axis_order(
+        &self,
+        crs: Crs,
+        axis_order: AxisOrder,
+    ) -> Result<TileSchema, GalileoError> {
+        let first = self.tile_matrices.first().ok_or_else(|| {
+            GalileoError::Generic(format!(
+                "WMTS tile matrix set {} has no tile matrices",
+                self.identifier
+            ))
+        })?;
+
+        if self.tile_matrices.iter().any(|matrix| {
+            matrix.tile_width != first.tile_width
+                || matrix.tile_height != first.tile_height
+                || matrix.top_left_corner != first.top_left_corner
+        }) {
+            return Err(GalileoError::Generic(format!(
+                "tile matrices of WMTS tile matrix set {} have different tile sizes or origins",
+                self.identifier
+            )));
+        }
+
+        let epsg_code = self.epsg_code();
+        let is_geographic = self.supported_crs.trim().ends_with("CRS84")
+            || epsg_code.is_some_and(|code| GEOGRAPHIC_CRS.contains(&code));
+        let meters_per_unit = if is_geographic {
+            METERS_PER_DEGREE
+        } else {
+            1.0
+        };
+
+        let [a, b] = first.top_left_corner;
+        let origin = match axis_order {
+            AxisOrder::EastingFirst => Point2d::new(a, b),
+            AxisOrder::NorthingFirst => Point2d::new(b, a),
+        };
+
+        let mut lods = vec![];
+        let mut bounds: Option<Rect> = None;
+        for (z, matrix) in self.tile_matrices.iter().enumerate() {
+            let resolution = matrix.scale_denominator * PIXEL_SIZE / meters_per_unit;
+            let lod = Lod::new(resolution, z as u32).ok_or_else(|| {
+                GalileoError::Generic(format!(
+                    "invalid scale denominator of WMTS tile matrix {}",
+                    matrix.identifier
+                ))
+            })?;
+            lods.push(lod);
+
+            let width = matrix.matrix_width as f64 * matrix.tile_width as f64 * resolution;
+            let height = matrix.matrix_height as f64 * matrix.tile_height as f64 * resolution;
+            let matrix_bounds = Rect::new(
+                origin.x(),
+                origin.y() - height,
+                origin.x() + width,
+                origin.y(),
+            );
+            bounds = Some(match bounds {
+                Some(bounds) => bounds.merge(matrix_bounds),
+                None => matrix_bounds,
+            });
+        }
+
+        Ok(TileSchema {
+            origin,
+            bounds: bounds
+                .unwrap_or_else(|| Rect::new(origin.x(), origin.y(), origin.x(), origin.y())),
+            lods: lods.into_iter().collect(),
+            tile_width: first.tile_width,
+            tile_height: first.tile_height,
+            y_direction: VerticalDirection::TopToBottom,
+            crs,
+        })
+    }
+}
+
+impl WmtsTileMatrix {
+    fn parse(element: &XmlElement) -> Result<Self, GalileoError> {
+        let number = |name: &str| -> Result<f64, GalileoError> {
+            element.required_text(name)?.parse().map_err(|_| {
+                GalileoError::Generic(format!("invalid value of {name} in WMTS tile matrix"))
+            })
+        };
+        let integer = |name: &str| -> Result<u32, GalileoError> {
+            element.required_text(name)?.parse().map_err(|_| {
+                GalileoError::Generic(format!("invalid value of {name} in WMTS tile matrix"))
+            })
+        };
+
+        let corner: Vec<f64> = element
+            .required_text("TopLeftCorner")?
+            .split_whitespace()
+            .map(|v| v.parse().ok())
+            .collect::<Option<_>>()
+            .unwrap_or_default();
+        let top_left_corner = corner.try_into().map_err(|_| {
+            GalileoError::Generic("invalid value of TopLeftCorner in WMTS tile matrix".into())
+        })?;
+
+        Ok(Self {
+            identifier: element.required_text("Identifier")?.to_string(),
+            scale_denominator: number("ScaleDenominator")?,
+            top_left_corner,
+            tile_width: integer("TileWidth")?,
+            tile_height: integer("TileHeight")?,
+            matrix_width: integer("MatrixWidth")?,
+            matrix_height: integer("MatrixHeight")?,
+        })
+    }
+}
+
+/// Finds the url for KVP `GetTile` requests in the `OperationsMetadata` section.
+fn get_tile_kvp_url(root: &XmlElement) -> Option<String> {
+    let operation = root
+        .child("OperationsMetadata")?
+        .children("Operation")
+        .find(|operation| operation.attribute("name") == Some("GetTile"))?;
+
+    operation
+        .children("DCP")
+        .flat_map(|dcp| dcp.children("HTTP"))
+        .flat_map(|http| http.children("Get"))
+        .find(|get| {
+            let mut encodings = get
+                .children("Constraint")
+                .filter(|constraint| constraint.attribute("name") == Some("GetEncoding"))
+                .flat_map(|constraint| constraint.children("AllowedValues"))
+                .flat_map(|values| values.children("Value"))
+                .peekable();
+            encodings.peek().is_none() || encodings.any(|value| value.text() == "KVP")
+        })?
+        .attribute("href")
+        .map(str::to_string)
+}
+
+/// Creates a url template for KVP `GetTile` requests.
+fn kvp_template(base: &str, layer: &str, format: &str) -> String {
+    let separator = if base.ends_with('?') || base.ends_with('&') {
+        ""
+    } else if base.contains('?') {
+        "&"
+    } else {
+        "?"
+    };
+
+    format!(
+        "{base}{separator}SERVICE=WMTS&REQUEST=GetTile&VERSION=1.0.0&LAYER={}\
+        &STYLE={{Style}}&FORMAT={}&TILEMATRIXSET={{TileMatrixSet}}&TILEMATRIX={{TileMatrix}}\
+        &TILEROW={{TileRow}}&TILECOL={{TileCol}}",
+        percent_encode(layer),
+        percent_encode(format),
+    )
+}
+
+/// Replaces `{name}` placeholders in the template with the values returned by `value`.
+/// Placeholders without a value are left as is.
+fn fill_template(template: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(length) = rest[start..].find('}') else {
+            break;
+        };
+
+        result.push_str(&rest[..start]);
+        let placeholder = &rest[start..start + length + 1];
+        match value(&placeholder[1..placeholder.len() - 1]) {
+            Some(value) => result.push_str(&value),
+            None => result.push_str(placeholder),
+        }
+        rest = &rest[start + length + 1..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Minimal DOM of an XML document. Names of elements and attributes are stored without namespace
+/// prefixes.
+#[derive(Debug, Default)]
+struct XmlElement {
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    children: Vec<XmlElement>,
+}
+
+impl XmlElement {
+    fn parse(xml: &str) -> Result<Self, GalileoError> {
+        let mut reader = Reader::from_str(xml);
+        let mut stack = vec![XmlElement::default()];
+
+        loop {
+            match reader.read_event().map_err(xml_error)? {
+                Event::Start(start) => stack.push(Self::from_start(&start)?),
+                Event::Empty(start) => {
+                    let element = Self::from_start(&start)?;
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(element);
+                    }
+                }
+                Event::End(_) => {
+                    let element = stack.pop().filter(|_| !stack.is_empty());
+                    match (element, stack.last_mut()) {
+                        (Some(element), Some(parent)) => parent.children.push(element),
+                        _ => return Err(xml_error("unexpected closing tag")),
+                    }
+                }
+                Event::Text(text) => {
+                    let text = text.xml10_content().map_err(xml_error)?;
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text);
+                    }
+                }
+                Event::CData(data) => {
+                    let text = data.decode().map_err(xml_error)?;
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text);
+                    }
+                }
+                Event::GeneralRef(reference) => {
+                    let resolved = match reference.resolve_char_ref().map_err(xml_error)? {
+                        Some(c) => c.to_string(),
+                        None => {
+                            let name = reference.decode().map_err(xml_error)?;
+                            quick_xml::escape::unescape(&format!("&{name};"))
+                                .map_err(xml_error)?
+                                .into_owned()
+                        }
+                    };
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&resolved);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        match stack.pop() {
+            Some(document) if stack.is_empty() => document
+                .children
+                .into_iter()
+                .next()
+                .ok_or_else(|| xml_error("document has no root element")),
+            _ => Err(xml_error("unexpected end of document")),
+        }
+    }
+
+    fn from_start(start: &BytesStart) -> Result<Self, GalileoError> {
+        let attributes = start
+            .attributes()
+            .map(|attribute| {
+                let attribute = attribute.map_err(xml_error)?;
+                let name =
+                    String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned();
+                let value = attribute
+                    .normalized_value(XmlVersion::default())
+                    .map_err(xml_error)?
+                    .into_owned();
+                Ok((name, value))
+            })
+            .collect::<Result<_, GalileoError>>()?;
+
+        Ok(Self {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+            attributes,
+            ..Default::default()
+        })
+    }
+
+    fn text(&self) -> &str {
+        self.text.trim()
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlElement> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text())
+    }
+
+    fn required_text(&self, name: &str) -> Result<&str, GalileoError> {
+        self.child_text(name).ok_or_else(|| {
+            GalileoError::Generic(format!(
+                "<{}> element of WMTS capabilities has no <{name}>",
+                self.name
+            ))
+        })
+    }
+}
+
+fn xml_error(err: impl std::fmt::Display) -> GalileoError {
+    GalileoError::Generic(format!("invalid WMTS capabilities document: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::geo::{Datum, ProjectionType};
+
+    const LAEA_REST: &str = include_str!("../test-data/wmts/laea_rest.xml");
+    const LV95_KVP: &str = include_str!("../test-data/wmts/lv95_kvp.xml");
+
+    fn local_crs(definition: &str) -> Crs {
+        Crs::new(Datum::WGS84, ProjectionType::Other(definition.into()))
+    }
+
+    #[test]
+    fn parses_layers() {
+        let capabilities: WmtsCapabilities = LAEA_REST.parse().unwrap();
+        assert_eq!(capabilities.layers.len(), 1);
+        assert!(capabilities.get_tile_kvp_url.is_none());
+
+        let layer = capabilities.layer("ortho").unwrap();
+        assert_eq!(layer.title.as_deref(), Some("Orthophoto"));
+        assert_eq!(layer.formats, vec!["image/jpeg", "image/png"]);
+        assert_eq!(layer.styles, vec!["infrared", "default"]);
+        assert_eq!(layer.default_style.as_deref(), Some("default"));
+        assert_eq!(layer.tile_matrix_sets, vec!["LAEA", "WGS84"]);
+        assert_eq!(layer.resource_urls.len(), 2);
+        assert!(layer.resource_urls[0].template.ends_with("?key=a&b"));
+        assert_eq!(
+            layer.dimensions,
+            vec![WmtsDimension {
+                identifier: "Time".into(),
+                default: Some("2024".into())
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_tile_matrix_sets() {
+        let capabilities: WmtsCapabilities = LAEA_REST.parse().unwrap();
+        let set = capabilities.tile_matrix_set("LAEA").unwrap();
+        assert_eq!(set.epsg_code(), Some(3035));
+        assert_eq!(set.crs(), None);
+
+        let ids: Vec<&str> = set
+            .tile_matrices
+            .iter()
+            .map(|matrix| matrix.identifier.as_str())
+            .collect();
+        assert_eq!(ids, vec!["LAEA:0", "LAEA:1", "LAEA:2"]);
+        assert_eq!(set.tile_matrices[0].top_left_corner, [5500000.0, 2000000.0]);
+        assert_eq!(set.tile_matrices[0].matrix_width, 4);
+
+        let set = capabilities.tile_matrix_set("WGS84").unwrap();
+        assert_eq!(set.epsg_code(), Some(4326));
+        assert_eq!(set.crs(), Some(Crs::WGS84));
+    }
+
+    #[test]
+    fn tile_schema_from_northing_first_crs() {
+        let capabilities: WmtsCapabilities = LAEA_REST.parse().unwrap();
+        let crs = local_crs(
+            "+proj=laea +lat_0=52 +lon_0=10 +x_0=4321000 +y_0=3210000 +ellps=GRS80 +units=m",
+        );
+        let schema = capabilities
+            .tile_matrix_set("LAEA")
+            .unwrap()
+            .tile_schema(crs.clone())
+            .unwrap();
+
+        assert_eq!(schema.crs, crs);
+        assert_eq!(schema.origin, Point2d::new(2000000.0, 5500000.0));
+        assert_eq!(schema.tile_width, 256);
+        assert_eq!(schema.y_direction, VerticalDirection::TopToBottom);
+        for (z, expected) in [(0, 4096.0), (1, 2048.0), (2, 1024.0)] {
+            let resolution = schema.lod_resolution(z).unwrap();
+            assert!(
+                (resolution - expected).abs() < 1e-6,
+                "{resolution} != {expected}"
+            );
+        }
+
+        let size = 4.0 * 256.0 * 4096.0;
+        assert_eq!(schema.bounds.x_min(), 2000000.0);
+        assert!((schema.bounds.x_max() - (2000000.0 + size)).abs() < 1e-3);
+        assert!((schema.bounds.y_min() - (5500000.0 - size)).abs() < 1e-3);
+        assert_eq!(schema.bounds.y_max(), 5500000.0);
+    }
+
+    #[test]
+    fn tile_schema_from_geographic_crs() {
+        let capabilities: WmtsCapabilities = LAEA_REST.parse().unwrap();
+        let set = capabilities.tile_matrix_set("WGS84").unwrap();
+        let schema = set.tile_schema(set.crs().unwrap()).unwrap();
+
+        assert_eq!(schema.origin, Point2d::new(-180.0, 90.0));
+        let resolution = schema.lod_resolution(0).unwrap();
+        assert!((resolution - 0.703125).abs() < 1e-9, "{resolution}");
+    }
+
+    #[test]
+    fn tile_schema_from_easting_first_crs() {
+        let capabilities: WmtsCapabilities = LV95_KVP.parse().unwrap();
+        let set = capabilities.tile_matrix_set("2056").unwrap();
+        assert_eq!(set.epsg_code(), Some(2056));
+
+        let crs = local_crs(
+            "+proj=somerc +lat_0=46.9524055555556 +lon_0=7.43958333333333 +k_0=1 \
+            +x_0=2600000 +y_0=1200000 +ellps=bessel +units=m",
+        );
+        let schema = set.tile_schema(crs).unwrap();
+        assert_eq!(schema.origin, Point2d::new(2420000.0, 1350000.0));
+        assert_eq!(schema.lods.len(), 3);
+        assert!((schema.lod_resolution(2).unwrap() - 1000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tile_schema_axis_order() {
+        let capabilities: WmtsCapabilities = LAEA_REST.parse().unwrap();
+        let mut set = capabilities.tile_matrix_set("LAEA").unwrap().clone();
+        let crs = local_crs("+proj=tmerc +lon_0=15 +k=0.9996 +x_0=500000 +ellps=GRS80 +units=m");
+
+        set.supported_crs = "urn:ogc:def:crs:EPSG::3006".into();
+        assert_eq!(set.axis_order(), Some(AxisOrder::NorthingFirst));
+        let schema = set.tile_schema(crs.clone()).unwrap();
+        assert_eq!(schema.origin, Point2d::new(2000000.0, 5500000.0));
+
+        set.supported_crs = "urn:ogc:def:crs:EPSG::32633".into();
+        assert_eq!(set.axis_order(), Some(AxisOrder::EastingFirst));
+
+        set.supported_crs = "urn:ogc:def:crs:EPSG::9999".into();
+        assert_eq!(set.axis_order(), None);
+        assert!(set.tile_schema(crs.clone()).is_err());
+
+        let schema = set
+            .tile_schema_with_axis_order(crs, AxisOrder::NorthingFirst)
+            .unwrap();
+        assert_eq!(schema.origin, Point2d::new(2000000.0, 5500000.0));
+    }
+
+    #[test]
+    fn rest_url_source() {
+        let capabilities: WmtsCapabilities = LAEA_REST.parse().unwrap();
+        let source = capabilities
+            .url_source("ortho", "LAEA", None, Some("image/png"))
+            .unwrap();
+        assert_eq!(
+            source(&TileIndex::new(3, 2, 1)),
+            "https://maps.example.com/wmts/ortho/default/2024/LAEA/LAEA:1/2/3.png?key=a&b"
+        );
+
+        let source = capabilities
+            .url_source("ortho", "WGS84", Some("infrared"), None)
+            .unwrap();
+        assert_eq!(
+            source(&TileIndex::new(1, 0, 0)),
+            "https://maps.example.com/wmts/ortho/infrared/2024/WGS84/0/0/1.jpg"
+        );
+    }
+
+    #[test]
+    fn kvp_url_source() {
+        let capabilities: WmtsCapabilities = LV95_KVP.parse().unwrap();
+        assert_eq!(
+            capabilities.get_tile_kvp_url.as_deref(),
+            Some("https://wmts.example.ch/service?")
+        );
+
+        let source = capabilities
+            .url_source("ch.example.national-map", "2056", None, None)
+            .unwrap();
+        assert_eq!(
+            source(&TileIndex::new(1, 0, 2)),
+            "https://wmts.example.ch/service?SERVICE=WMTS&REQUEST=GetTile&VERSION=1.0.0\
+            &LAYER=ch.example.national-map&STYLE=default&FORMAT=image%2Fjpeg\
+            &TILEMATRIXSET=2056&TILEMATRIX=2&TILEROW=0&TILECOL=1"
+        );
+    }
+
+    #[test]
+    fn url_source_errors() {
+        let capabilities: WmtsCapabilities = LAEA_REST.parse().unwrap();
+        assert!(capabilities
+            .url_source("unknown", "LAEA", None, None)
+            .is_err());
+        assert!(capabilities
+            .url_source("ortho", "unknown", None, None)
+            .is_err());
+        // No RESTful template for the format and no KVP support.
+        assert!(capabilities
+            .url_source("ortho", "LAEA", None, Some("image/webp"))
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_documents() {
+        assert!("<Capabilities><Contents>"
+            .parse::<WmtsCapabilities>()
+            .is_err());
+        assert!("<WMS_Capabilities/>".parse::<WmtsCapabilities>().is_err());
+        assert!("not xml".parse::<WmtsCapabilities>().is_err());
+    }
+}